@@ -1,13 +1,62 @@
 //! 基准测试示例
 
+use async_trait::async_trait;
 use pelican_framework::{
-    Pipeline,
-    sources::MockPacketSource,
-    processors::{PacketStatsProcessor, UpperCaseProcessor},
+    Bytes, DataChunk, Pipeline,
+    sources::{MemorySource, MockPacketSource},
+    processors::{FilterProcessor, PacketStatsProcessor, ProcessResult, Processor, UpperCaseProcessor},
     sinks::MetricsSink
 };
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::time::Instant;
 
+// 统计分配次数和字节数，用于观察扇出时负载是否被复制
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicU64 = AtomicU64::new(0);
+static ALLOCATED_BYTES: AtomicU64 = AtomicU64::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        ALLOCATED_BYTES.fetch_add(layout.size() as u64, Ordering::Relaxed);
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+// 模拟计算密集的分析：对每个数据包反复计算哈希
+struct DeepInspectionProcessor {
+    rounds: u32,
+}
+
+#[async_trait]
+impl Processor for DeepInspectionProcessor {
+    async fn process(&mut self, chunk: &mut DataChunk) -> anyhow::Result<ProcessResult> {
+        let mut digest = 0u64;
+        for round in 0..self.rounds {
+            let mut hasher = DefaultHasher::new();
+            (round, digest, &chunk.data).hash(&mut hasher);
+            digest = hasher.finish();
+        }
+        std::hint::black_box(digest);
+        Ok(ProcessResult::Continue)
+    }
+
+    fn name(&self) -> &str {
+        "DeepInspectionProcessor"
+    }
+}
+
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
     tracing_subscriber::fmt::init();
@@ -15,31 +64,74 @@ async fn main() -> anyhow::Result<()> {
     println!("🧪 鹈鹕框架 - 基准测试");
     println!("======================");
 
-    let packet_counts = [100, 500, 1000];
+    let count = 5000;
+    let cores = std::thread::available_parallelism().map(|n| n.get()).unwrap_or(4);
+    let mut worker_counts = vec![1, 2, 4, cores];
+    worker_counts.sort_unstable();
+    worker_counts.dedup();
+
+    let mut baseline = None;
+    for &workers in &worker_counts {
+        for ordered in [false, true] {
+            println!("\n测试 {} 个数据包, {} 个工作任务{}...", count, workers,
+                     if ordered { " (有序)" } else { "" });
 
-    for &count in &packet_counts {
-        println!("\n测试 {} 个数据包的处理性能...", count);
+            let start_time = Instant::now();
 
-        let start_time = Instant::now();
+            let pipeline = Pipeline::builder()
+                .add_source(Box::new(MockPacketSource::new(count).with_delay(0)))
+                .add_processor_factory(|| Box::new(PacketStatsProcessor::new()))
+                .add_processor_factory(|| Box::new(DeepInspectionProcessor { rounds: 2000 }))
+                .add_processor_factory(|| Box::new(UpperCaseProcessor::new()))
+                .add_sink(Box::new(MetricsSink::new()))
+                .with_workers(workers)
+                .with_ordered_output(ordered)
+                .build();
 
-        let pipeline = Pipeline::builder()
-            .add_source(Box::new(MockPacketSource::new(count).with_delay(0)))
-            .add_processor(Box::new(PacketStatsProcessor::new()))
-            .add_processor(Box::new(UpperCaseProcessor::new()))
-            .add_sink(Box::new(MetricsSink::new()))
-            .build();
+            pipeline.run().await?;
 
-        pipeline.run().await?;
+            let elapsed = start_time.elapsed().as_secs_f64();
+            let baseline = *baseline.get_or_insert(elapsed);
 
-        let elapsed = start_time.elapsed();
-        let packets_per_second = count as f64 / elapsed.as_secs_f64();
+            println!("结果:");
+            println!("  • 总耗时: {:.2} 秒", elapsed);
+            println!("  • 吞吐量: {:.2} 数据包/秒", count as f64 / elapsed);
+            println!("  • 加速比: {:.2}x", baseline / elapsed);
+        }
+    }
+
+    // 零拷贝扇出：同一个大负载经过只读处理器后投递到多个输出端
+    let payload_size = 64 * 1024;
+    let chunks = 2000;
+    let sinks = 8;
+    println!("\n测试零拷贝扇出: {} 个 {} KB 数据块 → {} 个输出端...", chunks, payload_size / 1024, sinks);
 
-        println!("结果:");
-        println!("  • 处理数量: {} 数据包", count);
-        println!("  • 总耗时: {:.2} 秒", elapsed.as_secs_f64());
-        println!("  • 吞吐量: {:.2} 数据包/秒", packets_per_second);
+    let payload = Bytes::from(vec![b'x'; payload_size]);
+    let mut builder = Pipeline::builder()
+        .add_source(Box::new(MemorySource::from_bytes(vec![payload; chunks]).with_delay(0)))
+        .add_processor(Box::new(FilterProcessor::new(b"x".to_vec())));
+    for _ in 0..sinks {
+        builder = builder.add_sink(Box::new(MetricsSink::new()));
     }
+    let pipeline = builder.build();
+
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed);
+    let allocated_bytes = ALLOCATED_BYTES.load(Ordering::Relaxed);
+    let start_time = Instant::now();
+    pipeline.run().await?;
+    let elapsed = start_time.elapsed().as_secs_f64();
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed) - allocations;
+    let allocated_bytes = ALLOCATED_BYTES.load(Ordering::Relaxed) - allocated_bytes;
+
+    let delivered = (chunks * sinks * payload_size) as f64;
+    println!("结果:");
+    println!("  • 总耗时: {:.2} 秒", elapsed);
+    println!("  • 投递数据量: {:.2} GB/秒", delivered / elapsed / 1e9);
+    println!("  • 每个数据块分配: {:.1} 次, {:.0} 字节（深拷贝需要 {} 字节）",
+             allocations as f64 / chunks as f64,
+             allocated_bytes as f64 / chunks as f64,
+             payload_size * sinks);
 
     println!("\n✅ 基准测试完成!");
     Ok(())
-}
\ No newline at end of file
+}
@@ -3,9 +3,6 @@
 use pelican_framework::{
     Pipeline,
     config::PipelineConfig,
-    sources::MockPacketSource,
-    processors::{PacketStatsProcessor, UpperCaseProcessor},
-    sinks::ConsoleSink
 };
 
 #[tokio::main]
@@ -44,17 +41,12 @@ async fn main() -> anyhow::Result<()> {
     "#;
 
     // 从YAML解析配置
-    let pipeline_config: PipelineConfig = serde_yaml::from_str(config)?;
+    let pipeline_config = PipelineConfig::from_yaml(config)?;
 
     println!("从配置创建管道: {}", pipeline_config.name);
 
-    // 手动创建管道（在实际实现中应该根据配置自动创建）
-    let pipeline = Pipeline::builder()
-        .add_source(Box::new(MockPacketSource::new(100).with_delay(10)))
-        .add_processor(Box::new(PacketStatsProcessor::new()))
-        .add_processor(Box::new(UpperCaseProcessor::new()))
-        .add_sink(Box::new(ConsoleSink::new()))
-        .build();
+    // 通过组件注册表根据 type/params 自动创建各组件
+    let pipeline = Pipeline::from_config(&pipeline_config)?;
 
     pipeline.run().await?;
 
@@ -1,13 +1,13 @@
 use pelican_framework::{
-    Pipeline,
-    sources::MockPacketSource,
+    Bytes, Pipeline,
+    sources::{MemorySource, MockPacketSource},
     processors::{
         FilterProcessor,
         PacketStatsProcessor,
         UpperCaseProcessor,
         ThreatDetectionProcessor
     },
-    sinks::{MetricsSink, ConsoleSink}
+    sinks::MetricsSink
 };
 use std::time::Instant;
 
@@ -54,6 +54,28 @@ async fn main() -> anyhow::Result<()> {
                  elapsed.as_millis() as f64 / packet_count as f64);
     }
 
+    // 零拷贝扇出：负载以 Bytes 共享，投递到多个输出端只增加引用计数
+    let (chunks, payload_size, sinks) = (2000, 64 * 1024, 4);
+    println!("\n🧪 测试: 零拷贝扇出 ({}个 {} KB 数据块 → {} 个输出端)", chunks, payload_size / 1024, sinks);
+    println!("{}", "-".repeat(40));
+
+    let payload = Bytes::from(vec![b'x'; payload_size]);
+    let mut builder = Pipeline::builder()
+        .add_source(Box::new(MemorySource::from_bytes(vec![payload; chunks]).with_delay(0)))
+        .add_processor(Box::new(FilterProcessor::new(b"x".to_vec())));
+    for _ in 0..sinks {
+        builder = builder.add_sink(Box::new(MetricsSink::new()));
+    }
+
+    let start_time = Instant::now();
+    builder.build().run().await?;
+    let elapsed = start_time.elapsed();
+
+    println!("📊 性能结果:");
+    println!("  • 总耗时: {:.3} 秒", elapsed.as_secs_f64());
+    println!("  • 投递数据量: {:.2} GB/秒",
+             (chunks * payload_size * sinks) as f64 / elapsed.as_secs_f64() / 1e9);
+
     println!("\n================================");
     println!("✅ 性能演示完成！");
     println!("鹈鹕框架展示了优秀的数据处理能力！");
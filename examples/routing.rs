@@ -0,0 +1,47 @@
+//! 分流示例：DNS 流量与疑似攻击流量走不同的分支
+
+use pelican_framework::{
+    Pipeline,
+    sources::{MemorySource, MockPacketSource},
+    processors::{Condition, IPAnalysisProcessor, Router, ThreatDetectionProcessor},
+    sinks::{ConsoleSink, MetricsSink, StatsSink}
+};
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    tracing_subscriber::fmt::init();
+
+    println!("🔀 鹈鹕框架 - 分流示例");
+    println!("======================");
+
+    let suspicious = vec![
+        b"HTTP GET /search?q=SELECT * FROM users".to_vec(),
+        b"HTTP POST /comment <script>alert(1)</script>".to_vec(),
+    ];
+
+    let router = Router::new("split")
+        .route("dns", Condition::StartsWith("DNS".to_string()))
+        .route("threats", Condition::ContainsAny(vec![
+            "SELECT * FROM".to_string(),
+            "<script>".to_string(),
+        ]));
+
+    let summary = Pipeline::builder()
+        .source("packets", Box::new(MockPacketSource::new(200).with_delay(0)))
+        .source("suspicious", Box::new(MemorySource::new(suspicious)))
+        .router(router, &["packets", "suspicious"])
+        .processor("dns_analysis", Box::new(IPAnalysisProcessor::new()), &["split.dns"])
+        .processor("threat_detection", Box::new(ThreatDetectionProcessor::new()), &["split.threats"])
+        .sink("dns_stats", Box::new(StatsSink::new()), &["dns_analysis"])
+        .sink("alerts", Box::new(ConsoleSink::new()), &["threat_detection"])
+        .sink("other", Box::new(MetricsSink::new()), &["split._unmatched"])
+        .build()
+        .run()
+        .await?;
+
+    println!("\n📊 各输出端:");
+    for sink in &summary.sinks {
+        println!("  • {}: {} 条", sink.name, sink.delivered);
+    }
+    Ok(())
+}
@@ -8,10 +8,8 @@ use pelican_framework::{
         security_processor::{EncryptionProcessor, DecryptionProcessor}
     },
     sinks::{ConsoleSink, SecureFileSink},
-    security::SecurityConfig,
-    ratelimit::RateLimiter,
+    security::{EncryptionAlgorithm, IntegrityAlgorithm, Kdf, KeyRing, KeySource, SecureFileReader, SecurityConfig, Zeroizing},
 };
-use std::time::Duration;
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
@@ -20,20 +18,24 @@ async fn main() -> anyhow::Result<()> {
     println!("🔒 鹈鹕框架 - 安全管道示例");
     println!("==============================");
 
+    // 密钥环：先使用编号 1 的密钥，运行中轮换到口令派生的编号 2
+    let keyring = KeyRing::single(1, &[
+        0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15,
+        16, 17, 18, 19, 20, 21, 22, 23, 24, 25, 26, 27, 28, 29, 30, 31,
+    ])?;
+
     // 创建安全配置
     let security_config = SecurityConfig {
         enable_encryption: true,
         enable_integrity_check: true,
-        encryption_key: Some(vec![
-            0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15,
-            16, 17, 18, 19, 20, 21, 22, 23, 24, 25, 26, 27, 28, 29, 30, 31,
-        ]),
+        encryption_algorithm: EncryptionAlgorithm::ChaCha20Poly1305,
+        encryption_key: None,
+        keyring: Some(keyring.clone()),
+        integrity_algorithm: IntegrityAlgorithm::HmacSha256,
+        integrity_key: Some(Zeroizing::new(b"pelican-example-integrity-key-32".to_vec())),
         integrity_salt: Some([1, 2, 3, 4, 5, 6, 7, 8]),
     };
 
-    // 创建加密密钥
-    let encryption_key = security_config.encryption_key.as_ref().unwrap();
-
     println!("🚀 启动安全管道...");
     let start_time = std::time::Instant::now();
 
@@ -44,21 +46,21 @@ async fn main() -> anyhow::Result<()> {
         // 添加统计处理器
         .add_processor(Box::new(PacketStatsProcessor::new()))
 
-        // 添加加密处理器
+        // 添加加密处理器（算法和密钥取自安全配置）
         .add_processor(Box::new(
-            EncryptionProcessor::new(encryption_key, "DataEncryptor").unwrap()
+            EncryptionProcessor::from_config(&security_config, "DataEncryptor").unwrap()
         ))
 
         // 添加解密处理器（演示用）
         .add_processor(Box::new(
-            DecryptionProcessor::new(encryption_key, "DataDecryptor").unwrap()
+            DecryptionProcessor::from_config(&security_config, "DataDecryptor").unwrap()
         ))
 
         // 添加输出端
         .add_sink(Box::new(ConsoleSink::new()))
         .add_sink(Box::new(
             SecureFileSink::new(
-                "secure_output.bin".to_string(),
+                "secure_example.bin".to_string(),
                 &security_config,
                 "SecureFileSink"
             ).unwrap()
@@ -66,17 +68,46 @@ async fn main() -> anyhow::Result<()> {
 
         .build();
 
-    // 运行管道
-    pipeline.run().await?;
+    // 预先用口令派生下一个密钥（Argon2id 比较耗时）
+    let next_key = KeySource::Passphrase {
+        passphrase: "correct horse battery staple".to_string().into(),
+        salt: b"pelican-example-salt".to_vec(),
+        kdf: Kdf::default(),
+    }
+    .load()?;
+
+    // 运行管道，中途轮换密钥：之后的数据块使用新密钥，解密处理器按密文中的密钥编号取密钥
+    let handle = pipeline.run();
+    tokio::spawn(async move {
+        tokio::time::sleep(std::time::Duration::from_millis(250)).await;
+        match keyring.insert(2, next_key).and_then(|()| keyring.set_active(2)) {
+            Ok(()) => println!("🔑 已轮换到密钥 {:?}", keyring.active_id()),
+            Err(e) => println!("❌ 密钥轮换失败: {}", e),
+        }
+    });
+    handle.await?;
 
     let elapsed = start_time.elapsed();
     println!("\n✅ 安全管道运行完成!");
     println!("总耗时: {:.2}秒", elapsed.as_secs_f64());
 
     println!("\n📊 安全功能已启用:");
-    println!("  • 数据加密: ✅");
-    println!("  • 完整性检查: ✅");
-    println!("  • 输出文件: secure_output.bin");
+    println!("  • 数据加密: ✅ ({})", security_config.encryption_algorithm);
+    println!("  • 完整性检查: ✅ ({})", security_config.integrity_algorithm);
+    println!("  • 输出文件: secure_example.bin");
+
+    // 读回输出文件：逐条校验并解密（轮换前后的记录按各自的密钥编号解密）
+    let reader = SecureFileReader::open("secure_example.bin", &security_config)?;
+    let key_id = reader.header().key_id;
+    let mut records = 0;
+    let mut bytes = 0;
+    for record in reader {
+        let record = record?;
+        records += 1;
+        bytes += record.data.len();
+    }
+    println!("\n📂 读回 secure_example.bin（创建时密钥编号 {}）: {} 条记录，共 {} 字节，全部通过校验",
+        key_id, records, bytes);
 
     Ok(())
 }
\ No newline at end of file
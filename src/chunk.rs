@@ -0,0 +1,292 @@
+use base64::Engine;
+use bytes::{Bytes, BytesMut};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+// 数据块属性值：处理器写入的注解，例如 protocol=TCP、threat=sql_injection
+#[derive(Debug, Clone, PartialEq)]
+pub enum AttributeValue {
+    Str(String),
+    Int(i64),
+    Float(f64),
+    Bytes(Vec<u8>),
+    Bool(bool),
+}
+
+impl AttributeValue {
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            AttributeValue::Str(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            AttributeValue::Int(n) => Some(*n),
+            _ => None,
+        }
+    }
+
+    // 整数也可以按浮点数读取
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            AttributeValue::Float(f) => Some(*f),
+            AttributeValue::Int(n) => Some(*n as f64),
+            _ => None,
+        }
+    }
+
+    pub fn as_bytes(&self) -> Option<&[u8]> {
+        match self {
+            AttributeValue::Bytes(b) => Some(b),
+            AttributeValue::Str(s) => Some(s.as_bytes()),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            AttributeValue::Bool(b) => Some(*b),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for AttributeValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AttributeValue::Str(s) => write!(f, "{}", s),
+            AttributeValue::Int(n) => write!(f, "{}", n),
+            AttributeValue::Float(v) => write!(f, "{}", v),
+            AttributeValue::Bytes(b) => write!(f, "0x{}", b.iter().map(|b| format!("{:02x}", b)).collect::<String>()),
+            AttributeValue::Bool(b) => write!(f, "{}", b),
+        }
+    }
+}
+
+impl From<&str> for AttributeValue {
+    fn from(value: &str) -> Self {
+        AttributeValue::Str(value.to_string())
+    }
+}
+
+impl From<String> for AttributeValue {
+    fn from(value: String) -> Self {
+        AttributeValue::Str(value)
+    }
+}
+
+impl From<i64> for AttributeValue {
+    fn from(value: i64) -> Self {
+        AttributeValue::Int(value)
+    }
+}
+
+impl From<u32> for AttributeValue {
+    fn from(value: u32) -> Self {
+        AttributeValue::Int(value as i64)
+    }
+}
+
+impl From<f64> for AttributeValue {
+    fn from(value: f64) -> Self {
+        AttributeValue::Float(value)
+    }
+}
+
+impl From<Vec<u8>> for AttributeValue {
+    fn from(value: Vec<u8>) -> Self {
+        AttributeValue::Bytes(value)
+    }
+}
+
+impl From<bool> for AttributeValue {
+    fn from(value: bool) -> Self {
+        AttributeValue::Bool(value)
+    }
+}
+
+// JSON/YAML 中字符串、数字和布尔值按原样书写，字节写成 { bytes: <base64> }
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum AttributeRepr {
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    Str(String),
+    Bytes { bytes: String },
+}
+
+impl Serialize for AttributeValue {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let repr = match self {
+            AttributeValue::Str(s) => AttributeRepr::Str(s.clone()),
+            AttributeValue::Int(n) => AttributeRepr::Int(*n),
+            AttributeValue::Float(f) => AttributeRepr::Float(*f),
+            AttributeValue::Bytes(b) => AttributeRepr::Bytes {
+                bytes: base64::engine::general_purpose::STANDARD.encode(b),
+            },
+            AttributeValue::Bool(b) => AttributeRepr::Bool(*b),
+        };
+        repr.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for AttributeValue {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(match AttributeRepr::deserialize(deserializer)? {
+            AttributeRepr::Bool(b) => AttributeValue::Bool(b),
+            AttributeRepr::Int(n) => AttributeValue::Int(n),
+            AttributeRepr::Float(f) => AttributeValue::Float(f),
+            AttributeRepr::Str(s) => AttributeValue::Str(s),
+            AttributeRepr::Bytes { bytes } => AttributeValue::Bytes(
+                base64::engine::general_purpose::STANDARD
+                    .decode(bytes)
+                    .map_err(serde::de::Error::custom)?,
+            ),
+        })
+    }
+}
+
+pub type Attributes = BTreeMap<String, AttributeValue>;
+
+// 当前时间（Unix 纳秒）
+pub fn now_ns() -> u64 {
+    system_time_to_ns(SystemTime::now())
+}
+
+// SystemTime → Unix 纳秒；早于 1970 年的时间记为 0，超出 u64 范围（2554 年之后）时取最大值
+pub fn system_time_to_ns(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|elapsed| u64::try_from(elapsed.as_nanos()).unwrap_or(u64::MAX))
+        .unwrap_or(0)
+}
+
+// Unix 纳秒 → SystemTime
+pub fn ns_to_system_time(ns: u64) -> SystemTime {
+    UNIX_EPOCH + Duration::from_nanos(ns)
+}
+
+// 数据流的基本单元
+//
+// 负载和注解都是共享的：克隆数据块（扇出到多个输出端、保留重试原件）只增加引用计数，
+// 修改时才复制（写时复制），只读的处理器不产生任何分配
+#[derive(Debug, Clone)]
+pub struct DataChunk {
+    // 管道接收时分配的全局编号，从 1 单调递增；0 表示尚未进入管道
+    pub id: u64,
+    pub data: Bytes,
+    // 进入管道的时间（Unix 纳秒）；DataChunk::new 记录创建时间，管道接收时更新
+    pub ingest_time_ns: u64,
+    // 事件实际发生的时间（Unix 纳秒），由数据源设置，例如 pcap 记录头中的捕获时间
+    pub event_time_ns: Option<u64>,
+    // 数据源内部的序号，与 source_id 一起可以检测某个数据源的丢失和乱序
+    pub sequence: u64,
+    // 处理器写入、下游读取的注解，通过 set_attribute 修改
+    pub attributes: Arc<Attributes>,
+    // 分区键，例如源 IP；用于分组和按键路由
+    pub key: Option<Arc<str>>,
+    // 产生该数据块的数据源，管道在接收时填入数据源名称
+    pub source_id: Option<Arc<str>>,
+}
+
+impl DataChunk {
+    // 接受 Vec<u8>、String、&'static [u8] 或 Bytes，均不复制负载
+    pub fn new(data: impl Into<Bytes>) -> Self {
+        Self {
+            id: 0,
+            data: data.into(),
+            ingest_time_ns: now_ns(),
+            event_time_ns: None,
+            sequence: 0,
+            attributes: Arc::default(),
+            key: None,
+            source_id: None,
+        }
+    }
+
+    pub fn with_sequence(mut self, sequence: u64) -> Self {
+        self.sequence = sequence;
+        self
+    }
+
+    pub fn with_event_time(mut self, time: SystemTime) -> Self {
+        self.event_time_ns = Some(system_time_to_ns(time));
+        self
+    }
+
+    pub fn with_event_time_ns(mut self, event_time_ns: u64) -> Self {
+        self.event_time_ns = Some(event_time_ns);
+        self
+    }
+
+    pub fn ingest_time(&self) -> SystemTime {
+        ns_to_system_time(self.ingest_time_ns)
+    }
+
+    pub fn event_time(&self) -> Option<SystemTime> {
+        self.event_time_ns.map(ns_to_system_time)
+    }
+
+    // 事件时间，未设置时使用进入管道的时间
+    pub fn time_ns(&self) -> u64 {
+        self.event_time_ns.unwrap_or(self.ingest_time_ns)
+    }
+
+    // 进入管道至今的时间，用于测量延迟
+    pub fn age(&self) -> Duration {
+        Duration::from_nanos(now_ns().saturating_sub(self.ingest_time_ns))
+    }
+
+    pub fn with_attribute<V: Into<AttributeValue>>(mut self, name: &str, value: V) -> Self {
+        self.set_attribute(name, value);
+        self
+    }
+
+    pub fn with_key(mut self, key: &str) -> Self {
+        self.key = Some(key.into());
+        self
+    }
+
+    pub fn with_source_id(mut self, source_id: &str) -> Self {
+        self.source_id = Some(source_id.into());
+        self
+    }
+
+    // 替换负载
+    pub fn set_data(&mut self, data: impl Into<Bytes>) {
+        self.data = data.into();
+    }
+
+    // 原地修改负载：没有其他数据块共享时直接复用缓冲区，否则先复制一份
+    pub fn modify_data<R>(&mut self, f: impl FnOnce(&mut BytesMut) -> R) -> R {
+        let mut buffer = BytesMut::from(std::mem::take(&mut self.data));
+        let result = f(&mut buffer);
+        self.data = buffer.freeze();
+        result
+    }
+
+    pub fn set_attribute<V: Into<AttributeValue>>(&mut self, name: &str, value: V) {
+        Arc::make_mut(&mut self.attributes).insert(name.to_string(), value.into());
+    }
+
+    pub fn attribute(&self, name: &str) -> Option<&AttributeValue> {
+        self.attributes.get(name)
+    }
+
+    // 字符串属性的快捷读取
+    pub fn attribute_str(&self, name: &str) -> Option<&str> {
+        self.attribute(name).and_then(AttributeValue::as_str)
+    }
+
+    pub fn remove_attribute(&mut self, name: &str) -> Option<AttributeValue> {
+        if !self.attributes.contains_key(name) {
+            return None;
+        }
+        Arc::make_mut(&mut self.attributes).remove(name)
+    }
+}
@@ -0,0 +1,117 @@
+use super::{ComponentConfig, ComponentRegistry, PipelineConfig};
+use crate::pipeline::ErrorAction;
+use crate::pipeline::topology::{self, NodeDecl, NodeKind};
+use std::fmt::Write;
+
+// 生成 Graphviz DOT 格式的管道拓扑，使用内置组件注册表识别路由器
+pub fn to_dot(config: &PipelineConfig) -> String {
+    to_dot_with_registry(config, &ComponentRegistry::with_builtins())
+}
+
+// 生成 Graphviz DOT 格式的管道拓扑：按 inputs 连接，未声明时为 数据源 → 处理器链 → 输出端
+pub fn to_dot_with_registry(config: &PipelineConfig, registry: &ComponentRegistry) -> String {
+    let mut dot = String::new();
+    let _ = writeln!(dot, "digraph {} {{", quote(&config.name));
+    let _ = writeln!(dot, "    rankdir=LR;");
+    let _ = writeln!(dot, "    node [fontname=\"Helvetica\"];");
+
+    write_nodes(&mut dot, "source", &config.sources, |_| ("ellipse", "#c6e2ff"));
+    write_nodes(&mut dot, "processor", &config.processors, |c| {
+        if registry.is_router(&c.component_type) {
+            ("diamond", "#ffd9b3")
+        } else {
+            ("box", "#fff2b3")
+        }
+    });
+    write_nodes(&mut dot, "sink", &config.sinks, |_| ("cylinder", "#c8f0c8"));
+    write_nodes(&mut dot, "dead_letter", config.dead_letter.as_slice(), |_| ("cylinder", "#f4b6b6"));
+
+    // 声明顺序与 topology::resolve 的输入一致：数据源、处理器、输出端
+    let mut ids = Vec::new();
+    let mut decls = Vec::new();
+    for (prefix, kind, components) in [
+        ("source", 0, &config.sources),
+        ("processor", 1, &config.processors),
+        ("sink", 2, &config.sinks),
+    ] {
+        for (i, component) in components.iter().enumerate() {
+            let node_kind = match kind {
+                0 => NodeKind::Source,
+                2 => NodeKind::Sink,
+                _ if registry.is_router(&component.component_type) => NodeKind::Router(
+                    registry.create_router(component).map(|r| r.outputs()).unwrap_or_default(),
+                ),
+                _ => NodeKind::Processor(
+                    registry.create_processor(component).map(|p| p.side_outputs()).unwrap_or_default(),
+                ),
+            };
+            ids.push(node_id(prefix, i));
+            decls.push(NodeDecl::new(&component.name, node_kind, component.inputs.clone()));
+        }
+    }
+
+    match topology::resolve(&decls) {
+        Ok(edges) => {
+            for edge in edges {
+                match decls[edge.from].output_name(edge.port) {
+                    Some(output) => {
+                        let _ = writeln!(
+                            dot,
+                            "    {} -> {} [label=\"{}\"];",
+                            ids[edge.from], ids[edge.to], escape(output)
+                        );
+                    }
+                    None => {
+                        let _ = writeln!(dot, "    {} -> {};", ids[edge.from], ids[edge.to]);
+                    }
+                }
+            }
+        }
+        Err(e) => {
+            let _ = writeln!(dot, "    // 拓扑无效: {}", e);
+        }
+    }
+
+    // 错误策略为 dead_letter 的组件用虚线连到死信输出端
+    if config.dead_letter.is_some() {
+        for (i, (_, component)) in config.components().enumerate().take(ids.len()) {
+            if component.on_error.is_some_and(|policy| policy.action == ErrorAction::DeadLetter) {
+                let _ = writeln!(
+                    dot,
+                    "    {} -> {} [style=dashed, color=\"#c0392b\", label=\"on_error\"];",
+                    ids[i], node_id("dead_letter", 0)
+                );
+            }
+        }
+    }
+
+    let _ = writeln!(dot, "}}");
+    dot
+}
+
+fn write_nodes<F>(dot: &mut String, prefix: &str, components: &[ComponentConfig], style: F)
+where
+    F: Fn(&ComponentConfig) -> (&'static str, &'static str),
+{
+    for (i, component) in components.iter().enumerate() {
+        let label = format!("{}\\n({})", escape(&component.name), escape(&component.component_type));
+        let (shape, color) = style(component);
+        let _ = writeln!(
+            dot,
+            "    {} [label=\"{}\", shape={}, style=filled, fillcolor=\"{}\"];",
+            node_id(prefix, i), label, shape, color
+        );
+    }
+}
+
+fn node_id(prefix: &str, index: usize) -> String {
+    format!("{}_{}", prefix, index)
+}
+
+fn quote(s: &str) -> String {
+    format!("\"{}\"", escape(s))
+}
+
+fn escape(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
+}
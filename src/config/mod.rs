@@ -1,17 +1,103 @@
+pub mod graph;
+pub mod registry;
+
+pub use registry::{ComponentRegistry, ParamKind, ParamSpec};
+
+use crate::pipeline::{ErrorAction, ErrorPolicy, OverflowPolicy, WatermarkStrategy};
+
+use base64::Engine;
 use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::Path;
 use thiserror::Error;
 
 #[derive(Debug, Error)]
 pub enum ConfigError {
     #[error("配置错误: {0}")]
     ConfigError(String),
+    #[error("配置解析失败: {0}")]
+    ParseError(String),
+    #[error("无法读取配置文件 {path}: {reason}")]
+    IoError { path: String, reason: String },
+    #[error("{kind} '{name}' 使用了未注册的类型 '{component_type}'")]
+    UnknownType {
+        kind: ComponentKind,
+        name: String,
+        component_type: String,
+    },
+    #[error("{kind} '{name}' 的参数 '{param}' 无效: {reason}")]
+    InvalidParam {
+        kind: ComponentKind,
+        name: String,
+        param: String,
+        reason: String,
+    },
+    #[error("{kind} '{name}' 创建失败: {reason}")]
+    BuildError {
+        kind: ComponentKind,
+        name: String,
+        reason: String,
+    },
+}
+
+// 组件类别，用于错误信息和注册表
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ComponentKind {
+    Source,
+    Processor,
+    Sink,
+}
+
+impl fmt::Display for ComponentKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ComponentKind::Source => write!(f, "数据源"),
+            ComponentKind::Processor => write!(f, "处理器"),
+            ComponentKind::Sink => write!(f, "输出端"),
+        }
+    }
+}
+
+fn default_max_queue_size() -> usize {
+    1000
+}
+
+fn default_workers() -> usize {
+    1
 }
 
-// 简化的配置结构
+// 管道配置 - 对应 configs/pipeline_config 的完整文档
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PipelineConfig {
     pub name: String,
+    #[serde(default)]
     pub description: Option<String>,
+    #[serde(default = "default_max_queue_size")]
+    pub max_queue_size: usize,
+    // 并行处理的工作任务数量
+    #[serde(default = "default_workers")]
+    pub workers: usize,
+    // 是否按进入管道的顺序投递到输出端
+    #[serde(default)]
+    pub ordered: bool,
+    #[serde(default)]
+    pub timeout_seconds: Option<u64>,
+    #[serde(default)]
+    pub drain_timeout_seconds: Option<u64>,
+    #[serde(default)]
+    pub handle_signals: bool,
+    // 水位线检查和广播的间隔（毫秒），默认 200
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub watermark_interval_ms: Option<u64>,
+    #[serde(default)]
+    pub sources: Vec<ComponentConfig>,
+    #[serde(default)]
+    pub processors: Vec<ComponentConfig>,
+    #[serde(default)]
+    pub sinks: Vec<ComponentConfig>,
+    // 死信输出端：接收 on_error 为 dead_letter 的组件未能处理的数据块
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dead_letter: Option<ComponentConfig>,
 }
 
 impl PipelineConfig {
@@ -19,6 +105,17 @@ impl PipelineConfig {
         Self {
             name: name.to_string(),
             description: None,
+            max_queue_size: default_max_queue_size(),
+            workers: default_workers(),
+            ordered: false,
+            timeout_seconds: None,
+            drain_timeout_seconds: None,
+            handle_signals: false,
+            watermark_interval_ms: None,
+            sources: Vec::new(),
+            processors: Vec::new(),
+            sinks: Vec::new(),
+            dead_letter: None,
         }
     }
 
@@ -26,4 +123,281 @@ impl PipelineConfig {
         self.description = Some(description.to_string());
         self
     }
-}
\ No newline at end of file
+
+    pub fn add_source(mut self, source: ComponentConfig) -> Self {
+        self.sources.push(source);
+        self
+    }
+
+    pub fn add_processor(mut self, processor: ComponentConfig) -> Self {
+        self.processors.push(processor);
+        self
+    }
+
+    pub fn add_sink(mut self, sink: ComponentConfig) -> Self {
+        self.sinks.push(sink);
+        self
+    }
+
+    pub fn with_dead_letter(mut self, sink: ComponentConfig) -> Self {
+        self.dead_letter = Some(sink);
+        self
+    }
+
+    pub fn from_yaml(content: &str) -> Result<Self, ConfigError> {
+        serde_yaml::from_str(content).map_err(|e| ConfigError::ParseError(e.to_string()))
+    }
+
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, ConfigError> {
+        let path = path.as_ref();
+        let content = std::fs::read_to_string(path).map_err(|e| ConfigError::IoError {
+            path: path.display().to_string(),
+            reason: e.to_string(),
+        })?;
+        Self::from_yaml(&content)
+    }
+
+    // Graphviz DOT 格式的拓扑图
+    pub fn to_dot(&self) -> String {
+        graph::to_dot(self)
+    }
+
+    // 使用自定义注册表识别路由器
+    pub fn to_dot_with_registry(&self, registry: &ComponentRegistry) -> String {
+        graph::to_dot_with_registry(self, registry)
+    }
+
+    // 遍历所有组件配置及其类别
+    pub fn components(&self) -> impl Iterator<Item = (ComponentKind, &ComponentConfig)> {
+        self.sources.iter().map(|c| (ComponentKind::Source, c))
+            .chain(self.processors.iter().map(|c| (ComponentKind::Processor, c)))
+            .chain(self.sinks.iter().map(|c| (ComponentKind::Sink, c)))
+            .chain(self.dead_letter.iter().map(|c| (ComponentKind::Sink, c)))
+    }
+
+    // 结构检查：至少一个数据源，组件名称不能重复，队列设置只用于输出端，
+    // 使用 dead_letter 错误策略时需要配置死信输出端
+    pub fn check_structure(&self) -> Result<(), ConfigError> {
+        if self.sources.is_empty() {
+            return Err(ConfigError::ConfigError(format!(
+                "管道 '{}' 至少需要一个数据源", self.name
+            )));
+        }
+        if self.max_queue_size == 0 {
+            return Err(ConfigError::ConfigError("max_queue_size 必须大于 0".to_string()));
+        }
+        if self.workers == 0 {
+            return Err(ConfigError::ConfigError("workers 必须大于 0".to_string()));
+        }
+
+        let mut seen = std::collections::HashSet::new();
+        for (kind, component) in self.components() {
+            if !seen.insert(component.name.as_str()) {
+                return Err(ConfigError::ConfigError(format!(
+                    "{} '{}' 与其他组件重名", kind, component.name
+                )));
+            }
+            if kind != ComponentKind::Sink && (component.queue_size.is_some() || component.overflow.is_some()) {
+                return Err(ConfigError::ConfigError(format!(
+                    "{} '{}': queue_size 和 overflow 只能用于输出端", kind, component.name
+                )));
+            }
+            if kind != ComponentKind::Source && component.watermark.is_some() {
+                return Err(ConfigError::ConfigError(format!(
+                    "{} '{}': watermark 只能用于数据源", kind, component.name
+                )));
+            }
+            if component.queue_size == Some(0) {
+                return Err(ConfigError::ConfigError(format!(
+                    "{} '{}': queue_size 必须大于 0", kind, component.name
+                )));
+            }
+            if component.on_error.is_some_and(|policy| policy.action == ErrorAction::DeadLetter) {
+                if kind == ComponentKind::Source {
+                    return Err(ConfigError::ConfigError(format!(
+                        "{} '{}': 数据源不支持 on_error: dead_letter", kind, component.name
+                    )));
+                }
+                if self.dead_letter.is_none() {
+                    return Err(ConfigError::ConfigError(format!(
+                        "{} '{}': on_error 为 dead_letter，但管道没有配置 dead_letter 输出端", kind, component.name
+                    )));
+                }
+            }
+        }
+
+        if let Some(dead_letter) = &self.dead_letter {
+            if dead_letter.inputs.is_some() {
+                return Err(ConfigError::ConfigError(format!(
+                    "死信输出端 '{}' 不能声明 inputs", dead_letter.name
+                )));
+            }
+            if dead_letter.on_error.is_some_and(|policy| policy.action == ErrorAction::DeadLetter) {
+                return Err(ConfigError::ConfigError(format!(
+                    "死信输出端 '{}' 的 on_error 不能为 dead_letter", dead_letter.name
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+// 单个组件的配置：名称、注册类型和参数
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ComponentConfig {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub component_type: String,
+    // 处理器和输出端的上游节点名，路由器输出写成 "路由器名.输出名"；省略时按线性方式连接
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub inputs: Option<Vec<String>>,
+    #[serde(default)]
+    pub params: serde_yaml::Mapping,
+    // 仅输出端：队列容量（默认使用 max_queue_size）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub queue_size: Option<usize>,
+    // 仅输出端：队列已满时的处理方式 block / drop_newest / drop_oldest
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub overflow: Option<OverflowPolicy>,
+    // 出错时的策略 drop / retry / dead_letter / fail，或 { action, retries, backoff_ms, max_backoff_ms }
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub on_error: Option<ErrorPolicy>,
+    // 仅数据源：水位线策略 { max_out_of_order_ms, idle_timeout_ms }
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub watermark: Option<WatermarkStrategy>,
+}
+
+impl ComponentConfig {
+    pub fn new(name: &str, component_type: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            component_type: component_type.to_string(),
+            inputs: None,
+            params: serde_yaml::Mapping::new(),
+            queue_size: None,
+            overflow: None,
+            on_error: None,
+            watermark: None,
+        }
+    }
+
+    pub fn with_inputs(mut self, inputs: &[&str]) -> Self {
+        self.inputs = Some(inputs.iter().map(|input| input.to_string()).collect());
+        self
+    }
+
+    pub fn with_queue_size(mut self, queue_size: usize) -> Self {
+        self.queue_size = Some(queue_size);
+        self
+    }
+
+    pub fn with_overflow(mut self, overflow: OverflowPolicy) -> Self {
+        self.overflow = Some(overflow);
+        self
+    }
+
+    pub fn with_on_error(mut self, policy: ErrorPolicy) -> Self {
+        self.on_error = Some(policy);
+        self
+    }
+
+    pub fn with_watermark(mut self, strategy: WatermarkStrategy) -> Self {
+        self.watermark = Some(strategy);
+        self
+    }
+
+    pub fn with_param<V: Into<serde_yaml::Value>>(mut self, key: &str, value: V) -> Self {
+        self.params.insert(serde_yaml::Value::String(key.to_string()), value.into());
+        self
+    }
+
+    pub fn param(&self, key: &str) -> Option<&serde_yaml::Value> {
+        self.params.get(key).filter(|v| !v.is_null())
+    }
+
+    // 构造指向本组件某个参数的错误
+    pub fn invalid_param(&self, kind: ComponentKind, param: &str, reason: impl Into<String>) -> ConfigError {
+        ConfigError::InvalidParam {
+            kind,
+            name: self.name.clone(),
+            param: param.to_string(),
+            reason: reason.into(),
+        }
+    }
+
+    pub fn get_str(&self, kind: ComponentKind, key: &str) -> Result<Option<String>, ConfigError> {
+        match self.param(key) {
+            None => Ok(None),
+            Some(serde_yaml::Value::String(s)) => Ok(Some(s.clone())),
+            Some(_) => Err(self.invalid_param(kind, key, "应为字符串")),
+        }
+    }
+
+    pub fn get_u64(&self, kind: ComponentKind, key: &str) -> Result<Option<u64>, ConfigError> {
+        match self.param(key) {
+            None => Ok(None),
+            Some(value) => value
+                .as_u64()
+                .map(Some)
+                .ok_or_else(|| self.invalid_param(kind, key, "应为非负整数")),
+        }
+    }
+
+    // 整数或小数
+    pub fn get_f64(&self, kind: ComponentKind, key: &str) -> Result<Option<f64>, ConfigError> {
+        match self.param(key) {
+            None => Ok(None),
+            Some(value) => value
+                .as_f64()
+                .map(Some)
+                .ok_or_else(|| self.invalid_param(kind, key, "应为数值")),
+        }
+    }
+
+    pub fn get_bool(&self, kind: ComponentKind, key: &str) -> Result<Option<bool>, ConfigError> {
+        match self.param(key) {
+            None => Ok(None),
+            Some(value) => value
+                .as_bool()
+                .map(Some)
+                .ok_or_else(|| self.invalid_param(kind, key, "应为布尔值")),
+        }
+    }
+
+    pub fn get_str_list(&self, kind: ComponentKind, key: &str) -> Result<Option<Vec<String>>, ConfigError> {
+        match self.param(key) {
+            None => Ok(None),
+            Some(serde_yaml::Value::Sequence(items)) => items
+                .iter()
+                .map(|item| {
+                    item.as_str()
+                        .map(str::to_string)
+                        .ok_or_else(|| self.invalid_param(kind, key, "列表元素应为字符串"))
+                })
+                .collect::<Result<Vec<_>, _>>()
+                .map(Some),
+            Some(_) => Err(self.invalid_param(kind, key, "应为字符串列表")),
+        }
+    }
+
+    // base64 编码的二进制参数（例如密钥）
+    pub fn get_base64(&self, kind: ComponentKind, key: &str) -> Result<Option<Vec<u8>>, ConfigError> {
+        match self.get_str(kind, key)? {
+            None => Ok(None),
+            Some(encoded) => base64::engine::general_purpose::STANDARD
+                .decode(encoded.trim())
+                .map(Some)
+                .map_err(|e| self.invalid_param(kind, key, format!("base64 解码失败: {}", e))),
+        }
+    }
+
+    pub fn require_str(&self, kind: ComponentKind, key: &str) -> Result<String, ConfigError> {
+        self.get_str(kind, key)?
+            .ok_or_else(|| self.invalid_param(kind, key, "缺少必填参数"))
+    }
+
+    pub fn require_base64(&self, kind: ComponentKind, key: &str) -> Result<Vec<u8>, ConfigError> {
+        self.get_base64(kind, key)?
+            .ok_or_else(|| self.invalid_param(kind, key, "缺少必填参数"))
+    }
+}
@@ -0,0 +1,759 @@
+use super::{ComponentConfig, ComponentKind, ConfigError};
+use crate::processors::{
+    DecryptionProcessor, EncryptionProcessor, FilterProcessor, GapDetectionProcessor, IPAnalysisProcessor,
+    PacketDecodeProcessor, PacketStatsProcessor, Processor, Router, ThreatDetectionProcessor, UpperCaseProcessor,
+    WindowProcessor,
+};
+use crate::packet::{BpfProgram, CaptureFormat, LinkType};
+use crate::security::{
+    EncryptionAlgorithm, IntegrityAlgorithm, KeyRing, KeyRingConfig, KeySource, SecretKey, SecurityConfig, Zeroizing,
+};
+use crate::sinks::{
+    AuditLogSink, ConsoleSink, JsonLinesSink, MetricsSink, PcapFileSink, SecureFileSink, Sink, StatsSink,
+};
+use crate::sources::{
+    JsonLinesSource, MemorySource, MockPacketSource, PacketCaptureSource, PcapFileSource, RepeatingSource, ReplaySource,
+    Source,
+};
+use crate::window::{AggregateSpec, Field, LatePolicy, TimeDomain, WindowKind};
+use ed25519_dalek::SigningKey;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+
+pub type SourceFactory =
+    Arc<dyn Fn(&ComponentConfig) -> Result<Box<dyn Source>, ConfigError> + Send + Sync>;
+pub type ProcessorFactory =
+    Arc<dyn Fn(&ComponentConfig) -> Result<Box<dyn Processor>, ConfigError> + Send + Sync>;
+pub type SinkFactory =
+    Arc<dyn Fn(&ComponentConfig) -> Result<Box<dyn Sink>, ConfigError> + Send + Sync>;
+pub type RouterFactory =
+    Arc<dyn Fn(&ComponentConfig) -> Result<Router, ConfigError> + Send + Sync>;
+
+// 参数类型，仅用于描述和展示
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParamKind {
+    String,
+    Integer,
+    Number,
+    Bool,
+    StringList,
+    Base64,
+    Mapping,
+}
+
+impl fmt::Display for ParamKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            ParamKind::String => "string",
+            ParamKind::Integer => "integer",
+            ParamKind::Number => "number",
+            ParamKind::Bool => "bool",
+            ParamKind::StringList => "list<string>",
+            ParamKind::Base64 => "base64",
+            ParamKind::Mapping => "map",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+// 组件参数说明，注册时声明，构建时用于拒绝未知参数
+#[derive(Debug, Clone)]
+pub struct ParamSpec {
+    pub name: &'static str,
+    pub kind: ParamKind,
+    pub required: bool,
+    pub description: &'static str,
+}
+
+impl ParamSpec {
+    pub fn required(name: &'static str, kind: ParamKind, description: &'static str) -> Self {
+        Self { name, kind, required: true, description }
+    }
+
+    pub fn optional(name: &'static str, kind: ParamKind, description: &'static str) -> Self {
+        Self { name, kind, required: false, description }
+    }
+}
+
+struct Registration<F> {
+    params: Vec<ParamSpec>,
+    factory: F,
+}
+
+// 组件注册表 - 按配置中的 `type` 查找工厂函数
+// 第三方 crate 可以通过 register_* 注册自己的组件
+#[derive(Default)]
+pub struct ComponentRegistry {
+    sources: BTreeMap<String, Registration<SourceFactory>>,
+    processors: BTreeMap<String, Registration<ProcessorFactory>>,
+    sinks: BTreeMap<String, Registration<SinkFactory>>,
+    // 路由器在配置中写在 processors 下，但产生多个命名输出
+    routers: BTreeMap<String, Registration<RouterFactory>>,
+}
+
+impl ComponentRegistry {
+    // 空注册表
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // 包含框架内置组件的注册表
+    pub fn with_builtins() -> Self {
+        let mut registry = Self::new();
+        register_builtin_sources(&mut registry);
+        register_builtin_processors(&mut registry);
+        register_builtin_sinks(&mut registry);
+        registry
+    }
+
+    pub fn register_source<F>(&mut self, type_name: &str, params: Vec<ParamSpec>, factory: F) -> &mut Self
+    where
+        F: Fn(&ComponentConfig) -> Result<Box<dyn Source>, ConfigError> + Send + Sync + 'static,
+    {
+        self.sources.insert(type_name.to_string(), Registration { params, factory: Arc::new(factory) });
+        self
+    }
+
+    pub fn register_processor<F>(&mut self, type_name: &str, params: Vec<ParamSpec>, factory: F) -> &mut Self
+    where
+        F: Fn(&ComponentConfig) -> Result<Box<dyn Processor>, ConfigError> + Send + Sync + 'static,
+    {
+        self.processors.insert(type_name.to_string(), Registration { params, factory: Arc::new(factory) });
+        self
+    }
+
+    pub fn register_sink<F>(&mut self, type_name: &str, params: Vec<ParamSpec>, factory: F) -> &mut Self
+    where
+        F: Fn(&ComponentConfig) -> Result<Box<dyn Sink>, ConfigError> + Send + Sync + 'static,
+    {
+        self.sinks.insert(type_name.to_string(), Registration { params, factory: Arc::new(factory) });
+        self
+    }
+
+    pub fn register_router<F>(&mut self, type_name: &str, params: Vec<ParamSpec>, factory: F) -> &mut Self
+    where
+        F: Fn(&ComponentConfig) -> Result<Router, ConfigError> + Send + Sync + 'static,
+    {
+        self.routers.insert(type_name.to_string(), Registration { params, factory: Arc::new(factory) });
+        self
+    }
+
+    // 该类型是否注册为路由器
+    pub fn is_router(&self, type_name: &str) -> bool {
+        self.routers.contains_key(type_name)
+    }
+
+    pub fn create_source(&self, config: &ComponentConfig) -> Result<Box<dyn Source>, ConfigError> {
+        let registration = lookup(&self.sources, ComponentKind::Source, config)?;
+        (registration.factory)(config)
+    }
+
+    pub fn create_processor(&self, config: &ComponentConfig) -> Result<Box<dyn Processor>, ConfigError> {
+        let registration = lookup(&self.processors, ComponentKind::Processor, config)?;
+        (registration.factory)(config)
+    }
+
+    // 返回可重复创建同一处理器的闭包，供并行工作任务各自持有实例
+    pub fn processor_factory(
+        &self,
+        config: &ComponentConfig,
+    ) -> Result<impl Fn() -> Result<Box<dyn Processor>, ConfigError> + Send + Sync + 'static, ConfigError> {
+        let factory = lookup(&self.processors, ComponentKind::Processor, config)?.factory.clone();
+        let config = config.clone();
+        Ok(move || factory(&config))
+    }
+
+    pub fn create_sink(&self, config: &ComponentConfig) -> Result<Box<dyn Sink>, ConfigError> {
+        let registration = lookup(&self.sinks, ComponentKind::Sink, config)?;
+        (registration.factory)(config)
+    }
+
+    pub fn create_router(&self, config: &ComponentConfig) -> Result<Router, ConfigError> {
+        let registration = lookup(&self.routers, ComponentKind::Processor, config)?;
+        (registration.factory)(config)
+    }
+
+    // 按类别列出已注册的类型及其参数说明
+    pub fn component_types(&self, kind: ComponentKind) -> Vec<(&str, &[ParamSpec])> {
+        match kind {
+            ComponentKind::Source => describe(&self.sources),
+            ComponentKind::Processor => {
+                let mut types = describe(&self.processors);
+                types.extend(describe(&self.routers));
+                types.sort_by_key(|(name, _)| *name);
+                types
+            }
+            ComponentKind::Sink => describe(&self.sinks),
+        }
+    }
+}
+
+fn describe<F>(map: &BTreeMap<String, Registration<F>>) -> Vec<(&str, &[ParamSpec])> {
+    map.iter()
+        .map(|(name, registration)| (name.as_str(), registration.params.as_slice()))
+        .collect()
+}
+
+// 查找注册项并校验参数名和必填参数
+fn lookup<'a, F>(
+    map: &'a BTreeMap<String, Registration<F>>,
+    kind: ComponentKind,
+    config: &ComponentConfig,
+) -> Result<&'a Registration<F>, ConfigError> {
+    let registration = map.get(&config.component_type).ok_or_else(|| ConfigError::UnknownType {
+        kind,
+        name: config.name.clone(),
+        component_type: config.component_type.clone(),
+    })?;
+
+    for key in config.params.keys() {
+        let key = key
+            .as_str()
+            .ok_or_else(|| config.invalid_param(kind, &format!("{:?}", key), "参数名必须是字符串"))?;
+        if !registration.params.iter().any(|spec| spec.name == key) {
+            return Err(config.invalid_param(kind, key, "未知参数"));
+        }
+    }
+
+    for spec in registration.params.iter().filter(|spec| spec.required) {
+        if config.param(spec.name).is_none() {
+            return Err(config.invalid_param(kind, spec.name, "缺少必填参数"));
+        }
+    }
+
+    Ok(registration)
+}
+
+fn register_builtin_sources(registry: &mut ComponentRegistry) {
+    use ComponentKind::Source as K;
+
+    registry.register_source(
+        "MemorySource",
+        vec![
+            ParamSpec::required("data", ParamKind::StringList, "依次发送的数据"),
+            ParamSpec::optional("delay_ms", ParamKind::Integer, "每条数据之间的延迟（毫秒），默认 10"),
+        ],
+        |config| {
+            let data = config
+                .get_str_list(K, "data")?
+                .unwrap_or_default()
+                .into_iter()
+                .map(String::into_bytes)
+                .collect();
+            let mut source = MemorySource::new(data).with_name(&config.name);
+            if let Some(delay_ms) = config.get_u64(K, "delay_ms")? {
+                source = source.with_delay(delay_ms);
+            }
+            Ok(Box::new(source))
+        },
+    );
+
+    registry.register_source(
+        "RepeatingSource",
+        vec![
+            ParamSpec::required("template", ParamKind::String, "数据模板"),
+            ParamSpec::required("count", ParamKind::Integer, "生成数量"),
+            ParamSpec::optional("delay_ms", ParamKind::Integer, "每条数据之间的延迟（毫秒），默认 0"),
+        ],
+        |config| {
+            let template = config.require_str(K, "template")?;
+            let count = config.get_u64(K, "count")?.unwrap_or_default();
+            let delay_ms = config.get_u64(K, "delay_ms")?.unwrap_or(0);
+            Ok(Box::new(RepeatingSource::new(template.into_bytes(), count as usize, delay_ms)))
+        },
+    );
+
+    registry.register_source(
+        "MockPacketSource",
+        vec![
+            ParamSpec::optional("packet_count", ParamKind::Integer, "生成的数据包数量，默认 1000"),
+            ParamSpec::optional("delay_ms", ParamKind::Integer, "每个数据包之间的延迟（毫秒），默认 1"),
+            ParamSpec::optional("raw_frames", ParamKind::Bool, "生成 Ethernet/IPv4 原始帧而不是文本，默认 false"),
+        ],
+        |config| {
+            let packet_count = config.get_u64(K, "packet_count")?.unwrap_or(1000);
+            let mut source = MockPacketSource::new(packet_count as usize);
+            if let Some(delay_ms) = config.get_u64(K, "delay_ms")? {
+                source = source.with_delay(delay_ms);
+            }
+            if let Some(raw_frames) = config.get_bool(K, "raw_frames")? {
+                source = source.with_raw_frames(raw_frames);
+            }
+            Ok(Box::new(source))
+        },
+    );
+
+    registry.register_source(
+        "PacketCaptureSource",
+        vec![
+            ParamSpec::required("device", ParamKind::String, "网卡名称"),
+            ParamSpec::optional("filter", ParamKind::String, "过滤表达式，例如 tcp port 80 and not host 10.0.0.1"),
+            ParamSpec::optional("packet_count", ParamKind::Integer, "抓到指定数量后结束，默认（或 0）一直抓包"),
+            ParamSpec::optional("snaplen", ParamKind::Integer, "每个数据包最多保留的字节数，默认 65535"),
+            ParamSpec::optional("promiscuous", ParamKind::Bool, "是否开启混杂模式，默认 true"),
+            ParamSpec::optional("buffer_mb", ParamKind::Integer, "内核接收环大小（MiB），默认 16"),
+        ],
+        |config| {
+            let mut source = PacketCaptureSource::new(&config.require_str(K, "device")?).with_name(&config.name);
+            if let Some(filter) = config.get_str(K, "filter")? {
+                // 提前检查语法，校验配置时即可发现错误
+                BpfProgram::compile(&filter, LinkType::Ethernet, 65535)
+                    .map_err(|e| config.invalid_param(K, "filter", e.to_string()))?;
+                source = source.with_filter(&filter);
+            }
+            if let Some(count) = config.get_u64(K, "packet_count")?.filter(|count| *count > 0) {
+                source = source.with_packet_count(count as usize);
+            }
+            if let Some(snaplen) = config.get_u64(K, "snaplen")? {
+                let snaplen = u32::try_from(snaplen).ok().filter(|snaplen| *snaplen > 0)
+                    .ok_or_else(|| config.invalid_param(K, "snaplen", "应为 1 到 4294967295 之间的整数"))?;
+                source = source.with_snaplen(snaplen);
+            }
+            if let Some(promiscuous) = config.get_bool(K, "promiscuous")? {
+                source = source.with_promiscuous(promiscuous);
+            }
+            if let Some(buffer_mb) = config.get_u64(K, "buffer_mb")? {
+                source = source.with_buffer_size((buffer_mb.max(1) as usize) << 20);
+            }
+            Ok(Box::new(source))
+        },
+    );
+
+    registry.register_source(
+        "PcapFileSource",
+        vec![
+            ParamSpec::required("file_path", ParamKind::String, "pcap 或 pcapng 文件路径（按文件头识别格式）"),
+            ParamSpec::optional("limit", ParamKind::Integer, "最多读取的数据包数量，默认全部"),
+        ],
+        |config| {
+            let mut source = PcapFileSource::new(&config.require_str(K, "file_path")?).with_name(&config.name);
+            if let Some(limit) = config.get_u64(K, "limit")? {
+                source = source.with_limit(limit);
+            }
+            Ok(Box::new(source))
+        },
+    );
+
+    registry.register_source(
+        "JsonLinesSource",
+        vec![ParamSpec::required("file_path", ParamKind::String, "JsonLinesSink 或死信输出端写出的文件")],
+        |config| Ok(Box::new(JsonLinesSource::new(&config.require_str(K, "file_path")?).with_name(&config.name))),
+    );
+
+    registry.register_source(
+        "ReplaySource",
+        vec![
+            ParamSpec::required("file_path", ParamKind::String, "要重放的抓包文件或 JSON Lines 文件"),
+            ParamSpec::optional("format", ParamKind::String, "pcap 或 jsonl，默认按扩展名判断（.jsonl/.json 为 jsonl）"),
+            ParamSpec::optional("speed", ParamKind::Number, "相对录制速度的倍数，默认 1；max 表示不等待"),
+            ParamSpec::optional("loops", ParamKind::Integer, "重放遍数，默认 1；0 表示一直循环"),
+            ParamSpec::optional("start_offset_ms", ParamKind::Integer, "从第一个数据块之后多少毫秒开始重放"),
+            ParamSpec::optional("end_offset_ms", ParamKind::Integer, "重放到第一个数据块之后多少毫秒为止"),
+            ParamSpec::optional("max_gap_ms", ParamKind::Integer, "录制中较长的空闲间隔按此值等待"),
+            ParamSpec::optional("retime", ParamKind::Bool, "把事件时间改写为重放时刻，默认 false"),
+        ],
+        |config| {
+            let file_path = config.require_str(K, "file_path")?;
+            let jsonl = match config.get_str(K, "format")?.as_deref() {
+                None => file_path.ends_with(".jsonl") || file_path.ends_with(".json"),
+                Some("pcap") => false,
+                Some("jsonl") => true,
+                Some(other) => {
+                    return Err(config.invalid_param(K, "format", format!("应为 pcap 或 jsonl，实际为 '{}'", other)))
+                }
+            };
+            let inner: Box<dyn Source> = if jsonl {
+                Box::new(JsonLinesSource::new(&file_path).with_name(&config.name))
+            } else {
+                Box::new(PcapFileSource::new(&file_path).with_name(&config.name))
+            };
+
+            let speed = match config.param("speed") {
+                None => Some(1.0),
+                Some(serde_yaml::Value::String(s)) if s == "max" => None,
+                Some(_) => match config.get_f64(K, "speed")? {
+                    Some(speed) if speed.is_finite() && speed > 0.0 => Some(speed),
+                    _ => return Err(config.invalid_param(K, "speed", "应为大于 0 的数值或 max")),
+                },
+            };
+            let millis = |key: &str| -> Result<Option<Duration>, ConfigError> {
+                Ok(config.get_u64(K, key)?.map(Duration::from_millis))
+            };
+
+            let mut source = ReplaySource::new(inner)
+                .with_name(&config.name)
+                .with_speed(speed)
+                .with_range(millis("start_offset_ms")?, millis("end_offset_ms")?);
+            if let Some(loops) = config.get_u64(K, "loops")? {
+                source = source.with_passes((loops > 0).then_some(loops));
+            }
+            if let Some(max_gap) = millis("max_gap_ms")? {
+                source = source.with_max_gap(max_gap);
+            }
+            if let Some(retime) = config.get_bool(K, "retime")? {
+                source = source.with_retime(retime);
+            }
+            Ok(Box::new(source))
+        },
+    );
+}
+
+fn register_builtin_processors(registry: &mut ComponentRegistry) {
+    use ComponentKind::Processor as K;
+
+    registry.register_processor(
+        "FilterProcessor",
+        vec![ParamSpec::required("pattern", ParamKind::String, "只保留包含该模式的数据")],
+        |config| {
+            let pattern = config.require_str(K, "pattern")?;
+            Ok(Box::new(FilterProcessor::new(pattern.into_bytes())))
+        },
+    );
+
+    registry.register_processor("UpperCaseProcessor", vec![], |_| Ok(Box::new(UpperCaseProcessor::new())));
+    registry.register_router(
+        "Router",
+        vec![ParamSpec::required(
+            "routes",
+            ParamKind::Mapping,
+            "输出名 → 条件 (attribute, has_attribute, key, source, contains, contains_any, starts_with, min_length, max_length, all, any, not)",
+        )],
+        Router::from_config,
+    );
+    registry.register_processor("PacketStatsProcessor", vec![], |_| Ok(Box::new(PacketStatsProcessor::new())));
+    registry.register_processor("IPAnalysisProcessor", vec![], |_| Ok(Box::new(IPAnalysisProcessor::new())));
+    registry.register_processor(
+        "PacketDecodeProcessor",
+        vec![
+            ParamSpec::optional("link_type", ParamKind::String, "默认链路层类型：ethernet（默认）、raw 或 linux_sll"),
+            ParamSpec::optional("strict", ParamKind::Bool, "解码失败时作为处理器错误交给错误策略，默认 false"),
+        ],
+        |config| {
+            let mut processor = PacketDecodeProcessor::new().with_name(&config.name);
+            if let Some(name) = config.get_str(K, "link_type")? {
+                let link_type = LinkType::parse(&name).ok_or_else(|| {
+                    config.invalid_param(K, "link_type", format!("应为 ethernet、raw 或 linux_sll，实际为 '{}'", name))
+                })?;
+                processor = processor.with_link_type(link_type);
+            }
+            if let Some(strict) = config.get_bool(K, "strict")? {
+                processor = processor.with_strict(strict);
+            }
+            Ok(Box::new(processor))
+        },
+    );
+    registry.register_processor("GapDetectionProcessor", vec![], |config| {
+        Ok(Box::new(GapDetectionProcessor::new().with_name(&config.name)))
+    });
+    registry.register_processor(
+        "WindowProcessor",
+        vec![
+            ParamSpec::required("window", ParamKind::String, "窗口类型：tumbling、sliding 或 session"),
+            ParamSpec::optional("size_ms", ParamKind::Integer, "窗口长度（毫秒），tumbling 和 sliding 必填"),
+            ParamSpec::optional("slide_ms", ParamKind::Integer, "滑动步长（毫秒），sliding 必填"),
+            ParamSpec::optional("gap_ms", ParamKind::Integer, "会话超时（毫秒），session 必填"),
+            ParamSpec::optional("time", ParamKind::String, "event（事件时间，默认）或 ingest（进入管道的时间）"),
+            ParamSpec::optional("group_by", ParamKind::String, "分组字段：key、source、length 或属性名"),
+            ParamSpec::optional(
+                "aggregates",
+                ParamKind::StringList,
+                "聚合：count、sum(字段)、min(字段)、max(字段)、distinct(字段)、top_k(字段, k)，默认 [count]",
+            ),
+            ParamSpec::optional(
+                "late_policy",
+                ParamKind::String,
+                "迟到数据：drop（默认）、side_output（发送到 late 输出）或 update（更新已关闭的窗口）",
+            ),
+            ParamSpec::optional("allowed_lateness_ms", ParamKind::Integer, "update 时窗口关闭后继续接受迟到数据的时间（毫秒）"),
+        ],
+        |config| {
+            let millis = |key: &str| -> Result<Duration, ConfigError> {
+                config
+                    .get_u64(K, key)?
+                    .map(Duration::from_millis)
+                    .ok_or_else(|| config.invalid_param(K, key, "该窗口类型缺少必填参数"))
+            };
+            let window = config.require_str(K, "window")?;
+            let kind = match window.as_str() {
+                "tumbling" => WindowKind::tumbling(millis("size_ms")?),
+                "sliding" => WindowKind::sliding(millis("size_ms")?, millis("slide_ms")?),
+                "session" => WindowKind::session(millis("gap_ms")?),
+                other => return Err(config.invalid_param(K, "window", format!("未知的窗口类型 '{}'", other))),
+            };
+            kind.validate().map_err(|e| config.invalid_param(K, "window", e))?;
+
+            let mut processor = WindowProcessor::new(kind).with_name(&config.name);
+            match config.get_str(K, "time")?.as_deref() {
+                None | Some("event") => {}
+                Some("ingest") => processor = processor.with_time_domain(TimeDomain::IngestTime),
+                Some(other) => {
+                    return Err(config.invalid_param(K, "time", format!("应为 event 或 ingest，实际为 '{}'", other)))
+                }
+            }
+            if let Some(field) = config.get_str(K, "group_by")? {
+                processor = processor.with_group_by(Field::parse(&field));
+            }
+            for expr in config.get_str_list(K, "aggregates")?.unwrap_or_default() {
+                let aggregate = AggregateSpec::parse(&expr).map_err(|e| config.invalid_param(K, "aggregates", e))?;
+                processor = processor.with_aggregate(aggregate);
+            }
+
+            let allowed_lateness = config.get_u64(K, "allowed_lateness_ms")?.map(Duration::from_millis);
+            let late_policy = match config.get_str(K, "late_policy")?.as_deref() {
+                None | Some("drop") => LatePolicy::Drop,
+                Some("side_output") => LatePolicy::SideOutput,
+                Some("update") => LatePolicy::Update {
+                    allowed_lateness: allowed_lateness
+                        .ok_or_else(|| config.invalid_param(K, "allowed_lateness_ms", "late_policy 为 update 时必填"))?,
+                },
+                Some(other) => {
+                    return Err(config.invalid_param(
+                        K,
+                        "late_policy",
+                        format!("应为 drop、side_output 或 update，实际为 '{}'", other),
+                    ))
+                }
+            };
+            if allowed_lateness.is_some() && !matches!(late_policy, LatePolicy::Update { .. }) {
+                return Err(config.invalid_param(K, "allowed_lateness_ms", "只能与 late_policy: update 一起使用"));
+            }
+            Ok(Box::new(processor.with_late_policy(late_policy)))
+        },
+    );
+
+    registry.register_processor(
+        "ThreatDetectionProcessor",
+        vec![
+            ParamSpec::optional("critical_patterns", ParamKind::StringList, "命中后立即停止管道的关键特征"),
+            ParamSpec::optional("deliver_critical", ParamKind::Bool, "停止前是否投递命中的数据块，默认 false"),
+        ],
+        |config| {
+            let mut processor = ThreatDetectionProcessor::new();
+            for pattern in config.get_str_list(K, "critical_patterns")?.unwrap_or_default() {
+                if pattern.is_empty() {
+                    return Err(config.invalid_param(K, "critical_patterns", "特征不能为空"));
+                }
+                processor = processor.with_critical_pattern(pattern.into_bytes());
+            }
+            if let Some(deliver) = config.get_bool(K, "deliver_critical")? {
+                processor = processor.with_critical_delivery(deliver);
+            }
+            Ok(Box::new(processor))
+        },
+    );
+
+    registry.register_processor(
+        "EncryptionProcessor",
+        vec![
+            ParamSpec::optional("key", ParamKind::Base64, "加密密钥（32 字节），与 keyring 二选一"),
+            ParamSpec::optional(
+                "keyring",
+                ParamKind::Mapping,
+                "密钥环：directory（<编号>.key 文件）、keys（id + file/env/passphrase_env）、active、reload_seconds",
+            ),
+            ParamSpec::optional("algorithm", ParamKind::String, "aes-256-gcm 或 chacha20-poly1305，默认 aes-256-gcm"),
+        ],
+        |config| {
+            let mut security_config = SecurityConfig { enable_encryption: true, ..SecurityConfig::default() };
+            encryption_params(config, K, &mut security_config)?;
+            let processor = EncryptionProcessor::from_config(&security_config, &config.name)
+                .map_err(|e| config.invalid_param(K, "key", e.to_string()))?;
+            Ok(Box::new(processor))
+        },
+    );
+
+    registry.register_processor(
+        "DecryptionProcessor",
+        vec![
+            ParamSpec::optional("key", ParamKind::Base64, "解密密钥（32 字节），与 keyring 二选一"),
+            ParamSpec::optional(
+                "keyring",
+                ParamKind::Mapping,
+                "密钥环：directory（<编号>.key 文件）、keys（id + file/env/passphrase_env）、active、reload_seconds",
+            ),
+            ParamSpec::optional("algorithm", ParamKind::String, "aes-256-gcm 或 chacha20-poly1305（解密按密文头部的算法编号）"),
+        ],
+        |config| {
+            let mut security_config = SecurityConfig { enable_encryption: true, ..SecurityConfig::default() };
+            encryption_params(config, K, &mut security_config)?;
+            let processor = DecryptionProcessor::from_config(&security_config, &config.name)
+                .map_err(|e| config.invalid_param(K, "key", e.to_string()))?;
+            Ok(Box::new(processor))
+        },
+    );
+}
+
+// 加密相关组件的 key/keyring 和 algorithm 参数
+fn encryption_params(
+    config: &ComponentConfig,
+    kind: ComponentKind,
+    security_config: &mut SecurityConfig,
+) -> Result<(), ConfigError> {
+    match (config.get_base64(kind, "key")?, config.param("keyring")) {
+        (Some(key), None) => security_config.encryption_key = Some(Zeroizing::new(key)),
+        (None, Some(value)) => {
+            let keyring_config: KeyRingConfig = serde_yaml::from_value(value.clone())
+                .map_err(|e| config.invalid_param(kind, "keyring", e.to_string()))?;
+            let keyring = KeyRing::from_config(&keyring_config)
+                .map_err(|e| config.invalid_param(kind, "keyring", e.to_string()))?;
+            security_config.keyring = Some(keyring);
+        }
+        _ => return Err(config.invalid_param(kind, "key", "key 和 keyring 必须且只能设置一个")),
+    }
+    if let Some(name) = config.get_str(kind, "algorithm")? {
+        security_config.encryption_algorithm = EncryptionAlgorithm::parse(&name)
+            .ok_or_else(|| config.invalid_param(kind, "algorithm", "应为 aes-256-gcm 或 chacha20-poly1305"))?;
+    }
+    Ok(())
+}
+
+fn register_builtin_sinks(registry: &mut ComponentRegistry) {
+    use ComponentKind::Sink as K;
+
+    registry.register_sink("ConsoleSink", vec![], |_| Ok(Box::new(ConsoleSink::new())));
+    registry.register_sink("StatsSink", vec![], |_| Ok(Box::new(StatsSink::new())));
+    registry.register_sink("MetricsSink", vec![], |_| Ok(Box::new(MetricsSink::new())));
+
+    registry.register_sink(
+        "JsonLinesSink",
+        vec![
+            ParamSpec::required("file_path", ParamKind::String, "输出文件路径，每行一条 JSON"),
+            ParamSpec::optional("append", ParamKind::Bool, "追加到已有文件，默认 true"),
+        ],
+        |config| {
+            let sink = JsonLinesSink::new(&config.require_str(K, "file_path")?)
+                .with_name(&config.name)
+                .with_append(config.get_bool(K, "append")?.unwrap_or(true));
+            Ok(Box::new(sink))
+        },
+    );
+
+    registry.register_sink(
+        "PcapFileSink",
+        vec![
+            ParamSpec::required("file_path", ParamKind::String, "输出的抓包文件路径"),
+            ParamSpec::optional("format", ParamKind::String, "pcap 或 pcapng，默认按扩展名判断（.pcapng 为 pcapng）"),
+            ParamSpec::optional("link_type", ParamKind::String, "数据块没有 link_type 属性时使用：ethernet（默认）、raw 或 linux_sll"),
+            ParamSpec::optional("snaplen", ParamKind::Integer, "每个数据包最多写入的字节数，默认不截断"),
+            ParamSpec::optional("rotate_mb", ParamKind::Integer, "文件超过指定大小（MiB）时切换到下一个文件"),
+            ParamSpec::optional("rotate_seconds", ParamKind::Integer, "文件打开超过指定秒数时切换到下一个文件"),
+        ],
+        |config| {
+            let mut sink = PcapFileSink::new(&config.require_str(K, "file_path")?).with_name(&config.name);
+            match config.get_str(K, "format")?.as_deref() {
+                None => {}
+                Some("pcap") => sink = sink.with_format(CaptureFormat::Pcap { nanos: true, big_endian: false }),
+                Some("pcapng") => sink = sink.with_format(CaptureFormat::PcapNg),
+                Some(other) => {
+                    return Err(config.invalid_param(K, "format", format!("应为 pcap 或 pcapng，实际为 '{}'", other)))
+                }
+            }
+            if let Some(name) = config.get_str(K, "link_type")? {
+                let link_type = LinkType::parse(&name).ok_or_else(|| {
+                    config.invalid_param(K, "link_type", format!("应为 ethernet、raw 或 linux_sll，实际为 '{}'", name))
+                })?;
+                sink = sink.with_link_type(link_type);
+            }
+            if let Some(snaplen) = config.get_u64(K, "snaplen")? {
+                let snaplen = u32::try_from(snaplen)
+                    .map_err(|_| config.invalid_param(K, "snaplen", "应为 0 到 4294967295 之间的整数"))?;
+                sink = sink.with_snaplen(snaplen);
+            }
+            if let Some(rotate_mb) = config.get_u64(K, "rotate_mb")?.filter(|rotate_mb| *rotate_mb > 0) {
+                sink = sink.with_rotate_size(rotate_mb << 20);
+            }
+            if let Some(seconds) = config.get_u64(K, "rotate_seconds")?.filter(|seconds| *seconds > 0) {
+                sink = sink.with_rotate_interval(Duration::from_secs(seconds));
+            }
+            Ok(Box::new(sink))
+        },
+    );
+
+    registry.register_sink(
+        "SecureFileSink",
+        vec![
+            ParamSpec::required("file_path", ParamKind::String, "输出文件路径"),
+            ParamSpec::optional("enable_encryption", ParamKind::Bool, "是否加密，默认 false；启用时需要 key 或 keyring"),
+            ParamSpec::optional("key", ParamKind::Base64, "加密密钥（32 字节），与 keyring 二选一"),
+            ParamSpec::optional(
+                "keyring",
+                ParamKind::Mapping,
+                "密钥环：directory（<编号>.key 文件）、keys（id + file/env/passphrase_env）、active、reload_seconds",
+            ),
+            ParamSpec::optional("algorithm", ParamKind::String, "aes-256-gcm 或 chacha20-poly1305，默认 aes-256-gcm"),
+            ParamSpec::optional("enable_integrity_check", ParamKind::Bool, "是否写入校验值，默认 true"),
+            ParamSpec::optional("integrity_algorithm", ParamKind::String, "hmac-sha256 或 blake3，默认 hmac-sha256"),
+            ParamSpec::optional("integrity_key", ParamKind::Base64, "完整性密钥，启用 enable_integrity_check 时必填"),
+        ],
+        |config| {
+            let defaults = SecurityConfig::default();
+            let mut security_config = SecurityConfig {
+                enable_encryption: config
+                    .get_bool(K, "enable_encryption")?
+                    .unwrap_or(defaults.enable_encryption),
+                enable_integrity_check: config
+                    .get_bool(K, "enable_integrity_check")?
+                    .unwrap_or(defaults.enable_integrity_check),
+                integrity_key: config.get_base64(K, "integrity_key")?.map(Zeroizing::new),
+                ..defaults
+            };
+            if let Some(name) = config.get_str(K, "integrity_algorithm")? {
+                security_config.integrity_algorithm = IntegrityAlgorithm::parse(&name)
+                    .filter(|algorithm| *algorithm != IntegrityAlgorithm::LegacyDefaultHasher)
+                    .ok_or_else(|| config.invalid_param(K, "integrity_algorithm", "应为 hmac-sha256 或 blake3"))?;
+            }
+            if security_config.enable_encryption {
+                encryption_params(config, K, &mut security_config)?;
+            }
+            let sink = SecureFileSink::new(config.require_str(K, "file_path")?, &security_config, &config.name)
+                .map_err(|e| ConfigError::BuildError {
+                    kind: K,
+                    name: config.name.clone(),
+                    reason: e.to_string(),
+                })?;
+            Ok(Box::new(sink))
+        },
+    );
+
+    registry.register_sink(
+        "AuditLogSink",
+        vec![
+            ParamSpec::required("file_path", ParamKind::String, "审计日志文件路径，已有文件校验通过后接着写入"),
+            ParamSpec::optional("signing_key", ParamKind::Base64, "Ed25519 签名私钥（32 字节种子），与 signing_key_file/signing_key_env 三选一"),
+            ParamSpec::optional("signing_key_file", ParamKind::String, "签名私钥文件：32 字节原始密钥或 base64 文本"),
+            ParamSpec::optional("signing_key_env", ParamKind::String, "保存 base64 签名私钥的环境变量"),
+            ParamSpec::optional("checkpoint_every", ParamKind::Integer, "每隔多少条记录写一个签名检查点，默认 1000，0 表示不按条数"),
+            ParamSpec::optional("checkpoint_seconds", ParamKind::Integer, "距上一个检查点超过指定秒数时写一个检查点"),
+        ],
+        |config| {
+            let key = match (
+                config.get_base64(K, "signing_key")?,
+                config.get_str(K, "signing_key_file")?,
+                config.get_str(K, "signing_key_env")?,
+            ) {
+                (Some(key), None, None) => SecretKey::from_slice(&key),
+                (None, Some(path), None) => KeySource::File(path.into()).load(),
+                (None, None, Some(name)) => KeySource::Env(name).load(),
+                _ => {
+                    return Err(config.invalid_param(
+                        K,
+                        "signing_key",
+                        "signing_key、signing_key_file 和 signing_key_env 必须且只能设置一个",
+                    ))
+                }
+            }
+            .map_err(|e| config.invalid_param(K, "signing_key", e.to_string()))?;
+            let signing_key = SigningKey::from_bytes(key.as_bytes().try_into().expect("SecretKey 为 32 字节"));
+            let mut sink = AuditLogSink::new(&config.require_str(K, "file_path")?, signing_key).with_name(&config.name);
+            if let Some(records) = config.get_u64(K, "checkpoint_every")? {
+                sink = sink.with_checkpoint_every(records);
+            }
+            if let Some(seconds) = config.get_u64(K, "checkpoint_seconds")?.filter(|seconds| *seconds > 0) {
+                sink = sink.with_checkpoint_interval(Duration::from_secs(seconds));
+            }
+            Ok(Box::new(sink))
+        },
+    );
+}
@@ -1,3 +1,4 @@
+pub mod chunk;
 pub mod pipeline;
 pub mod processors;
 pub mod sources;
@@ -6,9 +7,17 @@ pub mod metrics;
 pub mod ratelimit;
 pub mod security;
 pub mod config;
+pub mod window;
+pub mod packet;
 
 // 导出核心接口
-pub use pipeline::{Pipeline, PipelineBuilder};
+pub use chunk::{AttributeValue, Attributes, DataChunk, now_ns, ns_to_system_time, system_time_to_ns};
+pub use bytes::{Bytes, BytesMut};
+pub use pipeline::{
+    Pipeline, PipelineBuilder, PipelineHandle, PipelineController, RunSummary, Termination,
+    SinkOptions, OverflowPolicy, SinkReport, SourceReport, ErrorPolicy, ErrorAction, RetryPolicy, DeadLetterRecord,
+    WatermarkStrategy,
+};
 pub use processors::Processor;
 pub use sources::Source;
 pub use sinks::Sink;
@@ -17,42 +26,16 @@ pub use sinks::Sink;
 pub use processors::{
     FilterProcessor, UpperCaseProcessor,
     PacketStatsProcessor, PacketStatsSnapshot,
-    IPAnalysisProcessor, ThreatDetectionProcessor,
-    EncryptionProcessor, DecryptionProcessor
+    IPAnalysisProcessor, ThreatDetectionProcessor, PacketDecodeProcessor,
+    EncryptionProcessor, DecryptionProcessor, WindowProcessor
 };
-pub use sources::{MemorySource, MockPacketSource};
-pub use sinks::{ConsoleSink, StatsSink, MetricsSink, Metrics, SecureFileSink};
+pub use sources::{MemorySource, MockPacketSource, PcapFileSource, JsonLinesSource, ReplaySource};
+pub use sinks::{ConsoleSink, StatsSink, MetricsSink, Metrics, SecureFileSink, JsonLinesSink, PcapFileSink, AuditLogSink};
 // 根据您选择的版本调整导出
 pub use ratelimit::{RateLimiter, BackpressureController}; // 简化版本不需要 RateLimitPermit
 pub use metrics::{MetricsCollector, AdvancedMetricsCollector, MetricsSummary};
-pub use security::{SecurityConfig, DataEncryptor, DataIntegrityChecker, SecurityError};
-pub use config::PipelineConfig;
-
-use std::time::{SystemTime, UNIX_EPOCH};
-
-#[derive(Debug, Clone)]
-pub struct DataChunk {
-    pub data: Vec<u8>,
-    pub timestamp: u64,
-    pub sequence: u64,
-}
-
-impl DataChunk {
-    pub fn new(data: Vec<u8>) -> Self {
-        let timestamp = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_secs();
-
-        Self {
-            data,
-            timestamp,
-            sequence: 0,
-        }
-    }
-
-    pub fn with_sequence(mut self, sequence: u64) -> Self {
-        self.sequence = sequence;
-        self
-    }
-}
\ No newline at end of file
+pub use security::{
+    SecurityConfig, DataEncryptor, EncryptionAlgorithm, DataIntegrityChecker, IntegrityAlgorithm, KeyRing,
+    SecureFileReader, SecureFileWriter, AuditLogWriter, AuditLogVerifier, AuditReport, SecurityError,
+};
+pub use config::{PipelineConfig, ComponentConfig, ComponentRegistry, ConfigError};
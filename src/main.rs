@@ -1,32 +1,256 @@
-use std::env;
+use clap::{Parser, Subcommand};
+use pelican_framework::config::{ComponentKind, ComponentRegistry, PipelineConfig};
+use pelican_framework::pipeline::{Pipeline, Termination};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+// 退出码：配置错误与运行时错误分开，便于 systemd 和脚本判断
+const EXIT_RUNTIME_ERROR: u8 = 1;
+const EXIT_CONFIG_ERROR: u8 = 2;
+// 审计日志校验发现篡改或截断
+const EXIT_VERIFY_FAILED: u8 = 3;
+
+#[derive(Parser)]
+#[command(name = "pelican", version, about = "🐦 鹈鹕框架 (Pelican Framework) - 数据流处理管道")]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// 根据配置文件运行管道
+    Run {
+        /// YAML 配置文件路径
+        config: PathBuf,
+    },
+    /// 校验配置文件并创建所有组件，但不运行
+    Validate {
+        /// YAML 配置文件路径
+        config: PathBuf,
+    },
+    /// 输出管道拓扑的 Graphviz DOT 描述
+    Graph {
+        /// YAML 配置文件路径
+        config: PathBuf,
+    },
+    /// 列出已注册的组件类型及其参数
+    ListComponents,
+    /// 校验 AuditLogSink 写入的审计日志：哈希链、检查点签名和末尾是否完整
+    VerifyAudit {
+        /// 审计日志文件路径
+        file: PathBuf,
+        /// Ed25519 公钥（base64，AuditLogSink 打开日志时输出）
+        #[arg(long)]
+        public_key: String,
+    },
+    /// 运行内置示例 (basic, network, performance, benchmark)
+    Demo {
+        #[arg(default_value = "basic")]
+        name: String,
+    },
+}
 
 #[tokio::main]
-async fn main() -> anyhow::Result<()> {
+async fn main() -> ExitCode {
     // 初始化日志
     tracing_subscriber::fmt::init();
 
-    // 获取命令行参数
-    let args: Vec<String> = env::args().collect();
-    let example_name = args.get(1).map(|s| s.as_str()).unwrap_or("basic");
+    let cli = Cli::parse();
+    let registry = ComponentRegistry::with_builtins();
+
+    match cli.command {
+        Command::Run { config } => run_config(&config, &registry).await,
+        Command::Validate { config } => validate_config(&config, &registry),
+        Command::Graph { config } => graph_config(&config, &registry),
+        Command::ListComponents => {
+            list_components(&registry);
+            ExitCode::SUCCESS
+        }
+        Command::VerifyAudit { file, public_key } => verify_audit(&file, &public_key),
+        Command::Demo { name } => run_demo(&name).await,
+    }
+}
+
+fn verify_audit(path: &Path, public_key: &str) -> ExitCode {
+    use base64::Engine;
+    use pelican_framework::security::AuditLogVerifier;
+
+    let verifier = match base64::engine::general_purpose::STANDARD
+        .decode(public_key.trim())
+        .map_err(|e| e.to_string())
+        .and_then(|key| AuditLogVerifier::from_public_key(&key).map_err(|e| e.to_string()))
+    {
+        Ok(verifier) => verifier,
+        Err(e) => {
+            eprintln!("❌ 公钥无效: {}", e);
+            return ExitCode::from(EXIT_CONFIG_ERROR);
+        }
+    };
+    let report = match verifier.verify_file(path) {
+        Ok(report) => report,
+        Err(e) => {
+            eprintln!("❌ 无法校验 {}: {}", path.display(), e);
+            return ExitCode::from(EXIT_RUNTIME_ERROR);
+        }
+    };
+
+    println!(
+        "📜 {}: {} 条记录, {} 个检查点, 最后一个检查点覆盖 {} 条记录",
+        path.display(), report.records, report.checkpoints, report.signed_records
+    );
+    if let Some(issue) = &report.issue {
+        println!("❌ {}", issue);
+        return ExitCode::from(EXIT_VERIFY_FAILED);
+    }
+    // 没有签名的末尾记录由 issue 报告；这里只剩日志是否以关闭检查点结束
+    if report.sealed {
+        println!("✅ 哈希链和签名完整，日志以关闭检查点结束");
+    } else {
+        println!("⚠️  哈希链和签名完整，但日志没有以关闭检查点结束（仍在写入，或最后一个检查点之后的条目被截掉）");
+    }
+    ExitCode::SUCCESS
+}
+
+// 加载配置文件，失败时打印错误
+fn load_config(path: &Path) -> Result<PipelineConfig, ExitCode> {
+    PipelineConfig::from_file(path).map_err(|e| {
+        eprintln!("❌ {}", e);
+        ExitCode::from(EXIT_CONFIG_ERROR)
+    })
+}
+
+// 通过注册表创建管道，失败时打印错误
+fn build_pipeline(path: &Path, config: &PipelineConfig, registry: &ComponentRegistry) -> Result<Pipeline, ExitCode> {
+    Pipeline::from_config_with_registry(config, registry).map_err(|e| {
+        eprintln!("❌ {}: {}", path.display(), e);
+        ExitCode::from(EXIT_CONFIG_ERROR)
+    })
+}
+
+fn load_pipeline(path: &Path, registry: &ComponentRegistry) -> Result<(PipelineConfig, Pipeline), ExitCode> {
+    let config = load_config(path)?;
+    let pipeline = build_pipeline(path, &config, registry)?;
+    Ok((config, pipeline))
+}
+
+async fn run_config(path: &Path, registry: &ComponentRegistry) -> ExitCode {
+    let mut config = match load_config(path) {
+        Ok(config) => config,
+        Err(code) => return code,
+    };
+    // 命令行运行时总是响应 SIGINT/SIGTERM
+    config.handle_signals = true;
+
+    let pipeline = match build_pipeline(path, &config, registry) {
+        Ok(pipeline) => pipeline,
+        Err(code) => return code,
+    };
+
+    println!("🚀 运行管道: {}", config.name);
+    match pipeline.run().await {
+        Ok(summary) => {
+            println!(
+                "✅ 管道 '{}' 已结束 ({:?}): 处理 {}, 跳过 {}, 未处理 {}, 出错 {}",
+                config.name, summary.termination, summary.processed, summary.skipped, summary.undelivered,
+                summary.errors
+            );
+            if let Termination::Stopped { processor, reason } = &summary.termination {
+                println!("🛑 管道被处理器 '{}' 停止: {}", processor, reason);
+            }
+            for source in &summary.sources {
+                let mut metrics: Vec<_> = source.metrics.iter().collect();
+                metrics.sort();
+                let metrics: Vec<String> = metrics.iter().map(|(key, value)| format!("{} {}", key, value)).collect();
+                println!("   📥 {}: {}", source.name, metrics.join(", "));
+            }
+            for sink in &summary.sinks {
+                println!(
+                    "   📤 {}: 投递 {}, 丢弃 {}, 错误 {}",
+                    sink.name, sink.delivered, sink.dropped, sink.errors
+                );
+            }
+            if summary.termination == Termination::Aborted {
+                return ExitCode::from(EXIT_RUNTIME_ERROR);
+            }
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("❌ 管道 '{}' 运行失败: {}", config.name, e);
+            ExitCode::from(EXIT_RUNTIME_ERROR)
+        }
+    }
+}
+
+fn validate_config(path: &Path, registry: &ComponentRegistry) -> ExitCode {
+    match load_pipeline(path, registry) {
+        Ok((config, _)) => {
+            println!(
+                "✅ 配置有效: '{}' ({} 个数据源, {} 个处理器, {} 个输出端)",
+                config.name, config.sources.len(), config.processors.len(), config.sinks.len()
+            );
+            ExitCode::SUCCESS
+        }
+        Err(code) => code,
+    }
+}
+
+fn graph_config(path: &Path, registry: &ComponentRegistry) -> ExitCode {
+    match load_pipeline(path, registry) {
+        Ok((config, _)) => {
+            print!("{}", config.to_dot_with_registry(registry));
+            ExitCode::SUCCESS
+        }
+        Err(code) => code,
+    }
+}
+
+fn list_components(registry: &ComponentRegistry) {
+    for (kind, title) in [
+        (ComponentKind::Source, "数据源 (sources)"),
+        (ComponentKind::Processor, "处理器 (processors)"),
+        (ComponentKind::Sink, "输出端 (sinks)"),
+    ] {
+        println!("{}:", title);
+        for (type_name, params) in registry.component_types(kind) {
+            println!("  {}", type_name);
+            for param in params {
+                println!(
+                    "      {:<24} {:<14} {}{}",
+                    param.name,
+                    param.kind,
+                    if param.required { "[必填] " } else { "" },
+                    param.description
+                );
+            }
+        }
+        println!();
+    }
+}
 
-    match example_name {
+async fn run_demo(name: &str) -> ExitCode {
+    let result = match name {
         "basic" => run_basic_example().await,
         "network" => run_network_analyzer().await,
         "performance" => run_performance_demo().await,
         "benchmark" => run_benchmark().await,
         _ => {
-            println!("未知示例: {}", example_name);
+            eprintln!("未知示例: {}", name);
             print_usage();
-            Ok(())
+            return ExitCode::from(EXIT_CONFIG_ERROR);
+        }
+    };
+
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("❌ 示例运行失败: {}", e);
+            ExitCode::from(EXIT_RUNTIME_ERROR)
         }
     }
 }
 
 fn print_usage() {
-    println!();
-    println!("🐦 鹈鹕框架 (Pelican Framework) - 示例运行器");
-    println!("=============================================");
-    println!("用法: cargo run [示例名称]");
     println!();
     println!("可用示例:");
     println!("  basic        - 基础数据处理示例");
@@ -35,9 +259,8 @@ fn print_usage() {
     println!("  benchmark    - 基准测试");
     println!();
     println!("例如:");
-    println!("  cargo run basic");
-    println!("  cargo run network");
-    println!("  cargo run performance");
+    println!("  pelican demo basic");
+    println!("  pelican demo network");
 }
 
 async fn run_basic_example() -> anyhow::Result<()> {
@@ -139,9 +362,41 @@ async fn run_performance_demo() -> anyhow::Result<()> {
     println!("  • 总耗时: {:.3} 秒", elapsed.as_secs_f64());
     println!("  • 吞吐量: {:.2} 数据包/秒", throughput);
 
+    // 负载以 Bytes 共享，扇出到多个输出端不复制数据
+    let (chunks, payload_size, sinks) = (2000, 64 * 1024, 4);
+    let elapsed = run_fanout(chunks, payload_size, sinks).await?;
+    println!("📤 零拷贝扇出 ({} 个 {} KB 数据块 → {} 个输出端):", chunks, payload_size / 1024, sinks);
+    println!("  • 总耗时: {:.3} 秒", elapsed.as_secs_f64());
+    println!("  • 投递数据量: {:.2} GB/秒",
+             (chunks * payload_size * sinks) as f64 / elapsed.as_secs_f64() / 1e9);
+
     Ok(())
 }
 
+// 同一个负载经过只读处理器扇出到多个输出端，返回耗时
+async fn run_fanout(chunks: usize, payload_size: usize, sinks: usize) -> anyhow::Result<std::time::Duration> {
+    use pelican_framework::{
+        Bytes,
+        Pipeline,
+        sources::MemorySource,
+        processors::FilterProcessor,
+        sinks::MetricsSink
+    };
+    use std::time::Instant;
+
+    let payload = Bytes::from(vec![b'x'; payload_size]);
+    let mut builder = Pipeline::builder()
+        .add_source(Box::new(MemorySource::from_bytes(vec![payload; chunks]).with_delay(0)))
+        .add_processor(Box::new(FilterProcessor::new(b"x".to_vec())));
+    for _ in 0..sinks {
+        builder = builder.add_sink(Box::new(MetricsSink::new()));
+    }
+
+    let start_time = Instant::now();
+    builder.build().run().await?;
+    Ok(start_time.elapsed())
+}
+
 async fn run_benchmark() -> anyhow::Result<()> {
     use pelican_framework::{
         Pipeline,
@@ -177,6 +432,16 @@ async fn run_benchmark() -> anyhow::Result<()> {
         println!("  • 吞吐量: {:.2} 数据包/秒", packets_per_second);
     }
 
+    // 零拷贝：扇出耗时与负载大小基本无关
+    for payload_size in [1024, 64 * 1024, 1024 * 1024] {
+        println!("\n测试扇出 1000 个 {} KB 数据块到 4 个输出端...", payload_size / 1024);
+        let elapsed = run_fanout(1000, payload_size, 4).await?;
+        println!("结果:");
+        println!("  • 耗时: {:.3} 秒", elapsed.as_secs_f64());
+        println!("  • 投递数据量: {:.2} GB/秒",
+                 (1000 * payload_size * 4) as f64 / elapsed.as_secs_f64() / 1e9);
+    }
+
     println!("\n✅ 基准测试完成!");
     Ok(())
 }
\ No newline at end of file
@@ -138,6 +138,12 @@ impl AdvancedMetricsCollector {
     }
 }
 
+impl Default for AdvancedMetricsCollector {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct MetricsSummary {
     pub processed_total: u64,
@@ -0,0 +1,316 @@
+use super::{BpfProgram, CapturedPacket, LinkType};
+use std::ffi::CString;
+use std::io;
+use std::mem;
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+use std::ptr;
+use std::sync::atomic::{fence, Ordering};
+use tokio::io::unix::AsyncFd;
+
+// ARPHRD_* 设备类型（linux/if_arp.h）
+const ARPHRD_ETHER: u16 = 1;
+const ARPHRD_LOOPBACK: u16 = 772;
+const ARPHRD_NONE: u16 = 0xfffe;
+
+// AF_PACKET 接收环的参数
+#[derive(Debug, Clone)]
+pub struct RingOptions {
+    pub promiscuous: bool,
+    // 每个块的大小，必须是页大小的整数倍
+    pub block_size: u32,
+    pub block_count: u32,
+    // 块未写满时最多等待多久交给用户态（毫秒）
+    pub block_timeout_ms: u32,
+}
+
+impl Default for RingOptions {
+    fn default() -> Self {
+        Self {
+            promiscuous: true,
+            block_size: 1 << 20,
+            block_count: 16,
+            block_timeout_ms: 100,
+        }
+    }
+}
+
+// 内核的接收计数（自上次读取以来）
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RingStatistics {
+    // 通过过滤条件的数据包
+    pub packets: u64,
+    // 因接收环已满丢弃的数据包
+    pub drops: u64,
+    // 接收环被冻结（所有块都在用户态）的次数
+    pub freezes: u64,
+}
+
+// 映射到用户态的接收环内存
+struct RingMap {
+    ptr: *mut u8,
+    block_size: usize,
+    block_count: usize,
+    current: usize,
+    // 回环网卡上每个数据包会以发出和接收各出现一次，只保留接收的那份
+    skip_outgoing: bool,
+}
+
+// 接收环内存只通过 &mut PacketRing 访问
+unsafe impl Send for RingMap {}
+unsafe impl Sync for RingMap {}
+
+impl Drop for RingMap {
+    fn drop(&mut self) {
+        unsafe {
+            libc::munmap(self.ptr.cast(), self.block_size * self.block_count);
+        }
+    }
+}
+
+impl RingMap {
+    fn block(&self, index: usize) -> *mut libc::tpacket_block_desc {
+        unsafe { self.ptr.add(index * self.block_size).cast() }
+    }
+
+    // 取出当前块中的数据包并把块交还内核；当前块仍归内核所有时返回 None
+    fn take_block(&mut self, interface: u32, link_type: u32) -> Option<Vec<CapturedPacket>> {
+        let block = self.block(self.current);
+        let packets = unsafe {
+            let header = ptr::addr_of_mut!((*block).hdr.bh1);
+            let status = ptr::read_volatile(ptr::addr_of!((*header).block_status));
+            if status & libc::TP_STATUS_USER == 0 {
+                return None;
+            }
+            fence(Ordering::Acquire);
+
+            let count = (*header).num_pkts as usize;
+            let mut packets = Vec::with_capacity(count);
+            let mut offset = (*header).offset_to_first_pkt as usize;
+            for _ in 0..count {
+                if offset + mem::size_of::<libc::tpacket3_hdr>() > self.block_size {
+                    break;
+                }
+                let packet = block.cast::<u8>().add(offset).cast::<libc::tpacket3_hdr>();
+                let next = (*packet).tp_next_offset as usize;
+                let address = packet.cast::<u8>().add(libc::TPACKET_ALIGN(mem::size_of::<libc::tpacket3_hdr>()))
+                    .cast::<libc::sockaddr_ll>();
+                if self.skip_outgoing && (*address).sll_pkttype == libc::PACKET_OUTGOING {
+                    if next == 0 {
+                        break;
+                    }
+                    offset += next;
+                    continue;
+                }
+                let start = offset + usize::from((*packet).tp_mac);
+                let length = ((*packet).tp_snaplen as usize).min(self.block_size.saturating_sub(start));
+                let data = std::slice::from_raw_parts(block.cast::<u8>().add(start), length);
+                packets.push(CapturedPacket {
+                    timestamp_ns: Some(u64::from((*packet).tp_sec) * 1_000_000_000 + u64::from((*packet).tp_nsec)),
+                    link_type,
+                    interface,
+                    original_len: (*packet).tp_len,
+                    data: data.to_vec(),
+                });
+                if next == 0 {
+                    break;
+                }
+                offset += next;
+            }
+
+            fence(Ordering::Release);
+            ptr::write_volatile(ptr::addr_of_mut!((*header).block_status), libc::TP_STATUS_KERNEL);
+            packets
+        };
+        self.current = (self.current + 1) % self.block_count;
+        Some(packets)
+    }
+}
+
+// AF_PACKET 套接字上的 TPACKET_V3 接收环：内核把数据包按块写入共享内存，用户态按块读取
+//
+// 过滤条件编译为经典 BPF 挂到套接字上，在内核中执行；程序的返回值即 snaplen
+pub struct PacketRing {
+    fd: AsyncFd<OwnedFd>,
+    map: RingMap,
+    interface: u32,
+    link_type: LinkType,
+}
+
+fn last_error(action: &str) -> io::Error {
+    let error = io::Error::last_os_error();
+    io::Error::new(error.kind(), format!("{}: {}", action, error))
+}
+
+fn set_option<T>(fd: &OwnedFd, level: libc::c_int, name: libc::c_int, value: &T, action: &str) -> io::Result<()> {
+    let result = unsafe {
+        libc::setsockopt(
+            fd.as_raw_fd(),
+            level,
+            name,
+            (value as *const T).cast(),
+            mem::size_of::<T>() as libc::socklen_t,
+        )
+    };
+    if result < 0 {
+        return Err(last_error(action));
+    }
+    Ok(())
+}
+
+// 按网卡的硬件类型确定链路层类型，同时返回是否为回环网卡
+fn device_link_type(fd: &OwnedFd, device: &CString) -> io::Result<(LinkType, bool)> {
+    let mut request: libc::ifreq = unsafe { mem::zeroed() };
+    let name = device.as_bytes_with_nul();
+    if name.len() > request.ifr_name.len() {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "device name too long"));
+    }
+    for (target, &byte) in request.ifr_name.iter_mut().zip(name) {
+        *target = byte as libc::c_char;
+    }
+    if unsafe { libc::ioctl(fd.as_raw_fd(), libc::SIOCGIFHWADDR, &mut request) } < 0 {
+        return Err(last_error("SIOCGIFHWADDR"));
+    }
+    match unsafe { request.ifr_ifru.ifru_hwaddr.sa_family } {
+        ARPHRD_ETHER => Ok((LinkType::Ethernet, false)),
+        ARPHRD_LOOPBACK => Ok((LinkType::Ethernet, true)),
+        ARPHRD_NONE => Ok((LinkType::RawIp, false)),
+        other => Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            format!("unsupported hardware type {} on {}", other, device.to_string_lossy()),
+        )),
+    }
+}
+
+impl PacketRing {
+    // 打开网卡上的接收环；需要 CAP_NET_RAW 权限
+    //
+    // filter 按网卡的链路层类型编译，先用不接收任何数据包的套接字完成设置，再绑定到网卡
+    pub fn open(
+        device: &str,
+        options: &RingOptions,
+        filter: impl FnOnce(LinkType) -> io::Result<BpfProgram>,
+    ) -> io::Result<Self> {
+        let device_name = CString::new(device)
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "device name contains NUL"))?;
+        let interface = unsafe { libc::if_nametoindex(device_name.as_ptr()) };
+        if interface == 0 {
+            return Err(io::Error::new(io::ErrorKind::NotFound, format!("no such device: {}", device)));
+        }
+
+        let raw = unsafe { libc::socket(libc::AF_PACKET, libc::SOCK_RAW | libc::SOCK_NONBLOCK | libc::SOCK_CLOEXEC, 0) };
+        if raw < 0 {
+            return Err(last_error("socket(AF_PACKET)"));
+        }
+        let fd = unsafe { OwnedFd::from_raw_fd(raw) };
+        let (link_type, loopback) = device_link_type(&fd, &device_name)?;
+
+        let version = libc::tpacket_versions::TPACKET_V3 as libc::c_int;
+        set_option(&fd, libc::SOL_PACKET, libc::PACKET_VERSION, &version, "PACKET_VERSION")?;
+
+        let program = filter(link_type)?;
+        let instructions = program.instructions();
+        let fprog = libc::sock_fprog {
+            len: instructions.len() as libc::c_ushort,
+            filter: instructions.as_ptr() as *mut libc::sock_filter,
+        };
+        set_option(&fd, libc::SOL_SOCKET, libc::SO_ATTACH_FILTER, &fprog, "SO_ATTACH_FILTER")?;
+
+        let frame_size = 2048;
+        let request = libc::tpacket_req3 {
+            tp_block_size: options.block_size,
+            tp_block_nr: options.block_count,
+            tp_frame_size: frame_size,
+            tp_frame_nr: options.block_size / frame_size * options.block_count,
+            tp_retire_blk_tov: options.block_timeout_ms,
+            tp_sizeof_priv: 0,
+            tp_feature_req_word: 0,
+        };
+        set_option(&fd, libc::SOL_PACKET, libc::PACKET_RX_RING, &request, "PACKET_RX_RING")?;
+
+        let block_size = options.block_size as usize;
+        let block_count = options.block_count as usize;
+        let ptr = unsafe {
+            libc::mmap(
+                ptr::null_mut(),
+                block_size * block_count,
+                libc::PROT_READ | libc::PROT_WRITE,
+                libc::MAP_SHARED,
+                fd.as_raw_fd(),
+                0,
+            )
+        };
+        if ptr == libc::MAP_FAILED {
+            return Err(last_error("mmap"));
+        }
+        let map = RingMap { ptr: ptr.cast(), block_size, block_count, current: 0, skip_outgoing: loopback };
+
+        if options.promiscuous {
+            let membership = libc::packet_mreq {
+                mr_ifindex: interface as libc::c_int,
+                mr_type: libc::PACKET_MR_PROMISC as libc::c_ushort,
+                mr_alen: 0,
+                mr_address: [0; 8],
+            };
+            set_option(&fd, libc::SOL_PACKET, libc::PACKET_ADD_MEMBERSHIP, &membership, "PACKET_ADD_MEMBERSHIP")?;
+        }
+
+        let mut address: libc::sockaddr_ll = unsafe { mem::zeroed() };
+        address.sll_family = libc::AF_PACKET as libc::c_ushort;
+        address.sll_protocol = (libc::ETH_P_ALL as u16).to_be();
+        address.sll_ifindex = interface as libc::c_int;
+        let result = unsafe {
+            libc::bind(
+                fd.as_raw_fd(),
+                (&address as *const libc::sockaddr_ll).cast(),
+                mem::size_of::<libc::sockaddr_ll>() as libc::socklen_t,
+            )
+        };
+        if result < 0 {
+            return Err(last_error("bind"));
+        }
+
+        Ok(Self { fd: AsyncFd::new(fd)?, map, interface, link_type })
+    }
+
+    pub fn link_type(&self) -> LinkType {
+        self.link_type
+    }
+
+    // 等待下一个块交给用户态，返回其中的数据包
+    pub async fn next_block(&mut self) -> io::Result<Vec<CapturedPacket>> {
+        let link_type = self.link_type.code();
+        loop {
+            if let Some(packets) = self.map.take_block(self.interface, link_type) {
+                return Ok(packets);
+            }
+            let mut guard = self.fd.readable().await?;
+            if let Some(packets) = self.map.take_block(self.interface, link_type) {
+                return Ok(packets);
+            }
+            guard.clear_ready();
+        }
+    }
+
+    // 读取并清零内核的接收计数
+    pub fn statistics(&self) -> io::Result<RingStatistics> {
+        let mut stats: libc::tpacket_stats_v3 = unsafe { mem::zeroed() };
+        let mut length = mem::size_of::<libc::tpacket_stats_v3>() as libc::socklen_t;
+        let result = unsafe {
+            libc::getsockopt(
+                self.fd.get_ref().as_raw_fd(),
+                libc::SOL_PACKET,
+                libc::PACKET_STATISTICS,
+                (&mut stats as *mut libc::tpacket_stats_v3).cast(),
+                &mut length,
+            )
+        };
+        if result < 0 {
+            return Err(last_error("PACKET_STATISTICS"));
+        }
+        Ok(RingStatistics {
+            packets: u64::from(stats.tp_packets),
+            drops: u64::from(stats.tp_drops),
+            freezes: u64::from(stats.tp_freeze_q_cnt),
+        })
+    }
+}
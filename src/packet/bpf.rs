@@ -0,0 +1,873 @@
+use super::LinkType;
+use std::fmt;
+use std::net::IpAddr;
+use thiserror::Error;
+
+// 经典 BPF 指令编码（linux/filter.h）
+const BPF_LD: u16 = 0x00;
+const BPF_LDX: u16 = 0x01;
+const BPF_ALU: u16 = 0x04;
+const BPF_JMP: u16 = 0x05;
+const BPF_RET: u16 = 0x06;
+
+const BPF_W: u16 = 0x00;
+const BPF_H: u16 = 0x08;
+const BPF_B: u16 = 0x10;
+
+const BPF_ABS: u16 = 0x20;
+const BPF_IND: u16 = 0x40;
+const BPF_LEN: u16 = 0x80;
+const BPF_MSH: u16 = 0xa0;
+
+const BPF_AND: u16 = 0x50;
+const BPF_JA: u16 = 0x00;
+const BPF_JEQ: u16 = 0x10;
+const BPF_JGT: u16 = 0x20;
+const BPF_JGE: u16 = 0x30;
+const BPF_JSET: u16 = 0x40;
+
+// 内核允许的最大指令数（BPF_MAXINSNS）
+const MAX_INSTRUCTIONS: usize = 4096;
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum FilterError {
+    #[error("过滤表达式有误（第 {position} 个词）: {reason}")]
+    Syntax { position: usize, reason: String },
+    #[error("过滤表达式过于复杂，无法生成 BPF 程序")]
+    TooComplex,
+}
+
+// 一条经典 BPF 指令，布局与内核的 struct sock_filter 一致
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(C)]
+pub struct BpfInstruction {
+    pub code: u16,
+    pub jt: u8,
+    pub jf: u8,
+    pub k: u32,
+}
+
+// 经典 BPF 程序：由简单的过滤表达式编译而来，可以挂到 AF_PACKET 套接字上，也可以在用户态执行
+//
+// 表达式语法是 tcpdump 的一个子集：
+//   ip、ip6、arp、tcp、udp、icmp、icmp6
+//   [src|dst] host <IPv4/IPv6 地址>、[src|dst] net <地址/前缀长度>
+//   [tcp|udp] [src|dst] port <端口>、less <长度>、greater <长度>
+// 用 and/&&、or/||、not/! 和括号组合。接受的数据包按 snaplen 截断
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BpfProgram {
+    instructions: Vec<BpfInstruction>,
+}
+
+impl BpfProgram {
+    // 接受所有数据包，只按 snaplen 截断
+    pub fn accept_all(snaplen: u32) -> Self {
+        Self { instructions: vec![stmt(BPF_RET, snaplen)] }
+    }
+
+    // 按链路层类型编译过滤表达式，空表达式接受所有数据包
+    pub fn compile(expression: &str, link_type: LinkType, snaplen: u32) -> Result<Self, FilterError> {
+        let tokens = tokenize(expression);
+        if tokens.is_empty() {
+            return Ok(Self::accept_all(snaplen));
+        }
+        let mut parser = Parser { tokens, position: 0 };
+        let expr = parser.parse_or()?;
+        if let Some(token) = parser.peek() {
+            return Err(parser.error(format!("多余的 '{}'", token)));
+        }
+
+        let network_offset = match link_type {
+            LinkType::Ethernet => 14,
+            LinkType::RawIp => 0,
+            LinkType::LinuxSll => 16,
+        };
+        let mut generator = Generator { link_type, network_offset, ops: Vec::new(), labels: 0 };
+        let accept = generator.label();
+        let reject = generator.label();
+        generator.compile(&lower(&expr), accept, reject);
+        generator.ops.push(Op::Label(accept));
+        generator.ops.push(Op::Stmt(stmt(BPF_RET, snaplen)));
+        generator.ops.push(Op::Label(reject));
+        generator.ops.push(Op::Stmt(stmt(BPF_RET, 0)));
+        Ok(Self { instructions: generator.assemble()? })
+    }
+
+    pub fn instructions(&self) -> &[BpfInstruction] {
+        &self.instructions
+    }
+
+    // 在用户态执行程序，返回接受的字节数（0 表示丢弃）；越界读取与内核一样视为丢弃
+    pub fn run(&self, packet: &[u8]) -> u32 {
+        let load = |size: u16, offset: u32| -> Option<u32> {
+            let offset = offset as usize;
+            let width = match size {
+                BPF_W => 4,
+                BPF_H => 2,
+                _ => 1,
+            };
+            let bytes = packet.get(offset..offset.checked_add(width)?)?;
+            Some(bytes.iter().fold(0u32, |value, &byte| value << 8 | u32::from(byte)))
+        };
+
+        let (mut a, mut x) = (0u32, 0u32);
+        let mut pc = 0usize;
+        while let Some(insn) = self.instructions.get(pc) {
+            pc += 1;
+            let size = insn.code & 0x18;
+            match insn.code & 0x07 {
+                BPF_LD => {
+                    a = match insn.code & 0xe0 {
+                        BPF_ABS => match load(size, insn.k) {
+                            Some(value) => value,
+                            None => return 0,
+                        },
+                        BPF_IND => match load(size, x.wrapping_add(insn.k)) {
+                            Some(value) => value,
+                            None => return 0,
+                        },
+                        BPF_LEN => packet.len() as u32,
+                        _ => insn.k,
+                    }
+                }
+                BPF_LDX => {
+                    x = match insn.code & 0xe0 {
+                        BPF_MSH => match load(BPF_B, insn.k) {
+                            Some(value) => (value & 0x0f) * 4,
+                            None => return 0,
+                        },
+                        _ => insn.k,
+                    }
+                }
+                BPF_ALU => a &= insn.k,
+                BPF_JMP => {
+                    let taken = match insn.code & 0xf0 {
+                        BPF_JA => {
+                            pc += insn.k as usize;
+                            continue;
+                        }
+                        BPF_JEQ => a == insn.k,
+                        BPF_JGT => a > insn.k,
+                        BPF_JGE => a >= insn.k,
+                        _ => a & insn.k != 0,
+                    };
+                    pc += usize::from(if taken { insn.jt } else { insn.jf });
+                }
+                _ => return insn.k,
+            }
+        }
+        0
+    }
+
+    pub fn matches(&self, packet: &[u8]) -> bool {
+        self.run(packet) > 0
+    }
+}
+
+// 按 tcpdump -d 的格式输出，便于排查过滤条件
+impl fmt::Display for BpfProgram {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (index, insn) in self.instructions.iter().enumerate() {
+            let size = match insn.code & 0x18 {
+                BPF_H => "h",
+                BPF_B => "b",
+                _ => "",
+            };
+            let target = |offset: u8| index + 1 + usize::from(offset);
+            let text = match (insn.code & 0x07, insn.code & 0xe0) {
+                (BPF_LD, BPF_ABS) => format!("ld{} [{}]", size, insn.k),
+                (BPF_LD, BPF_IND) => format!("ld{} [x + {}]", size, insn.k),
+                (BPF_LD, BPF_LEN) => "ld #pktlen".to_string(),
+                (BPF_LDX, _) => format!("ldxb 4*([{}]&0xf)", insn.k),
+                (BPF_ALU, _) => format!("and #0x{:x}", insn.k),
+                (BPF_JMP, _) => {
+                    let name = match insn.code & 0xf0 {
+                        BPF_JA => {
+                            writeln!(f, "({:03}) ja {}", index, index + 1 + insn.k as usize)?;
+                            continue;
+                        }
+                        BPF_JEQ => "jeq",
+                        BPF_JGT => "jgt",
+                        BPF_JGE => "jge",
+                        _ => "jset",
+                    };
+                    format!("{} #0x{:x} jt {} jf {}", name, insn.k, target(insn.jt), target(insn.jf))
+                }
+                _ => format!("ret #{}", insn.k),
+            };
+            writeln!(f, "({:03}) {}", index, text)?;
+        }
+        Ok(())
+    }
+}
+
+fn stmt(code: u16, k: u32) -> BpfInstruction {
+    BpfInstruction { code, jt: 0, jf: 0, k }
+}
+
+// 语法树
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Direction {
+    Any,
+    Src,
+    Dst,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Primitive {
+    Ip,
+    Ip6,
+    Arp,
+    Protocol(u8),
+    Icmp,
+    Icmp6,
+    Host(Direction, IpAddr),
+    Net(Direction, IpAddr, u8),
+    Port(Option<u8>, Direction, u16),
+    Less(u32),
+    Greater(u32),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Expr {
+    Primitive(Primitive),
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+}
+
+fn tokenize(expression: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut current = String::new();
+    let mut chars = expression.chars().peekable();
+    while let Some(c) = chars.next() {
+        let symbol = match c {
+            '(' | ')' | '!' => Some(c.to_string()),
+            '&' | '|' if chars.peek() == Some(&c) => {
+                chars.next();
+                Some(format!("{}{}", c, c))
+            }
+            _ => None,
+        };
+        if symbol.is_some() || c.is_whitespace() {
+            if !current.is_empty() {
+                tokens.push(std::mem::take(&mut current));
+            }
+            tokens.extend(symbol);
+        } else {
+            current.push(c);
+        }
+    }
+    if !current.is_empty() {
+        tokens.push(current);
+    }
+    tokens
+}
+
+struct Parser {
+    tokens: Vec<String>,
+    position: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&str> {
+        self.tokens.get(self.position).map(String::as_str)
+    }
+
+    fn peek_at(&self, ahead: usize) -> Option<&str> {
+        self.tokens.get(self.position + ahead).map(String::as_str)
+    }
+
+    fn next(&mut self) -> Option<String> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token
+    }
+
+    // 下一个词有误（例如缺少）
+    fn error(&self, reason: String) -> FilterError {
+        FilterError::Syntax { position: self.position + 1, reason }
+    }
+
+    // 刚读取的词有误
+    fn error_last(&self, reason: String) -> FilterError {
+        FilterError::Syntax { position: self.position, reason }
+    }
+
+    fn expect_value(&mut self, what: &str) -> Result<String, FilterError> {
+        match self.peek() {
+            Some(token) if !matches!(token, "(" | ")" | "!" | "&&" | "||") => Ok(self.next().unwrap_or_default()),
+            _ => Err(self.error(format!("缺少{}", what))),
+        }
+    }
+
+    fn parse_or(&mut self) -> Result<Expr, FilterError> {
+        let mut expr = self.parse_and()?;
+        while matches!(self.peek(), Some("or" | "||")) {
+            self.next();
+            expr = Expr::Or(Box::new(expr), Box::new(self.parse_and()?));
+        }
+        Ok(expr)
+    }
+
+    fn parse_and(&mut self) -> Result<Expr, FilterError> {
+        let mut expr = self.parse_unary()?;
+        while matches!(self.peek(), Some("and" | "&&")) {
+            self.next();
+            expr = Expr::And(Box::new(expr), Box::new(self.parse_unary()?));
+        }
+        Ok(expr)
+    }
+
+    fn parse_unary(&mut self) -> Result<Expr, FilterError> {
+        match self.peek() {
+            Some("not" | "!") => {
+                self.next();
+                Ok(Expr::Not(Box::new(self.parse_unary()?)))
+            }
+            Some("(") => {
+                self.next();
+                let expr = self.parse_or()?;
+                if self.next().as_deref() != Some(")") {
+                    return Err(self.error_last("缺少 ')'".to_string()));
+                }
+                Ok(expr)
+            }
+            Some(_) => Ok(Expr::Primitive(self.parse_primitive()?)),
+            None => Err(self.error("表达式不完整".to_string())),
+        }
+    }
+
+    fn parse_primitive(&mut self) -> Result<Primitive, FilterError> {
+        let token = self.next().unwrap_or_default();
+        let protocol = match token.as_str() {
+            "tcp" => Some(6),
+            "udp" => Some(17),
+            _ => None,
+        };
+        // tcp/udp 后面可以跟端口条件，例如 tcp dst port 80
+        if let Some(protocol) = protocol {
+            let qualified = match self.peek() {
+                Some("port") => true,
+                Some("src" | "dst") => self.peek_at(1) == Some("port"),
+                _ => false,
+            };
+            if !qualified {
+                return Ok(Primitive::Protocol(protocol));
+            }
+            let direction = self.parse_direction();
+            self.next();
+            return Ok(Primitive::Port(Some(protocol), direction, self.parse_port()?));
+        }
+
+        match token.as_str() {
+            "ip" => Ok(Primitive::Ip),
+            "ip6" => Ok(Primitive::Ip6),
+            "arp" => Ok(Primitive::Arp),
+            "icmp" => Ok(Primitive::Icmp),
+            "icmp6" => Ok(Primitive::Icmp6),
+            "less" | "greater" => {
+                let value = self.expect_value("长度")?;
+                let length = value.parse().map_err(|_| self.error_last(format!("无效的长度 '{}'", value)))?;
+                Ok(if token == "less" { Primitive::Less(length) } else { Primitive::Greater(length) })
+            }
+            "src" | "dst" | "host" | "net" | "port" => {
+                self.position -= 1;
+                let direction = self.parse_direction();
+                match self.next().as_deref() {
+                    Some("host") => {
+                        let value = self.expect_value("地址")?;
+                        let address = value.parse().map_err(|_| self.error_last(format!("无效的地址 '{}'", value)))?;
+                        Ok(Primitive::Host(direction, address))
+                    }
+                    Some("net") => {
+                        let value = self.expect_value("网段")?;
+                        let (address, prefix) = self.parse_net(&value)?;
+                        Ok(Primitive::Net(direction, address, prefix))
+                    }
+                    Some("port") => Ok(Primitive::Port(None, direction, self.parse_port()?)),
+                    _ => Err(self.error_last("src/dst 后应为 host、net 或 port".to_string())),
+                }
+            }
+            other => Err(self.error_last(format!("不支持的条件 '{}'", other))),
+        }
+    }
+
+    fn parse_direction(&mut self) -> Direction {
+        let direction = match self.peek() {
+            Some("src") => Direction::Src,
+            Some("dst") => Direction::Dst,
+            _ => return Direction::Any,
+        };
+        self.next();
+        direction
+    }
+
+    fn parse_port(&mut self) -> Result<u16, FilterError> {
+        let value = self.expect_value("端口")?;
+        value.parse().map_err(|_| self.error_last(format!("无效的端口 '{}'", value)))
+    }
+
+    fn parse_net(&self, value: &str) -> Result<(IpAddr, u8), FilterError> {
+        let (address, prefix) = value.split_once('/').unwrap_or((value, ""));
+        let address: IpAddr = address.parse().map_err(|_| self.error_last(format!("无效的网段 '{}'", value)))?;
+        let max_prefix = if address.is_ipv4() { 32 } else { 128 };
+        let prefix = if prefix.is_empty() {
+            max_prefix
+        } else {
+            prefix.parse().ok().filter(|prefix| *prefix <= max_prefix)
+                .ok_or_else(|| self.error_last(format!("无效的前缀长度 '{}'", value)))?
+        };
+        Ok((address, prefix))
+    }
+}
+
+// 展开为对数据包字段的比较
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Load {
+    // 相对数据包开头的固定偏移
+    Absolute(u16, u32),
+    // 相对网络层头的固定偏移
+    Network(u16, u32),
+    // 相对 IPv4 传输层头的偏移（按 IHL 计算）
+    Transport(u16, u32),
+    Length,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Compare {
+    Eq,
+    Gt,
+    Ge,
+    Set,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Test {
+    Check { load: Load, mask: Option<u32>, compare: Compare, value: u32 },
+    // 链路层类型，例如以太网类型或 IP 版本号
+    Network(NetworkType),
+    Never,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum NetworkType {
+    Ipv4,
+    Ipv6,
+    Arp,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Tree {
+    Test(Test),
+    And(Box<Tree>, Box<Tree>),
+    Or(Box<Tree>, Box<Tree>),
+    Not(Box<Tree>),
+}
+
+fn and(a: Tree, b: Tree) -> Tree {
+    Tree::And(Box::new(a), Box::new(b))
+}
+
+fn or(a: Tree, b: Tree) -> Tree {
+    Tree::Or(Box::new(a), Box::new(b))
+}
+
+fn check(load: Load, compare: Compare, value: u32) -> Tree {
+    Tree::Test(Test::Check { load, mask: None, compare, value })
+}
+
+fn masked(load: Load, mask: u32, value: u32) -> Tree {
+    Tree::Test(Test::Check { load, mask: Some(mask), compare: Compare::Eq, value })
+}
+
+fn network(kind: NetworkType) -> Tree {
+    Tree::Test(Test::Network(kind))
+}
+
+// 按方向组合源和目的字段的条件
+fn directed(direction: Direction, test: impl Fn(bool) -> Tree) -> Tree {
+    match direction {
+        Direction::Src => test(true),
+        Direction::Dst => test(false),
+        Direction::Any => or(test(true), test(false)),
+    }
+}
+
+// 从 offset 开始比较地址的前 prefix 位
+fn address_test(offset: u32, octets: &[u8], prefix: u8) -> Tree {
+    let mut tests = Vec::new();
+    for (index, word) in octets.chunks(4).enumerate() {
+        let bits = u32::from(prefix).saturating_sub(index as u32 * 32).min(32);
+        if bits == 0 {
+            break;
+        }
+        let value = u32::from_be_bytes([word[0], word[1], word[2], word[3]]);
+        let mask = if bits == 32 { u32::MAX } else { !(u32::MAX >> bits) };
+        let load = Load::Network(BPF_W, offset + index as u32 * 4);
+        tests.push(if bits == 32 {
+            check(load, Compare::Eq, value)
+        } else {
+            masked(load, mask, value & mask)
+        });
+    }
+    tests.into_iter().reduce(and).unwrap_or(Tree::Not(Box::new(Tree::Test(Test::Never))))
+}
+
+fn ipv4_protocol(protocol: u8) -> Tree {
+    and(network(NetworkType::Ipv4), check(Load::Network(BPF_B, 9), Compare::Eq, u32::from(protocol)))
+}
+
+// 只比较紧跟 IPv6 固定头的下一个头，不展开扩展头
+fn ipv6_protocol(protocol: u8) -> Tree {
+    and(network(NetworkType::Ipv6), check(Load::Network(BPF_B, 6), Compare::Eq, u32::from(protocol)))
+}
+
+fn address(direction: Direction, address: IpAddr, prefix: u8) -> Tree {
+    match address {
+        IpAddr::V4(address) => and(
+            network(NetworkType::Ipv4),
+            directed(direction, |src| address_test(if src { 12 } else { 16 }, &address.octets(), prefix)),
+        ),
+        IpAddr::V6(address) => and(
+            network(NetworkType::Ipv6),
+            directed(direction, |src| address_test(if src { 8 } else { 24 }, &address.octets(), prefix)),
+        ),
+    }
+}
+
+fn port(protocol: Option<u8>, direction: Direction, port: u16) -> Tree {
+    let protocols = |test: fn(u8) -> Tree| match protocol {
+        Some(protocol) => test(protocol),
+        None => or(test(6), test(17)),
+    };
+    // IPv4 只有第一个分片带传输层头
+    let ipv4 = and(
+        protocols(ipv4_protocol),
+        and(
+            Tree::Not(Box::new(Tree::Test(Test::Check {
+                load: Load::Network(BPF_H, 6),
+                mask: None,
+                compare: Compare::Set,
+                value: 0x1fff,
+            }))),
+            directed(direction, |src| check(Load::Transport(BPF_H, if src { 0 } else { 2 }), Compare::Eq, u32::from(port))),
+        ),
+    );
+    let ipv6 = and(
+        protocols(ipv6_protocol),
+        directed(direction, |src| check(Load::Network(BPF_H, if src { 40 } else { 42 }), Compare::Eq, u32::from(port))),
+    );
+    or(ipv4, ipv6)
+}
+
+fn lower(expr: &Expr) -> Tree {
+    match expr {
+        Expr::And(a, b) => and(lower(a), lower(b)),
+        Expr::Or(a, b) => or(lower(a), lower(b)),
+        Expr::Not(a) => Tree::Not(Box::new(lower(a))),
+        Expr::Primitive(primitive) => match *primitive {
+            Primitive::Ip => network(NetworkType::Ipv4),
+            Primitive::Ip6 => network(NetworkType::Ipv6),
+            Primitive::Arp => network(NetworkType::Arp),
+            Primitive::Protocol(protocol) => or(ipv4_protocol(protocol), ipv6_protocol(protocol)),
+            Primitive::Icmp => ipv4_protocol(1),
+            Primitive::Icmp6 => ipv6_protocol(58),
+            Primitive::Host(direction, host) => {
+                let prefix = if host.is_ipv4() { 32 } else { 128 };
+                address(direction, host, prefix)
+            }
+            Primitive::Net(direction, net, prefix) => address(direction, net, prefix),
+            Primitive::Port(protocol, direction, number) => port(protocol, direction, number),
+            Primitive::Less(length) => Tree::Not(Box::new(check(Load::Length, Compare::Gt, length))),
+            Primitive::Greater(length) => check(Load::Length, Compare::Ge, length),
+        },
+    }
+}
+
+// 代码生成：条件跳转只能向前跳，先用标签表示跳转目标，最后统一计算偏移
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Label(usize);
+
+enum Op {
+    Stmt(BpfInstruction),
+    Jump { code: u16, k: u32, jt: Label, jf: Label },
+    Goto(Label),
+    Label(Label),
+}
+
+struct Generator {
+    link_type: LinkType,
+    network_offset: u32,
+    ops: Vec<Op>,
+    labels: usize,
+}
+
+impl Generator {
+    fn label(&mut self) -> Label {
+        self.labels += 1;
+        Label(self.labels - 1)
+    }
+
+    fn compile(&mut self, tree: &Tree, on_true: Label, on_false: Label) {
+        match tree {
+            Tree::And(a, b) => {
+                let next = self.label();
+                self.compile(a, next, on_false);
+                self.ops.push(Op::Label(next));
+                self.compile(b, on_true, on_false);
+            }
+            Tree::Or(a, b) => {
+                let next = self.label();
+                self.compile(a, on_true, next);
+                self.ops.push(Op::Label(next));
+                self.compile(b, on_true, on_false);
+            }
+            Tree::Not(a) => self.compile(a, on_false, on_true),
+            Tree::Test(test) => self.compile_test(test, on_true, on_false),
+        }
+    }
+
+    fn compile_test(&mut self, test: &Test, on_true: Label, on_false: Label) {
+        let (load, mask, compare, value) = match *test {
+            Test::Check { load, mask, compare, value } => (load, mask, compare, value),
+            Test::Never => {
+                self.ops.push(Op::Goto(on_false));
+                return;
+            }
+            Test::Network(kind) => match self.link_type {
+                LinkType::Ethernet | LinkType::LinuxSll => {
+                    let ether_type = match kind {
+                        NetworkType::Ipv4 => 0x0800,
+                        NetworkType::Ipv6 => 0x86dd,
+                        NetworkType::Arp => 0x0806,
+                    };
+                    // 以太网类型（SLL 的协议类型）位于网络层头之前的两个字节
+                    (Load::Absolute(BPF_H, self.network_offset - 2), None, Compare::Eq, ether_type)
+                }
+                LinkType::RawIp => match kind {
+                    NetworkType::Ipv4 => (Load::Network(BPF_B, 0), Some(0xf0), Compare::Eq, 0x40),
+                    NetworkType::Ipv6 => (Load::Network(BPF_B, 0), Some(0xf0), Compare::Eq, 0x60),
+                    NetworkType::Arp => {
+                        self.ops.push(Op::Goto(on_false));
+                        return;
+                    }
+                },
+            },
+        };
+
+        match load {
+            Load::Absolute(size, offset) => self.ops.push(Op::Stmt(stmt(BPF_LD | size | BPF_ABS, offset))),
+            Load::Network(size, offset) => {
+                self.ops.push(Op::Stmt(stmt(BPF_LD | size | BPF_ABS, self.network_offset + offset)));
+            }
+            Load::Transport(size, offset) => {
+                self.ops.push(Op::Stmt(stmt(BPF_LDX | BPF_B | BPF_MSH, self.network_offset)));
+                self.ops.push(Op::Stmt(stmt(BPF_LD | size | BPF_IND, self.network_offset + offset)));
+            }
+            Load::Length => self.ops.push(Op::Stmt(stmt(BPF_LD | BPF_W | BPF_LEN, 0))),
+        }
+        if let Some(mask) = mask {
+            self.ops.push(Op::Stmt(stmt(BPF_ALU | BPF_AND, mask)));
+        }
+        let code = BPF_JMP | match compare {
+            Compare::Eq => BPF_JEQ,
+            Compare::Gt => BPF_JGT,
+            Compare::Ge => BPF_JGE,
+            Compare::Set => BPF_JSET,
+        };
+        self.ops.push(Op::Jump { code, k: value, jt: on_true, jf: on_false });
+    }
+
+    fn assemble(self) -> Result<Vec<BpfInstruction>, FilterError> {
+        let mut positions = vec![0usize; self.labels];
+        let mut count = 0;
+        for op in &self.ops {
+            match op {
+                Op::Label(label) => positions[label.0] = count,
+                _ => count += 1,
+            }
+        }
+        if count > MAX_INSTRUCTIONS {
+            return Err(FilterError::TooComplex);
+        }
+
+        let mut instructions = Vec::with_capacity(count);
+        for op in self.ops {
+            let index = instructions.len();
+            let offset = |label: Label| positions[label.0] - index - 1;
+            instructions.push(match op {
+                Op::Label(_) => continue,
+                Op::Stmt(insn) => insn,
+                Op::Goto(target) => stmt(BPF_JMP | BPF_JA, offset(target) as u32),
+                Op::Jump { code, k, jt, jf } => BpfInstruction {
+                    code,
+                    jt: u8::try_from(offset(jt)).map_err(|_| FilterError::TooComplex)?,
+                    jf: u8::try_from(offset(jf)).map_err(|_| FilterError::TooComplex)?,
+                    k,
+                },
+            });
+        }
+        Ok(instructions)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ipv4(protocol: u8, src: [u8; 4], dst: [u8; 4], flags_fragment: u16, payload: &[u8]) -> Vec<u8> {
+        let mut packet = vec![0x45, 0];
+        packet.extend_from_slice(&(20 + payload.len() as u16).to_be_bytes());
+        packet.extend_from_slice(&[0, 1]);
+        packet.extend_from_slice(&flags_fragment.to_be_bytes());
+        packet.extend_from_slice(&[64, protocol, 0, 0]);
+        packet.extend_from_slice(&src);
+        packet.extend_from_slice(&dst);
+        packet.extend_from_slice(payload);
+        packet
+    }
+
+    fn ipv6(next_header: u8, src: &str, dst: &str, payload: &[u8]) -> Vec<u8> {
+        let mut packet = vec![0x60, 0, 0, 0];
+        packet.extend_from_slice(&(payload.len() as u16).to_be_bytes());
+        packet.extend_from_slice(&[next_header, 64]);
+        packet.extend_from_slice(&src.parse::<std::net::Ipv6Addr>().unwrap().octets());
+        packet.extend_from_slice(&dst.parse::<std::net::Ipv6Addr>().unwrap().octets());
+        packet.extend_from_slice(payload);
+        packet
+    }
+
+    // 端口之后补齐到 20 字节，足够 TCP 和 UDP 头
+    fn ports(src: u16, dst: u16) -> Vec<u8> {
+        let mut header = Vec::new();
+        header.extend_from_slice(&src.to_be_bytes());
+        header.extend_from_slice(&dst.to_be_bytes());
+        header.resize(20, 0);
+        header
+    }
+
+    fn ethernet(ether_type: u16, payload: &[u8]) -> Vec<u8> {
+        let mut frame = vec![0xff; 12];
+        frame.extend_from_slice(&ether_type.to_be_bytes());
+        frame.extend_from_slice(payload);
+        frame
+    }
+
+    fn compile(expression: &str) -> BpfProgram {
+        BpfProgram::compile(expression, LinkType::Ethernet, 65535).unwrap()
+    }
+
+    #[test]
+    fn empty_expression_accepts_everything() {
+        let program = BpfProgram::compile("  ", LinkType::Ethernet, 128).unwrap();
+        assert_eq!(program, BpfProgram::accept_all(128));
+        assert_eq!(program.run(&[0; 10]), 128);
+    }
+
+    #[test]
+    fn ip_filter_matches_tcpdump_output() {
+        let program = compile("ip");
+        assert_eq!(
+            program.to_string(),
+            "(000) ldh [12]\n(001) jeq #0x800 jt 2 jf 3\n(002) ret #65535\n(003) ret #0\n"
+        );
+        assert!(program.matches(&ethernet(0x0800, &[0x45; 20])));
+        assert!(!program.matches(&ethernet(0x86dd, &[0x60; 40])));
+    }
+
+    #[test]
+    fn tcp_port_filter_follows_ipv4_header_length() {
+        let program = compile("tcp dst port 80");
+        let http = ethernet(0x0800, &ipv4(6, [10, 0, 0, 1], [10, 0, 0, 2], 0x4000, &ports(40000, 80)));
+        let reply = ethernet(0x0800, &ipv4(6, [10, 0, 0, 2], [10, 0, 0, 1], 0x4000, &ports(80, 40000)));
+        let udp = ethernet(0x0800, &ipv4(17, [10, 0, 0, 1], [10, 0, 0, 2], 0, &ports(40000, 80)));
+        assert!(program.matches(&http));
+        assert!(!program.matches(&reply));
+        assert!(!program.matches(&udp));
+        assert!(compile("port 80").matches(&reply));
+
+        // 带 4 字节选项的 IPv4 头，端口偏移随 IHL 变化
+        let mut options = ipv4(6, [10, 0, 0, 1], [10, 0, 0, 2], 0, &[]);
+        options[0] = 0x46;
+        options.extend_from_slice(&[1, 1, 1, 0]);
+        options.extend_from_slice(&ports(40000, 80));
+        assert!(program.matches(&ethernet(0x0800, &options)));
+    }
+
+    #[test]
+    fn port_filter_skips_later_ipv4_fragments() {
+        let program = compile("port 53");
+        let first = ethernet(0x0800, &ipv4(17, [1, 1, 1, 1], [8, 8, 8, 8], 0x2000, &ports(5353, 53)));
+        let later = ethernet(0x0800, &ipv4(17, [1, 1, 1, 1], [8, 8, 8, 8], 0x0010, &ports(5353, 53)));
+        assert!(program.matches(&first));
+        assert!(!program.matches(&later));
+    }
+
+    #[test]
+    fn host_and_net_filters_compare_addresses() {
+        let packet = ethernet(0x0800, &ipv4(1, [192, 168, 1, 20], [10, 0, 0, 1], 0, &[8, 0, 0, 0]));
+        assert!(compile("src host 192.168.1.20").matches(&packet));
+        assert!(!compile("dst host 192.168.1.20").matches(&packet));
+        assert!(compile("net 192.168.0.0/16 and icmp").matches(&packet));
+        assert!(!compile("src net 192.168.2.0/24").matches(&packet));
+        assert!(compile("dst net 10.0.0.0/8 && !tcp").matches(&packet));
+
+        let v6 = ethernet(0x86dd, &ipv6(17, "2001:db8::1", "fe80::2", &ports(1000, 443)));
+        assert!(compile("ip6 and src net 2001:db8::/32").matches(&v6));
+        assert!(compile("dst host fe80::2 and udp port 443").matches(&v6));
+        assert!(!compile("host 2001:db8::2").matches(&v6));
+        assert!(!compile("host 192.168.1.20").matches(&v6));
+    }
+
+    #[test]
+    fn boolean_operators_and_length_tests() {
+        let short = ethernet(0x0806, &[0; 28]);
+        let long = ethernet(0x0800, &ipv4(17, [1, 2, 3, 4], [5, 6, 7, 8], 0, &[0; 200]));
+        let program = compile("arp or (udp and greater 200)");
+        assert!(program.matches(&short));
+        assert!(program.matches(&long));
+        assert!(!compile("not (arp or udp)").matches(&long));
+        assert!(compile("less 42").matches(&short));
+        assert!(!compile("less 41").matches(&short));
+    }
+
+    #[test]
+    fn link_type_changes_network_offset() {
+        let packet = ipv4(6, [10, 0, 0, 1], [10, 0, 0, 2], 0, &ports(22, 50000));
+        let raw = BpfProgram::compile("ip and tcp src port 22", LinkType::RawIp, 96).unwrap();
+        assert_eq!(raw.run(&packet), 96);
+        assert!(!BpfProgram::compile("arp", LinkType::RawIp, 96).unwrap().matches(&packet));
+        assert!(!BpfProgram::compile("ip6", LinkType::RawIp, 96).unwrap().matches(&packet));
+
+        let mut sll = vec![0; 14];
+        sll.extend_from_slice(&0x0800u16.to_be_bytes());
+        sll.extend_from_slice(&packet);
+        let program = BpfProgram::compile("tcp port 22", LinkType::LinuxSll, 96).unwrap();
+        assert!(program.matches(&sll));
+        assert!(!program.matches(&ethernet(0x0800, &packet)));
+    }
+
+    #[test]
+    fn out_of_bounds_loads_reject_packet() {
+        let program = compile("tcp port 80");
+        let packet = ethernet(0x0800, &ipv4(6, [10, 0, 0, 1], [10, 0, 0, 2], 0, &ports(40000, 80)));
+        assert!(program.matches(&packet));
+        assert!(!program.matches(&packet[..14 + 20 + 2]));
+        assert!(!program.matches(&packet[..13]));
+    }
+
+    #[test]
+    fn syntax_errors_report_position() {
+        let error = |expression| BpfProgram::compile(expression, LinkType::Ethernet, 100).unwrap_err();
+        assert!(matches!(error("tcp and"), FilterError::Syntax { position: 3, .. }));
+        assert!(matches!(error("host 300.1.1.1"), FilterError::Syntax { position: 2, .. }));
+        assert!(matches!(error("(ip or arp"), FilterError::Syntax { .. }));
+        assert!(matches!(error("net 10.0.0.0/33"), FilterError::Syntax { .. }));
+        assert!(matches!(error("vlan 10"), FilterError::Syntax { position: 1, .. }));
+        assert!(matches!(error("ip arp"), FilterError::Syntax { position: 2, .. }));
+    }
+}
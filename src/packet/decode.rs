@@ -0,0 +1,620 @@
+use super::LinkType;
+use std::fmt;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use thiserror::Error;
+
+const ETHER_TYPE_IPV4: u16 = 0x0800;
+const ETHER_TYPE_IPV6: u16 = 0x86dd;
+const ETHER_TYPE_VLAN: u16 = 0x8100;
+const ETHER_TYPE_QINQ: u16 = 0x88a8;
+
+const IP_PROTOCOL_ICMP: u8 = 1;
+const IP_PROTOCOL_TCP: u8 = 6;
+const IP_PROTOCOL_UDP: u8 = 17;
+const IP_PROTOCOL_ICMPV6: u8 = 58;
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum DecodeError {
+    #[error("{layer} 头不完整: 需要 {needed} 字节，实际 {available} 字节")]
+    Truncated { layer: &'static str, needed: usize, available: usize },
+    #[error("{layer} 头无效: {reason}")]
+    Invalid { layer: &'static str, reason: String },
+}
+
+fn need(layer: &'static str, data: &[u8], needed: usize) -> Result<(), DecodeError> {
+    if data.len() < needed {
+        return Err(DecodeError::Truncated { layer, needed, available: data.len() });
+    }
+    Ok(())
+}
+
+fn u16_at(data: &[u8], offset: usize) -> u16 {
+    u16::from_be_bytes([data[offset], data[offset + 1]])
+}
+
+fn u32_at(data: &[u8], offset: usize) -> u32 {
+    u32::from_be_bytes([data[offset], data[offset + 1], data[offset + 2], data[offset + 3]])
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MacAddr(pub [u8; 6]);
+
+impl fmt::Display for MacAddr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let [a, b, c, d, e, g] = self.0;
+        write!(f, "{:02x}:{:02x}:{:02x}:{:02x}:{:02x}:{:02x}", a, b, c, d, e, g)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EthernetHeader {
+    pub src: MacAddr,
+    pub dst: MacAddr,
+    // 去掉 VLAN 标签后的上层协议类型
+    pub ether_type: u16,
+    // VLAN ID，外层在前
+    pub vlans: Vec<u16>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IpHeader {
+    pub version: u8,
+    pub src: IpAddr,
+    pub dst: IpAddr,
+    // 上层协议号；IPv6 为扩展头之后的协议
+    pub protocol: u8,
+    // IPv4 TTL 或 IPv6 跳数限制
+    pub ttl: u8,
+    // 包括 IPv4 选项或 IPv6 扩展头
+    pub header_len: usize,
+    // IPv4 选项的字节数
+    pub options_len: usize,
+    // IPv6 扩展头的协议号，按出现顺序
+    pub extension_headers: Vec<u8>,
+    // 非首个分片：没有上层协议头
+    pub fragment: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TcpHeader {
+    pub src_port: u16,
+    pub dst_port: u16,
+    pub seq: u32,
+    pub ack: u32,
+    pub flags: u8,
+    pub window: u16,
+    pub header_len: usize,
+}
+
+impl TcpHeader {
+    // 标志位的文本形式，例如 "SYN|ACK"
+    pub fn flag_names(&self) -> String {
+        const NAMES: [&str; 8] = ["FIN", "SYN", "RST", "PSH", "ACK", "URG", "ECE", "CWR"];
+        NAMES
+            .iter()
+            .enumerate()
+            .filter(|(bit, _)| self.flags & (1 << bit) != 0)
+            .map(|(_, name)| *name)
+            .collect::<Vec<_>>()
+            .join("|")
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UdpHeader {
+    pub src_port: u16,
+    pub dst_port: u16,
+    pub length: u16,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Transport {
+    Tcp(TcpHeader),
+    Udp(UdpHeader),
+    Icmp { icmp_type: u8, code: u8 },
+    Icmpv6 { icmp_type: u8, code: u8 },
+}
+
+impl Transport {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Transport::Tcp(_) => "TCP",
+            Transport::Udp(_) => "UDP",
+            Transport::Icmp { .. } => "ICMP",
+            Transport::Icmpv6 { .. } => "ICMPv6",
+        }
+    }
+
+    pub fn ports(&self) -> Option<(u16, u16)> {
+        match self {
+            Transport::Tcp(tcp) => Some((tcp.src_port, tcp.dst_port)),
+            Transport::Udp(udp) => Some((udp.src_port, udp.dst_port)),
+            _ => None,
+        }
+    }
+}
+
+// 一个帧的解码结果；payload 为最内层已解码协议之后的数据在帧中的区间
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DecodedPacket {
+    pub link_type: LinkType,
+    pub ethernet: Option<EthernetHeader>,
+    pub ip: Option<IpHeader>,
+    pub transport: Option<Transport>,
+    pub payload_offset: usize,
+    pub payload_len: usize,
+    // 抓包长度小于 IP 总长度且截断在传输层头中：解码到 IP 层为止
+    pub truncated: bool,
+}
+
+impl DecodedPacket {
+    // 按链路层类型逐层解码；遇到不认识的协议时停在该层，头部不完整或无效时报错
+    pub fn decode(link_type: LinkType, data: &[u8]) -> Result<Self, DecodeError> {
+        let mut packet = DecodedPacket {
+            link_type,
+            ethernet: None,
+            ip: None,
+            transport: None,
+            payload_offset: 0,
+            payload_len: data.len(),
+            truncated: false,
+        };
+
+        let (ether_type, offset) = match link_type {
+            LinkType::Ethernet => {
+                let (ethernet, offset) = decode_ethernet(data)?;
+                let ether_type = ethernet.ether_type;
+                packet.ethernet = Some(ethernet);
+                (Some(ether_type), offset)
+            }
+            LinkType::LinuxSll => {
+                need("Linux SLL", data, 16)?;
+                (Some(u16_at(data, 14)), 16)
+            }
+            LinkType::RawIp => (None, 0),
+        };
+        packet.set_payload(offset, data.len());
+
+        let ip = &data[offset..];
+        let decoded = match ether_type {
+            Some(ETHER_TYPE_IPV4) => decode_ipv4(ip)?,
+            Some(ETHER_TYPE_IPV6) => decode_ipv6(ip)?,
+            Some(_) => return Ok(packet),
+            None => match ip.first().map(|byte| byte >> 4) {
+                Some(4) => decode_ipv4(ip)?,
+                Some(6) => decode_ipv6(ip)?,
+                _ => {
+                    return Err(DecodeError::Invalid { layer: "IP", reason: "无法识别的 IP 版本".to_string() })
+                }
+            },
+        };
+        let (header, ip_len) = decoded;
+        // 抓包长度可能小于 IP 总长度，按实际捕获的数据计算
+        let end = offset + ip_len.min(ip.len());
+        let l4 = offset + header.header_len;
+        packet.set_payload(l4, end);
+
+        if !header.fragment {
+            match decode_transport(header.protocol, &data[l4..end]) {
+                Ok(Some((transport, header_len))) => {
+                    packet.transport = Some(transport);
+                    packet.set_payload(l4 + header_len, end);
+                }
+                Ok(None) => {}
+                Err(DecodeError::Truncated { .. }) if ip_len > ip.len() => packet.truncated = true,
+                Err(e) => return Err(e),
+            }
+        }
+        packet.ip = Some(header);
+        Ok(packet)
+    }
+
+    fn set_payload(&mut self, start: usize, end: usize) {
+        self.payload_offset = start;
+        self.payload_len = end.saturating_sub(start);
+    }
+
+    // 已知的应用层协议（按端口判断）：DNS、HTTP
+    pub fn application(&self) -> Option<&'static str> {
+        let (src, dst) = self.transport.as_ref()?.ports()?;
+        [src, dst].into_iter().find_map(|port| match port {
+            53 => Some("DNS"),
+            80 | 8080 => Some("HTTP"),
+            _ => None,
+        })
+    }
+
+    // 最具体的协议名：应用层协议、传输层协议、IPv4/IPv6 或 OTHER
+    pub fn protocol(&self) -> &'static str {
+        if let Some(application) = self.application() {
+            return application;
+        }
+        match (&self.transport, &self.ip) {
+            (Some(transport), _) => transport.name(),
+            (None, Some(ip)) if ip.version == 4 => "IPv4",
+            (None, Some(_)) => "IPv6",
+            (None, None) => "OTHER",
+        }
+    }
+}
+
+fn decode_ethernet(data: &[u8]) -> Result<(EthernetHeader, usize), DecodeError> {
+    need("Ethernet", data, 14)?;
+    let mut dst = [0u8; 6];
+    let mut src = [0u8; 6];
+    dst.copy_from_slice(&data[0..6]);
+    src.copy_from_slice(&data[6..12]);
+
+    let mut ether_type = u16_at(data, 12);
+    let mut offset = 14;
+    let mut vlans = Vec::new();
+    while matches!(ether_type, ETHER_TYPE_VLAN | ETHER_TYPE_QINQ) {
+        need("802.1Q", data, offset + 4)?;
+        vlans.push(u16_at(data, offset) & 0x0fff);
+        ether_type = u16_at(data, offset + 2);
+        offset += 4;
+    }
+
+    Ok((EthernetHeader { src: MacAddr(src), dst: MacAddr(dst), ether_type, vlans }, offset))
+}
+
+// 返回 IP 头和 IP 包总长度
+fn decode_ipv4(data: &[u8]) -> Result<(IpHeader, usize), DecodeError> {
+    need("IPv4", data, 20)?;
+    let version = data[0] >> 4;
+    if version != 4 {
+        return Err(DecodeError::Invalid { layer: "IPv4", reason: format!("版本号为 {}", version) });
+    }
+    let header_len = usize::from(data[0] & 0x0f) * 4;
+    if header_len < 20 {
+        return Err(DecodeError::Invalid { layer: "IPv4", reason: format!("头长度 {} 小于 20", header_len) });
+    }
+    need("IPv4", data, header_len)?;
+    // 网卡分段卸载时抓到的包总长度可能为 0，按捕获长度处理
+    let total_len = match u16_at(data, 2) {
+        0 => data.len(),
+        len => usize::from(len),
+    };
+    if total_len < header_len {
+        return Err(DecodeError::Invalid {
+            layer: "IPv4",
+            reason: format!("总长度 {} 小于头长度 {}", total_len, header_len),
+        });
+    }
+
+    let fragment_offset = u16_at(data, 6) & 0x1fff;
+    let header = IpHeader {
+        version,
+        src: IpAddr::V4(Ipv4Addr::new(data[12], data[13], data[14], data[15])),
+        dst: IpAddr::V4(Ipv4Addr::new(data[16], data[17], data[18], data[19])),
+        protocol: data[9],
+        ttl: data[8],
+        header_len,
+        options_len: header_len - 20,
+        extension_headers: Vec::new(),
+        fragment: fragment_offset != 0,
+    };
+    Ok((header, total_len))
+}
+
+fn decode_ipv6(data: &[u8]) -> Result<(IpHeader, usize), DecodeError> {
+    need("IPv6", data, 40)?;
+    let version = data[0] >> 4;
+    if version != 6 {
+        return Err(DecodeError::Invalid { layer: "IPv6", reason: format!("版本号为 {}", version) });
+    }
+    let mut src = [0u8; 16];
+    let mut dst = [0u8; 16];
+    src.copy_from_slice(&data[8..24]);
+    dst.copy_from_slice(&data[24..40]);
+
+    let mut protocol = data[6];
+    let mut header_len = 40;
+    let mut extension_headers = Vec::new();
+    let mut fragment = false;
+    loop {
+        let ext_len = match protocol {
+            // 逐跳选项、路由、目的选项：长度以 8 字节为单位，不含前 8 字节
+            0 | 43 | 60 => {
+                need("IPv6 扩展头", data, header_len + 2)?;
+                (usize::from(data[header_len + 1]) + 1) * 8
+            }
+            // 分片头固定 8 字节
+            44 => {
+                need("IPv6 分片头", data, header_len + 8)?;
+                fragment = u16_at(data, header_len + 2) >> 3 != 0;
+                8
+            }
+            // 认证头：长度以 4 字节为单位，不含前 8 字节
+            51 => {
+                need("IPv6 认证头", data, header_len + 2)?;
+                (usize::from(data[header_len + 1]) + 2) * 4
+            }
+            _ => break,
+        };
+        need("IPv6 扩展头", data, header_len + ext_len)?;
+        extension_headers.push(protocol);
+        protocol = data[header_len];
+        header_len += ext_len;
+        if fragment {
+            break;
+        }
+    }
+
+    // 扩展头长度计入负载长度，超过时不能把扩展头之后的数据当作上层协议
+    let total_len = 40 + usize::from(u16_at(data, 4));
+    if header_len > total_len {
+        return Err(DecodeError::Invalid {
+            layer: "IPv6",
+            reason: format!("扩展头结束于第 {} 字节，超过包总长度 {}", header_len, total_len),
+        });
+    }
+
+    let header = IpHeader {
+        version,
+        src: IpAddr::V6(Ipv6Addr::from(src)),
+        dst: IpAddr::V6(Ipv6Addr::from(dst)),
+        protocol,
+        ttl: data[7],
+        header_len,
+        options_len: 0,
+        extension_headers,
+        fragment,
+    };
+    Ok((header, total_len))
+}
+
+// 不认识的协议返回 None；返回传输层头和头长度
+fn decode_transport(protocol: u8, data: &[u8]) -> Result<Option<(Transport, usize)>, DecodeError> {
+    let decoded = match protocol {
+        IP_PROTOCOL_TCP => {
+            need("TCP", data, 20)?;
+            let header_len = usize::from(data[12] >> 4) * 4;
+            if header_len < 20 {
+                return Err(DecodeError::Invalid { layer: "TCP", reason: format!("头长度 {} 小于 20", header_len) });
+            }
+            need("TCP", data, header_len)?;
+            let tcp = TcpHeader {
+                src_port: u16_at(data, 0),
+                dst_port: u16_at(data, 2),
+                seq: u32_at(data, 4),
+                ack: u32_at(data, 8),
+                flags: data[13],
+                window: u16_at(data, 14),
+                header_len,
+            };
+            (Transport::Tcp(tcp), header_len)
+        }
+        IP_PROTOCOL_UDP => {
+            need("UDP", data, 8)?;
+            let udp = UdpHeader { src_port: u16_at(data, 0), dst_port: u16_at(data, 2), length: u16_at(data, 4) };
+            (Transport::Udp(udp), 8)
+        }
+        IP_PROTOCOL_ICMP => {
+            need("ICMP", data, 8)?;
+            (Transport::Icmp { icmp_type: data[0], code: data[1] }, 8)
+        }
+        IP_PROTOCOL_ICMPV6 => {
+            need("ICMPv6", data, 4)?;
+            (Transport::Icmpv6 { icmp_type: data[0], code: data[1] }, 4)
+        }
+        _ => return Ok(None),
+    };
+    Ok(Some(decoded))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MAC_A: [u8; 6] = [0x02, 0, 0, 0, 0, 0x01];
+    const MAC_B: [u8; 6] = [0x02, 0, 0, 0, 0, 0x02];
+
+    fn ethernet(ether_type: u16, vlans: &[(u16, u16)], payload: &[u8]) -> Vec<u8> {
+        let mut frame = Vec::new();
+        frame.extend_from_slice(&MAC_B);
+        frame.extend_from_slice(&MAC_A);
+        for (tpid, vlan) in vlans {
+            frame.extend_from_slice(&tpid.to_be_bytes());
+            frame.extend_from_slice(&vlan.to_be_bytes());
+        }
+        frame.extend_from_slice(&ether_type.to_be_bytes());
+        frame.extend_from_slice(payload);
+        frame
+    }
+
+    fn ipv4(protocol: u8, flags_fragment: u16, payload: &[u8]) -> Vec<u8> {
+        let mut packet = vec![0x45, 0];
+        packet.extend_from_slice(&(20 + payload.len() as u16).to_be_bytes());
+        packet.extend_from_slice(&[0, 1]);
+        packet.extend_from_slice(&flags_fragment.to_be_bytes());
+        packet.extend_from_slice(&[64, protocol, 0, 0]);
+        packet.extend_from_slice(&[10, 0, 0, 1, 10, 0, 0, 2]);
+        packet.extend_from_slice(payload);
+        packet
+    }
+
+    // extensions 为已编码的扩展头（首字节为下一个头的协议号）
+    fn ipv6(next_header: u8, extensions: &[u8], payload: &[u8]) -> Vec<u8> {
+        let mut packet = vec![0x60, 0, 0, 0];
+        packet.extend_from_slice(&((extensions.len() + payload.len()) as u16).to_be_bytes());
+        packet.extend_from_slice(&[next_header, 64]);
+        packet.extend_from_slice(&Ipv6Addr::LOCALHOST.octets());
+        packet.extend_from_slice(&"2001:db8::1".parse::<Ipv6Addr>().unwrap().octets());
+        packet.extend_from_slice(extensions);
+        packet.extend_from_slice(payload);
+        packet
+    }
+
+    fn tcp(src_port: u16, dst_port: u16, flags: u8, payload: &[u8]) -> Vec<u8> {
+        let mut segment = Vec::new();
+        segment.extend_from_slice(&src_port.to_be_bytes());
+        segment.extend_from_slice(&dst_port.to_be_bytes());
+        segment.extend_from_slice(&1000u32.to_be_bytes());
+        segment.extend_from_slice(&0u32.to_be_bytes());
+        segment.extend_from_slice(&[0x50, flags, 0xff, 0xff, 0, 0, 0, 0]);
+        segment.extend_from_slice(payload);
+        segment
+    }
+
+    fn udp(src_port: u16, dst_port: u16, payload: &[u8]) -> Vec<u8> {
+        let mut datagram = Vec::new();
+        datagram.extend_from_slice(&src_port.to_be_bytes());
+        datagram.extend_from_slice(&dst_port.to_be_bytes());
+        datagram.extend_from_slice(&(8 + payload.len() as u16).to_be_bytes());
+        datagram.extend_from_slice(&[0, 0]);
+        datagram.extend_from_slice(payload);
+        datagram
+    }
+
+    fn payload<'a>(packet: &DecodedPacket, frame: &'a [u8]) -> &'a [u8] {
+        &frame[packet.payload_offset..packet.payload_offset + packet.payload_len]
+    }
+
+    #[test]
+    fn decodes_ipv4_tcp_over_ethernet() {
+        let frame = ethernet(ETHER_TYPE_IPV4, &[], &ipv4(IP_PROTOCOL_TCP, 0x4000, &tcp(40000, 80, 0x12, b"GET /")));
+        let packet = DecodedPacket::decode(LinkType::Ethernet, &frame).unwrap();
+
+        let ethernet = packet.ethernet.as_ref().unwrap();
+        assert_eq!(ethernet.src, MacAddr(MAC_A));
+        assert!(ethernet.vlans.is_empty());
+        let ip = packet.ip.as_ref().unwrap();
+        assert_eq!(ip.src, IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1)));
+        assert_eq!((ip.ttl, ip.header_len, ip.fragment), (64, 20, false));
+        match packet.transport.as_ref().unwrap() {
+            Transport::Tcp(tcp) => {
+                assert_eq!((tcp.src_port, tcp.dst_port), (40000, 80));
+                assert_eq!(tcp.flag_names(), "SYN|ACK");
+            }
+            other => panic!("unexpected transport {:?}", other),
+        }
+        assert_eq!(payload(&packet, &frame), b"GET /");
+        assert_eq!(packet.protocol(), "HTTP");
+    }
+
+    #[test]
+    fn strips_vlan_and_qinq_tags() {
+        let ip = ipv4(IP_PROTOCOL_UDP, 0, &udp(5353, 53, b"q"));
+        let frame = ethernet(ETHER_TYPE_IPV4, &[(ETHER_TYPE_QINQ, 0x2064), (ETHER_TYPE_VLAN, 0x0007)], &ip);
+        let packet = DecodedPacket::decode(LinkType::Ethernet, &frame).unwrap();
+
+        let ethernet = packet.ethernet.as_ref().unwrap();
+        assert_eq!(ethernet.vlans, vec![100, 7]);
+        assert_eq!(ethernet.ether_type, ETHER_TYPE_IPV4);
+        assert_eq!(packet.protocol(), "DNS");
+        assert_eq!(payload(&packet, &frame), b"q");
+
+        // VLAN 标签本身被截断
+        let err = DecodedPacket::decode(LinkType::Ethernet, &frame[..16]).unwrap_err();
+        assert!(matches!(err, DecodeError::Truncated { layer: "802.1Q", .. }), "{:?}", err);
+    }
+
+    #[test]
+    fn walks_ipv6_extension_header_chain() {
+        // 逐跳选项(8) -> 路由头(24) -> 目的选项(8) -> UDP
+        let mut extensions = vec![43, 0, 0, 0, 0, 0, 0, 0];
+        extensions.extend_from_slice(&[60, 2, 0, 0]);
+        extensions.extend_from_slice(&[0; 20]);
+        extensions.extend_from_slice(&[IP_PROTOCOL_UDP, 0, 0, 0, 0, 0, 0, 0]);
+        let frame = ipv6(0, &extensions, &udp(1234, 5678, b"data"));
+        let packet = DecodedPacket::decode(LinkType::RawIp, &frame).unwrap();
+
+        let ip = packet.ip.as_ref().unwrap();
+        assert_eq!(ip.version, 6);
+        assert_eq!(ip.extension_headers, vec![0, 43, 60]);
+        assert_eq!(ip.protocol, IP_PROTOCOL_UDP);
+        assert_eq!(ip.header_len, 40 + 40);
+        assert_eq!(packet.transport.as_ref().unwrap().ports(), Some((1234, 5678)));
+        assert_eq!(payload(&packet, &frame), b"data");
+    }
+
+    #[test]
+    fn ipv6_fragments_only_decode_transport_in_first_fragment() {
+        let first = ipv6(44, &[IP_PROTOCOL_UDP, 0, 0x00, 0x01, 0, 0, 0, 42], &udp(1, 2, b"head"));
+        let packet = DecodedPacket::decode(LinkType::RawIp, &first).unwrap();
+        assert!(!packet.ip.as_ref().unwrap().fragment);
+        assert_eq!(packet.transport.as_ref().unwrap().name(), "UDP");
+
+        // 偏移 8 字节的后续分片：没有 UDP 头，负载从分片头之后开始
+        let later = ipv6(44, &[IP_PROTOCOL_UDP, 0, 0x00, 0x08, 0, 0, 0, 42], b"tail");
+        let packet = DecodedPacket::decode(LinkType::RawIp, &later).unwrap();
+        let ip = packet.ip.as_ref().unwrap();
+        assert!(ip.fragment);
+        assert_eq!(ip.extension_headers, vec![44]);
+        assert!(packet.transport.is_none());
+        assert_eq!(payload(&packet, &later), b"tail");
+        assert_eq!(packet.protocol(), "IPv6");
+    }
+
+    #[test]
+    fn ipv4_later_fragment_has_no_transport() {
+        let frame = ipv4(IP_PROTOCOL_TCP, 0x0010, b"not a tcp header at all");
+        let packet = DecodedPacket::decode(LinkType::RawIp, &frame).unwrap();
+        assert!(packet.ip.as_ref().unwrap().fragment);
+        assert!(packet.transport.is_none());
+        assert_eq!(payload(&packet, &frame), b"not a tcp header at all");
+    }
+
+    #[test]
+    fn snapped_packet_stops_at_ip_layer() {
+        let frame = ethernet(ETHER_TYPE_IPV4, &[], &ipv4(IP_PROTOCOL_TCP, 0, &tcp(1, 2, 0x10, &[0; 100])));
+        // 截断在 TCP 头中：IP 层完整，传输层标记为截断
+        let packet = DecodedPacket::decode(LinkType::Ethernet, &frame[..14 + 20 + 10]).unwrap();
+        assert!(packet.truncated);
+        assert!(packet.transport.is_none());
+        assert!(packet.ip.is_some());
+
+        // 截断在 TCP 头之后：可以解码传输层，负载为实际捕获的部分
+        let packet = DecodedPacket::decode(LinkType::Ethernet, &frame[..14 + 20 + 20 + 5]).unwrap();
+        assert!(!packet.truncated);
+        assert_eq!(packet.payload_len, 5);
+
+        let err = DecodedPacket::decode(LinkType::Ethernet, &frame[..20]).unwrap_err();
+        assert!(matches!(err, DecodeError::Truncated { layer: "IPv4", .. }), "{:?}", err);
+        let err = DecodedPacket::decode(LinkType::Ethernet, &frame[..10]).unwrap_err();
+        assert!(matches!(err, DecodeError::Truncated { layer: "Ethernet", needed: 14, available: 10 }));
+    }
+
+    #[test]
+    fn complete_packet_with_short_transport_header_is_an_error() {
+        // IP 总长度与捕获长度一致但 TCP 头不完整：不是抓包截断，而是无效的包
+        let frame = ipv4(IP_PROTOCOL_TCP, 0, &[0; 10]);
+        let err = DecodedPacket::decode(LinkType::RawIp, &frame).unwrap_err();
+        assert!(matches!(err, DecodeError::Truncated { layer: "TCP", .. }), "{:?}", err);
+    }
+
+    #[test]
+    fn ipv6_extension_headers_longer_than_payload_are_invalid() {
+        // 负载长度只有 8 字节，但逐跳选项声明了 16 字节；捕获的数据足够长
+        let mut frame = ipv6(0, &[IP_PROTOCOL_UDP, 1], &[0; 30]);
+        frame[4..6].copy_from_slice(&8u16.to_be_bytes());
+        let err = DecodedPacket::decode(LinkType::RawIp, &frame).unwrap_err();
+        assert!(matches!(err, DecodeError::Invalid { layer: "IPv6", .. }), "{:?}", err);
+
+        let frame = ethernet(ETHER_TYPE_IPV6, &[], &frame);
+        assert!(DecodedPacket::decode(LinkType::Ethernet, &frame).is_err());
+    }
+
+    #[test]
+    fn decodes_linux_sll_and_rejects_unknown_raw_version() {
+        let mut frame = vec![0; 14];
+        frame.extend_from_slice(&ETHER_TYPE_IPV4.to_be_bytes());
+        frame.extend_from_slice(&ipv4(IP_PROTOCOL_ICMP, 0, &[8, 0, 0, 0, 0, 1, 0, 1]));
+        let packet = DecodedPacket::decode(LinkType::LinuxSll, &frame).unwrap();
+        assert!(packet.ethernet.is_none());
+        assert_eq!(packet.transport, Some(Transport::Icmp { icmp_type: 8, code: 0 }));
+
+        let err = DecodedPacket::decode(LinkType::RawIp, &[0x50; 40]).unwrap_err();
+        assert!(matches!(err, DecodeError::Invalid { layer: "IP", .. }));
+    }
+
+    #[test]
+    fn unknown_ether_type_stops_after_link_layer() {
+        let frame = ethernet(0x0806, &[], &[1; 28]);
+        let packet = DecodedPacket::decode(LinkType::Ethernet, &frame).unwrap();
+        assert!(packet.ip.is_none());
+        assert_eq!((packet.payload_offset, packet.payload_len), (14, 28));
+        assert_eq!(packet.protocol(), "OTHER");
+    }
+}
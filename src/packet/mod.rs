@@ -0,0 +1,69 @@
+pub mod bpf;
+mod decode;
+pub mod pcap;
+#[cfg(target_os = "linux")]
+pub mod afpacket;
+
+pub use decode::{
+    DecodeError, DecodedPacket, EthernetHeader, IpHeader, TcpHeader, Transport, UdpHeader, MacAddr,
+};
+pub use bpf::{BpfInstruction, BpfProgram, FilterError};
+pub use pcap::{CaptureFormat, CapturedPacket, PacketRecord, PcapError, PcapReader, PcapWriter};
+
+use std::fmt;
+
+// 记录数据块链路层类型的属性（pcap LINKTYPE_* 编号），解码时优先于处理器的默认值
+pub const LINK_TYPE_ATTRIBUTE: &str = "link_type";
+
+// 帧的链路层类型，编号与 pcap 的 LINKTYPE_* 一致
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LinkType {
+    // Ethernet II（可带 802.1Q/802.1ad VLAN 标签）
+    #[default]
+    Ethernet,
+    // 没有链路层头，按版本号区分 IPv4/IPv6
+    RawIp,
+    // Linux cooked capture（SLL），在 any 设备上抓包时使用
+    LinuxSll,
+}
+
+impl LinkType {
+    pub fn from_code(code: u32) -> Option<Self> {
+        match code {
+            1 => Some(LinkType::Ethernet),
+            // LINKTYPE_RAW、LINKTYPE_IPV4、LINKTYPE_IPV6
+            101 | 228 | 229 => Some(LinkType::RawIp),
+            113 => Some(LinkType::LinuxSll),
+            _ => None,
+        }
+    }
+
+    pub fn code(&self) -> u32 {
+        match self {
+            LinkType::Ethernet => 1,
+            LinkType::RawIp => 101,
+            LinkType::LinuxSll => 113,
+        }
+    }
+
+    // 配置中使用的名称：ethernet、raw、linux_sll
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "ethernet" => Some(LinkType::Ethernet),
+            "raw" => Some(LinkType::RawIp),
+            "linux_sll" => Some(LinkType::LinuxSll),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for LinkType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            LinkType::Ethernet => "ethernet",
+            LinkType::RawIp => "raw",
+            LinkType::LinuxSll => "linux_sll",
+        };
+        write!(f, "{}", name)
+    }
+}
@@ -0,0 +1,731 @@
+use std::io::{self, Read, Write};
+use thiserror::Error;
+
+const PCAP_MAGIC_MICROS: u32 = 0xa1b2_c3d4;
+const PCAP_MAGIC_NANOS: u32 = 0xa1b2_3c4d;
+
+const BLOCK_SECTION_HEADER: u32 = 0x0a0d_0d0a;
+const BLOCK_INTERFACE_DESCRIPTION: u32 = 0x0000_0001;
+const BLOCK_PACKET: u32 = 0x0000_0002;
+const BLOCK_SIMPLE_PACKET: u32 = 0x0000_0003;
+const BLOCK_ENHANCED_PACKET: u32 = 0x0000_0006;
+const BYTE_ORDER_MAGIC: u32 = 0x1a2b_3c4d;
+
+const OPTION_END: u16 = 0;
+const OPTION_IF_TSRESOL: u16 = 9;
+const OPTION_IF_TSOFFSET: u16 = 14;
+
+// 单个记录/块的上限，防止损坏的长度字段导致分配过大的内存
+const MAX_RECORD_LEN: usize = 64 * 1024 * 1024;
+
+#[derive(Debug, Error)]
+pub enum PcapError {
+    #[error("读取抓包文件失败: {0}")]
+    Io(#[from] io::Error),
+    #[error("抓包文件格式错误: {0}")]
+    Format(String),
+    // 文件在记录中间结束，常见于抓包时被中断的文件
+    #[error("抓包文件在记录中间结束")]
+    Truncated,
+}
+
+// 抓包文件格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CaptureFormat {
+    Pcap { nanos: bool, big_endian: bool },
+    PcapNg,
+}
+
+// 文件中的一个数据包
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CapturedPacket {
+    // 捕获时间（Unix 纳秒）；pcapng 简单包块没有时间戳
+    pub timestamp_ns: Option<u64>,
+    // pcap LINKTYPE_* 编号
+    pub link_type: u32,
+    // pcapng 接口序号，经典 pcap 为 0
+    pub interface: u32,
+    // 原始长度，大于 data.len() 表示被截断
+    pub original_len: u32,
+    pub data: Vec<u8>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Interface {
+    link_type: u32,
+    snap_len: u32,
+    // 时间戳单位：每秒的计数
+    ticks_per_second: u64,
+    offset_seconds: i64,
+}
+
+impl Interface {
+    fn timestamp_ns(&self, ticks: u64) -> u64 {
+        let ns = u128::from(ticks) * 1_000_000_000 / u128::from(self.ticks_per_second);
+        let offset = i128::from(self.offset_seconds) * 1_000_000_000;
+        u64::try_from((ns as i128 + offset).max(0)).unwrap_or(u64::MAX)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Endian {
+    big: bool,
+}
+
+impl Endian {
+    fn u16(&self, bytes: &[u8]) -> u16 {
+        let bytes = [bytes[0], bytes[1]];
+        if self.big { u16::from_be_bytes(bytes) } else { u16::from_le_bytes(bytes) }
+    }
+
+    fn u32(&self, bytes: &[u8]) -> u32 {
+        let bytes = [bytes[0], bytes[1], bytes[2], bytes[3]];
+        if self.big { u32::from_be_bytes(bytes) } else { u32::from_le_bytes(bytes) }
+    }
+
+    fn put_u16(&self, buf: &mut Vec<u8>, value: u16) {
+        buf.extend_from_slice(&if self.big { value.to_be_bytes() } else { value.to_le_bytes() });
+    }
+
+    fn put_u32(&self, buf: &mut Vec<u8>, value: u32) {
+        buf.extend_from_slice(&if self.big { value.to_be_bytes() } else { value.to_le_bytes() });
+    }
+
+    fn u64(&self, bytes: &[u8]) -> u64 {
+        let mut array = [0u8; 8];
+        array.copy_from_slice(&bytes[..8]);
+        if self.big { u64::from_be_bytes(array) } else { u64::from_le_bytes(array) }
+    }
+}
+
+enum State {
+    Pcap { endian: Endian, interface: Interface },
+    PcapNg { endian: Endian, interfaces: Vec<Interface> },
+}
+
+// 不依赖 libpcap 的抓包文件读取器：经典 pcap（两种字节序、微秒/纳秒时间戳）和 pcapng
+// （节头块、接口描述块、增强包块、简单包块，可以有多个节和多个接口），根据文件头自动识别
+pub struct PcapReader<R: Read> {
+    reader: R,
+    state: State,
+}
+
+impl<R: Read> PcapReader<R> {
+    pub fn new(mut reader: R) -> Result<Self, PcapError> {
+        let mut magic = [0u8; 4];
+        read_exact(&mut reader, &mut magic).map_err(|e| match e {
+            PcapError::Truncated => PcapError::Format("文件为空或不完整".to_string()),
+            e => e,
+        })?;
+
+        let state = if u32::from_le_bytes(magic) == BLOCK_SECTION_HEADER {
+            let endian = read_section_header(&mut reader)?;
+            State::PcapNg { endian, interfaces: Vec::new() }
+        } else {
+            let (endian, nanos) = match (u32::from_le_bytes(magic), u32::from_be_bytes(magic)) {
+                (PCAP_MAGIC_MICROS, _) => (Endian { big: false }, false),
+                (PCAP_MAGIC_NANOS, _) => (Endian { big: false }, true),
+                (_, PCAP_MAGIC_MICROS) => (Endian { big: true }, false),
+                (_, PCAP_MAGIC_NANOS) => (Endian { big: true }, true),
+                _ => return Err(PcapError::Format(format!("无法识别的文件头 {:02x?}", magic))),
+            };
+            let mut header = [0u8; 20];
+            read_exact(&mut reader, &mut header)?;
+            let interface = Interface {
+                // 高 4 位为 FCS 信息
+                link_type: endian.u32(&header[16..20]) & 0x0fff_ffff,
+                snap_len: endian.u32(&header[12..16]),
+                ticks_per_second: if nanos { 1_000_000_000 } else { 1_000_000 },
+                offset_seconds: 0,
+            };
+            State::Pcap { endian, interface }
+        };
+        Ok(Self { reader, state })
+    }
+
+    pub fn format(&self) -> CaptureFormat {
+        match &self.state {
+            State::Pcap { endian, interface } => CaptureFormat::Pcap {
+                nanos: interface.ticks_per_second == 1_000_000_000,
+                big_endian: endian.big,
+            },
+            State::PcapNg { .. } => CaptureFormat::PcapNg,
+        }
+    }
+
+    // 读取下一个数据包，文件结束时返回 None；跳过不认识的 pcapng 块
+    pub fn next_packet(&mut self) -> Result<Option<CapturedPacket>, PcapError> {
+        match self.state {
+            State::Pcap { endian, interface } => self.next_pcap(endian, interface),
+            State::PcapNg { .. } => self.next_pcapng(),
+        }
+    }
+
+    fn next_pcap(&mut self, endian: Endian, interface: Interface) -> Result<Option<CapturedPacket>, PcapError> {
+        let mut header = [0u8; 16];
+        if !read_or_eof(&mut self.reader, &mut header)? {
+            return Ok(None);
+        }
+        let seconds = u64::from(endian.u32(&header[0..4]));
+        let fraction = u64::from(endian.u32(&header[4..8]));
+        let captured_len = endian.u32(&header[8..12]) as usize;
+        if captured_len > MAX_RECORD_LEN {
+            return Err(PcapError::Format(format!("数据包长度 {} 过大", captured_len)));
+        }
+
+        let mut data = vec![0u8; captured_len];
+        read_exact(&mut self.reader, &mut data)?;
+        Ok(Some(CapturedPacket {
+            timestamp_ns: Some(interface.timestamp_ns(seconds * interface.ticks_per_second + fraction)),
+            link_type: interface.link_type,
+            interface: 0,
+            original_len: endian.u32(&header[12..16]),
+            data,
+        }))
+    }
+
+    fn next_pcapng(&mut self) -> Result<Option<CapturedPacket>, PcapError> {
+        loop {
+            let mut header = [0u8; 8];
+            if !read_or_eof(&mut self.reader, &mut header)? {
+                return Ok(None);
+            }
+
+            // 新的节：重新确定字节序，接口列表清空
+            if u32::from_le_bytes([header[0], header[1], header[2], header[3]]) == BLOCK_SECTION_HEADER {
+                let endian = read_section_header_after_type(&mut self.reader, [header[4], header[5], header[6], header[7]])?;
+                self.state = State::PcapNg { endian, interfaces: Vec::new() };
+                continue;
+            }
+
+            let State::PcapNg { endian, interfaces } = &mut self.state else { unreachable!() };
+            let endian = *endian;
+            let block_type = endian.u32(&header[0..4]);
+            let body = read_block_body(&mut self.reader, endian, endian.u32(&header[4..8]))?;
+
+            match block_type {
+                BLOCK_INTERFACE_DESCRIPTION => interfaces.push(parse_interface(endian, &body)?),
+                BLOCK_ENHANCED_PACKET | BLOCK_PACKET => {
+                    if body.len() < 20 {
+                        return Err(PcapError::Format("增强包块长度不足".to_string()));
+                    }
+                    let (interface_id, original_len) = match block_type {
+                        BLOCK_ENHANCED_PACKET => (endian.u32(&body[0..4]), endian.u32(&body[16..20])),
+                        _ => (u32::from(endian.u16(&body[0..2])), endian.u32(&body[16..20])),
+                    };
+                    let interface = interfaces.get(interface_id as usize).ok_or_else(|| {
+                        PcapError::Format(format!("数据包引用了未声明的接口 {}", interface_id))
+                    })?;
+                    let ticks = (u64::from(endian.u32(&body[4..8])) << 32) | u64::from(endian.u32(&body[8..12]));
+                    let captured_len = endian.u32(&body[12..16]) as usize;
+                    let data = body.get(20..20 + captured_len).ok_or_else(|| {
+                        PcapError::Format(format!("数据包长度 {} 超出块的范围", captured_len))
+                    })?;
+                    return Ok(Some(CapturedPacket {
+                        timestamp_ns: Some(interface.timestamp_ns(ticks)),
+                        link_type: interface.link_type,
+                        interface: interface_id,
+                        original_len,
+                        data: data.to_vec(),
+                    }));
+                }
+                BLOCK_SIMPLE_PACKET => {
+                    if body.len() < 4 {
+                        return Err(PcapError::Format("简单包块长度不足".to_string()));
+                    }
+                    let interface = interfaces
+                        .first()
+                        .ok_or_else(|| PcapError::Format("简单包块之前没有接口描述块".to_string()))?;
+                    let original_len = endian.u32(&body[0..4]);
+                    // 简单包块没有捕获长度，取原始长度、快照长度和块长度中的最小值
+                    let mut captured_len = (original_len as usize).min(body.len() - 4);
+                    if interface.snap_len > 0 {
+                        captured_len = captured_len.min(interface.snap_len as usize);
+                    }
+                    return Ok(Some(CapturedPacket {
+                        timestamp_ns: None,
+                        link_type: interface.link_type,
+                        interface: 0,
+                        original_len,
+                        data: body[4..4 + captured_len].to_vec(),
+                    }));
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+// 要写入的一个数据包
+#[derive(Debug, Clone, Copy)]
+pub struct PacketRecord<'a> {
+    // 捕获时间（Unix 纳秒）
+    pub timestamp_ns: u64,
+    // pcap LINKTYPE_* 编号
+    pub link_type: u32,
+    // pcapng 中按 (link_type, interface) 分配接口描述块
+    pub interface: u32,
+    // 原始长度，小于负载长度时按负载长度写入
+    pub original_len: u32,
+    pub data: &'a [u8],
+}
+
+// 抓包文件写入器，输出可以直接用 Wireshark/tcpdump 打开
+//
+// 经典 pcap 的文件头在写入第一个数据包时按其链路层类型生成，之后的数据包必须是同一链路层类型；
+// pcapng 为每个链路层类型和接口写一个接口描述块（纳秒时间戳）。snaplen 大于 0 时截断超出的部分
+pub struct PcapWriter<W: Write> {
+    writer: W,
+    format: CaptureFormat,
+    snaplen: u32,
+    endian: Endian,
+    // 经典 pcap 文件头中的链路层类型，写入文件头后确定
+    pcap_link_type: Option<u32>,
+    // pcapng 已写入的接口：(链路层类型, 原接口序号)
+    interfaces: Vec<(u32, u32)>,
+    bytes_written: u64,
+    packets: u64,
+}
+
+// 文件头中 snaplen 为 0 时使用的值
+const DEFAULT_SNAPLEN: u32 = 262_144;
+
+impl<W: Write> PcapWriter<W> {
+    // pcapng 立即写入节头块，经典 pcap 的文件头推迟到第一个数据包
+    pub fn new(writer: W, format: CaptureFormat, snaplen: u32) -> Result<Self, PcapError> {
+        let big_endian = matches!(format, CaptureFormat::Pcap { big_endian: true, .. });
+        let mut this = Self {
+            writer,
+            format,
+            snaplen,
+            endian: Endian { big: big_endian },
+            pcap_link_type: None,
+            interfaces: Vec::new(),
+            bytes_written: 0,
+            packets: 0,
+        };
+        if format == CaptureFormat::PcapNg {
+            let mut body = Vec::with_capacity(16);
+            this.endian.put_u32(&mut body, BYTE_ORDER_MAGIC);
+            this.endian.put_u16(&mut body, 1);
+            this.endian.put_u16(&mut body, 0);
+            // 节长度未知
+            body.extend_from_slice(&[0xff; 8]);
+            this.write_block(BLOCK_SECTION_HEADER, &body)?;
+        }
+        Ok(this)
+    }
+
+    pub fn format(&self) -> CaptureFormat {
+        self.format
+    }
+
+    pub fn bytes_written(&self) -> u64 {
+        self.bytes_written
+    }
+
+    pub fn packets(&self) -> u64 {
+        self.packets
+    }
+
+    fn captured_len(&self, packet: &PacketRecord) -> usize {
+        match self.snaplen {
+            0 => packet.data.len(),
+            snaplen => packet.data.len().min(snaplen as usize),
+        }
+    }
+
+    // 写入该数据包将增加的字节数（不含可能需要的文件头或接口描述块）
+    pub fn encoded_len(&self, packet: &PacketRecord) -> u64 {
+        let captured = self.captured_len(packet) as u64;
+        match self.format {
+            CaptureFormat::Pcap { .. } => 16 + captured,
+            CaptureFormat::PcapNg => 32 + captured.next_multiple_of(4),
+        }
+    }
+
+    pub fn write_packet(&mut self, packet: &PacketRecord) -> Result<(), PcapError> {
+        let data = &packet.data[..self.captured_len(packet)];
+        let original_len = packet.original_len.max(packet.data.len() as u32);
+        let endian = self.endian;
+        let mut record = Vec::with_capacity(32 + data.len() + 3);
+
+        match self.format {
+            CaptureFormat::Pcap { nanos, .. } => {
+                match self.pcap_link_type {
+                    None => self.write_pcap_header(packet.link_type, nanos)?,
+                    Some(link_type) if link_type != packet.link_type => {
+                        return Err(PcapError::Format(format!(
+                            "经典 pcap 文件只能有一种链路层类型: 文件为 {}，数据包为 {}",
+                            link_type, packet.link_type
+                        )));
+                    }
+                    Some(_) => {}
+                }
+                let seconds = packet.timestamp_ns / 1_000_000_000;
+                let fraction = packet.timestamp_ns % 1_000_000_000;
+                endian.put_u32(&mut record, u32::try_from(seconds).unwrap_or(u32::MAX));
+                endian.put_u32(&mut record, if nanos { fraction as u32 } else { (fraction / 1000) as u32 });
+                endian.put_u32(&mut record, data.len() as u32);
+                endian.put_u32(&mut record, original_len);
+                record.extend_from_slice(data);
+                self.writer.write_all(&record)?;
+                self.bytes_written += record.len() as u64;
+            }
+            CaptureFormat::PcapNg => {
+                let interface = self.pcapng_interface(packet.link_type, packet.interface)?;
+                endian.put_u32(&mut record, interface);
+                endian.put_u32(&mut record, (packet.timestamp_ns >> 32) as u32);
+                endian.put_u32(&mut record, packet.timestamp_ns as u32);
+                endian.put_u32(&mut record, data.len() as u32);
+                endian.put_u32(&mut record, original_len);
+                record.extend_from_slice(data);
+                self.write_block(BLOCK_ENHANCED_PACKET, &record)?;
+            }
+        }
+        self.packets += 1;
+        Ok(())
+    }
+
+    pub fn flush(&mut self) -> Result<(), PcapError> {
+        self.writer.flush()?;
+        Ok(())
+    }
+
+    pub fn get_ref(&self) -> &W {
+        &self.writer
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+
+    fn write_pcap_header(&mut self, link_type: u32, nanos: bool) -> Result<(), PcapError> {
+        let mut header = Vec::with_capacity(24);
+        self.endian.put_u32(&mut header, if nanos { PCAP_MAGIC_NANOS } else { PCAP_MAGIC_MICROS });
+        self.endian.put_u16(&mut header, 2);
+        self.endian.put_u16(&mut header, 4);
+        // thiszone、sigfigs
+        self.endian.put_u32(&mut header, 0);
+        self.endian.put_u32(&mut header, 0);
+        self.endian.put_u32(&mut header, if self.snaplen == 0 { DEFAULT_SNAPLEN } else { self.snaplen });
+        self.endian.put_u32(&mut header, link_type);
+        self.writer.write_all(&header)?;
+        self.bytes_written += header.len() as u64;
+        self.pcap_link_type = Some(link_type);
+        Ok(())
+    }
+
+    // 返回 pcapng 接口序号，第一次出现时写入接口描述块
+    fn pcapng_interface(&mut self, link_type: u32, interface: u32) -> Result<u32, PcapError> {
+        if let Some(index) = self.interfaces.iter().position(|&key| key == (link_type, interface)) {
+            return Ok(index as u32);
+        }
+        let link_type_code = u16::try_from(link_type)
+            .map_err(|_| PcapError::Format(format!("链路层类型 {} 超出 pcapng 范围", link_type)))?;
+        let mut body = Vec::with_capacity(20);
+        self.endian.put_u16(&mut body, link_type_code);
+        self.endian.put_u16(&mut body, 0);
+        self.endian.put_u32(&mut body, self.snaplen);
+        // if_tsresol = 9：纳秒时间戳
+        self.endian.put_u16(&mut body, OPTION_IF_TSRESOL);
+        self.endian.put_u16(&mut body, 1);
+        body.extend_from_slice(&[9, 0, 0, 0]);
+        self.endian.put_u16(&mut body, OPTION_END);
+        self.endian.put_u16(&mut body, 0);
+        self.write_block(BLOCK_INTERFACE_DESCRIPTION, &body)?;
+        self.interfaces.push((link_type, interface));
+        Ok(self.interfaces.len() as u32 - 1)
+    }
+
+    // 写入 pcapng 块：类型、总长度、按 4 字节对齐的内容、总长度
+    fn write_block(&mut self, block_type: u32, body: &[u8]) -> Result<(), PcapError> {
+        let padding = body.len().next_multiple_of(4) - body.len();
+        let total = (12 + body.len() + padding) as u32;
+        let mut block = Vec::with_capacity(total as usize);
+        self.endian.put_u32(&mut block, block_type);
+        self.endian.put_u32(&mut block, total);
+        block.extend_from_slice(body);
+        block.resize(block.len() + padding, 0);
+        self.endian.put_u32(&mut block, total);
+        self.writer.write_all(&block)?;
+        self.bytes_written += u64::from(total);
+        Ok(())
+    }
+}
+
+impl<R: Read> Iterator for PcapReader<R> {
+    type Item = Result<CapturedPacket, PcapError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_packet().transpose()
+    }
+}
+
+fn read_exact<R: Read>(reader: &mut R, buf: &mut [u8]) -> Result<(), PcapError> {
+    reader.read_exact(buf).map_err(|e| match e.kind() {
+        io::ErrorKind::UnexpectedEof => PcapError::Truncated,
+        _ => PcapError::Io(e),
+    })
+}
+
+// 在记录边界处读到文件末尾时返回 false
+fn read_or_eof<R: Read>(reader: &mut R, buf: &mut [u8]) -> Result<bool, PcapError> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match reader.read(&mut buf[filled..]) {
+            Ok(0) if filled == 0 => return Ok(false),
+            Ok(0) => return Err(PcapError::Truncated),
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(PcapError::Io(e)),
+        }
+    }
+    Ok(true)
+}
+
+// 已读取块类型，读取节头块的其余部分并返回该节的字节序
+fn read_section_header<R: Read>(reader: &mut R) -> Result<Endian, PcapError> {
+    let mut length = [0u8; 4];
+    read_exact(reader, &mut length)?;
+    read_section_header_after_type(reader, length)
+}
+
+fn read_section_header_after_type<R: Read>(reader: &mut R, length: [u8; 4]) -> Result<Endian, PcapError> {
+    let mut magic = [0u8; 4];
+    read_exact(reader, &mut magic)?;
+    let endian = match (u32::from_le_bytes(magic), u32::from_be_bytes(magic)) {
+        (BYTE_ORDER_MAGIC, _) => Endian { big: false },
+        (_, BYTE_ORDER_MAGIC) => Endian { big: true },
+        _ => return Err(PcapError::Format("节头块的字节序标记无效".to_string())),
+    };
+    let block_len = endian.u32(&length);
+    // 剩余部分：版本号、节长度、选项和结尾的块长度
+    let rest = (block_len as usize)
+        .checked_sub(12)
+        .filter(|rest| *rest >= 16 && block_len.is_multiple_of(4) && (block_len as usize) <= MAX_RECORD_LEN)
+        .ok_or_else(|| PcapError::Format(format!("节头块长度 {} 无效", block_len)))?;
+    let mut body = vec![0u8; rest];
+    read_exact(reader, &mut body)?;
+    let major = endian.u16(&body[0..2]);
+    if major != 1 {
+        return Err(PcapError::Format(format!("不支持的 pcapng 版本 {}", major)));
+    }
+    Ok(endian)
+}
+
+// 读取块的正文（不含块类型、开头和结尾的块长度）
+fn read_block_body<R: Read>(reader: &mut R, endian: Endian, block_len: u32) -> Result<Vec<u8>, PcapError> {
+    let block_len = block_len as usize;
+    if block_len < 12 || !block_len.is_multiple_of(4) || block_len > MAX_RECORD_LEN {
+        return Err(PcapError::Format(format!("块长度 {} 无效", block_len)));
+    }
+    let mut body = vec![0u8; block_len - 8];
+    read_exact(reader, &mut body)?;
+    let trailer = endian.u32(&body[block_len - 12..]) as usize;
+    if trailer != block_len {
+        return Err(PcapError::Format(format!("块首尾长度不一致: {} 与 {}", block_len, trailer)));
+    }
+    body.truncate(block_len - 12);
+    Ok(body)
+}
+
+fn parse_interface(endian: Endian, body: &[u8]) -> Result<Interface, PcapError> {
+    if body.len() < 8 {
+        return Err(PcapError::Format("接口描述块长度不足".to_string()));
+    }
+    let mut interface = Interface {
+        link_type: u32::from(endian.u16(&body[0..2])),
+        snap_len: endian.u32(&body[4..8]),
+        ticks_per_second: 1_000_000,
+        offset_seconds: 0,
+    };
+
+    let mut options = &body[8..];
+    while options.len() >= 4 {
+        let code = endian.u16(&options[0..2]);
+        let len = usize::from(endian.u16(&options[2..4]));
+        if code == OPTION_END {
+            break;
+        }
+        let value = options
+            .get(4..4 + len)
+            .ok_or_else(|| PcapError::Format("接口描述块选项超出块的范围".to_string()))?;
+        match code {
+            OPTION_IF_TSRESOL if len == 1 => {
+                // 最高位为 0 表示 10 的负幂，为 1 表示 2 的负幂
+                let exponent = u32::from(value[0] & 0x7f);
+                let ticks = match value[0] & 0x80 {
+                    0 => 10u64.checked_pow(exponent),
+                    _ => 2u64.checked_pow(exponent),
+                };
+                interface.ticks_per_second = ticks
+                    .filter(|ticks| *ticks > 0)
+                    .ok_or_else(|| PcapError::Format(format!("时间戳精度 {:#04x} 无效", value[0])))?;
+            }
+            OPTION_IF_TSOFFSET if len == 8 => interface.offset_seconds = endian.u64(value) as i64,
+            _ => {}
+        }
+        // 选项按 4 字节对齐
+        let padded = 4 + len.div_ceil(4) * 4;
+        options = options.get(padded..).unwrap_or_default();
+    }
+    Ok(interface)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn fixture(name: &str) -> Vec<u8> {
+        std::fs::read(format!("{}/fixtures/{}", env!("CARGO_MANIFEST_DIR"), name)).unwrap()
+    }
+
+    fn read_all(bytes: &[u8]) -> (CaptureFormat, Vec<CapturedPacket>) {
+        let reader = PcapReader::new(Cursor::new(bytes)).unwrap();
+        let format = reader.format();
+        (format, reader.collect::<Result<Vec<_>, _>>().unwrap())
+    }
+
+    fn record(packet: &CapturedPacket) -> PacketRecord<'_> {
+        PacketRecord {
+            timestamp_ns: packet.timestamp_ns.unwrap_or(0),
+            link_type: packet.link_type,
+            interface: packet.interface,
+            original_len: packet.original_len,
+            data: &packet.data,
+        }
+    }
+
+    fn write_all(format: CaptureFormat, snaplen: u32, packets: &[CapturedPacket]) -> Vec<u8> {
+        let mut writer = PcapWriter::new(Vec::new(), format, snaplen).unwrap();
+        for packet in packets {
+            let record = record(packet);
+            let before = writer.bytes_written();
+            let expected = writer.encoded_len(&record);
+            writer.write_packet(&record).unwrap();
+            if before > 0 && format != CaptureFormat::PcapNg {
+                assert_eq!(writer.bytes_written() - before, expected);
+            }
+        }
+        assert_eq!(writer.packets(), packets.len() as u64);
+        let bytes = writer.into_inner();
+        assert!(!bytes.is_empty());
+        bytes
+    }
+
+    #[test]
+    fn reads_little_endian_microsecond_pcap() {
+        let (format, packets) = read_all(&fixture("sample.pcap"));
+        assert_eq!(format, CaptureFormat::Pcap { nanos: false, big_endian: false });
+        assert_eq!(packets.len(), 5);
+        assert_eq!(packets[0].timestamp_ns, Some(1_760_000_000_000_000_000));
+        assert_eq!(packets[1].timestamp_ns, Some(1_760_000_001_250_000_000));
+        assert!(packets.iter().all(|p| p.link_type == 1 && p.interface == 0));
+        assert!(packets.iter().all(|p| p.original_len as usize == p.data.len()));
+        assert_eq!(packets.iter().map(|p| p.data.len()).collect::<Vec<_>>(), vec![54, 59, 76, 46, 106]);
+        // 第三个数据包带 VLAN 标签
+        assert_eq!(&packets[2].data[12..16], &[0x81, 0x00, 0x00, 0x64]);
+    }
+
+    #[test]
+    fn reads_big_endian_nanosecond_pcap_with_snapped_packets() {
+        let (format, packets) = read_all(&fixture("sample_be_nanos.pcap"));
+        assert_eq!(format, CaptureFormat::Pcap { nanos: true, big_endian: true });
+        assert_eq!(packets.len(), 5);
+        assert_eq!(packets[0].timestamp_ns, Some(1_760_000_000_123_456_789));
+        assert_eq!(packets[4].timestamp_ns, Some(1_760_000_004_617_283_945));
+        assert!(packets.iter().all(|p| p.data.len() == 40));
+        assert_eq!(packets.iter().map(|p| p.original_len).collect::<Vec<_>>(), vec![54, 59, 76, 46, 106]);
+
+        // 与小端文件中同一数据包的前 40 字节相同
+        let (_, little) = read_all(&fixture("sample.pcap"));
+        for (snapped, full) in packets.iter().zip(&little) {
+            assert_eq!(snapped.data[..], full.data[..40]);
+        }
+    }
+
+    #[test]
+    fn reads_pcapng_with_several_interfaces() {
+        let (format, packets) = read_all(&fixture("sample.pcapng"));
+        assert_eq!(format, CaptureFormat::PcapNg);
+        assert_eq!(packets.len(), 7);
+
+        // 接口 0 为以太网，接口 1 为原始 IP（IPv6），并带有纳秒精度的时间戳
+        assert_eq!((packets[5].link_type, packets[5].interface), (101, 1));
+        assert_eq!(packets[5].timestamp_ns, Some(1_760_000_010_000_000_042));
+        assert_eq!(packets[5].data[0] >> 4, 6);
+        assert!(packets[..5].iter().all(|p| p.link_type == 1 && p.interface == 0));
+        assert_eq!(packets[1].timestamp_ns, Some(1_760_000_001_001_000_000));
+
+        // 简单包块没有时间戳，属于第一个接口
+        assert_eq!(packets[6].timestamp_ns, None);
+        assert_eq!((packets[6].link_type, packets[6].interface), (1, 0));
+        assert_eq!(packets[6].data.len(), 54);
+    }
+
+    #[test]
+    fn pcap_round_trips_through_writer() {
+        for name in ["sample.pcap", "sample_be_nanos.pcap"] {
+            let original = fixture(name);
+            let (format, packets) = read_all(&original);
+            let written = write_all(format, 65535, &packets);
+            let (reread_format, reread) = read_all(&written);
+            assert_eq!(reread_format, format);
+            assert_eq!(reread, packets, "{}", name);
+        }
+    }
+
+    #[test]
+    fn pcap_converts_between_timestamp_precisions() {
+        let (_, packets) = read_all(&fixture("sample_be_nanos.pcap"));
+        let written = write_all(CaptureFormat::Pcap { nanos: false, big_endian: false }, 0, &packets);
+        let (_, reread) = read_all(&written);
+        assert_eq!(reread[0].timestamp_ns, Some(1_760_000_000_123_456_000));
+        assert_eq!(reread[0].original_len, 54);
+    }
+
+    #[test]
+    fn pcapng_round_trips_through_writer() {
+        let (_, packets) = read_all(&fixture("sample.pcapng"));
+        // 只比较有时间戳的数据包，简单包块写回时变成增强包块
+        let packets: Vec<_> = packets.into_iter().filter(|p| p.timestamp_ns.is_some()).collect();
+        let written = write_all(CaptureFormat::PcapNg, 0, &packets);
+        let (format, reread) = read_all(&written);
+        assert_eq!(format, CaptureFormat::PcapNg);
+        assert_eq!(reread, packets);
+    }
+
+    #[test]
+    fn writer_applies_snaplen() {
+        let (_, packets) = read_all(&fixture("sample.pcap"));
+        let written = write_all(CaptureFormat::PcapNg, 40, &packets);
+        let (_, reread) = read_all(&written);
+        for (snapped, full) in reread.iter().zip(&packets) {
+            assert_eq!(snapped.data[..], full.data[..40]);
+            assert_eq!(snapped.original_len, full.original_len);
+        }
+    }
+
+    #[test]
+    fn classic_pcap_rejects_mixed_link_types() {
+        let (_, packets) = read_all(&fixture("sample.pcapng"));
+        let mut writer = PcapWriter::new(Vec::new(), CaptureFormat::Pcap { nanos: true, big_endian: false }, 0).unwrap();
+        writer.write_packet(&record(&packets[0])).unwrap();
+        assert!(matches!(writer.write_packet(&record(&packets[5])), Err(PcapError::Format(_))));
+    }
+
+    #[test]
+    fn truncated_and_unknown_files_are_errors() {
+        let original = fixture("sample.pcap");
+        let mut reader = PcapReader::new(Cursor::new(&original[..original.len() - 10])).unwrap();
+        for _ in 0..4 {
+            assert!(reader.next_packet().unwrap().is_some());
+        }
+        assert!(matches!(reader.next_packet(), Err(PcapError::Truncated)));
+
+        assert!(matches!(PcapReader::new(Cursor::new(&[0u8; 2][..])), Err(PcapError::Format(_))));
+        assert!(matches!(PcapReader::new(Cursor::new(&[0x42u8; 24][..])), Err(PcapError::Format(_))));
+    }
+}
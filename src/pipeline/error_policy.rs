@@ -0,0 +1,324 @@
+use crate::{Attributes, DataChunk};
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+
+const DEFAULT_RETRIES: u32 = 3;
+const DEFAULT_BACKOFF_MS: u64 = 100;
+const DEFAULT_MAX_BACKOFF_MS: u64 = 10_000;
+
+// 重试用尽（或不重试）后的处理方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorAction {
+    // 记录日志后丢弃数据块
+    #[default]
+    Drop,
+    // 连同错误信息发送到死信输出端
+    DeadLetter,
+    // 使整个管道失败
+    Fail,
+}
+
+impl fmt::Display for ErrorAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ErrorAction::Drop => write!(f, "drop"),
+            ErrorAction::DeadLetter => write!(f, "dead_letter"),
+            ErrorAction::Fail => write!(f, "fail"),
+        }
+    }
+}
+
+// 指数退避重试：第 n 次重试前等待 initial_backoff * 2^n，不超过 max_backoff
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    pub max_retries: u32,
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+}
+
+impl RetryPolicy {
+    pub fn new(max_retries: u32) -> Self {
+        Self {
+            max_retries,
+            initial_backoff: Duration::from_millis(DEFAULT_BACKOFF_MS),
+            max_backoff: Duration::from_millis(DEFAULT_MAX_BACKOFF_MS),
+        }
+    }
+
+    pub fn with_backoff(mut self, initial: Duration, max: Duration) -> Self {
+        self.initial_backoff = initial;
+        self.max_backoff = max.max(initial);
+        self
+    }
+
+    // 第 attempt 次重试（从 0 开始）前的等待时间
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        self.initial_backoff
+            .checked_mul(1u32.checked_shl(attempt).unwrap_or(u32::MAX))
+            .unwrap_or(self.max_backoff)
+            .min(self.max_backoff)
+    }
+}
+
+// 组件出错时的策略：可选的重试，之后执行 action
+//
+// YAML 中可以简写为 on_error: drop | retry | dead_letter | fail，
+// 或写成 { action: dead_letter, retries: 3, backoff_ms: 100, max_backoff_ms: 10000 }
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "ErrorPolicySpec", into = "ErrorPolicyFields")]
+pub struct ErrorPolicy {
+    pub action: ErrorAction,
+    pub retry: Option<RetryPolicy>,
+}
+
+impl ErrorPolicy {
+    pub fn drop() -> Self {
+        Self { action: ErrorAction::Drop, retry: None }
+    }
+
+    pub fn dead_letter() -> Self {
+        Self { action: ErrorAction::DeadLetter, retry: None }
+    }
+
+    pub fn fail() -> Self {
+        Self { action: ErrorAction::Fail, retry: None }
+    }
+
+    pub fn with_retry(mut self, retry: RetryPolicy) -> Self {
+        self.retry = Some(retry);
+        self
+    }
+
+    pub fn max_retries(&self) -> u32 {
+        self.retry.map_or(0, |retry| retry.max_retries)
+    }
+
+    // 重试或死信都需要保留进入组件时的数据块
+    pub fn keeps_original(&self) -> bool {
+        self.max_retries() > 0 || self.action == ErrorAction::DeadLetter
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ErrorPolicySpec {
+    Shorthand(String),
+    Fields(ErrorPolicyFields),
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ErrorPolicyFields {
+    #[serde(default)]
+    action: ErrorAction,
+    #[serde(default)]
+    retries: u32,
+    #[serde(default = "default_backoff_ms")]
+    backoff_ms: u64,
+    #[serde(default = "default_max_backoff_ms")]
+    max_backoff_ms: u64,
+}
+
+fn default_backoff_ms() -> u64 {
+    DEFAULT_BACKOFF_MS
+}
+
+fn default_max_backoff_ms() -> u64 {
+    DEFAULT_MAX_BACKOFF_MS
+}
+
+impl TryFrom<ErrorPolicySpec> for ErrorPolicy {
+    type Error = String;
+
+    fn try_from(spec: ErrorPolicySpec) -> Result<Self, Self::Error> {
+        match spec {
+            ErrorPolicySpec::Shorthand(action) => match action.as_str() {
+                "drop" => Ok(ErrorPolicy::drop()),
+                "dead_letter" => Ok(ErrorPolicy::dead_letter()),
+                "fail" => Ok(ErrorPolicy::fail()),
+                "retry" => Ok(ErrorPolicy::drop().with_retry(RetryPolicy::new(DEFAULT_RETRIES))),
+                other => Err(format!("未知的错误策略 '{}'，可选 drop / retry / dead_letter / fail", other)),
+            },
+            ErrorPolicySpec::Fields(fields) => {
+                let mut policy = ErrorPolicy { action: fields.action, retry: None };
+                if fields.retries > 0 {
+                    policy = policy.with_retry(RetryPolicy::new(fields.retries).with_backoff(
+                        Duration::from_millis(fields.backoff_ms),
+                        Duration::from_millis(fields.max_backoff_ms),
+                    ));
+                }
+                Ok(policy)
+            }
+        }
+    }
+}
+
+impl From<ErrorPolicy> for ErrorPolicyFields {
+    fn from(policy: ErrorPolicy) -> Self {
+        let retry = policy.retry.unwrap_or_else(|| RetryPolicy::new(0));
+        Self {
+            action: policy.action,
+            retries: retry.max_retries,
+            backoff_ms: retry.initial_backoff.as_millis() as u64,
+            max_backoff_ms: retry.max_backoff.as_millis() as u64,
+        }
+    }
+}
+
+// 死信记录：出错组件、错误信息和进入该组件时的原始数据块，序列化为 JSON
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DeadLetterRecord {
+    pub component: String,
+    // processor 或 sink
+    pub component_kind: String,
+    pub error: String,
+    pub attempts: u32,
+    // 失败时间（Unix 纳秒）
+    pub failed_at_ns: u64,
+    pub chunk: DeadLetterChunk,
+}
+
+// 原始数据块，负载以 base64 保存
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DeadLetterChunk {
+    // 原始数据块的全局编号，重放时会重新分配
+    #[serde(default)]
+    pub id: u64,
+    pub data: String,
+    pub ingest_time_ns: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub event_time_ns: Option<u64>,
+    pub sequence: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Attributes::is_empty")]
+    pub attributes: Attributes,
+}
+
+impl DeadLetterRecord {
+    pub fn new(component: &str, component_kind: &str, error: &anyhow::Error, attempts: u32, chunk: &DataChunk) -> Self {
+        Self {
+            component: component.to_string(),
+            component_kind: component_kind.to_string(),
+            error: format!("{:#}", error),
+            attempts,
+            failed_at_ns: crate::now_ns(),
+            chunk: DeadLetterChunk {
+                id: chunk.id,
+                data: base64::engine::general_purpose::STANDARD.encode(&chunk.data),
+                ingest_time_ns: chunk.ingest_time_ns,
+                event_time_ns: chunk.event_time_ns,
+                sequence: chunk.sequence,
+                key: chunk.key.as_deref().map(str::to_string),
+                source_id: chunk.source_id.as_deref().map(str::to_string),
+                attributes: chunk.attributes.as_ref().clone(),
+            },
+        }
+    }
+
+    // 以 JSON 负载的数据块发送到死信输出端
+    pub fn to_chunk(&self) -> DataChunk {
+        let json = serde_json::to_vec(self).expect("dead letter record serializes to JSON");
+        DataChunk::new(json).with_sequence(self.chunk.sequence)
+    }
+
+    // 解析死信输出端写出的一条 JSON 记录
+    pub fn from_json(json: &[u8]) -> anyhow::Result<Self> {
+        Ok(serde_json::from_slice(json)?)
+    }
+
+    // 还原原始数据块，用于重放
+    pub fn original_chunk(&self) -> anyhow::Result<DataChunk> {
+        self.chunk.restore()
+    }
+}
+
+impl DeadLetterChunk {
+    // 还原为数据块；JsonLinesSink 写出的非 JSON 负载数据块也是这个格式
+    pub fn restore(&self) -> anyhow::Result<DataChunk> {
+        let data = base64::engine::general_purpose::STANDARD.decode(&self.data)?;
+        let mut chunk = DataChunk::new(data).with_sequence(self.sequence);
+        chunk.id = self.id;
+        chunk.ingest_time_ns = self.ingest_time_ns;
+        chunk.event_time_ns = self.event_time_ns;
+        chunk.key = self.key.as_deref().map(Arc::from);
+        chunk.source_id = self.source_id.as_deref().map(Arc::from);
+        chunk.attributes = Arc::new(self.attributes.clone());
+        Ok(chunk)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backoff_doubles_up_to_the_limit() {
+        let retry = RetryPolicy::new(5).with_backoff(Duration::from_millis(100), Duration::from_millis(1_000));
+        let backoffs: Vec<_> = (0..6).map(|attempt| retry.backoff(attempt).as_millis()).collect();
+        assert_eq!(backoffs, vec![100, 200, 400, 800, 1_000, 1_000]);
+        // 移位溢出时取上限
+        assert_eq!(retry.backoff(40), Duration::from_millis(1_000));
+        // 上限不小于初始等待
+        let retry = RetryPolicy::new(1).with_backoff(Duration::from_secs(2), Duration::from_secs(1));
+        assert_eq!(retry.backoff(3), Duration::from_secs(2));
+    }
+
+    #[test]
+    fn parses_shorthand_and_full_policies() {
+        let parse = |yaml: &str| serde_yaml::from_str::<ErrorPolicy>(yaml);
+        assert_eq!(parse("drop").unwrap(), ErrorPolicy::drop());
+        assert_eq!(parse("fail").unwrap(), ErrorPolicy::fail());
+        assert_eq!(parse("dead_letter").unwrap(), ErrorPolicy::dead_letter());
+        assert_eq!(parse("retry").unwrap(), ErrorPolicy::drop().with_retry(RetryPolicy::new(DEFAULT_RETRIES)));
+        assert!(parse("ignore").unwrap_err().to_string().contains("未知的错误策略"));
+
+        let policy = parse("{ action: dead_letter, retries: 2, backoff_ms: 5, max_backoff_ms: 50 }").unwrap();
+        assert_eq!(policy.action, ErrorAction::DeadLetter);
+        assert_eq!(policy.max_retries(), 2);
+        assert_eq!(policy.retry.unwrap().backoff(10), Duration::from_millis(50));
+        assert!(policy.keeps_original());
+        assert!(!ErrorPolicy::fail().keeps_original());
+        assert_eq!(parse("{ action: fail }").unwrap(), ErrorPolicy::fail());
+        assert!(parse("{ action: fail, retry: 3 }").is_err());
+
+        // 序列化后再解析得到相同的策略
+        let yaml = serde_yaml::to_string(&policy).unwrap();
+        assert_eq!(parse(&yaml).unwrap(), policy);
+    }
+
+    #[test]
+    fn dead_letter_record_keeps_the_original_chunk() {
+        let mut chunk = DataChunk::new(vec![0u8, 159, 146, 150])
+            .with_sequence(42)
+            .with_event_time_ns(1_700_000_000_000_000_000)
+            .with_key("flow-7")
+            .with_source_id("capture")
+            .with_attribute("port", 443i64);
+        chunk.id = 9;
+        let error = anyhow::anyhow!("invalid header").context("decode failed");
+        let record = DeadLetterRecord::new("decoder", "processor", &error, 3, &chunk);
+        assert_eq!(record.error, "decode failed: invalid header");
+
+        let letter = record.to_chunk();
+        assert_eq!(letter.sequence, 42);
+        let parsed = DeadLetterRecord::from_json(&letter.data).unwrap();
+        assert_eq!(parsed, record);
+        assert_eq!((parsed.component.as_str(), parsed.component_kind.as_str(), parsed.attempts), ("decoder", "processor", 3));
+
+        let restored = parsed.original_chunk().unwrap();
+        assert_eq!(restored.data, chunk.data);
+        assert_eq!((restored.id, restored.sequence), (9, 42));
+        assert_eq!(restored.ingest_time_ns, chunk.ingest_time_ns);
+        assert_eq!(restored.event_time_ns, chunk.event_time_ns);
+        assert_eq!(restored.key.as_deref(), Some("flow-7"));
+        assert_eq!(restored.source_id.as_deref(), Some("capture"));
+        assert_eq!(restored.attribute("port").and_then(|value| value.as_i64()), Some(443));
+    }
+}
@@ -0,0 +1,1196 @@
+mod error_policy;
+mod output;
+mod sink;
+mod state;
+pub mod topology;
+mod watermark;
+mod worker;
+
+use crate::config::{ComponentRegistry, ConfigError, PipelineConfig};
+use crate::processors::{Processor, Router};
+use output::Collector;
+use sink::SinkQueue;
+use std::collections::HashMap;
+use std::future::{Future, IntoFuture};
+use std::pin::Pin;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+use topology::{NodeDecl, NodeKind, Topology};
+use state::RunState;
+use watermark::{WatermarkTracker, DEFAULT_WATERMARK_INTERVAL};
+use worker::{Dispatched, Worker, WorkerInput, WorkerNode};
+
+pub use error_policy::{DeadLetterChunk, DeadLetterRecord, ErrorAction, ErrorPolicy, RetryPolicy};
+pub use sink::{OverflowPolicy, SinkOptions, SinkReport};
+pub use watermark::WatermarkStrategy;
+
+const DEFAULT_QUEUE_SIZE: usize = 1000;
+const DEFAULT_DRAIN_TIMEOUT: Duration = Duration::from_secs(30);
+
+// 处理器工厂：并行模式下为每个工作任务创建独立的处理器实例
+type ProcessorFactory = Arc<dyn Fn() -> anyhow::Result<Box<dyn Processor>> + Send + Sync>;
+
+// 拓扑中的一个处理节点：直接添加的实例只能用于单个工作任务
+enum ProcessorSlot {
+    Instance(Option<Box<dyn Processor>>),
+    Factory {
+        first: Option<Box<dyn Processor>>,
+        factory: ProcessorFactory,
+    },
+    Router(Arc<Router>),
+}
+
+impl ProcessorSlot {
+    // 为下一个工作任务取得节点实例，优先使用已创建的实例
+    fn instantiate(&mut self) -> anyhow::Result<WorkerNode> {
+        match self {
+            ProcessorSlot::Instance(processor) => processor
+                .take()
+                .map(WorkerNode::Processor)
+                .ok_or_else(|| anyhow::anyhow!("Processor instance cannot be shared between workers")),
+            ProcessorSlot::Factory { first, factory } => match first.take() {
+                Some(processor) => Ok(WorkerNode::Processor(processor)),
+                None => factory().map(WorkerNode::Processor),
+            },
+            ProcessorSlot::Router(router) => Ok(WorkerNode::Router(router.clone())),
+        }
+    }
+
+    fn kind(&self) -> NodeKind {
+        match self {
+            ProcessorSlot::Router(router) => NodeKind::Router(router.outputs()),
+            ProcessorSlot::Instance(processor) | ProcessorSlot::Factory { first: processor, .. } => {
+                NodeKind::Processor(processor.as_ref().map(|p| p.side_outputs()).unwrap_or_default())
+            }
+        }
+    }
+}
+
+// 带名称和输入引用的拓扑节点；inputs 为 None 时按线性方式连接
+struct Named<T> {
+    name: String,
+    inputs: Option<Vec<String>>,
+    item: T,
+}
+
+// 节点集合，构建器和管道共用
+#[derive(Default)]
+struct Nodes {
+    sources: Vec<Named<Box<dyn crate::Source>>>,
+    processors: Vec<Named<ProcessorSlot>>,
+    sinks: Vec<Named<(Box<dyn crate::Sink>, SinkOptions)>>,
+    // 通过 edge() 追加的连接 (from, to)
+    edges: Vec<(String, String)>,
+    // 按组件名设置的错误策略，未设置的组件使用 drop
+    error_policies: Vec<(String, ErrorPolicy)>,
+    // 死信输出端，不参与拓扑连接
+    dead_letter: Option<Named<(Box<dyn crate::Sink>, SinkOptions)>>,
+    // 按数据源名设置的水位线策略，未设置的数据源不允许乱序
+    watermarks: Vec<(String, WatermarkStrategy)>,
+}
+
+impl Nodes {
+    fn contains(&self, name: &str) -> bool {
+        self.sources.iter().any(|n| n.name == name)
+            || self.processors.iter().any(|n| n.name == name)
+            || self.sinks.iter().chain(&self.dead_letter).any(|n| n.name == name)
+    }
+
+    // 未命名的组件使用其 name()，重名时追加序号
+    fn unique_name(&self, base: &str) -> String {
+        let mut name = base.to_string();
+        let mut n = 2;
+        while self.contains(&name) {
+            name = format!("{}#{}", base, n);
+            n += 1;
+        }
+        name
+    }
+
+    // 每个数据源、处理器、输出端（含死信输出端）的错误策略
+    fn policies(&self) -> Result<[Vec<ErrorPolicy>; 3], String> {
+        let lookup = |name: &str| {
+            self.error_policies
+                .iter()
+                .rev()
+                .find(|(n, _)| n == name)
+                .map(|(_, policy)| *policy)
+                .unwrap_or_default()
+        };
+
+        for (name, policy) in &self.error_policies {
+            if !self.contains(name) {
+                return Err(format!("错误策略指向不存在的组件 '{}'", name));
+            }
+            if policy.action == ErrorAction::DeadLetter {
+                if self.dead_letter.is_none() {
+                    return Err(format!("'{}' 的错误策略为 dead_letter，但没有配置死信输出端", name));
+                }
+                if self.sources.iter().any(|n| &n.name == name) {
+                    return Err(format!("数据源 '{}' 不支持 dead_letter 错误策略", name));
+                }
+            }
+        }
+
+        Ok([
+            self.sources.iter().map(|n| lookup(&n.name)).collect(),
+            self.processors.iter().map(|n| lookup(&n.name)).collect(),
+            self.sinks.iter().chain(&self.dead_letter).map(|n| lookup(&n.name)).collect(),
+        ])
+    }
+
+    // 每个数据源的水位线策略
+    fn watermark_strategies(&self) -> Result<Vec<WatermarkStrategy>, String> {
+        for (name, _) in &self.watermarks {
+            if !self.sources.iter().any(|n| &n.name == name) {
+                return Err(format!("水位线策略指向不存在的数据源 '{}'", name));
+            }
+        }
+        Ok(self
+            .sources
+            .iter()
+            .map(|n| {
+                self.watermarks
+                    .iter()
+                    .rev()
+                    .find(|(name, _)| *name == n.name)
+                    .map(|(_, strategy)| *strategy)
+                    .unwrap_or_default()
+            })
+            .collect())
+    }
+
+    fn decls(&self) -> Result<Vec<NodeDecl>, String> {
+        let mut decls: Vec<NodeDecl> = self.sources.iter()
+            .map(|n| NodeDecl::new(&n.name, NodeKind::Source, None))
+            .chain(self.processors.iter().map(|n| NodeDecl::new(&n.name, n.item.kind(), n.inputs.clone())))
+            .chain(self.sinks.iter().map(|n| NodeDecl::new(&n.name, NodeKind::Sink, n.inputs.clone())))
+            .collect();
+
+        for (from, to) in &self.edges {
+            let decl = decls
+                .iter_mut()
+                .find(|decl| &decl.name == to)
+                .ok_or_else(|| format!("连接 '{}' -> '{}' 的目标不存在", from, to))?;
+            decl.inputs.get_or_insert_with(Vec::new).push(from.clone());
+        }
+        Ok(decls)
+    }
+}
+
+// 确保这些结构体是pub的
+pub struct PipelineBuilder {
+    nodes: Nodes,
+    queue_size: usize,
+    workers: usize,
+    ordered: bool,
+    timeout: Option<Duration>,
+    drain_timeout: Duration,
+    handle_signals: bool,
+    watermark_interval: Duration,
+}
+
+pub struct Pipeline {
+    nodes: Nodes,
+    queue_size: usize,
+    workers: usize,
+    ordered: bool,
+    timeout: Option<Duration>,
+    drain_timeout: Duration,
+    handle_signals: bool,
+    watermark_interval: Duration,
+}
+
+impl Default for PipelineBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PipelineBuilder {
+    pub fn new() -> Self {
+        Self {
+            nodes: Nodes::default(),
+            queue_size: DEFAULT_QUEUE_SIZE,
+            workers: 1,
+            ordered: false,
+            timeout: None,
+            drain_timeout: DEFAULT_DRAIN_TIMEOUT,
+            handle_signals: false,
+            watermark_interval: DEFAULT_WATERMARK_INTERVAL,
+        }
+    }
+
+    pub fn add_source(self, source: Box<dyn crate::Source>) -> Self {
+        let name = self.nodes.unique_name(source.name());
+        self.source(&name, source)
+    }
+
+    pub fn add_processor(self, processor: Box<dyn crate::Processor>) -> Self {
+        let name = self.nodes.unique_name(processor.name());
+        self.push_processor(name, None, ProcessorSlot::Instance(Some(processor)))
+    }
+
+    // 通过工厂添加处理器，每个工作任务各自持有一个实例
+    pub fn add_processor_factory<F>(self, factory: F) -> Self
+    where
+        F: Fn() -> Box<dyn crate::Processor> + Send + Sync + 'static,
+    {
+        let first = factory();
+        let name = self.nodes.unique_name(first.name());
+        self.push_processor(name, None, factory_slot(first, factory))
+    }
+
+    pub fn add_sink(self, sink: Box<dyn crate::Sink>) -> Self {
+        self.add_sink_with(sink, SinkOptions::default())
+    }
+
+    // 添加输出端并指定其队列容量和溢出策略；每个输出端在独立任务中运行
+    pub fn add_sink_with(self, sink: Box<dyn crate::Sink>, options: SinkOptions) -> Self {
+        let name = self.nodes.unique_name(sink.name());
+        self.push_sink(name, None, sink, options)
+    }
+
+    // 以下方法按名称添加节点并声明输入，用于构建有分支的拓扑（DAG）
+    // 输入引用其他节点的名称，路由器的输出写成 "路由器名.输出名"
+    // 只要有一个节点声明了输入，所有处理器和输出端都必须声明
+
+    pub fn source(mut self, name: &str, source: Box<dyn crate::Source>) -> Self {
+        self.nodes.sources.push(Named { name: name.to_string(), inputs: None, item: source });
+        self
+    }
+
+    pub fn processor(self, name: &str, processor: Box<dyn crate::Processor>, inputs: &[&str]) -> Self {
+        self.push_processor(name.to_string(), Some(to_inputs(inputs)), ProcessorSlot::Instance(Some(processor)))
+    }
+
+    pub fn processor_factory<F>(self, name: &str, factory: F, inputs: &[&str]) -> Self
+    where
+        F: Fn() -> Box<dyn crate::Processor> + Send + Sync + 'static,
+    {
+        let first = factory();
+        self.push_processor(name.to_string(), Some(to_inputs(inputs)), factory_slot(first, factory))
+    }
+
+    // 路由器以自身名称作为节点名
+    pub fn router(self, router: Router, inputs: &[&str]) -> Self {
+        let name = router.name().to_string();
+        self.push_processor(name, Some(to_inputs(inputs)), ProcessorSlot::Router(Arc::new(router)))
+    }
+
+    pub fn sink(self, name: &str, sink: Box<dyn crate::Sink>, inputs: &[&str]) -> Self {
+        self.sink_with(name, sink, SinkOptions::default(), inputs)
+    }
+
+    pub fn sink_with(self, name: &str, sink: Box<dyn crate::Sink>, options: SinkOptions, inputs: &[&str]) -> Self {
+        self.push_sink(name.to_string(), Some(to_inputs(inputs)), sink, options)
+    }
+
+    // 追加一条连接：from 的输出流向 to
+    pub fn edge(mut self, from: &str, to: &str) -> Self {
+        self.nodes.edges.push((from.to_string(), to.to_string()));
+        self
+    }
+
+    // 设置组件出错时的策略：重试、丢弃、发送到死信输出端或使管道失败
+    pub fn on_error(mut self, name: &str, policy: ErrorPolicy) -> Self {
+        self.nodes.error_policies.push((name.to_string(), policy));
+        self
+    }
+
+    // 死信输出端接收错误策略为 dead_letter 的组件未能处理的数据块（JSON 格式的 DeadLetterRecord）
+    pub fn with_dead_letter_sink(self, sink: Box<dyn crate::Sink>) -> Self {
+        self.with_dead_letter_sink_with(sink, SinkOptions::default())
+    }
+
+    pub fn with_dead_letter_sink_with(mut self, sink: Box<dyn crate::Sink>, options: SinkOptions) -> Self {
+        let name = self.nodes.unique_name(sink.name());
+        self.nodes.dead_letter = Some(Named { name, inputs: None, item: (sink, options) });
+        self
+    }
+
+    // 设置数据源的水位线策略：允许的事件时间乱序程度和空闲超时
+    pub fn with_watermark(mut self, source: &str, strategy: WatermarkStrategy) -> Self {
+        self.nodes.watermarks.push((source.to_string(), strategy));
+        self
+    }
+
+    // 水位线检查和广播的间隔，默认 200 毫秒
+    pub fn with_watermark_interval(mut self, interval: Duration) -> Self {
+        self.watermark_interval = interval.max(Duration::from_millis(1));
+        self
+    }
+
+    fn push_processor(mut self, name: String, inputs: Option<Vec<String>>, item: ProcessorSlot) -> Self {
+        self.nodes.processors.push(Named { name, inputs, item });
+        self
+    }
+
+    fn push_sink(mut self, name: String, inputs: Option<Vec<String>>, sink: Box<dyn crate::Sink>, options: SinkOptions) -> Self {
+        self.nodes.sinks.push(Named { name, inputs, item: (sink, options) });
+        self
+    }
+
+    // 数据源与处理阶段之间的队列容量
+    pub fn with_queue_size(mut self, queue_size: usize) -> Self {
+        self.queue_size = queue_size.max(1);
+        self
+    }
+
+    // 并行处理的工作任务数量；大于 1 时所有处理器都必须通过工厂添加
+    pub fn with_workers(mut self, workers: usize) -> Self {
+        self.workers = workers.max(1);
+        self
+    }
+
+    // 有序模式：输出端按数据块进入管道的顺序接收（单数据源时即 sequence 顺序）
+    pub fn with_ordered_output(mut self, ordered: bool) -> Self {
+        self.ordered = ordered;
+        self
+    }
+
+    // 管道最长运行时间，超时后优雅停止
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    // 停止后排空队列的最长时间，超过后剩余数据块被丢弃
+    pub fn with_drain_timeout(mut self, drain_timeout: Duration) -> Self {
+        self.drain_timeout = drain_timeout;
+        self
+    }
+
+    // 收到 SIGINT/SIGTERM 时优雅停止，第二次信号立即中止
+    pub fn with_signal_handling(mut self, enabled: bool) -> Self {
+        self.handle_signals = enabled;
+        self
+    }
+
+    pub fn build(self) -> Pipeline {
+        Pipeline {
+            nodes: self.nodes,
+            queue_size: self.queue_size,
+            workers: self.workers,
+            ordered: self.ordered,
+            timeout: self.timeout,
+            drain_timeout: self.drain_timeout,
+            handle_signals: self.handle_signals,
+            watermark_interval: self.watermark_interval,
+        }
+    }
+}
+
+// 管道结束的原因
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Termination {
+    // 所有数据源正常结束
+    Completed,
+    // 通过 shutdown() 或信号请求停止
+    Shutdown,
+    // 达到配置的运行时间上限
+    Timeout,
+    // 通过 abort() 立即中止
+    Aborted,
+    // 处理器返回 ProcessResult::Stop / StopAndDeliver
+    Stopped { processor: String, reason: String },
+}
+
+fn to_inputs(inputs: &[&str]) -> Vec<String> {
+    inputs.iter().map(|input| input.to_string()).collect()
+}
+
+fn factory_slot<F>(first: Box<dyn Processor>, factory: F) -> ProcessorSlot
+where
+    F: Fn() -> Box<dyn Processor> + Send + Sync + 'static,
+{
+    ProcessorSlot::Factory {
+        first: Some(first),
+        factory: Arc::new(move || Ok(factory())),
+    }
+}
+
+// 一次运行的统计结果
+#[derive(Debug, Clone)]
+pub struct RunSummary {
+    pub processed: u64,
+    pub skipped: u64,
+    // 因排空超时或中止而未处理的数据块
+    pub undelivered: u64,
+    pub termination: Termination,
+    // 数据源和处理器出错的次数（按错误策略重试成功的不计）
+    pub errors: u64,
+    // 每个输出端的投递、丢弃和错误计数
+    pub sinks: Vec<SinkReport>,
+    // 数据源自己的计数（例如抓包的内核丢包数），没有计数的数据源不列出
+    pub sources: Vec<SourceReport>,
+}
+
+// 单个数据源结束时报告的计数
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceReport {
+    pub name: String,
+    pub metrics: HashMap<String, u64>,
+}
+
+// 可克隆的控制器，用于从其他任务停止管道
+#[derive(Clone)]
+pub struct PipelineController {
+    shutdown: CancellationToken,
+    abort: CancellationToken,
+}
+
+impl PipelineController {
+    fn new() -> Self {
+        let abort = CancellationToken::new();
+        // 中止同时意味着停止数据源
+        let shutdown = abort.child_token();
+        Self { shutdown, abort }
+    }
+
+    // 优雅停止：停止数据源，排空队列中的数据块，刷新输出端
+    pub fn shutdown(&self) {
+        self.shutdown.cancel();
+    }
+
+    // 立即中止：取消所有任务，不再排空和刷新
+    pub fn abort(&self) {
+        self.abort.cancel();
+    }
+
+    pub fn is_shutting_down(&self) -> bool {
+        self.shutdown.is_cancelled()
+    }
+}
+
+// Pipeline::run 返回的句柄，可以 await 等待管道结束
+pub struct PipelineHandle {
+    controller: PipelineController,
+    task: JoinHandle<anyhow::Result<RunSummary>>,
+}
+
+impl PipelineHandle {
+    pub fn controller(&self) -> PipelineController {
+        self.controller.clone()
+    }
+
+    pub fn shutdown(&self) {
+        self.controller.shutdown();
+    }
+
+    pub fn abort(&self) {
+        self.controller.abort();
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.task.is_finished()
+    }
+
+    pub async fn wait(self) -> anyhow::Result<RunSummary> {
+        match self.task.await {
+            Ok(result) => result,
+            Err(e) => Err(anyhow::anyhow!("Pipeline task failed: {}", e)),
+        }
+    }
+}
+
+impl IntoFuture for PipelineHandle {
+    type Output = anyhow::Result<RunSummary>;
+    type IntoFuture = Pin<Box<dyn Future<Output = Self::Output> + Send>>;
+
+    fn into_future(self) -> Self::IntoFuture {
+        Box::pin(self.wait())
+    }
+}
+
+impl Pipeline {
+    pub fn builder() -> PipelineBuilder {
+        PipelineBuilder::new()
+    }
+
+    // 使用内置组件注册表从配置创建管道
+    pub fn from_config(config: &PipelineConfig) -> Result<Pipeline, ConfigError> {
+        Self::from_config_with_registry(config, &ComponentRegistry::with_builtins())
+    }
+
+    // 使用自定义注册表从配置创建管道
+    pub fn from_config_with_registry(
+        config: &PipelineConfig,
+        registry: &ComponentRegistry,
+    ) -> Result<Pipeline, ConfigError> {
+        config.check_structure()?;
+
+        let mut builder = Pipeline::builder()
+            .with_queue_size(config.max_queue_size)
+            .with_workers(config.workers)
+            .with_ordered_output(config.ordered)
+            .with_signal_handling(config.handle_signals);
+        if let Some(seconds) = config.timeout_seconds {
+            builder = builder.with_timeout(Duration::from_secs(seconds));
+        }
+        if let Some(seconds) = config.drain_timeout_seconds {
+            builder = builder.with_drain_timeout(Duration::from_secs(seconds));
+        }
+        if let Some(millis) = config.watermark_interval_ms {
+            builder = builder.with_watermark_interval(Duration::from_millis(millis));
+        }
+
+        for source in &config.sources {
+            builder = builder.source(&source.name, registry.create_source(source)?);
+            if let Some(strategy) = source.watermark {
+                builder = builder.with_watermark(&source.name, strategy);
+            }
+        }
+        for processor in &config.processors {
+            let name = processor.name.clone();
+            let inputs = processor.inputs.clone();
+            let slot = if registry.is_router(&processor.component_type) {
+                ProcessorSlot::Router(Arc::new(registry.create_router(processor)?))
+            } else if config.workers > 1 {
+                let factory = registry.processor_factory(processor)?;
+                ProcessorSlot::Factory {
+                    first: Some(factory()?),
+                    factory: Arc::new(move || Ok(factory()?)),
+                }
+            } else {
+                ProcessorSlot::Instance(Some(registry.create_processor(processor)?))
+            };
+            builder = builder.push_processor(name, inputs, slot);
+        }
+        let sink_options = |sink: &crate::config::ComponentConfig| {
+            let options = SinkOptions::new().with_overflow(sink.overflow.unwrap_or_default());
+            match sink.queue_size {
+                Some(queue_size) => options.with_queue_size(queue_size),
+                None => options,
+            }
+        };
+        for sink in &config.sinks {
+            let options = sink_options(sink);
+            builder = builder.push_sink(sink.name.clone(), sink.inputs.clone(), registry.create_sink(sink)?, options);
+        }
+        if let Some(sink) = &config.dead_letter {
+            let options = sink_options(sink);
+            builder.nodes.dead_letter = Some(Named {
+                name: sink.name.clone(),
+                inputs: None,
+                item: (registry.create_sink(sink)?, options),
+            });
+        }
+        for (_, component) in config.components() {
+            if let Some(policy) = component.on_error {
+                builder = builder.on_error(&component.name, policy);
+            }
+        }
+
+        // 拓扑和错误策略的错误（未知输入、环路等）在构建时报告
+        builder
+            .nodes
+            .decls()
+            .and_then(|decls| Topology::build(&decls))
+            .and_then(|_| builder.nodes.policies().map(drop))
+            .and_then(|_| builder.nodes.watermark_strategies().map(drop))
+            .map_err(ConfigError::ConfigError)?;
+
+        Ok(builder.build())
+    }
+
+    // 在后台启动管道，必须在 Tokio 运行时中调用
+    pub fn run(self) -> PipelineHandle {
+        let controller = PipelineController::new();
+        if self.handle_signals {
+            spawn_signal_listener(controller.clone());
+        }
+
+        let task = tokio::spawn(self.execute(controller.clone()));
+        PipelineHandle { controller, task }
+    }
+
+    async fn execute(self, controller: PipelineController) -> anyhow::Result<RunSummary> {
+        use tokio::sync::mpsc;
+        use tracing::{debug, info, warn, error};
+
+        let nodes = self.nodes;
+        info!("Starting Pelican Pipeline with {} sources, {} processors, {} sinks, {} workers",
+              nodes.sources.len(), nodes.processors.len(), nodes.sinks.len(), self.workers);
+
+        if nodes.sources.is_empty() {
+            return Err(anyhow::anyhow!("No sources configured"));
+        }
+
+        let [source_policies, processor_policies, sink_policies] = nodes
+            .policies()
+            .map_err(|e| anyhow::anyhow!("Invalid error policy: {}", e))?;
+        let mut watermarks = WatermarkTracker::new(
+            nodes.watermark_strategies().map_err(|e| anyhow::anyhow!("Invalid watermark strategy: {}", e))?,
+        );
+        let mut topology = nodes
+            .decls()
+            .and_then(|decls| Topology::build(&decls))
+            .map_err(|e| anyhow::anyhow!("Invalid pipeline topology: {}", e))?;
+        topology.policies = processor_policies;
+        topology.dead_letter = nodes.dead_letter.as_ref().map(|_| nodes.sinks.len());
+        let topology = Arc::new(topology);
+
+        let workers = self.workers;
+        if workers > 1 {
+            let instance = nodes.processors.iter().find_map(|node| match &node.item {
+                ProcessorSlot::Instance(Some(_)) => Some(node.name.clone()),
+                _ => None,
+            });
+            if let Some(name) = instance {
+                return Err(anyhow::anyhow!(
+                    "Processor {} was added as an instance and cannot run on {} workers; use add_processor_factory",
+                    name, workers
+                ));
+            }
+        }
+
+        // 为每个工作任务准备一套独立的节点实例
+        let mut slots: Vec<ProcessorSlot> = nodes.processors.into_iter().map(|node| node.item).collect();
+        let mut chains: Vec<Vec<WorkerNode>> = Vec::with_capacity(workers);
+        for _ in 0..workers {
+            let chain = slots
+                .iter_mut()
+                .map(ProcessorSlot::instantiate)
+                .collect::<anyhow::Result<Vec<_>>>()?;
+            chains.push(chain);
+        }
+
+        let (source_ids, mut sources): (Vec<_>, Vec<_>) =
+            nodes.sources.into_iter().map(|node| (node.name, node.item)).unzip();
+        let mut sink_names = Vec::with_capacity(nodes.sinks.len());
+        let mut sink_options = Vec::with_capacity(nodes.sinks.len());
+        let mut sinks = Vec::with_capacity(nodes.sinks.len());
+        for node in nodes.sinks.into_iter().chain(nodes.dead_letter) {
+            let (sink, options) = node.item;
+            sink_names.push(node.name);
+            sink_options.push(options);
+            sinks.push(sink);
+        }
+
+        // 按下游到上游的顺序打开：输出端 → 处理器 → 数据源
+        open_sinks(&mut sinks).await?;
+        if let Err(e) = open_processors(&mut chains).await {
+            close_sinks(&mut sinks, &mut Vec::new()).await;
+            return Err(e);
+        }
+        if let Err(e) = open_sources(&mut sources).await {
+            let mut hook_errors = Vec::new();
+            close_processors(&mut chains, &mut hook_errors).await;
+            close_sinks(&mut sinks, &mut hook_errors).await;
+            return Err(e);
+        }
+
+        let state = Arc::new(RunState::new(self.ordered));
+
+        // 每个输出端一个任务和一个有界队列，慢输出端只影响自己的队列
+        // sink_discard 仅在排空超时后取消，处理器停止时仍投递已处理的数据块
+        let sink_discard = CancellationToken::new();
+        let queues: Vec<Arc<SinkQueue>> = sink_names
+            .iter()
+            .zip(&sink_options)
+            .map(|(name, options)| Arc::new(SinkQueue::new(
+                name,
+                options.queue_size.unwrap_or(self.queue_size),
+                options.overflow,
+            )))
+            .collect();
+        let dead_letter_queue = topology.dead_letter.map(|index| queues[index].clone());
+        let mut sink_handles = Vec::with_capacity(sinks.len());
+        for ((sink, queue), policy) in sinks.into_iter().zip(&queues).zip(sink_policies) {
+            let queue = queue.clone();
+            // 死信输出端自身出错时不再转发
+            let dead_letter = dead_letter_queue.clone().filter(|dlq| !Arc::ptr_eq(dlq, &queue));
+            let discard = sink_discard.clone();
+            let state = state.clone();
+            sink_handles.push(tokio::spawn(async move {
+                queue.run(sink, discard, policy, dead_letter, state).await
+            }));
+        }
+        let sink_queues = queues;
+
+        // 输出阶段
+        let (output_tx, output_rx) = mpsc::channel(self.queue_size);
+        let collector = tokio::spawn(
+            Collector::new(sink_queues.clone(), topology.dead_letter, sink_discard.clone()).run(output_rx),
+        );
+
+        // 工作任务，每个持有一套节点实例
+        let worker_queue = self.queue_size.div_ceil(workers).max(1);
+        let mut worker_txs = Vec::with_capacity(workers);
+        let mut worker_handles = Vec::with_capacity(workers);
+        for (id, nodes) in chains.into_iter().enumerate() {
+            let (tx, rx) = mpsc::channel::<WorkerInput>(worker_queue);
+            let worker = Worker {
+                id,
+                nodes,
+                topology: topology.clone(),
+                output: output_tx.clone(),
+                state: state.clone(),
+            };
+            worker_txs.push(tx);
+            worker_handles.push(tokio::spawn(worker.run(rx)));
+        }
+        drop(output_tx);
+
+        // 创建通道连接各个处理阶段；数据源结束时发送 None，水位线不再等待它
+        let (source_tx, mut processor_rx) = mpsc::channel::<(usize, Option<crate::DataChunk>)>(self.queue_size);
+
+        // 启动数据源 - 转移所有权到异步任务中，结束后交还数据源以便关闭
+        // 每个数据源的输出带上数据源下标后转发，以便按拓扑找到它的下游；
+        // 未设置 source_id 的数据块填入数据源的节点名，并记录进入管道的时间
+        let mut source_handles = vec![];
+        for (((index, mut source), policy), source_id) in
+            sources.into_iter().enumerate().zip(source_policies).zip(source_ids)
+        {
+            let tx = source_tx.clone();
+            let source_id: Arc<str> = source_id.into();
+            let source_name = source.name().to_string();
+            let shutdown = controller.shutdown.clone();
+            let state = state.clone();
+
+            let handle = tokio::spawn(async move {
+                info!("Starting source: {}", source_name);
+                let (local_tx, mut local_rx) = mpsc::channel::<crate::DataChunk>(1);
+                let forward = async move {
+                    while let Some(mut chunk) = local_rx.recv().await {
+                        if chunk.source_id.is_none() {
+                            chunk.source_id = Some(source_id.clone());
+                        }
+                        chunk.ingest_time_ns = crate::now_ns();
+                        if tx.send((index, Some(chunk))).await.is_err() {
+                            return;
+                        }
+                    }
+                    let _ = tx.send((index, None)).await;
+                };
+                // 出错时按错误策略重新调用 stream_data，重试用尽后记录日志或使管道失败
+                let produce = async {
+                    let mut attempt = 0;
+                    loop {
+                        let result = tokio::select! {
+                            biased;
+                            _ = shutdown.cancelled() => {
+                                info!("Source {} stopped by shutdown", source_name);
+                                break;
+                            }
+                            result = source.stream_data(local_tx.clone()) => result,
+                        };
+                        let Err(e) = result else {
+                            info!("Source {} finished", source_name);
+                            break;
+                        };
+                        if let Some(retry) = policy.retry.filter(|retry| attempt < retry.max_retries) {
+                            let backoff = retry.backoff(attempt);
+                            attempt += 1;
+                            warn!("Source {} error (attempt {}), retrying in {:?}: {}", source_name, attempt, backoff, e);
+                            tokio::select! {
+                                _ = shutdown.cancelled() => break,
+                                _ = tokio::time::sleep(backoff) => {}
+                            }
+                            continue;
+                        }
+                        error!("Source {} error: {}", source_name, e);
+                        state.errors.fetch_add(1, Ordering::Relaxed);
+                        if policy.action == ErrorAction::Fail {
+                            state.request_fail(format!("source {}: {:#}", source_name, e));
+                        }
+                        break;
+                    }
+                    drop(local_tx);
+                };
+                tokio::join!(produce, forward);
+                source
+            });
+            source_handles.push(handle);
+        }
+
+        // 释放source_tx，这样当所有源完成时，processor_rx.recv()会返回None
+        drop(source_tx);
+
+        let mut received: u64 = 0;
+        let mut next_worker = 0;
+        let mut termination = Termination::Completed;
+
+        // 超时后触发优雅停止；停止后最多再排空 drain_timeout
+        let run_deadline = self.timeout.map(|timeout| tokio::time::Instant::now() + timeout);
+        let mut drain_deadline: Option<tokio::time::Instant> = None;
+
+        // 定期检查水位线，前进时广播给所有工作任务
+        let mut watermark_tick = tokio::time::interval(self.watermark_interval);
+        watermark_tick.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+        // 分发循环：把数据块轮流分配给有空闲容量的工作任务
+        loop {
+            if drain_deadline.is_none() && controller.is_shutting_down() {
+                if termination == Termination::Completed {
+                    termination = Termination::Shutdown;
+                }
+                info!("Shutdown requested, draining in-flight chunks");
+                drain_deadline = Some(tokio::time::Instant::now() + self.drain_timeout);
+            }
+
+            let next = tokio::select! {
+                biased;
+                _ = controller.abort.cancelled() => {
+                    termination = Termination::Aborted;
+                    break;
+                }
+                _ = state.stopped.cancelled() => break,
+                _ = sleep_until_opt(drain_deadline) => {
+                    warn!("Drain timeout reached with {} chunks still queued", processor_rx.len());
+                    state.discard.cancel();
+                    sink_discard.cancel();
+                    break;
+                }
+                _ = sleep_until_opt(run_deadline), if drain_deadline.is_none() => {
+                    warn!("Pipeline timeout reached, stopping sources");
+                    termination = Termination::Timeout;
+                    controller.shutdown();
+                    continue;
+                }
+                _ = controller.shutdown.cancelled(), if drain_deadline.is_none() => continue,
+                _ = watermark_tick.tick() => {
+                    let Some(watermark_ns) = watermarks.advance() else { continue };
+                    debug!("Watermark advanced to {}", watermark_ns);
+                    tokio::select! {
+                        biased;
+                        _ = controller.abort.cancelled() => {
+                            termination = Termination::Aborted;
+                            break;
+                        }
+                        _ = state.stopped.cancelled() => break,
+                        result = broadcast_watermark(&worker_txs, watermark_ns) => {
+                            if result.is_err() {
+                                break;
+                            }
+                        }
+                    }
+                    continue;
+                }
+                next = processor_rx.recv() => next,
+            };
+            let Some((source, chunk)) = next else { break };
+            let Some(mut chunk) = chunk else {
+                watermarks.finish(source);
+                continue;
+            };
+            watermarks.observe(source, chunk.time_ns());
+
+            // 全局编号按进入管道的顺序从 1 递增，数据源自己的 sequence 保持不变
+            received += 1;
+            chunk.id = received;
+            let dispatched = Dispatched { index: received - 1, source, chunk };
+            // 所有工作任务都满时等待，但仍响应中止和停止请求
+            tokio::select! {
+                biased;
+                _ = controller.abort.cancelled() => {
+                    termination = Termination::Aborted;
+                    break;
+                }
+                _ = state.stopped.cancelled() => {
+                    state.undelivered.fetch_add(1, Ordering::Relaxed);
+                    break;
+                }
+                result = dispatch(&worker_txs, &mut next_worker, dispatched) => {
+                    if result.is_err() {
+                        break;
+                    }
+                }
+            }
+        }
+
+        // 处理器要求停止：取消数据源，队列中剩余的数据块不再处理
+        if let Some((processor, reason)) = state.stop_request() {
+            if termination != Termination::Aborted {
+                info!("Pipeline stopped by processor {}: {}", processor, reason);
+                termination = Termination::Stopped { processor, reason };
+            }
+        }
+        controller.shutdown();
+
+        // 未来得及分发的数据块
+        processor_rx.close();
+        let mut undelivered: u64 = 0;
+        while let Ok((_, chunk)) = processor_rx.try_recv() {
+            undelivered += u64::from(chunk.is_some());
+        }
+
+        // 中止时不再调用任何生命周期钩子；先取消 discard，正在退避等待重试的任务立即放弃
+        if termination == Termination::Aborted {
+            state.discard.cancel();
+            sink_discard.cancel();
+            for handle in source_handles.iter().map(JoinHandle::abort_handle)
+                .chain(worker_handles.iter().map(JoinHandle::abort_handle))
+                .chain(std::iter::once(collector.abort_handle()))
+                .chain(sink_handles.iter().map(JoinHandle::abort_handle))
+            {
+                handle.abort();
+            }
+            controller.abort.cancel();
+            info!("Pelican Pipeline aborted. Total: {} received, {} undelivered", received, undelivered);
+            return Ok(RunSummary {
+                processed: received,
+                skipped: state.skipped.load(Ordering::Relaxed),
+                undelivered,
+                termination,
+                errors: state.errors.load(Ordering::Relaxed),
+                sinks: sink_queues.iter().map(|queue| queue.report()).collect(),
+                sources: Vec::new(),
+            });
+        }
+
+        // 按上游到下游的顺序收尾：
+        // 关闭数据源 → 排空并刷新处理器 → 刷新输出端 → 关闭处理器 → 关闭输出端
+        let mut hook_errors = Vec::new();
+        let mut source_reports = Vec::new();
+        for handle in source_handles {
+            match handle.await {
+                Ok(mut source) => {
+                    if let Err(e) = source.close().await {
+                        hook_errors.push(format!("source {} close: {}", source.name(), e));
+                    }
+                    let metrics = source.metrics();
+                    if !metrics.is_empty() {
+                        source_reports.push(SourceReport { name: source.name().to_string(), metrics });
+                    }
+                }
+                Err(e) => hook_errors.push(format!("source task failed: {}", e)),
+            }
+        }
+
+        // 关闭工作任务的输入，等待它们处理完队列；超过排空期限则丢弃剩余数据块
+        drop(worker_txs);
+        let drain_deadline = drain_deadline.unwrap_or_else(|| tokio::time::Instant::now() + self.drain_timeout);
+        let discard = state.discard.clone();
+        let watch_sink_discard = sink_discard.clone();
+        let drain_watch = tokio::spawn(async move {
+            tokio::time::sleep_until(drain_deadline).await;
+            discard.cancel();
+            watch_sink_discard.cancel();
+        });
+
+        let mut chains = Vec::with_capacity(workers);
+        for handle in worker_handles {
+            match handle.await {
+                Ok((processors, errors)) => {
+                    chains.push(processors);
+                    hook_errors.extend(errors);
+                }
+                Err(e) => hook_errors.push(format!("worker task failed: {}", e)),
+            }
+        }
+        if let Err(e) = collector.await {
+            hook_errors.push(format!("output task failed: {}", e));
+        }
+
+        // 死信输出端最后结束，接收其他输出端排空时产生的死信
+        let dead_letter_handle = topology.dead_letter.map(|_| sink_handles.pop().expect("dead letter sink task"));
+        let mut sinks = Vec::with_capacity(sink_handles.len() + 1);
+        for handle in sink_handles.into_iter().chain(dead_letter_handle) {
+            if sinks.len() == topology.dead_letter.unwrap_or(usize::MAX) {
+                sink_queues[sinks.len()].close();
+            }
+            match handle.await {
+                Ok(sink) => sinks.push(sink),
+                Err(e) => hook_errors.push(format!("sink task failed: {}", e)),
+            }
+        }
+        drain_watch.abort();
+
+        for sink in &mut sinks {
+            if let Err(e) = sink.flush().await {
+                hook_errors.push(format!("sink {} flush: {}", sink.name(), e));
+            }
+        }
+        close_processors(&mut chains, &mut hook_errors).await;
+        close_sinks(&mut sinks, &mut hook_errors).await;
+
+        // 结束信号监听任务
+        controller.abort.cancel();
+
+        let skipped = state.skipped.load(Ordering::Relaxed);
+        undelivered += state.undelivered.load(Ordering::Relaxed);
+        info!("Pelican Pipeline stopped ({:?}). Total: {} processed, {} skipped, {} undelivered",
+              termination, received, skipped, undelivered);
+        let sink_reports: Vec<SinkReport> = sink_queues.iter().map(|queue| queue.report()).collect();
+        for report in &sink_reports {
+            if report.dropped > 0 || report.errors > 0 {
+                warn!("Sink {}: {} delivered, {} dropped, {} errors",
+                      report.name, report.delivered, report.dropped, report.errors);
+            }
+        }
+
+        // 错误策略为 fail 的组件出错：已按正常流程收尾，返回错误
+        if let Some(failure) = state.failure() {
+            for e in &hook_errors {
+                error!("Lifecycle hook error: {}", e);
+            }
+            return Err(anyhow::anyhow!("Pipeline failed: {}", failure));
+        }
+
+        if !hook_errors.is_empty() {
+            for e in &hook_errors {
+                error!("Lifecycle hook error: {}", e);
+            }
+            return Err(anyhow::anyhow!("Pipeline lifecycle hooks failed: {}", hook_errors.join("; ")));
+        }
+
+        Ok(RunSummary {
+            processed: received,
+            skipped,
+            undelivered,
+            termination,
+            errors: state.errors.load(Ordering::Relaxed),
+            sinks: sink_reports,
+            sources: source_reports,
+        })
+    }
+}
+
+// 优先选择有空闲容量的工作任务，全部满时等待轮到的那个
+async fn dispatch(
+    workers: &[tokio::sync::mpsc::Sender<WorkerInput>],
+    next_worker: &mut usize,
+    dispatched: Dispatched,
+) -> Result<(), ()> {
+    use tokio::sync::mpsc::error::TrySendError;
+
+    let start = *next_worker;
+    *next_worker = (start + 1) % workers.len();
+    let mut input = WorkerInput::Chunk(dispatched);
+    for offset in 0..workers.len() {
+        match workers[(start + offset) % workers.len()].try_send(input) {
+            Ok(()) => return Ok(()),
+            Err(TrySendError::Full(returned)) => input = returned,
+            Err(TrySendError::Closed(_)) => return Err(()),
+        }
+    }
+    workers[start].send(input).await.map_err(|_| ())
+}
+
+// 水位线发送给每个工作任务，各自的处理器实例都需要前进
+async fn broadcast_watermark(workers: &[tokio::sync::mpsc::Sender<WorkerInput>], watermark_ns: u64) -> Result<(), ()> {
+    for worker in workers {
+        worker.send(WorkerInput::Watermark(watermark_ns)).await.map_err(|_| ())?;
+    }
+    Ok(())
+}
+
+// 依次打开数据源，失败时关闭已打开的数据源
+async fn open_sources(sources: &mut [Box<dyn crate::Source>]) -> anyhow::Result<()> {
+    for i in 0..sources.len() {
+        if let Err(e) = sources[i].open().await {
+            let error = anyhow::anyhow!("Source {} open failed: {}", sources[i].name(), e);
+            for source in sources[..i].iter_mut().rev() {
+                let _ = source.close().await;
+            }
+            return Err(error);
+        }
+    }
+    Ok(())
+}
+
+// 依次打开所有工作任务的处理器，失败时关闭已打开的处理器
+async fn open_processors(chains: &mut [Vec<WorkerNode>]) -> anyhow::Result<()> {
+    let mut opened: Vec<(usize, usize)> = Vec::new();
+    for worker in 0..chains.len() {
+        for index in 0..chains[worker].len() {
+            let Some(processor) = chains[worker][index].processor_mut() else { continue };
+            if let Err(e) = processor.open().await {
+                let error = anyhow::anyhow!("Processor {} open failed: {}", processor.name(), e);
+                for &(w, i) in opened.iter().rev() {
+                    if let Some(processor) = chains[w][i].processor_mut() {
+                        let _ = processor.close().await;
+                    }
+                }
+                return Err(error);
+            }
+            opened.push((worker, index));
+        }
+    }
+    Ok(())
+}
+
+// 依次打开输出端，失败时关闭已打开的输出端
+async fn open_sinks(sinks: &mut [Box<dyn crate::Sink>]) -> anyhow::Result<()> {
+    for i in 0..sinks.len() {
+        if let Err(e) = sinks[i].open().await {
+            let error = anyhow::anyhow!("Sink {} open failed: {}", sinks[i].name(), e);
+            for sink in sinks[..i].iter_mut().rev() {
+                let _ = sink.close().await;
+            }
+            return Err(error);
+        }
+    }
+    Ok(())
+}
+
+async fn close_processors(chains: &mut [Vec<WorkerNode>], hook_errors: &mut Vec<String>) {
+    for processor in chains.iter_mut().flatten().filter_map(WorkerNode::processor_mut) {
+        if let Err(e) = processor.close().await {
+            hook_errors.push(format!("processor {} close: {}", processor.name(), e));
+        }
+    }
+}
+
+async fn close_sinks(sinks: &mut [Box<dyn crate::Sink>], hook_errors: &mut Vec<String>) {
+    for sink in sinks {
+        if let Err(e) = sink.close().await {
+            hook_errors.push(format!("sink {} close: {}", sink.name(), e));
+        }
+    }
+}
+
+async fn sleep_until_opt(deadline: Option<tokio::time::Instant>) {
+    match deadline {
+        Some(deadline) => tokio::time::sleep_until(deadline).await,
+        None => std::future::pending().await,
+    }
+}
+
+// 第一次 SIGINT/SIGTERM 优雅停止，第二次立即中止
+fn spawn_signal_listener(controller: PipelineController) {
+    use tracing::{info, warn};
+
+    tokio::spawn(async move {
+        for attempt in 0..2 {
+            tokio::select! {
+                _ = controller.abort.cancelled() => return,
+                _ = wait_for_signal() => {}
+            }
+            if attempt == 0 {
+                info!("Received termination signal, shutting down gracefully (repeat to abort)");
+                controller.shutdown();
+            } else {
+                warn!("Received second termination signal, aborting");
+                controller.abort();
+            }
+        }
+    });
+}
+
+#[cfg(unix)]
+async fn wait_for_signal() {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    match signal(SignalKind::terminate()) {
+        Ok(mut sigterm) => {
+            tokio::select! {
+                _ = tokio::signal::ctrl_c() => {}
+                _ = sigterm.recv() => {}
+            }
+        }
+        Err(_) => {
+            let _ = tokio::signal::ctrl_c().await;
+        }
+    }
+}
+
+#[cfg(not(unix))]
+async fn wait_for_signal() {
+    let _ = tokio::signal::ctrl_c().await;
+}
@@ -0,0 +1,138 @@
+use super::sink::SinkQueue;
+use super::worker::WorkerOutput;
+use crate::DataChunk;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use tokio::sync::mpsc;
+use tokio_util::sync::CancellationToken;
+
+// 输出阶段：汇总所有工作任务的结果，有序模式下按分发顺序重排后放入对应输出端的队列
+pub(crate) struct Collector {
+    queues: Vec<Arc<SinkQueue>>,
+    // 死信队列由管道在所有输出端结束后关闭
+    dead_letter: Option<usize>,
+    discard: CancellationToken,
+    next_index: u64,
+    pending: BTreeMap<u64, Vec<(usize, DataChunk)>>,
+}
+
+impl Collector {
+    pub fn new(queues: Vec<Arc<SinkQueue>>, dead_letter: Option<usize>, discard: CancellationToken) -> Self {
+        Self {
+            queues,
+            dead_letter,
+            discard,
+            next_index: 0,
+            pending: BTreeMap::new(),
+        }
+    }
+
+    pub async fn run(mut self, mut input: mpsc::Receiver<WorkerOutput>) {
+        while let Some(output) = input.recv().await {
+            match output.index {
+                Some(index) => {
+                    self.pending.insert(index, output.outputs);
+                    while let Some(outputs) = self.pending.remove(&self.next_index) {
+                        self.next_index += 1;
+                        self.deliver(outputs).await;
+                    }
+                }
+                None => self.deliver(output.outputs).await,
+            }
+        }
+
+        // 中途停止时可能留有缺口，剩余数据块按顺序投递
+        let pending = std::mem::take(&mut self.pending);
+        for outputs in pending.into_values() {
+            self.deliver(outputs).await;
+        }
+
+        for (index, queue) in self.queues.iter().enumerate() {
+            if Some(index) != self.dead_letter {
+                queue.close();
+            }
+        }
+    }
+
+    async fn deliver(&mut self, outputs: Vec<(usize, DataChunk)>) {
+        for (sink, chunk) in outputs {
+            self.queues[sink].push(chunk, &self.discard).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pipeline::error_policy::ErrorPolicy;
+    use crate::pipeline::sink::OverflowPolicy;
+    use crate::pipeline::state::RunState;
+    use crate::sinks::Sink;
+    use async_trait::async_trait;
+    use std::sync::Mutex;
+
+    #[derive(Clone, Default)]
+    struct CollectSink(Arc<Mutex<Vec<Vec<u8>>>>);
+
+    #[async_trait]
+    impl Sink for CollectSink {
+        async fn send(&mut self, chunk: DataChunk) -> anyhow::Result<()> {
+            self.0.lock().unwrap().push(chunk.data.to_vec());
+            Ok(())
+        }
+
+        fn name(&self) -> &str {
+            "CollectSink"
+        }
+    }
+
+    fn output(index: Option<u64>, data: &[&str]) -> WorkerOutput {
+        WorkerOutput {
+            index,
+            outputs: data.iter().map(|data| (0, DataChunk::new(data.as_bytes().to_vec()))).collect(),
+        }
+    }
+
+    // 按顺序送入输出阶段，返回输出端收到的数据
+    async fn collect(outputs: Vec<WorkerOutput>) -> Vec<String> {
+        let queue = Arc::new(SinkQueue::new("output", 64, OverflowPolicy::Block));
+        let discard = CancellationToken::new();
+        let (tx, rx) = mpsc::channel(64);
+        for output in outputs {
+            tx.send(output).await.unwrap();
+        }
+        drop(tx);
+        Collector::new(vec![queue.clone()], None, discard.clone()).run(rx).await;
+
+        let sink = CollectSink::default();
+        queue.run(Box::new(sink.clone()), discard, ErrorPolicy::drop(), None, Arc::new(RunState::new(true))).await;
+        let data = sink.0.lock().unwrap();
+        data.iter().map(|data| String::from_utf8(data.clone()).unwrap()).collect()
+    }
+
+    #[tokio::test]
+    async fn reorders_by_dispatch_index() {
+        let outputs = vec![
+            output(Some(2), &["c"]),
+            output(Some(0), &["a1", "a2"]),
+            // 被跳过的数据块也占一个序号，重排缓冲才能越过它
+            output(Some(3), &[]),
+            output(Some(1), &["b"]),
+            output(Some(4), &["e"]),
+        ];
+        assert_eq!(collect(outputs).await, ["a1", "a2", "b", "c", "e"]);
+    }
+
+    #[tokio::test]
+    async fn unordered_outputs_and_gaps_are_still_delivered() {
+        let outputs = vec![
+            output(None, &["flush"]),
+            output(Some(1), &["b"]),
+            output(Some(0), &["a"]),
+            // 中途停止时序号 3 永远不会到达，剩余数据块在结束时按顺序投递
+            output(Some(5), &["f"]),
+            output(Some(4), &["e"]),
+        ];
+        assert_eq!(collect(outputs).await, ["flush", "a", "b", "e", "f"]);
+    }
+}
@@ -0,0 +1,451 @@
+use super::error_policy::{DeadLetterRecord, ErrorAction, ErrorPolicy};
+use super::state::RunState;
+use crate::sinks::Sink;
+use crate::DataChunk;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::sync::Notify;
+use tokio_util::sync::CancellationToken;
+use tracing::{error, warn};
+
+// 输出端队列已满时的处理方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OverflowPolicy {
+    // 等待输出端腾出空间，慢输出端会反压整个管道
+    #[default]
+    Block,
+    // 丢弃新到的数据块
+    DropNewest,
+    // 丢弃队列中最旧的数据块
+    DropOldest,
+}
+
+// 单个输出端的队列设置
+#[derive(Debug, Clone, Default)]
+pub struct SinkOptions {
+    // 未设置时使用管道的 queue_size
+    pub queue_size: Option<usize>,
+    pub overflow: OverflowPolicy,
+}
+
+impl SinkOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_queue_size(mut self, queue_size: usize) -> Self {
+        self.queue_size = Some(queue_size.max(1));
+        self
+    }
+
+    pub fn with_overflow(mut self, overflow: OverflowPolicy) -> Self {
+        self.overflow = overflow;
+        self
+    }
+}
+
+// 单个输出端的运行统计
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SinkReport {
+    pub name: String,
+    pub delivered: u64,
+    pub dropped: u64,
+    pub errors: u64,
+}
+
+struct QueueState {
+    chunks: VecDeque<DataChunk>,
+    closed: bool,
+}
+
+// 输出阶段与单个输出端任务之间的有界队列（单消费者）
+//
+// 普通输出端只有输出阶段一个生产者；死信队列还会被各个转发死信的输出端任务同时写入，
+// 等待空间的生产者可能不止一个
+pub(crate) struct SinkQueue {
+    name: String,
+    capacity: usize,
+    overflow: OverflowPolicy,
+    state: Mutex<QueueState>,
+    readable: Notify,
+    writable: Notify,
+    delivered: AtomicU64,
+    dropped: AtomicU64,
+    errors: AtomicU64,
+}
+
+impl SinkQueue {
+    pub fn new(name: &str, capacity: usize, overflow: OverflowPolicy) -> Self {
+        Self {
+            name: name.to_string(),
+            capacity: capacity.max(1),
+            overflow,
+            state: Mutex::new(QueueState {
+                chunks: VecDeque::with_capacity(capacity.clamp(1, 1024)),
+                closed: false,
+            }),
+            readable: Notify::new(),
+            writable: Notify::new(),
+            delivered: AtomicU64::new(0),
+            dropped: AtomicU64::new(0),
+            errors: AtomicU64::new(0),
+        }
+    }
+
+    // 放入数据块；Block 策略下等待空间，discard 取消后放弃并计为丢弃
+    pub async fn push(&self, chunk: DataChunk, discard: &CancellationToken) {
+        let mut chunk = Some(chunk);
+        loop {
+            // 先登记等待再检查队列，检查之后发出的 notify_waiters 不会被错过
+            let writable = self.writable.notified();
+            tokio::pin!(writable);
+            writable.as_mut().enable();
+            {
+                let mut state = self.state.lock().unwrap();
+                if state.closed {
+                    // 输出端任务已经结束（例如按 Fail 策略退出），不再等待空间
+                    self.dropped.fetch_add(1, Ordering::Relaxed);
+                    return;
+                }
+                if state.chunks.len() < self.capacity {
+                    state.chunks.extend(chunk.take());
+                } else {
+                    match self.overflow {
+                        OverflowPolicy::Block => {}
+                        OverflowPolicy::DropNewest => {
+                            self.record_drop();
+                            return;
+                        }
+                        OverflowPolicy::DropOldest => {
+                            state.chunks.pop_front();
+                            state.chunks.extend(chunk.take());
+                            self.record_drop();
+                        }
+                    }
+                }
+            }
+            if chunk.is_none() {
+                self.readable.notify_one();
+                return;
+            }
+
+            tokio::select! {
+                _ = writable => {}
+                _ = discard.cancelled() => {
+                    self.record_drop();
+                    return;
+                }
+            }
+        }
+    }
+
+    async fn pop(&self) -> Option<DataChunk> {
+        loop {
+            {
+                let mut state = self.state.lock().unwrap();
+                if let Some(chunk) = state.chunks.pop_front() {
+                    drop(state);
+                    self.writable.notify_one();
+                    return Some(chunk);
+                }
+                if state.closed {
+                    return None;
+                }
+            }
+            self.readable.notified().await;
+        }
+    }
+
+    // 不再有新数据块，输出端任务处理完队列后结束
+    pub fn close(&self) {
+        self.state.lock().unwrap().closed = true;
+        self.readable.notify_one();
+    }
+
+    pub fn report(&self) -> SinkReport {
+        SinkReport {
+            name: self.name.clone(),
+            delivered: self.delivered.load(Ordering::Relaxed),
+            dropped: self.dropped.load(Ordering::Relaxed),
+            errors: self.errors.load(Ordering::Relaxed),
+        }
+    }
+
+    fn record_drop(&self) {
+        // 只在第一次丢弃时告警，避免日志刷屏
+        if self.dropped.fetch_add(1, Ordering::Relaxed) == 0 {
+            warn!("Sink {} queue is full ({} chunks), dropping chunks ({:?})",
+                  self.name, self.capacity, self.overflow);
+        }
+    }
+
+    // 输出端任务：依次把队列中的数据块发送到输出端，结束后交还输出端
+    // 发送失败时按错误策略重试，之后丢弃、转发到死信队列或使管道失败
+    pub async fn run(
+        &self,
+        mut sink: Box<dyn Sink>,
+        discard: CancellationToken,
+        policy: ErrorPolicy,
+        dead_letter: Option<Arc<SinkQueue>>,
+        state: Arc<RunState>,
+    ) -> Box<dyn Sink> {
+        'chunks: while let Some(chunk) = self.pop().await {
+            let original = policy.keeps_original().then(|| chunk.clone());
+            let mut chunk = Some(chunk);
+            let mut attempt = 0;
+            let error = loop {
+                let next = chunk.take().or_else(|| original.clone()).expect("original kept for retry");
+                let result = tokio::select! {
+                    biased;
+                    result = sink.send(next) => result,
+                    _ = discard.cancelled() => {
+                        self.dropped.fetch_add(1, Ordering::Relaxed);
+                        break 'chunks;
+                    }
+                };
+                match result {
+                    Ok(()) => {
+                        self.delivered.fetch_add(1, Ordering::Relaxed);
+                        continue 'chunks;
+                    }
+                    Err(e) if attempt < policy.max_retries() => {
+                        let backoff = policy.retry.map(|retry| retry.backoff(attempt)).unwrap_or_default();
+                        attempt += 1;
+                        warn!("Sink {} error (attempt {}), retrying in {:?}: {}", self.name, attempt, backoff, e);
+                        tokio::select! {
+                            _ = tokio::time::sleep(backoff) => {}
+                            _ = discard.cancelled() => {
+                                self.dropped.fetch_add(1, Ordering::Relaxed);
+                                break 'chunks;
+                            }
+                        }
+                    }
+                    Err(e) => break e,
+                }
+            };
+
+            self.errors.fetch_add(1, Ordering::Relaxed);
+            error!("Sink {} error: {}", self.name, error);
+            match (policy.action, &dead_letter, &original) {
+                (ErrorAction::DeadLetter, Some(dead_letter), Some(original)) => {
+                    let record = DeadLetterRecord::new(&self.name, "sink", &error, attempt + 1, original);
+                    dead_letter.push(record.to_chunk(), &discard).await;
+                }
+                (ErrorAction::Fail, _, _) => {
+                    state.request_fail(format!("sink {}: {:#}", self.name, error));
+                    break;
+                }
+                _ => {}
+            }
+        }
+
+        // 排空超时或提前退出后剩余的数据块计为丢弃；关闭队列并唤醒所有等待空间的生产者，之后放入的数据块直接丢弃
+        let remaining = {
+            let mut state = self.state.lock().unwrap();
+            state.closed = true;
+            state.chunks.drain(..).count()
+        };
+        self.writable.notify_waiters();
+        self.dropped.fetch_add(remaining as u64, Ordering::Relaxed);
+        sink
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use std::time::Duration;
+
+    struct FailingSink;
+
+    #[async_trait]
+    impl Sink for FailingSink {
+        async fn send(&mut self, _chunk: DataChunk) -> anyhow::Result<()> {
+            anyhow::bail!("disk full")
+        }
+
+        fn name(&self) -> &str {
+            "FailingSink"
+        }
+    }
+
+    #[derive(Clone, Default)]
+    struct CollectSink(Arc<Mutex<Vec<u8>>>);
+
+    #[async_trait]
+    impl Sink for CollectSink {
+        async fn send(&mut self, chunk: DataChunk) -> anyhow::Result<()> {
+            self.0.lock().unwrap().extend_from_slice(&chunk.data);
+            Ok(())
+        }
+
+        fn name(&self) -> &str {
+            "CollectSink"
+        }
+    }
+
+    // 在没有消费者的情况下放入 0..5，再关闭队列交给输出端，返回输出端收到的数据和统计
+    async fn overflow(policy: OverflowPolicy) -> (Vec<u8>, SinkReport) {
+        let queue = SinkQueue::new("overflow", 2, policy);
+        let discard = CancellationToken::new();
+        for i in 0..5u8 {
+            queue.push(DataChunk::new(vec![i]), &discard).await;
+        }
+        queue.close();
+        let sink = CollectSink::default();
+        queue.run(Box::new(sink.clone()), discard, ErrorPolicy::drop(), None, Arc::new(RunState::new(false))).await;
+        let data = sink.0.lock().unwrap().clone();
+        (data, queue.report())
+    }
+
+    #[tokio::test]
+    async fn drop_newest_keeps_the_queued_chunks() {
+        let (data, report) = overflow(OverflowPolicy::DropNewest).await;
+        assert_eq!(data, [0, 1]);
+        assert_eq!((report.delivered, report.dropped), (2, 3));
+    }
+
+    #[tokio::test]
+    async fn drop_oldest_keeps_the_latest_chunks() {
+        let (data, report) = overflow(OverflowPolicy::DropOldest).await;
+        assert_eq!(data, [3, 4]);
+        assert_eq!((report.delivered, report.dropped), (2, 3));
+    }
+
+    #[tokio::test]
+    async fn block_waits_for_space_until_discarded() {
+        let queue = Arc::new(SinkQueue::new("slow", 1, OverflowPolicy::Block));
+        let discard = CancellationToken::new();
+        queue.push(DataChunk::new(vec![0]), &discard).await;
+
+        let blocked = {
+            let (queue, discard) = (queue.clone(), discard.clone());
+            tokio::spawn(async move { queue.push(DataChunk::new(vec![1]), &discard).await })
+        };
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert!(!blocked.is_finished());
+
+        // 输出端取走一个数据块后等待中的 push 放入队列
+        assert_eq!(queue.pop().await.unwrap().data[..], [0]);
+        tokio::time::timeout(Duration::from_secs(2), blocked).await.expect("push stayed blocked").unwrap();
+
+        // 排空超时后放弃等待，计为丢弃
+        let blocked = {
+            let (queue, discard) = (queue.clone(), discard.clone());
+            tokio::spawn(async move { queue.push(DataChunk::new(vec![2]), &discard).await })
+        };
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert!(!blocked.is_finished());
+        discard.cancel();
+        tokio::time::timeout(Duration::from_secs(2), blocked).await.expect("push ignored discard").unwrap();
+        assert_eq!(queue.report().dropped, 1);
+        assert_eq!(queue.pop().await.unwrap().data[..], [1]);
+    }
+
+    #[test]
+    fn sink_options_parse_overflow_policies() {
+        let policy: OverflowPolicy = serde_yaml::from_str("drop_oldest").unwrap();
+        assert_eq!(policy, OverflowPolicy::DropOldest);
+        assert_eq!(serde_yaml::from_str::<OverflowPolicy>("drop_newest").unwrap(), OverflowPolicy::DropNewest);
+        assert!(serde_yaml::from_str::<OverflowPolicy>("drop").is_err());
+
+        let options = SinkOptions::new().with_queue_size(0).with_overflow(OverflowPolicy::DropNewest);
+        assert_eq!(options.queue_size, Some(1));
+        assert_eq!(SinkOptions::default().overflow, OverflowPolicy::Block);
+    }
+
+    // 第一次发送等到 gate 放行后失败
+    struct GatedFailingSink {
+        gate: Arc<Notify>,
+    }
+
+    #[async_trait]
+    impl Sink for GatedFailingSink {
+        async fn send(&mut self, _chunk: DataChunk) -> anyhow::Result<()> {
+            self.gate.notified().await;
+            anyhow::bail!("dead letter store unavailable")
+        }
+
+        fn name(&self) -> &str {
+            "GatedFailingSink"
+        }
+    }
+
+    #[tokio::test]
+    async fn failing_dead_letter_sink_releases_every_blocked_producer() {
+        let dead_letter = Arc::new(SinkQueue::new("dead letters", 1, OverflowPolicy::Block));
+        let discard = CancellationToken::new();
+        let state = Arc::new(RunState::new(false));
+        let gate = Arc::new(Notify::new());
+        let task = {
+            let (dead_letter, discard, state) = (dead_letter.clone(), discard.clone(), state.clone());
+            let sink = Box::new(GatedFailingSink { gate: gate.clone() });
+            tokio::spawn(async move { dead_letter.run(sink, discard, ErrorPolicy::fail(), None, state).await })
+        };
+
+        // 第一个数据块被输出端取走并卡在发送中，第二个占满队列
+        dead_letter.push(DataChunk::new(b"first".to_vec()), &discard).await;
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        dead_letter.push(DataChunk::new(b"second".to_vec()), &discard).await;
+
+        // 输出阶段和另一个输出端任务同时等待死信队列的空间
+        let producers: Vec<_> = (0..2u8)
+            .map(|i| {
+                let (dead_letter, discard) = (dead_letter.clone(), discard.clone());
+                tokio::spawn(async move { dead_letter.push(DataChunk::new(vec![i]), &discard).await })
+            })
+            .collect();
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(producers.iter().all(|producer| !producer.is_finished()));
+
+        gate.notify_one();
+        for producer in producers {
+            tokio::time::timeout(Duration::from_secs(2), producer)
+                .await
+                .expect("producer stayed blocked after the dead letter sink failed")
+                .unwrap();
+        }
+        task.await.unwrap();
+
+        assert!(state.failure().is_some());
+        assert!(!discard.is_cancelled());
+        let report = dead_letter.report();
+        assert_eq!((report.delivered, report.errors, report.dropped), (0, 1, 3));
+    }
+
+    #[tokio::test]
+    async fn fail_action_releases_blocked_pushers() {
+        let queue = Arc::new(SinkQueue::new("failing", 1, OverflowPolicy::Block));
+        let discard = CancellationToken::new();
+        let state = Arc::new(RunState::new(false));
+        let task = {
+            let queue = queue.clone();
+            let discard = discard.clone();
+            let state = state.clone();
+            tokio::spawn(async move {
+                queue.run(Box::new(FailingSink), discard, ErrorPolicy::fail(), None, state).await
+            })
+        };
+
+        // 队列容量为 1，输出端失败退出后后续的 push 不能一直阻塞到排空超时
+        let pushes = async {
+            for i in 0..5u8 {
+                queue.push(DataChunk::new(vec![i]), &discard).await;
+            }
+        };
+        tokio::time::timeout(Duration::from_secs(2), pushes)
+            .await
+            .expect("push blocked after the sink task failed");
+        task.await.unwrap();
+
+        assert!(state.failure().is_some());
+        let report = queue.report();
+        assert_eq!(report.errors, 1);
+        assert_eq!(report.delivered, 0);
+        assert_eq!(report.dropped, 4);
+    }
+}
@@ -0,0 +1,62 @@
+use std::sync::atomic::AtomicU64;
+use std::sync::Mutex;
+use tokio_util::sync::CancellationToken;
+
+// 一次运行中各任务共享的状态
+pub(crate) struct RunState {
+    pub ordered: bool,
+    pub skipped: AtomicU64,
+    pub undelivered: AtomicU64,
+    // 数据源和处理器出错的次数
+    pub errors: AtomicU64,
+    // 第一个要求停止管道的处理器及原因
+    stop_request: Mutex<Option<(String, String)>>,
+    // 第一个因错误策略 fail 而使管道失败的组件及错误
+    failure: Mutex<Option<String>>,
+    // 有处理器要求停止或组件失败时取消
+    pub stopped: CancellationToken,
+    // 取消后工作任务不再处理剩余数据块（停止、失败或排空超时）
+    pub discard: CancellationToken,
+}
+
+impl RunState {
+    pub fn new(ordered: bool) -> Self {
+        Self {
+            ordered,
+            skipped: AtomicU64::new(0),
+            undelivered: AtomicU64::new(0),
+            errors: AtomicU64::new(0),
+            stop_request: Mutex::new(None),
+            failure: Mutex::new(None),
+            stopped: CancellationToken::new(),
+            discard: CancellationToken::new(),
+        }
+    }
+
+    pub fn stop_request(&self) -> Option<(String, String)> {
+        self.stop_request.lock().unwrap().clone()
+    }
+
+    pub fn failure(&self) -> Option<String> {
+        self.failure.lock().unwrap().clone()
+    }
+
+    pub fn request_stop(&self, processor: &str, reason: String) {
+        let mut request = self.stop_request.lock().unwrap();
+        if request.is_none() {
+            *request = Some((processor.to_string(), reason));
+        }
+        // 停止后队列中剩余的数据块不再处理
+        self.discard.cancel();
+        self.stopped.cancel();
+    }
+
+    pub fn request_fail(&self, failure: String) {
+        let mut current = self.failure.lock().unwrap();
+        if current.is_none() {
+            *current = Some(failure);
+        }
+        self.discard.cancel();
+        self.stopped.cancel();
+    }
+}
@@ -0,0 +1,382 @@
+use super::error_policy::ErrorPolicy;
+use std::collections::{HashMap, VecDeque};
+
+// 拓扑中的节点类别；处理器带有它的命名输出（主输出之外），路由器带有它的输出名
+#[derive(Debug, Clone)]
+pub enum NodeKind {
+    Source,
+    Processor(Vec<String>),
+    Router(Vec<String>),
+    Sink,
+}
+
+// 节点声明：名称、类别和输入引用（None 表示沿用线性连接）
+#[derive(Debug, Clone)]
+pub struct NodeDecl {
+    pub name: String,
+    pub kind: NodeKind,
+    pub inputs: Option<Vec<String>>,
+}
+
+impl NodeDecl {
+    pub fn new(name: &str, kind: NodeKind, inputs: Option<Vec<String>>) -> Self {
+        Self {
+            name: name.to_string(),
+            kind,
+            inputs,
+        }
+    }
+
+    fn is_node(&self) -> bool {
+        matches!(self.kind, NodeKind::Processor(_) | NodeKind::Router(_))
+    }
+
+    // 输出名对应的端口：处理器的端口 0 为主输出，命名输出从 1 开始
+    pub fn output_port(&self, output: &str) -> Option<usize> {
+        match &self.kind {
+            NodeKind::Router(outputs) => outputs.iter().position(|name| name == output),
+            NodeKind::Processor(outputs) => outputs.iter().position(|name| name == output).map(|port| port + 1),
+            _ => None,
+        }
+    }
+
+    // 端口的显示名，处理器主输出为 None
+    pub fn output_name(&self, port: usize) -> Option<&str> {
+        match &self.kind {
+            NodeKind::Router(outputs) => outputs.get(port).map(String::as_str),
+            NodeKind::Processor(outputs) => port.checked_sub(1).and_then(|i| outputs.get(i)).map(String::as_str),
+            _ => None,
+        }
+    }
+}
+
+// 一条边：from 的第 port 个输出连接到 to（均为声明列表中的下标）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Edge {
+    pub from: usize,
+    pub port: usize,
+    pub to: usize,
+}
+
+// 解析节点声明得到所有边；检查名称重复、未知引用和环路
+//
+// 没有任何处理器或输出端声明 inputs 时按线性方式连接：
+// 所有数据源 → 处理器依次相连 → 所有输出端
+pub fn resolve(decls: &[NodeDecl]) -> Result<Vec<Edge>, String> {
+    let mut names = HashMap::new();
+    for (i, decl) in decls.iter().enumerate() {
+        if names.insert(decl.name.as_str(), i).is_some() {
+            return Err(format!("组件名称 '{}' 重复", decl.name));
+        }
+        if matches!(decl.kind, NodeKind::Source) && decl.inputs.is_some() {
+            return Err(format!("数据源 '{}' 不能声明 inputs", decl.name));
+        }
+    }
+
+    let explicit = decls.iter().any(|decl| decl.inputs.is_some());
+    let sources: Vec<usize> = decls
+        .iter()
+        .enumerate()
+        .filter(|(_, decl)| matches!(decl.kind, NodeKind::Source))
+        .map(|(i, _)| i)
+        .collect();
+
+    let mut edges = Vec::new();
+    let mut previous: Option<usize> = None;
+    for (to, decl) in decls.iter().enumerate() {
+        if matches!(decl.kind, NodeKind::Source) {
+            continue;
+        }
+
+        let inputs = if explicit {
+            let inputs = decl
+                .inputs
+                .as_ref()
+                .filter(|inputs| !inputs.is_empty())
+                .ok_or_else(|| format!("'{}' 缺少 inputs（管道中已有组件显式声明了 inputs）", decl.name))?;
+            inputs
+                .iter()
+                .map(|input| parse_input(decls, &names, &decl.name, input))
+                .collect::<Result<Vec<_>, _>>()?
+        } else {
+            match previous {
+                Some(prev) if matches!(decls[prev].kind, NodeKind::Router(_)) => {
+                    return Err(format!(
+                        "'{}' 位于路由器 '{}' 之后，需要用 inputs 指定路由器的输出",
+                        decl.name, decls[prev].name
+                    ));
+                }
+                Some(prev) => vec![(prev, 0)],
+                None => sources.iter().map(|&source| (source, 0)).collect(),
+            }
+        };
+
+        for (from, port) in inputs {
+            let edge = Edge { from, port, to };
+            if !edges.contains(&edge) {
+                edges.push(edge);
+            }
+        }
+        if decl.is_node() {
+            previous = Some(to);
+        }
+    }
+
+    topological_order(decls, &edges)?;
+    Ok(edges)
+}
+
+// 解析 "节点名"、"路由器名.输出名" 或 "处理器名.命名输出"
+fn parse_input(
+    decls: &[NodeDecl],
+    names: &HashMap<&str, usize>,
+    owner: &str,
+    input: &str,
+) -> Result<(usize, usize), String> {
+    if let Some(&from) = names.get(input) {
+        return match &decls[from].kind {
+            NodeKind::Source | NodeKind::Processor(_) => Ok((from, 0)),
+            NodeKind::Router(_) => Err(format!(
+                "'{}' 引用了路由器 '{}'，需写成 '{}.<输出名>'", owner, input, input
+            )),
+            NodeKind::Sink => Err(format!("'{}' 不能以输出端 '{}' 作为输入", owner, input)),
+        };
+    }
+
+    if let Some((node, output)) = input.rsplit_once('.') {
+        if let Some(&from) = names.get(node) {
+            let (label, outputs) = match &decls[from].kind {
+                NodeKind::Router(outputs) => ("路由器", outputs),
+                NodeKind::Processor(outputs) => ("处理器", outputs),
+                _ => return Err(format!("'{}' 引用了不存在的输入 '{}'", owner, input)),
+            };
+            return decls[from]
+                .output_port(output)
+                .map(|port| (from, port))
+                .ok_or_else(|| format!(
+                    "'{}' 引用了{} '{}' 不存在的输出 '{}'（可用: {}）",
+                    owner, label, node, output, outputs.join(", ")
+                ));
+        }
+    }
+
+    Err(format!("'{}' 引用了不存在的输入 '{}'", owner, input))
+}
+
+// 处理器和路由器的拓扑顺序，存在环路时报错
+pub fn topological_order(decls: &[NodeDecl], edges: &[Edge]) -> Result<Vec<usize>, String> {
+    let mut in_degree: HashMap<usize, usize> = decls
+        .iter()
+        .enumerate()
+        .filter(|(_, decl)| decl.is_node())
+        .map(|(i, _)| (i, 0))
+        .collect();
+    for edge in edges {
+        if decls[edge.from].is_node() && decls[edge.to].is_node() {
+            *in_degree.get_mut(&edge.to).unwrap() += 1;
+        }
+    }
+
+    let mut ready: VecDeque<usize> = (0..decls.len())
+        .filter(|i| in_degree.get(i) == Some(&0))
+        .collect();
+    let mut order = Vec::with_capacity(in_degree.len());
+    while let Some(node) = ready.pop_front() {
+        order.push(node);
+        for edge in edges.iter().filter(|edge| edge.from == node && decls[edge.to].is_node()) {
+            let degree = in_degree.get_mut(&edge.to).unwrap();
+            *degree -= 1;
+            if *degree == 0 {
+                ready.push_back(edge.to);
+            }
+        }
+    }
+
+    if order.len() < in_degree.len() {
+        let mut cycle: Vec<&str> = in_degree
+            .keys()
+            .filter(|i| !order.contains(i))
+            .map(|&i| decls[i].name.as_str())
+            .collect();
+        cycle.sort_unstable();
+        return Err(format!("处理器之间存在环路: {}", cycle.join(", ")));
+    }
+    Ok(order)
+}
+
+// 运行时的连接目标
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Target {
+    Node(usize),
+    Sink(usize),
+}
+
+// 按类别下标组织的拓扑，供工作任务遍历
+#[derive(Debug, Default)]
+pub(crate) struct Topology {
+    // 每个数据源的下游
+    pub sources: Vec<Vec<Target>>,
+    // 每个处理器/路由器每个输出的下游
+    pub nodes: Vec<Vec<Vec<Target>>>,
+    // 处理器/路由器的拓扑顺序，用于按上游到下游刷新
+    pub order: Vec<usize>,
+    // 每个处理器的错误策略
+    pub policies: Vec<ErrorPolicy>,
+    // 死信输出端的下标
+    pub dead_letter: Option<usize>,
+    // 每个处理器的命名输出（端口从 1 开始），路由器为空
+    pub side_outputs: Vec<Vec<String>>,
+}
+
+impl Topology {
+    pub fn build(decls: &[NodeDecl]) -> Result<Self, String> {
+        let edges = resolve(decls)?;
+
+        // 声明下标 → 类别内下标
+        let mut counts = [0usize; 3];
+        let index: Vec<usize> = decls
+            .iter()
+            .map(|decl| {
+                let slot = match decl.kind {
+                    NodeKind::Source => 0,
+                    NodeKind::Processor(_) | NodeKind::Router(_) => 1,
+                    NodeKind::Sink => 2,
+                };
+                counts[slot] += 1;
+                counts[slot] - 1
+            })
+            .collect();
+
+        let mut topology = Topology {
+            sources: vec![Vec::new(); counts[0]],
+            nodes: Vec::with_capacity(counts[1]),
+            order: Vec::new(),
+            policies: vec![ErrorPolicy::default(); counts[1]],
+            dead_letter: None,
+            side_outputs: Vec::with_capacity(counts[1]),
+        };
+        for decl in decls.iter().filter(|decl| decl.is_node()) {
+            let (ports, side_outputs) = match &decl.kind {
+                NodeKind::Router(outputs) => (outputs.len(), Vec::new()),
+                NodeKind::Processor(outputs) => (outputs.len() + 1, outputs.clone()),
+                _ => (1, Vec::new()),
+            };
+            topology.nodes.push(vec![Vec::new(); ports]);
+            topology.side_outputs.push(side_outputs);
+        }
+
+        for edge in &edges {
+            let target = match decls[edge.to].kind {
+                NodeKind::Sink => Target::Sink(index[edge.to]),
+                _ => Target::Node(index[edge.to]),
+            };
+            let from = index[edge.from];
+            match decls[edge.from].kind {
+                NodeKind::Source => topology.sources[from].push(target),
+                _ => topology.nodes[from][edge.port].push(target),
+            }
+        }
+
+        topology.order = topological_order(decls, &edges)?
+            .into_iter()
+            .map(|decl| index[decl])
+            .collect();
+        Ok(topology)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn source(name: &str) -> NodeDecl {
+        NodeDecl::new(name, NodeKind::Source, None)
+    }
+
+    fn processor(name: &str, inputs: &[&str]) -> NodeDecl {
+        NodeDecl::new(name, NodeKind::Processor(Vec::new()), declared(inputs))
+    }
+
+    fn router(name: &str, outputs: &[&str], inputs: &[&str]) -> NodeDecl {
+        let outputs = outputs.iter().map(|output| output.to_string()).collect();
+        NodeDecl::new(name, NodeKind::Router(outputs), declared(inputs))
+    }
+
+    fn sink(name: &str, inputs: &[&str]) -> NodeDecl {
+        NodeDecl::new(name, NodeKind::Sink, declared(inputs))
+    }
+
+    // 空切片表示沿用线性连接
+    fn declared(inputs: &[&str]) -> Option<Vec<String>> {
+        (!inputs.is_empty()).then(|| inputs.iter().map(|input| input.to_string()).collect())
+    }
+
+    #[test]
+    fn linear_pipelines_chain_processors() {
+        let decls = [source("a"), source("b"), processor("p1", &[]), processor("p2", &[]), sink("out", &[])];
+        let topology = Topology::build(&decls).unwrap();
+        assert_eq!(topology.sources, vec![vec![Target::Node(0)], vec![Target::Node(0)]]);
+        assert_eq!(topology.nodes, vec![vec![vec![Target::Node(1)]], vec![vec![Target::Sink(0)]]]);
+        assert_eq!(topology.order, vec![0, 1]);
+    }
+
+    #[test]
+    fn router_outputs_fan_out_to_separate_branches() {
+        let decls = [
+            source("packets"),
+            router("split", &["dns", "http", "_unmatched"], &["packets"]),
+            processor("dns_parser", &["split.dns"]),
+            sink("dns_out", &["dns_parser"]),
+            sink("http_out", &["split.http"]),
+            sink("rest", &["split._unmatched", "dns_parser"]),
+        ];
+        let topology = Topology::build(&decls).unwrap();
+        assert_eq!(topology.sources, vec![vec![Target::Node(0)]]);
+        assert_eq!(
+            topology.nodes[0],
+            vec![vec![Target::Node(1)], vec![Target::Sink(1)], vec![Target::Sink(2)]]
+        );
+        assert_eq!(topology.nodes[1], vec![vec![Target::Sink(0), Target::Sink(2)]]);
+        // 路由器在它的下游之前刷新
+        assert_eq!(topology.order, vec![0, 1]);
+    }
+
+    #[test]
+    fn cycles_are_rejected() {
+        let decls = [
+            source("input"),
+            processor("a", &["input", "c"]),
+            processor("b", &["a"]),
+            processor("c", &["b"]),
+            processor("d", &["input"]),
+            sink("out", &["c", "d"]),
+        ];
+        let error = resolve(&decls).unwrap_err();
+        assert_eq!(error, "处理器之间存在环路: a, b, c");
+
+        // 路由器的输出同样参与环路检查
+        let decls = [
+            source("input"),
+            router("split", &["again", "_unmatched"], &["input", "retry"]),
+            processor("retry", &["split.again"]),
+            sink("out", &["split._unmatched"]),
+        ];
+        assert_eq!(resolve(&decls).unwrap_err(), "处理器之间存在环路: retry, split");
+    }
+
+    #[test]
+    fn invalid_references_are_rejected() {
+        let error = |decls: &[NodeDecl]| resolve(decls).unwrap_err();
+        let split = || router("split", &["dns", "_unmatched"], &["input"]);
+
+        assert!(error(&[source("input"), processor("input", &[])]).contains("重复"));
+        assert!(error(&[source("input"), split(), sink("out", &["split"])]).contains("split.<输出名>"));
+        assert!(error(&[source("input"), split(), sink("out", &["split.http"])]).contains("不存在的输出 'http'"));
+        assert!(error(&[source("input"), sink("a", &["input"]), sink("b", &["a"])]).contains("不能以输出端"));
+        assert!(error(&[source("input"), sink("out", &["missing"])]).contains("不存在的输入 'missing'"));
+        assert!(error(&[source("input"), split(), sink("out", &[])]).contains("缺少 inputs"));
+        // 线性连接不能越过路由器
+        let decls = [source("input"), NodeDecl::new("split", NodeKind::Router(vec!["dns".into()]), None), sink("out", &[])];
+        assert!(error(&decls).contains("需要用 inputs 指定路由器的输出"));
+    }
+}
@@ -0,0 +1,197 @@
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+
+pub(crate) const DEFAULT_WATERMARK_INTERVAL: Duration = Duration::from_millis(200);
+
+// 数据源的水位线策略：事件时间最多乱序 max_out_of_orderness，
+// 超过 idle_timeout 没有数据的数据源不再阻挡水位线前进
+//
+// YAML 中写成 watermark: { max_out_of_order_ms: 500, idle_timeout_ms: 5000 }
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(from = "WatermarkFields", into = "WatermarkFields")]
+pub struct WatermarkStrategy {
+    pub max_out_of_orderness: Duration,
+    pub idle_timeout: Option<Duration>,
+}
+
+impl WatermarkStrategy {
+    pub fn bounded(max_out_of_orderness: Duration) -> Self {
+        Self { max_out_of_orderness, idle_timeout: None }
+    }
+
+    pub fn with_idle_timeout(mut self, idle_timeout: Duration) -> Self {
+        self.idle_timeout = Some(idle_timeout);
+        self
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct WatermarkFields {
+    #[serde(default)]
+    max_out_of_order_ms: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    idle_timeout_ms: Option<u64>,
+}
+
+impl From<WatermarkFields> for WatermarkStrategy {
+    fn from(fields: WatermarkFields) -> Self {
+        Self {
+            max_out_of_orderness: Duration::from_millis(fields.max_out_of_order_ms),
+            idle_timeout: fields.idle_timeout_ms.map(Duration::from_millis),
+        }
+    }
+}
+
+impl From<WatermarkStrategy> for WatermarkFields {
+    fn from(strategy: WatermarkStrategy) -> Self {
+        Self {
+            max_out_of_order_ms: strategy.max_out_of_orderness.as_millis() as u64,
+            idle_timeout_ms: strategy.idle_timeout.map(|timeout| timeout.as_millis() as u64),
+        }
+    }
+}
+
+struct SourceWatermark {
+    strategy: WatermarkStrategy,
+    // 该数据源见过的最大事件时间
+    max_time_ns: Option<u64>,
+    last_active: Instant,
+    finished: bool,
+}
+
+impl SourceWatermark {
+    fn is_idle(&self, now: Instant) -> bool {
+        self.strategy
+            .idle_timeout
+            .is_some_and(|timeout| now.duration_since(self.last_active) >= timeout)
+    }
+}
+
+// 分发阶段维护的水位线：每个数据源为 最大事件时间 - 允许的乱序，
+// 管道水位线取所有活跃数据源中的最小值，只增不减
+//
+// 还没有产生数据的数据源会阻挡水位线，直到它产生数据、空闲超时或结束
+pub(crate) struct WatermarkTracker {
+    sources: Vec<SourceWatermark>,
+    current_ns: u64,
+}
+
+impl WatermarkTracker {
+    pub fn new(strategies: Vec<WatermarkStrategy>) -> Self {
+        let now = Instant::now();
+        Self {
+            sources: strategies
+                .into_iter()
+                .map(|strategy| SourceWatermark { strategy, max_time_ns: None, last_active: now, finished: false })
+                .collect(),
+            current_ns: 0,
+        }
+    }
+
+    pub fn observe(&mut self, source: usize, time_ns: u64) {
+        let state = &mut self.sources[source];
+        state.max_time_ns = Some(state.max_time_ns.map_or(time_ns, |max| max.max(time_ns)));
+        state.last_active = Instant::now();
+    }
+
+    pub fn finish(&mut self, source: usize) {
+        self.sources[source].finished = true;
+    }
+
+    // 水位线前进时返回新值
+    pub fn advance(&mut self) -> Option<u64> {
+        let now = Instant::now();
+        let mut watermark: Option<u64> = None;
+        for source in self.sources.iter().filter(|source| !source.finished && !source.is_idle(now)) {
+            let bound = u64::try_from(source.strategy.max_out_of_orderness.as_nanos()).unwrap_or(u64::MAX);
+            let source_watermark = source.max_time_ns?.saturating_sub(bound);
+            watermark = Some(watermark.map_or(source_watermark, |w| w.min(source_watermark)));
+        }
+
+        // 所有数据源都结束或空闲时取见过的最大事件时间
+        let watermark = match watermark {
+            Some(watermark) => watermark,
+            None => self.sources.iter().filter_map(|source| source.max_time_ns).max()?,
+        };
+        (watermark > self.current_ns).then(|| {
+            self.current_ns = watermark;
+            watermark
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MS: u64 = 1_000_000;
+
+    fn bounded(ms: u64) -> WatermarkStrategy {
+        WatermarkStrategy::bounded(Duration::from_millis(ms))
+    }
+
+    #[test]
+    fn watermark_trails_the_slowest_source_by_its_bound() {
+        let mut tracker = WatermarkTracker::new(vec![bounded(100), bounded(0)]);
+        tracker.observe(0, 1000 * MS);
+        // 第二个数据源还没有数据，水位线不前进
+        assert_eq!(tracker.advance(), None);
+
+        tracker.observe(1, 2000 * MS);
+        assert_eq!(tracker.advance(), Some(900 * MS));
+        // 乱序到达的较早事件时间不会让水位线后退
+        tracker.observe(0, 500 * MS);
+        assert_eq!(tracker.advance(), None);
+
+        tracker.observe(0, 3000 * MS);
+        assert_eq!(tracker.advance(), Some(2000 * MS));
+        assert_eq!(tracker.advance(), None);
+    }
+
+    #[test]
+    fn finished_and_idle_sources_stop_holding_the_watermark_back() {
+        let idle = bounded(0).with_idle_timeout(Duration::from_millis(20));
+        let mut tracker = WatermarkTracker::new(vec![bounded(0), bounded(0), idle]);
+        tracker.observe(0, 1000 * MS);
+        tracker.observe(1, 5000 * MS);
+        tracker.observe(2, 100 * MS);
+        assert_eq!(tracker.advance(), Some(100 * MS));
+
+        std::thread::sleep(Duration::from_millis(30));
+        assert_eq!(tracker.advance(), Some(1000 * MS));
+
+        tracker.finish(0);
+        assert_eq!(tracker.advance(), Some(5000 * MS));
+
+        // 空闲的数据源恢复产生数据后重新参与，但水位线不会后退
+        tracker.observe(2, 200 * MS);
+        assert_eq!(tracker.advance(), None);
+
+        // 全部结束时取见过的最大事件时间
+        tracker.finish(1);
+        tracker.finish(2);
+        tracker.observe(1, 6000 * MS);
+        assert_eq!(tracker.advance(), Some(6000 * MS));
+    }
+
+    #[test]
+    fn source_that_never_produces_blocks_until_it_finishes() {
+        let mut tracker = WatermarkTracker::new(vec![bounded(0), bounded(0)]);
+        tracker.observe(0, 1000 * MS);
+        assert_eq!(tracker.advance(), None);
+        tracker.finish(1);
+        assert_eq!(tracker.advance(), Some(1000 * MS));
+    }
+
+    #[test]
+    fn strategy_round_trips_through_yaml() {
+        let strategy: WatermarkStrategy = serde_yaml::from_str("{ max_out_of_order_ms: 500, idle_timeout_ms: 5000 }").unwrap();
+        assert_eq!(strategy, bounded(500).with_idle_timeout(Duration::from_secs(5)));
+        let yaml = serde_yaml::to_string(&strategy).unwrap();
+        assert_eq!(serde_yaml::from_str::<WatermarkStrategy>(&yaml).unwrap(), strategy);
+
+        assert_eq!(serde_yaml::from_str::<WatermarkStrategy>("{}").unwrap(), WatermarkStrategy::default());
+        assert!(serde_yaml::from_str::<WatermarkStrategy>("{ max_out_of_order: 500 }").is_err());
+    }
+}
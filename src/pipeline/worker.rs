@@ -0,0 +1,461 @@
+use super::error_policy::{DeadLetterRecord, ErrorAction};
+use super::state::RunState;
+use super::topology::{Target, Topology};
+use crate::processors::{ProcessResult, Processor, Router};
+use crate::DataChunk;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use tokio::sync::mpsc;
+use tracing::{debug, error, info, warn};
+
+// 分发给工作任务的数据块，index 为分发顺序，source 为来源数据源的下标
+pub(crate) struct Dispatched {
+    pub index: u64,
+    pub source: usize,
+    pub chunk: DataChunk,
+}
+
+// 工作任务的输入：数据块，或广播给所有工作任务的水位线
+pub(crate) enum WorkerInput {
+    Chunk(Dispatched),
+    Watermark(u64),
+}
+
+// 工作任务送往输出阶段的结果：(输出端下标, 数据块)
+// 有序模式下每个分发的数据块都会产生一条（全部被跳过时 outputs 为空），以便重排缓冲前进
+pub(crate) struct WorkerOutput {
+    pub index: Option<u64>,
+    pub outputs: Vec<(usize, DataChunk)>,
+}
+
+// 工作任务持有的拓扑节点实例
+pub(crate) enum WorkerNode {
+    Processor(Box<dyn Processor>),
+    Router(Arc<Router>),
+}
+
+impl WorkerNode {
+    pub fn processor_mut(&mut self) -> Option<&mut Box<dyn Processor>> {
+        match self {
+            WorkerNode::Processor(processor) => Some(processor),
+            WorkerNode::Router(_) => None,
+        }
+    }
+}
+
+// 一个工作任务持有整个拓扑的一套独立节点实例
+pub(crate) struct Worker {
+    pub id: usize,
+    pub nodes: Vec<WorkerNode>,
+    pub topology: Arc<Topology>,
+    pub output: mpsc::Sender<WorkerOutput>,
+    pub state: Arc<RunState>,
+}
+
+impl Worker {
+    // 处理输入直到通道关闭，然后刷新处理器；返回节点以便关闭
+    pub async fn run(mut self, mut input: mpsc::Receiver<WorkerInput>) -> (Vec<WorkerNode>, Vec<String>) {
+        let topology = self.topology.clone();
+        while let Some(input) = input.recv().await {
+            let Dispatched { index, source, chunk } = match input {
+                WorkerInput::Chunk(dispatched) => dispatched,
+                WorkerInput::Watermark(watermark_ns) => {
+                    if !self.state.discard.is_cancelled() {
+                        self.on_watermark(watermark_ns).await;
+                    }
+                    continue;
+                }
+            };
+            let outputs = if self.state.discard.is_cancelled() {
+                self.state.undelivered.fetch_add(1, Ordering::Relaxed);
+                Vec::new()
+            } else {
+                self.handle(chunk, &topology.sources[source]).await
+            };
+            self.emit(Some(index), outputs).await;
+        }
+
+        let mut hook_errors = Vec::new();
+        self.flush(&mut hook_errors).await;
+        debug!("Worker {} finished", self.id);
+        (self.nodes, hook_errors)
+    }
+
+    async fn emit(&self, index: Option<u64>, outputs: Vec<(usize, DataChunk)>) {
+        if outputs.is_empty() && !(self.state.ordered && index.is_some()) {
+            return;
+        }
+        let index = if self.state.ordered { index } else { None };
+        let _ = self.output.send(WorkerOutput { index, outputs }).await;
+    }
+
+    // 把数据块送入 targets 并沿拓扑向下游传递，返回到达各输出端的数据块
+    async fn handle(&mut self, chunk: DataChunk, targets: &[Target]) -> Vec<(usize, DataChunk)> {
+        let topology = self.topology.clone();
+        let mut outputs = Vec::new();
+        let mut pending = Vec::new();
+        fan_out(&mut pending, targets, chunk);
+
+        while let Some((target, mut chunk)) = pending.pop() {
+            let node = match target {
+                Target::Sink(sink) => {
+                    outputs.push((sink, chunk));
+                    continue;
+                }
+                Target::Node(node) => node,
+            };
+
+            let processor = match &mut self.nodes[node] {
+                WorkerNode::Router(router) => {
+                    let ports = router.select(&chunk);
+                    if let Some((&last, rest)) = ports.split_last() {
+                        for &port in rest {
+                            fan_out(&mut pending, &topology.nodes[node][port], chunk.clone());
+                        }
+                        fan_out(&mut pending, &topology.nodes[node][last], chunk);
+                    }
+                    continue;
+                }
+                WorkerNode::Processor(processor) => processor,
+            };
+
+            // 按错误策略重试，保留进入处理器时的数据块用于重试和死信
+            let policy = topology.policies[node];
+            let original = policy.keeps_original().then(|| chunk.clone());
+            let mut attempts = 0;
+            let result = loop {
+                attempts += 1;
+                match processor.process(&mut chunk).await {
+                    Err(e) if attempts <= policy.max_retries() => {
+                        let backoff = policy.retry.map(|retry| retry.backoff(attempts - 1)).unwrap_or_default();
+                        warn!("Processor {} error (attempt {}), retrying in {:?}: {}",
+                              processor.name(), attempts, backoff, e);
+                        // 中止或排空超时后不再等待退避，数据块计为未处理
+                        tokio::select! {
+                            _ = tokio::time::sleep(backoff) => {}
+                            _ = self.state.discard.cancelled() => break None,
+                        }
+                        if let Some(original) = &original {
+                            chunk = original.clone();
+                        }
+                    }
+                    result => break Some(result),
+                }
+            };
+            let Some(result) = result else {
+                self.state.undelivered.fetch_add(1, Ordering::Relaxed);
+                continue;
+            };
+
+            // 数据块继续流向的输出端口，None 表示不再向下游传递
+            let port = match result {
+                Ok(ProcessResult::Continue) => Some(0),
+                Ok(ProcessResult::Skip) => {
+                    self.state.skipped.fetch_add(1, Ordering::Relaxed);
+                    debug!("Skipping chunk {} due to processor {}", chunk.sequence, processor.name());
+                    None
+                }
+                Ok(ProcessResult::Stop(reason)) => {
+                    info!("Processor {} requested stop at chunk {}: {}", processor.name(), chunk.sequence, reason);
+                    self.state.request_stop(processor.name(), reason);
+                    None
+                }
+                Ok(ProcessResult::StopAndDeliver(reason)) => {
+                    info!("Processor {} requested stop after chunk {}: {}", processor.name(), chunk.sequence, reason);
+                    self.state.request_stop(processor.name(), reason);
+                    Some(0)
+                }
+                Ok(ProcessResult::Emit(chunks)) => {
+                    // 待处理栈后进先出，逆序入栈以保持发出顺序
+                    for chunk in chunks.into_iter().rev() {
+                        fan_out(&mut pending, &topology.nodes[node][0], chunk);
+                    }
+                    None
+                }
+                Ok(ProcessResult::SideOutput(output)) => {
+                    let port = topology.side_outputs[node].iter().position(|name| *name == output);
+                    if port.is_none() {
+                        self.state.errors.fetch_add(1, Ordering::Relaxed);
+                        error!("Processor {} sent chunk {} to undeclared output '{}'",
+                               processor.name(), chunk.sequence, output);
+                    }
+                    port.map(|port| port + 1)
+                }
+                Err(e) => {
+                    self.state.errors.fetch_add(1, Ordering::Relaxed);
+                    match policy.action {
+                        ErrorAction::Drop => {
+                            error!("Processor {} error: {}", processor.name(), e);
+                        }
+                        ErrorAction::DeadLetter => {
+                            warn!("Processor {} error, sending chunk {} to dead letter: {}",
+                                  processor.name(), chunk.sequence, e);
+                            let original = original.as_ref().unwrap_or(&chunk);
+                            let record = DeadLetterRecord::new(processor.name(), "processor", &e, attempts, original);
+                            if let Some(dead_letter) = topology.dead_letter {
+                                outputs.push((dead_letter, record.to_chunk()));
+                            }
+                        }
+                        ErrorAction::Fail => {
+                            error!("Processor {} failed, stopping pipeline: {}", processor.name(), e);
+                            self.state.request_fail(format!("processor {}: {:#}", processor.name(), e));
+                        }
+                    }
+                    None
+                }
+            };
+
+            // 只有需要处理的数据才继续流向下游
+            if let Some(port) = port {
+                fan_out(&mut pending, &topology.nodes[node][port], chunk);
+            }
+        }
+
+        outputs
+    }
+
+    // 按拓扑顺序通知处理器水位线前进，上游产生的数据块先流经下游，再通知下游
+    async fn on_watermark(&mut self, watermark_ns: u64) {
+        let topology = self.topology.clone();
+        for &node in &topology.order {
+            let Some(processor) = self.nodes[node].processor_mut() else { continue };
+            match processor.on_watermark(watermark_ns).await {
+                Ok(chunks) => {
+                    for chunk in chunks {
+                        let outputs = self.handle(chunk, &topology.nodes[node][0]).await;
+                        self.emit(None, outputs).await;
+                    }
+                }
+                Err(e) => {
+                    self.state.errors.fetch_add(1, Ordering::Relaxed);
+                    error!("Processor {} watermark error: {}", processor.name(), e);
+                }
+            }
+        }
+    }
+
+    // 按拓扑顺序刷新处理器，处理器缓冲的数据块继续流向下游
+    async fn flush(&mut self, hook_errors: &mut Vec<String>) {
+        let topology = self.topology.clone();
+        for &node in &topology.order {
+            let Some(processor) = self.nodes[node].processor_mut() else { continue };
+            match processor.flush().await {
+                Ok(chunks) => {
+                    for chunk in chunks {
+                        let outputs = self.handle(chunk, &topology.nodes[node][0]).await;
+                        self.emit(None, outputs).await;
+                    }
+                }
+                Err(e) => hook_errors.push(format!("processor {} flush: {}", processor.name(), e)),
+            }
+        }
+    }
+}
+
+// 把数据块放入每个下游目标的待处理栈；逆序入栈使下游按声明顺序处理，第一个目标直接使用原数据块
+fn fan_out(pending: &mut Vec<(Target, DataChunk)>, targets: &[Target], chunk: DataChunk) {
+    let mut chunk = Some(chunk);
+    for (i, &target) in targets.iter().enumerate().rev() {
+        let chunk = match i {
+            0 => chunk.take(),
+            _ => chunk.clone(),
+        };
+        pending.extend(chunk.map(|chunk| (target, chunk)));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::pipeline::{DeadLetterRecord, ErrorPolicy, Pipeline, RetryPolicy, RunSummary};
+    use crate::processors::{ProcessResult, Processor};
+    use crate::sinks::Sink;
+    use crate::sources::MemorySource;
+    use crate::DataChunk;
+    use async_trait::async_trait;
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    // 收集收到的数据块
+    #[derive(Clone, Default)]
+    struct CollectSink(Arc<Mutex<Vec<DataChunk>>>);
+
+    impl CollectSink {
+        fn data(&self) -> Vec<Vec<u8>> {
+            self.0.lock().unwrap().iter().map(|chunk| chunk.data.to_vec()).collect()
+        }
+    }
+
+    #[async_trait]
+    impl Sink for CollectSink {
+        async fn send(&mut self, chunk: DataChunk) -> anyhow::Result<()> {
+            self.0.lock().unwrap().push(chunk);
+            Ok(())
+        }
+
+        fn name(&self) -> &str {
+            "CollectSink"
+        }
+    }
+
+    // 先改写数据块，含 "bad" 的数据块前 failures 次处理失败（u32::MAX 为一直失败）
+    struct Flaky {
+        failures: u32,
+        attempts: u32,
+    }
+
+    #[async_trait]
+    impl Processor for Flaky {
+        async fn process(&mut self, chunk: &mut DataChunk) -> anyhow::Result<ProcessResult> {
+            chunk.modify_data(|data| data.extend_from_slice(b"+"));
+            if !chunk.data.starts_with(b"bad") {
+                return Ok(ProcessResult::Continue);
+            }
+            self.attempts += 1;
+            if self.attempts <= self.failures {
+                anyhow::bail!("cannot parse {}", String::from_utf8_lossy(&chunk.data));
+            }
+            self.attempts = 0;
+            Ok(ProcessResult::Continue)
+        }
+
+        fn name(&self) -> &str {
+            "Flaky"
+        }
+    }
+
+    async fn run(failures: u32, policy: ErrorPolicy, dead_letter: Option<CollectSink>) -> (anyhow::Result<RunSummary>, CollectSink) {
+        let sink = CollectSink::default();
+        let data = vec![b"one".to_vec(), b"bad".to_vec(), b"two".to_vec()];
+        let mut builder = Pipeline::builder()
+            .source("input", Box::new(MemorySource::new(data).with_delay(0)))
+            .processor("parser", Box::new(Flaky { failures, attempts: 0 }), &["input"])
+            .sink("output", Box::new(sink.clone()), &["parser"])
+            .on_error("parser", policy);
+        if let Some(dead_letter) = dead_letter {
+            builder = builder.with_dead_letter_sink(Box::new(dead_letter));
+        }
+        let result = tokio::time::timeout(Duration::from_secs(5), builder.build().run()).await.expect("pipeline hung");
+        (result, sink)
+    }
+
+    fn fast_retry(retries: u32) -> RetryPolicy {
+        RetryPolicy::new(retries).with_backoff(Duration::from_millis(1), Duration::from_millis(1))
+    }
+
+    // 按数据内容等待不同的时间，使各工作任务完成的顺序与分发顺序不同
+    struct Jitter;
+
+    #[async_trait]
+    impl Processor for Jitter {
+        async fn process(&mut self, chunk: &mut DataChunk) -> anyhow::Result<ProcessResult> {
+            let delay = chunk.data.iter().map(|byte| u64::from(*byte)).sum::<u64>() * 7 % 5;
+            tokio::time::sleep(Duration::from_millis(delay)).await;
+            Ok(ProcessResult::Continue)
+        }
+
+        fn name(&self) -> &str {
+            "Jitter"
+        }
+    }
+
+    async fn run_workers(ordered: bool) -> Vec<Vec<u8>> {
+        let sink = CollectSink::default();
+        let data: Vec<Vec<u8>> = (0..200u32).map(|i| i.to_string().into_bytes()).collect();
+        let summary = Pipeline::builder()
+            .source("input", Box::new(MemorySource::new(data).with_delay(0)))
+            .processor_factory("jitter", || Box::new(Jitter), &["input"])
+            .sink("output", Box::new(sink.clone()), &["jitter"])
+            .with_workers(4)
+            .with_ordered_output(ordered)
+            .build()
+            .run()
+            .await
+            .unwrap();
+        assert_eq!(summary.sinks[0].delivered, 200);
+        sink.data()
+    }
+
+    #[tokio::test]
+    async fn ordered_output_keeps_dispatch_order_across_workers() {
+        let expected: Vec<Vec<u8>> = (0..200u32).map(|i| i.to_string().into_bytes()).collect();
+        assert_eq!(run_workers(true).await, expected);
+
+        // 无序模式下同样不丢数据块
+        let mut unordered = run_workers(false).await;
+        unordered.sort();
+        let mut expected = expected;
+        expected.sort();
+        assert_eq!(unordered, expected);
+    }
+
+    #[tokio::test]
+    async fn drop_policy_discards_failed_chunks() {
+        let (summary, sink) = run(u32::MAX, ErrorPolicy::drop(), None).await;
+        let summary = summary.unwrap();
+        assert_eq!(sink.data(), vec![b"one+".to_vec(), b"two+".to_vec()]);
+        assert_eq!(summary.errors, 1);
+        assert_eq!(summary.sinks[0].delivered, 2);
+    }
+
+    #[tokio::test]
+    async fn retries_start_from_the_original_chunk() {
+        let (summary, sink) = run(2, ErrorPolicy::drop().with_retry(fast_retry(2)), None).await;
+        // 第三次处理成功，只带一次处理器的改写
+        assert_eq!(sink.data(), vec![b"one+".to_vec(), b"bad+".to_vec(), b"two+".to_vec()]);
+        assert_eq!(summary.unwrap().errors, 0);
+
+        // 重试用尽后按 action 处理
+        let (summary, sink) = run(3, ErrorPolicy::drop().with_retry(fast_retry(2)), None).await;
+        assert_eq!(sink.data().len(), 2);
+        assert_eq!(summary.unwrap().errors, 1);
+    }
+
+    #[tokio::test]
+    async fn dead_letter_policy_forwards_the_original_chunk() {
+        let dead_letter = CollectSink::default();
+        let policy = ErrorPolicy::dead_letter().with_retry(fast_retry(1));
+        let (summary, sink) = run(u32::MAX, policy, Some(dead_letter.clone())).await;
+        assert_eq!(sink.data(), vec![b"one+".to_vec(), b"two+".to_vec()]);
+        assert_eq!(summary.unwrap().errors, 1);
+
+        let letters = dead_letter.0.lock().unwrap();
+        assert_eq!(letters.len(), 1);
+        let record = DeadLetterRecord::from_json(&letters[0].data).unwrap();
+        assert_eq!((record.component.as_str(), record.component_kind.as_str()), ("Flaky", "processor"));
+        assert_eq!(record.error, "cannot parse bad+");
+        assert_eq!(record.attempts, 2);
+        let original = record.original_chunk().unwrap();
+        assert_eq!(&original.data[..], b"bad");
+        assert_eq!(original.sequence, 1);
+        assert_eq!(original.source_id.as_deref(), Some("input"));
+    }
+
+    #[tokio::test]
+    async fn fail_policy_fails_the_pipeline() {
+        let (result, _) = run(u32::MAX, ErrorPolicy::fail(), None).await;
+        let error = result.unwrap_err().to_string();
+        assert!(error.contains("processor Flaky") && error.contains("cannot parse bad+"), "{}", error);
+    }
+
+    #[tokio::test]
+    async fn retry_backoff_gives_up_at_drain_timeout() {
+        let sink = CollectSink::default();
+        let retry = RetryPolicy::new(5).with_backoff(Duration::from_secs(60), Duration::from_secs(60));
+        let pipeline = Pipeline::builder()
+            .source("input", Box::new(MemorySource::new(vec![b"bad".to_vec()]).with_delay(0)))
+            .processor("parser", Box::new(Flaky { failures: u32::MAX, attempts: 0 }), &["input"])
+            .sink("output", Box::new(sink.clone()), &["parser"])
+            .on_error("parser", ErrorPolicy::fail().with_retry(retry))
+            .with_drain_timeout(Duration::from_millis(100))
+            .build();
+
+        // 退避等待 60 秒，排空超时后立即放弃
+        let summary = tokio::time::timeout(Duration::from_secs(5), pipeline.run())
+            .await
+            .expect("worker kept sleeping in retry backoff after the drain timeout")
+            .unwrap();
+        assert_eq!(summary.undelivered, 1);
+        assert_eq!(summary.errors, 0);
+        assert!(sink.data().is_empty());
+    }
+}
@@ -1,15 +1,38 @@
 pub mod packet_processor;
+pub mod packet_decode;
 pub mod security_processor;  // 添加安全处理器模块
+pub mod router;
+pub mod sequence;
+pub mod window_processor;
 
 // 重新导出 packet_processor 中的公共类型
 pub use packet_processor::{
     PacketStatsProcessor, PacketStatsSnapshot,
-    IPAnalysisProcessor, ThreatDetectionProcessor
+    IPAnalysisProcessor, ThreatDetectionProcessor,
+    PROTOCOL_ATTRIBUTE, SRC_IP_ATTRIBUTE, DST_IP_ATTRIBUTE, THREAT_ATTRIBUTE
+};
+
+pub use packet_decode::{
+    PacketDecodeProcessor, DECODE_ERROR_ATTRIBUTE, DST_PORT_ATTRIBUTE, ETHER_TYPE_ATTRIBUTE, ETH_DST_ATTRIBUTE,
+    ETH_SRC_ATTRIBUTE, ICMP_CODE_ATTRIBUTE, ICMP_TYPE_ATTRIBUTE, IP_FRAGMENT_ATTRIBUTE, IP_PROTOCOL_ATTRIBUTE,
+    IP_VERSION_ATTRIBUTE, PAYLOAD_LENGTH_ATTRIBUTE, PAYLOAD_OFFSET_ATTRIBUTE, SRC_PORT_ATTRIBUTE, TCP_FLAGS_ATTRIBUTE,
+    TRANSPORT_ATTRIBUTE, TRUNCATED_ATTRIBUTE, TTL_ATTRIBUTE, VLAN_ID_ATTRIBUTE,
 };
 
 // 重新导出 security_processor 中的公共类型
 pub use security_processor::{EncryptionProcessor, DecryptionProcessor};
 
+pub use router::{Condition, Router, UNMATCHED_ROUTE};
+
+pub use sequence::{
+    GapDetectionProcessor, SequenceEvent, SequenceTracker, SourceSequenceStats,
+    SEQUENCE_GAP_ATTRIBUTE, OUT_OF_ORDER_ATTRIBUTE
+};
+
+pub use window_processor::{
+    WindowProcessor, LATE_OUTPUT, WINDOW_END_ATTRIBUTE, WINDOW_START_ATTRIBUTE, WINDOW_UPDATE_ATTRIBUTE,
+};
+
 use async_trait::async_trait;
 use crate::DataChunk;
 
@@ -17,13 +40,42 @@ use crate::DataChunk;
 pub trait Processor: Send + Sync {
     async fn process(&mut self, chunk: &mut DataChunk) -> anyhow::Result<ProcessResult>;
     fn name(&self) -> &str;
+
+    // 管道启动前调用
+    async fn open(&mut self) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    // 数据源全部停止后调用，返回的数据块会继续流经后续处理器和输出端
+    async fn flush(&mut self) -> anyhow::Result<Vec<DataChunk>> {
+        Ok(Vec::new())
+    }
+
+    // 管道水位线前进时调用：此后到达的数据块事件时间预计不早于 watermark_ns
+    // 返回的数据块（例如关闭的窗口）继续流经后续处理器和输出端
+    async fn on_watermark(&mut self, _watermark_ns: u64) -> anyhow::Result<Vec<DataChunk>> {
+        Ok(Vec::new())
+    }
+
+    // 除主输出外的命名输出，下游用 "处理器名.输出名" 引用，数据块通过 ProcessResult::SideOutput 发送
+    fn side_outputs(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    // 管道结束时调用，释放资源
+    async fn close(&mut self) -> anyhow::Result<()> {
+        Ok(())
+    }
 }
 
 #[derive(Debug, Clone)]
 pub enum ProcessResult {
-    Continue,  // 继续处理
-    Skip,      // 跳过后续处理
-    Stop,      // 停止处理
+    Continue,                // 继续处理
+    Skip,                    // 跳过后续处理
+    Stop(String),            // 停止整个管道，当前数据块不投递（附带原因）
+    StopAndDeliver(String),  // 当前数据块继续处理并投递后停止整个管道
+    Emit(Vec<DataChunk>),    // 当前数据块被处理器吸收，改为向下游发送这些数据块（可以为空）
+    SideOutput(String),      // 当前数据块发送到指定的命名输出，而不是主输出
 }
 
 // 数据过滤处理器
@@ -70,10 +122,17 @@ impl UpperCaseProcessor {
     }
 }
 
+impl Default for UpperCaseProcessor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 #[async_trait]
 impl Processor for UpperCaseProcessor {
     async fn process(&mut self, chunk: &mut DataChunk) -> anyhow::Result<ProcessResult> {
-        chunk.data = chunk.data.iter().map(|&b| b.to_ascii_uppercase()).collect();
+        // 写时复制：负载未被共享时原地转换
+        chunk.modify_data(|data| data.make_ascii_uppercase());
         Ok(ProcessResult::Continue)
     }
 
@@ -0,0 +1,162 @@
+use async_trait::async_trait;
+use crate::DataChunk;
+use crate::packet::{DecodedPacket, LinkType, Transport, LINK_TYPE_ATTRIBUTE};
+use crate::processors::{Processor, ProcessResult, DST_IP_ATTRIBUTE, PROTOCOL_ATTRIBUTE, SRC_IP_ATTRIBUTE};
+use tracing::{debug, info};
+
+// 解码后写入的属性名（protocol、src_ip、dst_ip 与 PacketStatsProcessor/IPAnalysisProcessor 共用）
+pub const ETH_SRC_ATTRIBUTE: &str = "eth_src";
+pub const ETH_DST_ATTRIBUTE: &str = "eth_dst";
+pub const ETHER_TYPE_ATTRIBUTE: &str = "ether_type";
+pub const VLAN_ID_ATTRIBUTE: &str = "vlan_id";
+pub const IP_VERSION_ATTRIBUTE: &str = "ip_version";
+pub const IP_PROTOCOL_ATTRIBUTE: &str = "ip_protocol";
+pub const TTL_ATTRIBUTE: &str = "ttl";
+pub const IP_FRAGMENT_ATTRIBUTE: &str = "ip_fragment";
+pub const TRANSPORT_ATTRIBUTE: &str = "transport";
+pub const SRC_PORT_ATTRIBUTE: &str = "src_port";
+pub const DST_PORT_ATTRIBUTE: &str = "dst_port";
+pub const TCP_FLAGS_ATTRIBUTE: &str = "tcp_flags";
+pub const ICMP_TYPE_ATTRIBUTE: &str = "icmp_type";
+pub const ICMP_CODE_ATTRIBUTE: &str = "icmp_code";
+pub const PAYLOAD_OFFSET_ATTRIBUTE: &str = "payload_offset";
+pub const PAYLOAD_LENGTH_ATTRIBUTE: &str = "payload_length";
+pub const TRUNCATED_ATTRIBUTE: &str = "truncated";
+pub const DECODE_ERROR_ATTRIBUTE: &str = "decode_error";
+
+// 数据包解码处理器：把负载按原始帧解析（Ethernet II、802.1Q VLAN、IPv4、IPv6 扩展头、TCP、UDP、ICMP），
+// 解码出的字段写入属性
+//
+// protocol 为按端口识别的 DNS/HTTP，否则为 TCP/UDP/ICMP/ICMPv6/IPv4/IPv6/OTHER，
+// 后续的 PacketStatsProcessor 和 IPAnalysisProcessor 直接使用这些属性而不再匹配负载文本
+//
+// 链路层类型取数据块的 link_type 属性（pcap 编号），没有时使用处理器的默认值。
+// 抓包长度截断在传输层头中的数据包只解码到 IP 层，并写入 truncated 属性。
+// 解码失败时写入 decode_error 属性后继续传递；strict 模式下作为处理器错误交给错误策略
+pub struct PacketDecodeProcessor {
+    link_type: LinkType,
+    strict: bool,
+    decoded: u64,
+    failed: u64,
+    name: String,
+}
+
+impl PacketDecodeProcessor {
+    pub fn new() -> Self {
+        Self {
+            link_type: LinkType::default(),
+            strict: false,
+            decoded: 0,
+            failed: 0,
+            name: "PacketDecodeProcessor".to_string(),
+        }
+    }
+
+    pub fn with_name(mut self, name: &str) -> Self {
+        self.name = name.to_string();
+        self
+    }
+
+    pub fn with_link_type(mut self, link_type: LinkType) -> Self {
+        self.link_type = link_type;
+        self
+    }
+
+    pub fn with_strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    fn link_type_of(&self, chunk: &DataChunk) -> LinkType {
+        chunk
+            .attribute(LINK_TYPE_ATTRIBUTE)
+            .and_then(|value| value.as_i64())
+            .and_then(|code| u32::try_from(code).ok())
+            .and_then(LinkType::from_code)
+            .unwrap_or(self.link_type)
+    }
+}
+
+impl Default for PacketDecodeProcessor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// 把解码结果写入数据块属性
+fn annotate(chunk: &mut DataChunk, packet: &DecodedPacket) {
+    if let Some(ethernet) = &packet.ethernet {
+        chunk.set_attribute(ETH_SRC_ATTRIBUTE, ethernet.src.to_string());
+        chunk.set_attribute(ETH_DST_ATTRIBUTE, ethernet.dst.to_string());
+        chunk.set_attribute(ETHER_TYPE_ATTRIBUTE, u32::from(ethernet.ether_type));
+        if let Some(&vlan) = ethernet.vlans.first() {
+            chunk.set_attribute(VLAN_ID_ATTRIBUTE, u32::from(vlan));
+        }
+    }
+
+    if let Some(ip) = &packet.ip {
+        chunk.set_attribute(IP_VERSION_ATTRIBUTE, u32::from(ip.version));
+        chunk.set_attribute(SRC_IP_ATTRIBUTE, ip.src.to_string());
+        chunk.set_attribute(DST_IP_ATTRIBUTE, ip.dst.to_string());
+        chunk.set_attribute(IP_PROTOCOL_ATTRIBUTE, u32::from(ip.protocol));
+        chunk.set_attribute(TTL_ATTRIBUTE, u32::from(ip.ttl));
+        if ip.fragment {
+            chunk.set_attribute(IP_FRAGMENT_ATTRIBUTE, true);
+        }
+    }
+
+    if let Some(transport) = &packet.transport {
+        chunk.set_attribute(TRANSPORT_ATTRIBUTE, transport.name());
+        if let Some((src_port, dst_port)) = transport.ports() {
+            chunk.set_attribute(SRC_PORT_ATTRIBUTE, u32::from(src_port));
+            chunk.set_attribute(DST_PORT_ATTRIBUTE, u32::from(dst_port));
+        }
+        match transport {
+            Transport::Tcp(tcp) => chunk.set_attribute(TCP_FLAGS_ATTRIBUTE, tcp.flag_names()),
+            Transport::Icmp { icmp_type, code } | Transport::Icmpv6 { icmp_type, code } => {
+                chunk.set_attribute(ICMP_TYPE_ATTRIBUTE, u32::from(*icmp_type));
+                chunk.set_attribute(ICMP_CODE_ATTRIBUTE, u32::from(*code));
+            }
+            Transport::Udp(_) => {}
+        }
+    }
+
+    if packet.truncated {
+        chunk.set_attribute(TRUNCATED_ATTRIBUTE, true);
+    }
+    chunk.set_attribute(PAYLOAD_OFFSET_ATTRIBUTE, packet.payload_offset as i64);
+    chunk.set_attribute(PAYLOAD_LENGTH_ATTRIBUTE, packet.payload_len as i64);
+    chunk.set_attribute(PROTOCOL_ATTRIBUTE, packet.protocol());
+}
+
+#[async_trait]
+impl Processor for PacketDecodeProcessor {
+    async fn process(&mut self, chunk: &mut DataChunk) -> anyhow::Result<ProcessResult> {
+        let link_type = self.link_type_of(chunk);
+        match DecodedPacket::decode(link_type, &chunk.data) {
+            Ok(packet) => {
+                self.decoded += 1;
+                annotate(chunk, &packet);
+            }
+            Err(e) if self.strict => {
+                self.failed += 1;
+                anyhow::bail!("packet {} ({}): {}", chunk.sequence, link_type, e);
+            }
+            Err(e) => {
+                self.failed += 1;
+                debug!("[{}] Failed to decode packet {}: {}", self.name, chunk.sequence, e);
+                chunk.set_attribute(DECODE_ERROR_ATTRIBUTE, e.to_string());
+            }
+        }
+        Ok(ProcessResult::Continue)
+    }
+
+    async fn close(&mut self) -> anyhow::Result<()> {
+        info!("[{}] Decoded {} packets, {} failed", self.name, self.decoded, self.failed);
+        Ok(())
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
@@ -4,9 +4,22 @@ use crate::processors::{Processor, ProcessResult};
 use std::collections::HashMap;
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
-use tracing::info;  // 移除 debug
+use tracing::{info, warn};
 
-// ... 其余代码保持不变 ...
+// 处理器写入的属性名
+pub const PROTOCOL_ATTRIBUTE: &str = "protocol";
+pub const SRC_IP_ATTRIBUTE: &str = "src_ip";
+pub const DST_IP_ATTRIBUTE: &str = "dst_ip";
+pub const THREAT_ATTRIBUTE: &str = "threat";
+
+// 按负载中的关键字判断协议：TCP、UDP、HTTP、DNS 或 OTHER
+fn classify_protocol(data: &[u8]) -> &'static str {
+    let data_str = String::from_utf8_lossy(data);
+    ["TCP", "UDP", "HTTP", "DNS"]
+        .into_iter()
+        .find(|protocol| data_str.contains(protocol))
+        .unwrap_or("OTHER")
+}
 
 // 数据包统计分析器
 pub struct PacketStatsProcessor {
@@ -46,6 +59,12 @@ impl PacketStatsProcessor {
     }
 }
 
+impl Default for PacketStatsProcessor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct PacketStatsSnapshot {
     pub total_packets: u64,
@@ -63,23 +82,28 @@ impl Processor for PacketStatsProcessor {
         self.stats.total_packets.fetch_add(1, Ordering::Relaxed);
         self.stats.total_bytes.fetch_add(chunk.data.len() as u64, Ordering::Relaxed);
 
-        let data_str = String::from_utf8_lossy(&chunk.data);
-
-        if data_str.contains("TCP") {
-            self.stats.tcp_packets.fetch_add(1, Ordering::Relaxed);
-        } else if data_str.contains("UDP") {
-            self.stats.udp_packets.fetch_add(1, Ordering::Relaxed);
-        } else if data_str.contains("HTTP") {
-            self.stats.http_packets.fetch_add(1, Ordering::Relaxed);
-        } else if data_str.contains("DNS") {
-            self.stats.dns_packets.fetch_add(1, Ordering::Relaxed);
-        } else {
-            self.stats.other_packets.fetch_add(1, Ordering::Relaxed);
-        }
+        // 上游已标注协议时直接使用，否则解析负载并写入 protocol 属性
+        let protocol = match chunk.attribute_str(PROTOCOL_ATTRIBUTE) {
+            Some(protocol) => protocol.to_string(),
+            None => {
+                let protocol = classify_protocol(&chunk.data);
+                chunk.set_attribute(PROTOCOL_ATTRIBUTE, protocol);
+                protocol.to_string()
+            }
+        };
+
+        let counter = match protocol.as_str() {
+            "TCP" => &self.stats.tcp_packets,
+            "UDP" => &self.stats.udp_packets,
+            "HTTP" => &self.stats.http_packets,
+            "DNS" => &self.stats.dns_packets,
+            _ => &self.stats.other_packets,
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
 
         // 每100个包打印一次统计
         let total = self.stats.total_packets.load(Ordering::Relaxed);
-        if total % 100 == 0 {
+        if total.is_multiple_of(100) {
             let stats = self.get_stats();
             info!(
                 "Packet Stats - Total: {}, TCP: {}, UDP: {}, HTTP: {}, DNS: {}, Other: {}, Bytes: {}",
@@ -100,6 +124,8 @@ impl Processor for PacketStatsProcessor {
 // IP地址分析器
 pub struct IPAnalysisProcessor {
     ip_counter: HashMap<String, u64>,
+    // 本实例处理过的数据块数量（sequence 只在单个数据源内唯一，不能用来计数）
+    processed: u64,
     name: String,
 }
 
@@ -107,6 +133,7 @@ impl IPAnalysisProcessor {
     pub fn new() -> Self {
         Self {
             ip_counter: HashMap::new(),
+            processed: 0,
             name: "IPAnalysisProcessor".to_string(),
         }
     }
@@ -121,21 +148,44 @@ impl IPAnalysisProcessor {
     }
 }
 
+impl Default for IPAnalysisProcessor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 #[async_trait]
 impl Processor for IPAnalysisProcessor {
     async fn process(&mut self, chunk: &mut DataChunk) -> anyhow::Result<ProcessResult> {
-        let data_str = String::from_utf8_lossy(&chunk.data);
-
-        // 简单的IP地址提取（实际应用中应该使用更复杂的解析）
-        for part in data_str.split_whitespace() {
-            if part.contains('.') && part.chars().all(|c| c.is_ascii_digit() || c == '.') {
-                let ip = part.to_string();
-                *self.ip_counter.entry(ip).or_insert(0) += 1;
+        // 上游已标注地址时直接使用，否则从负载中提取
+        // （简单的IP地址提取，实际应用中应该使用更复杂的解析）并写入 src_ip/dst_ip 属性
+        let mut ips: Vec<String> = [SRC_IP_ATTRIBUTE, DST_IP_ATTRIBUTE]
+            .into_iter()
+            .filter_map(|name| chunk.attribute_str(name).map(str::to_string))
+            .collect();
+        if ips.is_empty() {
+            let data_str = String::from_utf8_lossy(&chunk.data);
+            ips = data_str
+                .split_whitespace()
+                .filter(|part| part.contains('.') && part.chars().all(|c| c.is_ascii_digit() || c == '.'))
+                .map(str::to_string)
+                .collect();
+            for (name, ip) in [SRC_IP_ATTRIBUTE, DST_IP_ATTRIBUTE].into_iter().zip(&ips) {
+                chunk.set_attribute(name, ip.as_str());
             }
         }
 
+        // 源地址作为默认分区键
+        if chunk.key.is_none() {
+            chunk.key = ips.first().map(|ip| ip.as_str().into());
+        }
+        for ip in ips {
+            *self.ip_counter.entry(ip).or_insert(0) += 1;
+        }
+
         // 每200个包打印一次IP统计
-        if chunk.sequence % 200 == 0 && chunk.sequence > 0 {
+        self.processed += 1;
+        if self.processed.is_multiple_of(200) {
             let top_ips = self.get_top_ips(5);
             info!("Top IPs by packet count: {:?}", top_ips);
         }
@@ -150,7 +200,11 @@ impl Processor for IPAnalysisProcessor {
 
 // 实时威胁检测处理器
 pub struct ThreatDetectionProcessor {
-    suspicious_patterns: Vec<Vec<u8>>,
+    // (威胁类别, 特征)，命中时写入 threat 属性
+    suspicious_patterns: Vec<(&'static str, Vec<u8>)>,
+    // 关键威胁特征：一旦命中立即停止整个管道
+    critical_patterns: Vec<Vec<u8>>,
+    deliver_critical: bool,
     threat_count: u64,
     name: String,
 }
@@ -158,31 +212,72 @@ pub struct ThreatDetectionProcessor {
 impl ThreatDetectionProcessor {
     pub fn new() -> Self {
         let suspicious_patterns = vec![
-            b"malware".to_vec(),
-            b"exploit".to_vec(),
-            b"injection".to_vec(),
-            b"SELECT * FROM".to_vec(), // SQL注入特征
-            b"<script>".to_vec(),      // XSS特征
+            ("malware", b"malware".to_vec()),
+            ("exploit", b"exploit".to_vec()),
+            ("injection", b"injection".to_vec()),
+            ("sql_injection", b"SELECT * FROM".to_vec()), // SQL注入特征
+            ("xss", b"<script>".to_vec()),                // XSS特征
         ];
 
         Self {
             suspicious_patterns,
+            critical_patterns: Vec::new(),
+            deliver_critical: false,
             threat_count: 0,
             name: "ThreatDetectionProcessor".to_string(),
         }
     }
 
+    // 命中关键特征时停止管道（威胁响应场景）
+    pub fn with_critical_pattern(mut self, pattern: Vec<u8>) -> Self {
+        self.critical_patterns.push(pattern);
+        self
+    }
+
+    // 停止前是否仍把命中的数据块投递到输出端（例如告警输出）
+    pub fn with_critical_delivery(mut self, deliver: bool) -> Self {
+        self.deliver_critical = deliver;
+        self
+    }
+
     pub fn get_threat_count(&self) -> u64 {
         self.threat_count
     }
 }
 
+impl Default for ThreatDetectionProcessor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 #[async_trait]
 impl Processor for ThreatDetectionProcessor {
     async fn process(&mut self, chunk: &mut DataChunk) -> anyhow::Result<ProcessResult> {
-        for pattern in &self.suspicious_patterns {
+        if let Some(pattern) = self
+            .critical_patterns
+            .iter()
+            .find(|pattern| !pattern.is_empty() && chunk.data.windows(pattern.len()).any(|window| window == pattern.as_slice()))
+        {
+            self.threat_count += 1;
+            let reason = format!(
+                "critical indicator '{}' in packet {}",
+                String::from_utf8_lossy(pattern),
+                chunk.sequence
+            );
+            warn!("🛑 CRITICAL THREAT! {}", reason);
+            chunk.set_attribute(THREAT_ATTRIBUTE, "critical");
+            return Ok(if self.deliver_critical {
+                ProcessResult::StopAndDeliver(reason)
+            } else {
+                ProcessResult::Stop(reason)
+            });
+        }
+
+        for (threat, pattern) in &self.suspicious_patterns {
             if chunk.data.windows(pattern.len()).any(|window| window == pattern) {
                 self.threat_count += 1;
+                chunk.set_attribute(THREAT_ATTRIBUTE, *threat);
                 info!(
                     "🚨 THREAT DETECTED! Pattern: '{}' in packet {}",
                     String::from_utf8_lossy(pattern),
@@ -0,0 +1,313 @@
+use crate::config::{ComponentConfig, ComponentKind, ConfigError};
+use crate::{AttributeValue, DataChunk};
+use std::fmt;
+use std::sync::Arc;
+
+// 未匹配任何路由的数据块从这个输出离开
+pub const UNMATCHED_ROUTE: &str = "_unmatched";
+
+// 路由条件，可在 YAML 中书写，例如 { contains: "DNS" }、{ attribute: { protocol: "DNS" } } 或 { any: [...] }
+#[derive(Debug, Clone, PartialEq)]
+pub enum Condition {
+    // 属性等于给定值（整数与浮点数按数值比较）
+    Attribute(String, AttributeValue),
+    HasAttribute(String),
+    Key(String),
+    Source(String),
+    Contains(String),
+    ContainsAny(Vec<String>),
+    StartsWith(String),
+    MinLength(usize),
+    MaxLength(usize),
+    All(Vec<Condition>),
+    Any(Vec<Condition>),
+    Not(Box<Condition>),
+}
+
+impl Condition {
+    pub fn matches(&self, chunk: &DataChunk) -> bool {
+        match self {
+            Condition::Attribute(name, expected) => chunk.attribute(name).is_some_and(|value| {
+                value == expected || matches!((value.as_f64(), expected.as_f64()), (Some(a), Some(b)) if a == b)
+            }),
+            Condition::HasAttribute(name) => chunk.attribute(name).is_some(),
+            Condition::Key(key) => chunk.key.as_deref() == Some(key.as_str()),
+            Condition::Source(source) => chunk.source_id.as_deref() == Some(source.as_str()),
+            Condition::Contains(pattern) => contains(&chunk.data, pattern.as_bytes()),
+            Condition::ContainsAny(patterns) => patterns.iter().any(|p| contains(&chunk.data, p.as_bytes())),
+            Condition::StartsWith(prefix) => chunk.data.starts_with(prefix.as_bytes()),
+            Condition::MinLength(len) => chunk.data.len() >= *len,
+            Condition::MaxLength(len) => chunk.data.len() <= *len,
+            Condition::All(conditions) => conditions.iter().all(|c| c.matches(chunk)),
+            Condition::Any(conditions) => conditions.iter().any(|c| c.matches(chunk)),
+            Condition::Not(condition) => !condition.matches(chunk),
+        }
+    }
+
+    // 从 YAML 映射解析；同一映射中的多个条件需同时满足
+    pub fn from_yaml(value: &serde_yaml::Value) -> Result<Self, String> {
+        let mapping = value.as_mapping().ok_or("条件应为映射，例如 { contains: \"DNS\" }")?;
+        let mut conditions = mapping
+            .iter()
+            .map(|(key, value)| {
+                let key = key.as_str().ok_or("条件名应为字符串")?;
+                parse_condition(key, value)
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+        match conditions.len() {
+            0 => Err("条件不能为空".to_string()),
+            1 => Ok(conditions.remove(0)),
+            _ => Ok(Condition::All(conditions)),
+        }
+    }
+}
+
+fn parse_condition(key: &str, value: &serde_yaml::Value) -> Result<Condition, String> {
+    let string = || value.as_str().map(str::to_string).ok_or(format!("{} 应为字符串", key));
+    let length = || value.as_u64().map(|n| n as usize).ok_or(format!("{} 应为非负整数", key));
+    let list = || value.as_sequence().ok_or(format!("{} 应为列表", key));
+
+    match key {
+        "attribute" => {
+            let mapping = value.as_mapping().ok_or("attribute 应为 属性名 → 值 的映射")?;
+            let mut conditions = mapping
+                .iter()
+                .map(|(name, value)| {
+                    let name = name.as_str().ok_or("属性名应为字符串")?;
+                    Ok(Condition::Attribute(name.to_string(), attribute_value(name, value)?))
+                })
+                .collect::<Result<Vec<_>, String>>()?;
+            match conditions.len() {
+                0 => Err("attribute 不能为空".to_string()),
+                1 => Ok(conditions.remove(0)),
+                _ => Ok(Condition::All(conditions)),
+            }
+        }
+        "has_attribute" => Ok(Condition::HasAttribute(string()?)),
+        "key" => Ok(Condition::Key(string()?)),
+        "source" => Ok(Condition::Source(string()?)),
+        "contains" => Ok(Condition::Contains(string()?)),
+        "starts_with" => Ok(Condition::StartsWith(string()?)),
+        "min_length" => Ok(Condition::MinLength(length()?)),
+        "max_length" => Ok(Condition::MaxLength(length()?)),
+        "contains_any" => list()?
+            .iter()
+            .map(|item| item.as_str().map(str::to_string).ok_or(format!("{} 的元素应为字符串", key)))
+            .collect::<Result<_, _>>()
+            .map(Condition::ContainsAny),
+        "all" => list()?.iter().map(Condition::from_yaml).collect::<Result<_, _>>().map(Condition::All),
+        "any" => list()?.iter().map(Condition::from_yaml).collect::<Result<_, _>>().map(Condition::Any),
+        "not" => Condition::from_yaml(value).map(|c| Condition::Not(Box::new(c))),
+        _ => Err(format!("未知条件 '{}'", key)),
+    }
+}
+
+fn attribute_value(name: &str, value: &serde_yaml::Value) -> Result<AttributeValue, String> {
+    match value {
+        serde_yaml::Value::String(s) => Ok(AttributeValue::Str(s.clone())),
+        serde_yaml::Value::Bool(b) => Ok(AttributeValue::Bool(*b)),
+        serde_yaml::Value::Number(n) => Ok(match n.as_i64() {
+            Some(i) => AttributeValue::Int(i),
+            None => AttributeValue::Float(n.as_f64().unwrap_or(f64::NAN)),
+        }),
+        _ => Err(format!("属性 '{}' 的值应为字符串、数字或布尔值", name)),
+    }
+}
+
+fn contains(data: &[u8], pattern: &[u8]) -> bool {
+    pattern.is_empty() || data.windows(pattern.len()).any(|window| window == pattern)
+}
+
+type Predicate = Arc<dyn Fn(&DataChunk) -> bool + Send + Sync>;
+
+struct Route {
+    name: String,
+    predicate: Predicate,
+}
+
+// 路由器：按条件把数据块分发到命名输出，下游通过 "路由器名.输出名" 引用
+// 一个数据块会进入所有匹配的输出；都不匹配时进入 _unmatched
+#[derive(Clone)]
+pub struct Router {
+    name: String,
+    routes: Vec<Arc<Route>>,
+}
+
+impl Router {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            routes: Vec::new(),
+        }
+    }
+
+    pub fn route(self, output: &str, condition: Condition) -> Self {
+        self.route_fn(output, move |chunk| condition.matches(chunk))
+    }
+
+    pub fn route_fn<F>(mut self, output: &str, predicate: F) -> Self
+    where
+        F: Fn(&DataChunk) -> bool + Send + Sync + 'static,
+    {
+        self.routes.push(Arc::new(Route {
+            name: output.to_string(),
+            predicate: Arc::new(predicate),
+        }));
+        self
+    }
+
+    // 从配置创建：params.routes 为 输出名 → 条件 的映射
+    pub fn from_config(config: &ComponentConfig) -> Result<Self, ConfigError> {
+        let kind = ComponentKind::Processor;
+        let routes = match config.param("routes") {
+            Some(serde_yaml::Value::Mapping(routes)) => routes,
+            Some(_) => return Err(config.invalid_param(kind, "routes", "应为 输出名 → 条件 的映射")),
+            None => return Err(config.invalid_param(kind, "routes", "缺少必填参数")),
+        };
+
+        let mut router = Router::new(&config.name);
+        for (output, condition) in routes {
+            let output = output
+                .as_str()
+                .ok_or_else(|| config.invalid_param(kind, "routes", "输出名应为字符串"))?;
+            if output == UNMATCHED_ROUTE || output.is_empty() || output.contains('.') {
+                return Err(config.invalid_param(kind, "routes", format!("无效的输出名 '{}'", output)));
+            }
+            let condition = Condition::from_yaml(condition)
+                .map_err(|e| config.invalid_param(kind, "routes", format!("输出 '{}' 的条件无效: {}", output, e)))?;
+            router = router.route(output, condition);
+        }
+        Ok(router)
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    // 所有输出名，最后一个为 _unmatched
+    pub fn outputs(&self) -> Vec<String> {
+        self.routes
+            .iter()
+            .map(|route| route.name.clone())
+            .chain(std::iter::once(UNMATCHED_ROUTE.to_string()))
+            .collect()
+    }
+
+    // 返回数据块应进入的输出下标（与 outputs() 对应）
+    pub fn select(&self, chunk: &DataChunk) -> Vec<usize> {
+        let matched: Vec<usize> = self
+            .routes
+            .iter()
+            .enumerate()
+            .filter(|(_, route)| (route.predicate)(chunk))
+            .map(|(i, _)| i)
+            .collect();
+        if matched.is_empty() {
+            vec![self.routes.len()]
+        } else {
+            matched
+        }
+    }
+}
+
+impl fmt::Debug for Router {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Router")
+            .field("name", &self.name)
+            .field("outputs", &self.outputs())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pipeline::Pipeline;
+    use crate::sinks::Sink;
+    use crate::sources::MemorySource;
+    use async_trait::async_trait;
+    use std::sync::Mutex;
+
+    #[derive(Clone, Default)]
+    struct CollectSink(Arc<Mutex<Vec<String>>>);
+
+    #[async_trait]
+    impl Sink for CollectSink {
+        async fn send(&mut self, chunk: DataChunk) -> anyhow::Result<()> {
+            self.0.lock().unwrap().push(String::from_utf8_lossy(&chunk.data).into_owned());
+            Ok(())
+        }
+
+        fn name(&self) -> &str {
+            "CollectSink"
+        }
+    }
+
+    fn yaml(text: &str) -> serde_yaml::Value {
+        serde_yaml::from_str(text).unwrap()
+    }
+
+    #[test]
+    fn chunks_go_to_every_matching_route_or_unmatched() {
+        let router = Router::new("split")
+            .route("dns", Condition::Contains("DNS".to_string()))
+            .route("large", Condition::MinLength(8));
+        assert_eq!(router.outputs(), ["dns", "large", UNMATCHED_ROUTE]);
+        assert_eq!(router.select(&DataChunk::new(b"DNS".to_vec())), [0]);
+        assert_eq!(router.select(&DataChunk::new(b"long DNS query".to_vec())), [0, 1]);
+        assert_eq!(router.select(&DataChunk::new(b"HTTP".to_vec())), [2]);
+    }
+
+    #[test]
+    fn conditions_parse_from_yaml() {
+        let condition = Condition::from_yaml(&yaml("{ attribute: { port: 53 }, not: { contains: TCP } }")).unwrap();
+        let udp = DataChunk::new(b"UDP".to_vec()).with_attribute("port", 53.0);
+        assert!(condition.matches(&udp));
+        assert!(!condition.matches(&DataChunk::new(b"TCP".to_vec()).with_attribute("port", 53i64)));
+        assert!(!condition.matches(&DataChunk::new(b"UDP".to_vec())));
+
+        let condition = Condition::from_yaml(&yaml("{ any: [{ source: dns_tap }, { key: resolver }] }")).unwrap();
+        assert!(condition.matches(&DataChunk::new(Vec::new()).with_source_id("dns_tap")));
+        assert!(!condition.matches(&DataChunk::new(Vec::new()).with_source_id("other")));
+
+        assert!(Condition::from_yaml(&yaml("{}")).is_err());
+        assert!(Condition::from_yaml(&yaml("{ regex: x }")).unwrap_err().contains("未知条件"));
+        assert!(Condition::from_yaml(&yaml("{ min_length: -1 }")).is_err());
+    }
+
+    #[test]
+    fn from_config_rejects_reserved_output_names() {
+        let config = |routes: &str| -> ComponentConfig {
+            serde_yaml::from_str(&format!("{{ name: split, type: router, params: {{ routes: {} }} }}", routes)).unwrap()
+        };
+        let router = Router::from_config(&config("{ dns: { contains: DNS } }")).unwrap();
+        assert_eq!(router.outputs(), ["dns", UNMATCHED_ROUTE]);
+        assert!(Router::from_config(&config("{ _unmatched: { contains: DNS } }")).is_err());
+        assert!(Router::from_config(&config("{ a.b: { contains: DNS } }")).is_err());
+        assert!(Router::from_config(&config("[dns]")).is_err());
+    }
+
+    #[tokio::test]
+    async fn pipeline_routes_chunks_to_branches() {
+        let (dns, http, rest) = (CollectSink::default(), CollectSink::default(), CollectSink::default());
+        let data = ["DNS query", "HTTP GET", "DNS over HTTP", "ARP"].map(|s| s.as_bytes().to_vec()).to_vec();
+        let router = Router::new("split")
+            .route("dns", Condition::StartsWith("DNS".to_string()))
+            .route("http", Condition::Contains("HTTP".to_string()));
+        let summary = Pipeline::builder()
+            .source("input", Box::new(MemorySource::new(data).with_delay(0)))
+            .router(router, &["input"])
+            .sink("dns", Box::new(dns.clone()), &["split.dns"])
+            .sink("http", Box::new(http.clone()), &["split.http"])
+            .sink("rest", Box::new(rest.clone()), &["split._unmatched"])
+            .build()
+            .run()
+            .await
+            .unwrap();
+
+        assert_eq!(*dns.0.lock().unwrap(), ["DNS query", "DNS over HTTP"]);
+        assert_eq!(*http.0.lock().unwrap(), ["HTTP GET", "DNS over HTTP"]);
+        assert_eq!(*rest.0.lock().unwrap(), ["ARP"]);
+        assert_eq!(summary.sinks.iter().map(|sink| sink.delivered).sum::<u64>(), 5);
+    }
+}
@@ -1,35 +1,44 @@
 use async_trait::async_trait;
 use crate::DataChunk;
 use crate::processors::{Processor, ProcessResult};
-use crate::security::DataEncryptor;
+use crate::security::{DataEncryptor, SecurityConfig, SecurityError};
 use tracing::error;
 
+// 用认证加密替换数据块负载，附加数据绑定数据块序号
 pub struct EncryptionProcessor {
     encryptor: DataEncryptor,
     name: String,
 }
 
 impl EncryptionProcessor {
-    pub fn new(key: &[u8], name: &str) -> Result<Self, crate::security::SecurityError> {
+    // 使用默认算法（AES-256-GCM）
+    pub fn new(key: &[u8], name: &str) -> Result<Self, SecurityError> {
         let encryptor = DataEncryptor::new(key)?;
         Ok(Self {
             encryptor,
             name: name.to_string(),
         })
     }
+
+    pub fn from_config(config: &SecurityConfig, name: &str) -> Result<Self, SecurityError> {
+        Ok(Self {
+            encryptor: DataEncryptor::from_config(config)?,
+            name: name.to_string(),
+        })
+    }
 }
 
 #[async_trait]
 impl Processor for EncryptionProcessor {
     async fn process(&mut self, chunk: &mut DataChunk) -> anyhow::Result<ProcessResult> {
-        match self.encryptor.encrypt(&chunk.data) {
+        match self.encryptor.encrypt(&chunk.data, chunk.sequence) {
             Ok(encrypted_data) => {
-                chunk.data = encrypted_data;
+                chunk.set_data(encrypted_data);
                 Ok(ProcessResult::Continue)
             }
             Err(e) => {
-                error!("加密失败: {}", e);
-                Err(anyhow::anyhow!("加密失败: {}", e))
+                error!("[{}] {}", self.name, e);
+                Err(anyhow::Error::new(e))
             }
         }
     }
@@ -39,32 +48,40 @@ impl Processor for EncryptionProcessor {
     }
 }
 
+// 解密 EncryptionProcessor 的输出；密文被篡改或序号不匹配时返回 SecurityError::DecryptionError
 pub struct DecryptionProcessor {
     encryptor: DataEncryptor,
     name: String,
 }
 
 impl DecryptionProcessor {
-    pub fn new(key: &[u8], name: &str) -> Result<Self, crate::security::SecurityError> {
+    pub fn new(key: &[u8], name: &str) -> Result<Self, SecurityError> {
         let encryptor = DataEncryptor::new(key)?;
         Ok(Self {
             encryptor,
             name: name.to_string(),
         })
     }
+
+    pub fn from_config(config: &SecurityConfig, name: &str) -> Result<Self, SecurityError> {
+        Ok(Self {
+            encryptor: DataEncryptor::from_config(config)?,
+            name: name.to_string(),
+        })
+    }
 }
 
 #[async_trait]
 impl Processor for DecryptionProcessor {
     async fn process(&mut self, chunk: &mut DataChunk) -> anyhow::Result<ProcessResult> {
-        match self.encryptor.decrypt(&chunk.data) {
+        match self.encryptor.decrypt(&chunk.data, chunk.sequence) {
             Ok(decrypted_data) => {
-                chunk.data = decrypted_data;
+                chunk.set_data(decrypted_data);
                 Ok(ProcessResult::Continue)
             }
             Err(e) => {
-                error!("解密失败: {}", e);
-                Err(anyhow::anyhow!("解密失败: {}", e))
+                error!("[{}] {}", self.name, e);
+                Err(anyhow::Error::new(e))
             }
         }
     }
@@ -72,4 +89,4 @@ impl Processor for DecryptionProcessor {
     fn name(&self) -> &str {
         &self.name
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,151 @@
+use async_trait::async_trait;
+use crate::DataChunk;
+use crate::processors::{Processor, ProcessResult};
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+use tracing::{info, warn};
+
+// 处理器写入的属性名
+pub const SEQUENCE_GAP_ATTRIBUTE: &str = "sequence_gap";
+pub const OUT_OF_ORDER_ATTRIBUTE: &str = "out_of_order";
+
+// 单个数据源的序号观察结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SequenceEvent {
+    // 该数据源的第一个数据块
+    First,
+    InOrder,
+    // 跳过了 missing 个序号
+    Gap { expected: u64, received: u64, missing: u64 },
+    // 序号不大于已见过的最大序号（重复或乱序到达）
+    OutOfOrder { expected: u64, received: u64 },
+}
+
+// 单个数据源的序号统计
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SourceSequenceStats {
+    pub received: u64,
+    pub gaps: u64,
+    pub missing: u64,
+    pub out_of_order: u64,
+    pub last_sequence: u64,
+}
+
+// 按 source_id 跟踪每个数据源的 sequence，检测丢失和乱序
+#[derive(Debug, Default)]
+pub struct SequenceTracker {
+    sources: BTreeMap<String, SourceSequenceStats>,
+}
+
+impl SequenceTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn observe(&mut self, source_id: &str, sequence: u64) -> SequenceEvent {
+        let Some(stats) = self.sources.get_mut(source_id) else {
+            self.sources.insert(source_id.to_string(), SourceSequenceStats {
+                received: 1,
+                last_sequence: sequence,
+                ..Default::default()
+            });
+            return SequenceEvent::First;
+        };
+
+        stats.received += 1;
+        let expected = stats.last_sequence + 1;
+        if sequence == expected {
+            stats.last_sequence = sequence;
+            SequenceEvent::InOrder
+        } else if sequence > expected {
+            let missing = sequence - expected;
+            stats.gaps += 1;
+            stats.missing += missing;
+            stats.last_sequence = sequence;
+            SequenceEvent::Gap { expected, received: sequence, missing }
+        } else {
+            stats.out_of_order += 1;
+            SequenceEvent::OutOfOrder { expected, received: sequence }
+        }
+    }
+
+    // 按数据块的 source_id 和 sequence 观察；没有 source_id 的数据块归为 ""
+    pub fn observe_chunk(&mut self, chunk: &DataChunk) -> SequenceEvent {
+        self.observe(chunk.source_id.as_deref().unwrap_or(""), chunk.sequence)
+    }
+
+    pub fn stats(&self, source_id: &str) -> Option<&SourceSequenceStats> {
+        self.sources.get(source_id)
+    }
+
+    pub fn sources(&self) -> &BTreeMap<String, SourceSequenceStats> {
+        &self.sources
+    }
+}
+
+// 序号检测处理器：发现某个数据源丢失或乱序的数据块时记录日志并写入属性
+//
+// 多个工作任务时每个实例只看到部分数据块，应在 workers: 1 的管道中使用
+pub struct GapDetectionProcessor {
+    tracker: Arc<Mutex<SequenceTracker>>,
+    name: String,
+}
+
+impl GapDetectionProcessor {
+    pub fn new() -> Self {
+        Self {
+            tracker: Arc::new(Mutex::new(SequenceTracker::new())),
+            name: "GapDetectionProcessor".to_string(),
+        }
+    }
+
+    pub fn with_name(mut self, name: &str) -> Self {
+        self.name = name.to_string();
+        self
+    }
+
+    // 共享的跟踪器，管道结束后可以读取每个数据源的统计
+    pub fn tracker(&self) -> Arc<Mutex<SequenceTracker>> {
+        self.tracker.clone()
+    }
+}
+
+impl Default for GapDetectionProcessor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl Processor for GapDetectionProcessor {
+    async fn process(&mut self, chunk: &mut DataChunk) -> anyhow::Result<ProcessResult> {
+        let event = self.tracker.lock().unwrap().observe_chunk(chunk);
+        let source = chunk.source_id.as_deref().unwrap_or("");
+        match event {
+            SequenceEvent::Gap { expected, received, missing } => {
+                warn!("[{}] Source {} skipped {} chunks (expected sequence {}, got {})",
+                      self.name, source, missing, expected, received);
+                chunk.set_attribute(SEQUENCE_GAP_ATTRIBUTE, missing as i64);
+            }
+            SequenceEvent::OutOfOrder { expected, received } => {
+                warn!("[{}] Source {} chunk out of order (expected sequence {}, got {})",
+                      self.name, source, expected, received);
+                chunk.set_attribute(OUT_OF_ORDER_ATTRIBUTE, true);
+            }
+            SequenceEvent::First | SequenceEvent::InOrder => {}
+        }
+        Ok(ProcessResult::Continue)
+    }
+
+    async fn close(&mut self) -> anyhow::Result<()> {
+        for (source, stats) in self.tracker.lock().unwrap().sources() {
+            info!("[{}] Source {}: {} received, {} gaps ({} missing), {} out of order",
+                  self.name, source, stats.received, stats.gaps, stats.missing, stats.out_of_order);
+        }
+        Ok(())
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
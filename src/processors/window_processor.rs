@@ -0,0 +1,228 @@
+use async_trait::async_trait;
+use crate::DataChunk;
+use crate::processors::{Processor, ProcessResult};
+use crate::window::{
+    AggregateSpec, Assignment, ClosedWindow, Field, LatePolicy, TimeDomain, WindowKind, WindowStore,
+};
+use serde_json::{json, Map, Value};
+use tracing::{debug, info, warn};
+
+// 聚合数据块上的窗口区间属性（Unix 纳秒）
+pub const WINDOW_START_ATTRIBUTE: &str = "window_start_ns";
+pub const WINDOW_END_ATTRIBUTE: &str = "window_end_ns";
+// 迟到数据更新后重新输出的结果带有此属性（值为 true）
+pub const WINDOW_UPDATE_ATTRIBUTE: &str = "window_update";
+// LatePolicy::SideOutput 时迟到数据块的输出名
+pub const LATE_OUTPUT: &str = "late";
+
+// 窗口聚合处理器：吸收输入数据块，每个窗口（和分组）关闭时输出一个聚合数据块
+//
+// 聚合数据块的负载为 JSON，例如
+// {"window":"tumbling","start_ns":..,"end_ns":..,"protocol":"DNS","count":40,"sum(length)":1040}
+// 分组值同时写入分区键，source_id 为处理器名称
+//
+// 事件时间窗口在管道水位线越过窗口结束时间时关闭，进入时间窗口随数据块的进入时间关闭；
+// 迟到数据块按 LatePolicy 丢弃、发送到 late 输出，或在允许迟到时间内更新窗口并重新输出
+// （负载中带 "update": true）
+//
+// 多个工作任务时每个实例只看到部分数据块，应在 workers: 1 的管道中使用
+pub struct WindowProcessor {
+    store: WindowStore,
+    late_policy: LatePolicy,
+    emitted: u64,
+    name: String,
+}
+
+impl WindowProcessor {
+    pub fn new(kind: WindowKind) -> Self {
+        Self {
+            store: WindowStore::new(kind),
+            late_policy: LatePolicy::default(),
+            emitted: 0,
+            name: format!("WindowProcessor({})", kind.name()),
+        }
+    }
+
+    pub fn with_name(mut self, name: &str) -> Self {
+        self.name = name.to_string();
+        self
+    }
+
+    pub fn with_time_domain(mut self, time: TimeDomain) -> Self {
+        self.store = self.store.with_time_domain(time);
+        self
+    }
+
+    pub fn with_group_by(mut self, field: Field) -> Self {
+        self.store = self.store.with_group_by(field);
+        self
+    }
+
+    // 未添加任何聚合时只输出 count
+    pub fn with_aggregate(mut self, aggregate: AggregateSpec) -> Self {
+        self.store = self.store.with_aggregate(aggregate);
+        self
+    }
+
+    pub fn with_late_policy(mut self, policy: LatePolicy) -> Self {
+        if let LatePolicy::Update { allowed_lateness } = policy {
+            self.store = self.store.with_allowed_lateness(allowed_lateness);
+        }
+        self.late_policy = policy;
+        self
+    }
+
+    fn result_chunk(&mut self, window: ClosedWindow) -> DataChunk {
+        let mut payload = Map::new();
+        payload.insert("window".to_string(), json!(self.store.kind().name()));
+        payload.insert("start_ns".to_string(), json!(window.start_ns));
+        payload.insert("end_ns".to_string(), json!(window.end_ns));
+        if let Some(field) = self.store.group_by() {
+            payload.insert(field.to_string(), json!(window.group));
+        }
+        payload.extend(window.values);
+        if window.updated {
+            payload.insert("update".to_string(), json!(true));
+        }
+
+        self.emitted += 1;
+        let data = serde_json::to_vec(&Value::Object(payload)).expect("window result serializes to JSON");
+        let mut chunk = DataChunk::new(data)
+            .with_sequence(self.emitted)
+            .with_event_time_ns(window.end_ns)
+            .with_source_id(&self.name)
+            .with_attribute(WINDOW_START_ATTRIBUTE, window.start_ns as i64)
+            .with_attribute(WINDOW_END_ATTRIBUTE, window.end_ns as i64);
+        if let Some(group) = &window.group {
+            chunk = chunk.with_key(group);
+        }
+        if window.updated {
+            chunk = chunk.with_attribute(WINDOW_UPDATE_ATTRIBUTE, true);
+        }
+        chunk
+    }
+
+    fn emit(&mut self, windows: Vec<ClosedWindow>) -> Vec<DataChunk> {
+        if !windows.is_empty() {
+            debug!("[{}] Closed {} windows", self.name, windows.len());
+        }
+        windows.into_iter().map(|window| self.result_chunk(window)).collect()
+    }
+}
+
+#[async_trait]
+impl Processor for WindowProcessor {
+    async fn process(&mut self, chunk: &mut DataChunk) -> anyhow::Result<ProcessResult> {
+        // 进入时间单调递增，直接用数据块推进窗口
+        let mut closed = match self.store.time_domain() {
+            TimeDomain::IngestTime => self.store.advance(chunk.ingest_time_ns),
+            TimeDomain::EventTime => Vec::new(),
+        };
+
+        match self.store.add(chunk) {
+            Assignment::Added => {}
+            Assignment::Updated(updated) => closed.extend(updated),
+            Assignment::Late if self.late_policy == LatePolicy::SideOutput && closed.is_empty() => {
+                return Ok(ProcessResult::SideOutput(LATE_OUTPUT.to_string()));
+            }
+            Assignment::Late => {
+                debug!("[{}] Late chunk {} (watermark {})", self.name, chunk.sequence, self.store.watermark());
+                if self.late_policy == LatePolicy::SideOutput {
+                    // 同一次调用只能产生一种结果，关闭的窗口优先
+                    warn!("[{}] Dropping late chunk {} while emitting closed windows", self.name, chunk.sequence);
+                }
+            }
+        }
+        Ok(ProcessResult::Emit(self.emit(closed)))
+    }
+
+    // 事件时间窗口随管道水位线关闭
+    async fn on_watermark(&mut self, watermark_ns: u64) -> anyhow::Result<Vec<DataChunk>> {
+        if self.store.time_domain() != TimeDomain::EventTime {
+            return Ok(Vec::new());
+        }
+        let closed = self.store.advance(watermark_ns);
+        Ok(self.emit(closed))
+    }
+
+    // 数据源停止后关闭所有未结束的窗口
+    async fn flush(&mut self) -> anyhow::Result<Vec<DataChunk>> {
+        let closed = self.store.close_all();
+        Ok(self.emit(closed))
+    }
+
+    async fn close(&mut self) -> anyhow::Result<()> {
+        info!("[{}] Emitted {} window results, {} late chunks ({:?})",
+              self.name, self.emitted, self.store.late(), self.late_policy);
+        Ok(())
+    }
+
+    fn side_outputs(&self) -> Vec<String> {
+        match self.late_policy {
+            LatePolicy::SideOutput => vec![LATE_OUTPUT.to_string()],
+            _ => Vec::new(),
+        }
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    const S: u64 = 1_000_000_000;
+
+    fn chunk(time_s: u64) -> DataChunk {
+        DataChunk::new(b"x".to_vec()).with_event_time_ns(time_s * S)
+    }
+
+    fn window(policy: LatePolicy) -> WindowProcessor {
+        WindowProcessor::new(WindowKind::tumbling(Duration::from_secs(10))).with_late_policy(policy)
+    }
+
+    fn result(chunk: &DataChunk) -> Value {
+        serde_json::from_slice(&chunk.data).unwrap()
+    }
+
+    #[tokio::test]
+    async fn late_chunks_follow_the_late_policy() {
+        let mut dropping = window(LatePolicy::Drop);
+        let mut side = window(LatePolicy::SideOutput);
+        assert!(dropping.side_outputs().is_empty());
+        assert_eq!(side.side_outputs(), [LATE_OUTPUT]);
+
+        for processor in [&mut dropping, &mut side] {
+            assert!(matches!(processor.process(&mut chunk(3)).await.unwrap(), ProcessResult::Emit(out) if out.is_empty()));
+            let closed = processor.on_watermark(10 * S).await.unwrap();
+            assert_eq!(result(&closed[0])["count"], 1);
+        }
+
+        assert!(matches!(dropping.process(&mut chunk(4)).await.unwrap(), ProcessResult::Emit(out) if out.is_empty()));
+        assert!(matches!(side.process(&mut chunk(4)).await.unwrap(), ProcessResult::SideOutput(output) if output == LATE_OUTPUT));
+        assert_eq!((dropping.store.late(), side.store.late()), (1, 1));
+    }
+
+    #[tokio::test]
+    async fn allowed_lateness_reemits_updated_results() {
+        let mut processor = window(LatePolicy::Update { allowed_lateness: Duration::from_secs(5) });
+        processor.process(&mut chunk(3)).await.unwrap();
+        let closed = processor.on_watermark(12 * S).await.unwrap();
+        assert!(closed[0].attribute(WINDOW_UPDATE_ATTRIBUTE).is_none());
+
+        let ProcessResult::Emit(updated) = processor.process(&mut chunk(4)).await.unwrap() else {
+            panic!("expected the updated window result")
+        };
+        assert_eq!(result(&updated[0])["count"], 2);
+        assert_eq!(result(&updated[0])["update"], true);
+        assert!(updated[0].attribute(WINDOW_UPDATE_ATTRIBUTE).is_some());
+
+        // 超过允许迟到时间后按迟到数据丢弃
+        processor.on_watermark(15 * S).await.unwrap();
+        assert!(matches!(processor.process(&mut chunk(5)).await.unwrap(), ProcessResult::Emit(out) if out.is_empty()));
+        assert_eq!(processor.store.late(), 1);
+    }
+}
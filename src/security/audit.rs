@@ -0,0 +1,721 @@
+use super::container::read_full;
+use super::SecurityError;
+use crate::chunk::now_ns;
+use aes_gcm::aead::rand_core::RngCore;
+use aes_gcm::aead::OsRng;
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey, SIGNATURE_LENGTH};
+use sha2::{Digest, Sha256};
+use std::fmt;
+use std::fs::{File, OpenOptions};
+use std::io::{BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+use tracing::warn;
+
+// 审计日志文件的魔数
+pub const AUDIT_MAGIC: [u8; 8] = *b"PLCNAUD\0";
+// 审计日志格式版本
+pub const AUDIT_VERSION: u8 = 1;
+// 文件头长度：魔数(8) + 版本(1) + 哈希算法(1) + 保留(2) + 日志标识(16) + 签名公钥(32)
+pub const AUDIT_HEADER_LEN: usize = 60;
+// 哈希算法编号：1 为 SHA-256
+const HASH_SHA256: u8 = 1;
+const HASH_LEN: usize = 32;
+// 检查点签名的域分隔前缀，避免签名被挪作他用
+const CHECKPOINT_DOMAIN: &[u8] = b"pelican-audit-checkpoint-v1";
+
+const ENTRY_RECORD: u8 = 1;
+const ENTRY_CHECKPOINT: u8 = 2;
+// 条目公共字段：类型(1) + 编号(8) + 前一条目的哈希(32)
+const ENTRY_FIXED_LEN: usize = 1 + 8 + HASH_LEN;
+// 记录：序号(8) + 时间(8) + 数据
+const RECORD_FIXED_LEN: usize = ENTRY_FIXED_LEN + 16;
+// 检查点：时间(8) + 记录数(8) + 是否为关闭检查点(1) + 签名(64)
+const CHECKPOINT_LEN: usize = ENTRY_FIXED_LEN + 17 + SIGNATURE_LENGTH;
+const MAX_ENTRY_LEN: u32 = 256 * 1024 * 1024;
+
+// 每隔多少条记录写一个检查点的默认值
+pub const DEFAULT_CHECKPOINT_EVERY: u64 = 1000;
+
+// 审计日志文件头
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AuditHeader {
+    pub version: u8,
+    // 随机生成的日志标识
+    pub log_id: [u8; 16],
+    // 检查点的签名公钥，仅用于识别；校验时必须使用另行保存的可信公钥
+    pub public_key: [u8; 32],
+}
+
+impl AuditHeader {
+    pub fn to_bytes(&self) -> [u8; AUDIT_HEADER_LEN] {
+        let mut bytes = [0u8; AUDIT_HEADER_LEN];
+        bytes[..8].copy_from_slice(&AUDIT_MAGIC);
+        bytes[8] = self.version;
+        bytes[9] = HASH_SHA256;
+        bytes[12..28].copy_from_slice(&self.log_id);
+        bytes[28..].copy_from_slice(&self.public_key);
+        bytes
+    }
+
+    pub fn parse(bytes: &[u8]) -> Result<Self, SecurityError> {
+        if bytes.len() < AUDIT_HEADER_LEN || bytes[..8] != AUDIT_MAGIC {
+            return Err(SecurityError::FormatError("不是审计日志文件".to_string()));
+        }
+        if bytes[8] != AUDIT_VERSION {
+            return Err(SecurityError::FormatError(format!("不支持的审计日志版本: {}", bytes[8])));
+        }
+        if bytes[9] != HASH_SHA256 {
+            return Err(SecurityError::FormatError(format!("未知的哈希算法编号: {}", bytes[9])));
+        }
+        let mut log_id = [0u8; 16];
+        log_id.copy_from_slice(&bytes[12..28]);
+        let mut public_key = [0u8; 32];
+        public_key.copy_from_slice(&bytes[28..AUDIT_HEADER_LEN]);
+        Ok(Self { version: bytes[8], log_id, public_key })
+    }
+
+    // 哈希链的起点
+    fn genesis_hash(&self) -> [u8; HASH_LEN] {
+        Sha256::digest(self.to_bytes()).into()
+    }
+}
+
+// 校验发现的第一个问题；位置和编号都从 0 开始，记录和检查点共用编号
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AuditIssue {
+    // 条目中保存的前一条目哈希与实际不符：之前的条目被改动或替换
+    BrokenLink { index: u64 },
+    // 该位置出现了编号为 found 的条目，而编号为 position 的条目出现在文件后面：条目顺序被调换
+    Reordered { position: u64, found: u64 },
+    // 该位置出现了编号为 found 的条目，编号为 position 的条目不存在：条目被删除或重复
+    Missing { position: u64, found: u64 },
+    // 检查点签名无效
+    BadSignature { index: u64 },
+    // 条目无法解析
+    Malformed { position: u64, reason: String },
+    // 文件末尾有不完整的条目（写入时中断或被截断）
+    TruncatedTail { position: u64, bytes: u64 },
+    // 从该位置起的 records 条记录之后没有检查点：哈希链不带密钥，这些记录可能是截掉末尾后重新追加的
+    UnsignedTail { position: u64, records: u64 },
+}
+
+impl fmt::Display for AuditIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::BrokenLink { index } => {
+                write!(f, "哈希链在第 {} 条断开：之前的条目被改动或替换", index)
+            }
+            Self::Reordered { position, found } => {
+                write!(f, "第 {} 个位置出现了第 {} 条：条目顺序被调换", position, found)
+            }
+            Self::Missing { position, found } => {
+                write!(f, "第 {} 个位置出现了第 {} 条：条目被删除或重复", position, found)
+            }
+            Self::BadSignature { index } => write!(f, "第 {} 条检查点的签名无效", index),
+            Self::Malformed { position, reason } => write!(f, "第 {} 个位置的条目无法解析: {}", position, reason),
+            Self::TruncatedTail { position, bytes } => {
+                write!(f, "文件在第 {} 个位置被截断，末尾有 {} 字节不完整的条目", position, bytes)
+            }
+            Self::UnsignedTail { position, records } => {
+                write!(f, "第 {} 个位置之后的 {} 条记录没有检查点签名，无法确认不是伪造的", position, records)
+            }
+        }
+    }
+}
+
+// 审计日志的校验结果
+#[derive(Debug, Clone)]
+pub struct AuditReport {
+    pub header: AuditHeader,
+    // 校验通过的条目数（记录和检查点）
+    pub entries: u64,
+    pub records: u64,
+    pub checkpoints: u64,
+    // 最后一个有效检查点签名覆盖的记录数；之后的记录只有哈希链保护，可以被整条截掉而不被发现
+    pub signed_records: u64,
+    // 最后一个条目是关闭时写入的检查点；为 false 且没有问题时日志以普通检查点结束，之后的条目可能被整条截掉
+    pub sealed: bool,
+    // 最后一个校验通过的条目的哈希
+    pub head_hash: [u8; 32],
+    // 哈希链校验通过部分的字节数（含文件头），包括没有签名的末尾记录
+    pub valid_len: u64,
+    pub issue: Option<AuditIssue>,
+}
+
+impl AuditReport {
+    // 没有问题：全部记录都在检查点签名之内
+    pub fn is_intact(&self) -> bool {
+        self.issue.is_none()
+    }
+
+    // 最后一个检查点之后的记录数
+    pub fn unsigned_records(&self) -> u64 {
+        self.records - self.signed_records
+    }
+}
+
+// 防篡改的审计日志写入器：哈希链加定期签名的检查点
+//
+// 文件格式：文件头(60 字节) 之后是若干条目，每个条目为 条目长度(u32 LE) | 类型(u8) | 编号(u64 LE) | 前一条目的哈希(32) | 内容
+// 记录的内容为 序号(u64 LE) | 时间(u64 LE) | 数据；
+// 检查点的内容为 时间(u64 LE) | 记录数(u64 LE) | 是否为关闭检查点(u8) | Ed25519 签名(64)，签名覆盖检查点中签名之前的全部字节。
+// 第一个条目的"前一条目哈希"为文件头的 SHA-256，之后为前一条目（不含长度字段）的 SHA-256。
+// 检查点通过前一条目的哈希间接签名了之前的整条链，改动、删除、插入或调换任何已签名的条目都会被发现；
+// 最后一个检查点之后的记录只有哈希链保护，从末尾整条截掉无法发现，关闭时写入的检查点用来证明日志是完整结束的
+pub struct AuditLogWriter<W: Write> {
+    writer: W,
+    signing_key: SigningKey,
+    head_hash: [u8; HASH_LEN],
+    next_index: u64,
+    records: u64,
+    checkpoint_every: u64,
+    since_checkpoint: u64,
+}
+
+impl<W: Write> AuditLogWriter<W> {
+    // 写入新的文件头
+    pub fn new(mut writer: W, signing_key: SigningKey) -> Result<Self, SecurityError> {
+        let mut log_id = [0u8; 16];
+        OsRng.fill_bytes(&mut log_id);
+        let header = AuditHeader {
+            version: AUDIT_VERSION,
+            log_id,
+            public_key: signing_key.verifying_key().to_bytes(),
+        };
+        writer.write_all(&header.to_bytes())?;
+        Ok(Self {
+            writer,
+            signing_key,
+            head_hash: header.genesis_hash(),
+            next_index: 0,
+            records: 0,
+            checkpoint_every: DEFAULT_CHECKPOINT_EVERY,
+            since_checkpoint: 0,
+        })
+    }
+
+    // 接着已校验的日志写入；report 必须没有问题，全部记录都已签名，且日志的公钥与 signing_key 对应。
+    // 接着写入后的检查点会签名之前的整条链，没有签名的末尾记录无法确认来源，不能被签进去
+    pub fn resume(writer: W, signing_key: SigningKey, report: &AuditReport) -> Result<Self, SecurityError> {
+        if let Some(issue) = &report.issue {
+            return Err(SecurityError::IntegrityError(format!("审计日志已损坏，不能接着写入: {}", issue)));
+        }
+        if report.unsigned_records() > 0 {
+            return Err(SecurityError::IntegrityError(format!(
+                "审计日志末尾有 {} 条没有检查点签名的记录，不能接着写入；请用 pelican verify-audit 检查后移走该文件",
+                report.unsigned_records()
+            )));
+        }
+        if report.header.public_key != signing_key.verifying_key().to_bytes() {
+            return Err(SecurityError::KeyError("签名密钥与审计日志的公钥不一致".to_string()));
+        }
+        Ok(Self {
+            writer,
+            signing_key,
+            head_hash: report.head_hash,
+            next_index: report.entries,
+            records: report.records,
+            checkpoint_every: DEFAULT_CHECKPOINT_EVERY,
+            since_checkpoint: 0,
+        })
+    }
+
+    // 每隔 records 条记录自动写一个检查点，0 表示只在调用 checkpoint/seal 时写
+    pub fn with_checkpoint_every(mut self, records: u64) -> Self {
+        self.checkpoint_every = records;
+        self
+    }
+
+    pub fn verifying_key(&self) -> VerifyingKey {
+        self.signing_key.verifying_key()
+    }
+
+    pub fn head_hash(&self) -> [u8; 32] {
+        self.head_hash
+    }
+
+    // 已写入（含接着写入前已有）的记录数，不含检查点
+    pub fn record_count(&self) -> u64 {
+        self.records
+    }
+
+    // 上一个检查点之后写入的记录数
+    pub fn unsigned_records(&self) -> u64 {
+        self.since_checkpoint
+    }
+
+    pub fn append(&mut self, sequence: u64, timestamp_ns: u64, data: &[u8]) -> Result<(), SecurityError> {
+        let mut entry = self.entry_prefix(ENTRY_RECORD, RECORD_FIXED_LEN + data.len());
+        entry.extend_from_slice(&sequence.to_le_bytes());
+        entry.extend_from_slice(&timestamp_ns.to_le_bytes());
+        entry.extend_from_slice(data);
+        self.write_entry(&entry)?;
+        self.records += 1;
+        self.since_checkpoint += 1;
+        if self.checkpoint_every > 0 && self.since_checkpoint >= self.checkpoint_every {
+            self.checkpoint()?;
+        }
+        Ok(())
+    }
+
+    // 写入签名检查点，覆盖到目前为止的全部条目
+    pub fn checkpoint(&mut self) -> Result<(), SecurityError> {
+        self.write_checkpoint(false)
+    }
+
+    // 写入关闭检查点，表示日志在此正常结束；之后仍可以接着写入
+    pub fn seal(&mut self) -> Result<(), SecurityError> {
+        self.write_checkpoint(true)
+    }
+
+    pub fn flush(&mut self) -> Result<(), SecurityError> {
+        self.writer.flush()?;
+        Ok(())
+    }
+
+    pub fn get_ref(&self) -> &W {
+        &self.writer
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+
+    fn write_checkpoint(&mut self, closing: bool) -> Result<(), SecurityError> {
+        let mut entry = self.entry_prefix(ENTRY_CHECKPOINT, CHECKPOINT_LEN);
+        entry.extend_from_slice(&now_ns().to_le_bytes());
+        entry.extend_from_slice(&self.records.to_le_bytes());
+        entry.push(closing as u8);
+        let signature = self.signing_key.sign(&checkpoint_message(&entry));
+        entry.extend_from_slice(&signature.to_bytes());
+        self.write_entry(&entry)?;
+        self.since_checkpoint = 0;
+        Ok(())
+    }
+
+    fn entry_prefix(&self, kind: u8, len: usize) -> Vec<u8> {
+        let mut entry = Vec::with_capacity(len);
+        entry.push(kind);
+        entry.extend_from_slice(&self.next_index.to_le_bytes());
+        entry.extend_from_slice(&self.head_hash);
+        entry
+    }
+
+    fn write_entry(&mut self, entry: &[u8]) -> Result<(), SecurityError> {
+        let len = u32::try_from(entry.len())
+            .ok()
+            .filter(|len| *len <= MAX_ENTRY_LEN)
+            .ok_or_else(|| SecurityError::FormatError(format!("审计记录过大: {} 字节", entry.len())))?;
+        self.writer.write_all(&len.to_le_bytes())?;
+        self.writer.write_all(entry)?;
+        self.head_hash = Sha256::digest(entry).into();
+        self.next_index += 1;
+        Ok(())
+    }
+}
+
+impl AuditLogWriter<BufWriter<File>> {
+    // 打开文件：不存在或为空时新建，否则先校验整个日志再接着写入。
+    // 末尾不完整的条目（例如写入时进程退出）会被截掉并记录警告；其他问题，包括最后一个检查点之后还有记录，
+    // 都返回错误，不会在被篡改的日志后追加
+    pub fn open(path: impl AsRef<Path>, signing_key: SigningKey) -> Result<Self, SecurityError> {
+        let path = path.as_ref();
+        let mut file = OpenOptions::new().read(true).write(true).create(true).truncate(false).open(path)?;
+        if file.metadata()?.len() == 0 {
+            return Self::new(BufWriter::new(file), signing_key);
+        }
+
+        let verifier = AuditLogVerifier::new(signing_key.verifying_key());
+        let mut report = verifier.verify(BufReader::new(&mut file))?;
+        if let Some(AuditIssue::TruncatedTail { bytes, .. }) = report.issue {
+            warn!("审计日志 {} 末尾有 {} 字节不完整的条目，已截断", path.display(), bytes);
+            file.set_len(report.valid_len)?;
+            report.issue = None;
+        }
+        // 截掉不完整的条目后仍由 resume 检查没有签名的记录
+        file.seek(SeekFrom::Start(report.valid_len))?;
+        Self::resume(BufWriter::new(file), signing_key, &report)
+    }
+}
+
+// 审计日志校验器：按可信公钥检查哈希链和检查点签名，报告发现的第一个问题
+pub struct AuditLogVerifier {
+    verifying_key: VerifyingKey,
+}
+
+impl AuditLogVerifier {
+    pub fn new(verifying_key: VerifyingKey) -> Self {
+        Self { verifying_key }
+    }
+
+    // 32 字节的 Ed25519 公钥
+    pub fn from_public_key(public_key: &[u8]) -> Result<Self, SecurityError> {
+        let bytes = <[u8; 32]>::try_from(public_key)
+            .map_err(|_| SecurityError::KeyError(format!("公钥必须为 32 字节，实际为 {} 字节", public_key.len())))?;
+        let verifying_key =
+            VerifyingKey::from_bytes(&bytes).map_err(|e| SecurityError::KeyError(format!("无效的公钥: {}", e)))?;
+        Ok(Self::new(verifying_key))
+    }
+
+    pub fn verify_file(&self, path: impl AsRef<Path>) -> Result<AuditReport, SecurityError> {
+        self.verify(BufReader::new(File::open(path)?))
+    }
+
+    // 文件头无效或公钥不一致时返回错误；条目中的问题记录在 AuditReport::issue 中。
+    // 哈希链完整但最后一个检查点之后还有记录时报告 UnsignedTail
+    pub fn verify<R: Read>(&self, mut reader: R) -> Result<AuditReport, SecurityError> {
+        let mut header_bytes = [0u8; AUDIT_HEADER_LEN];
+        if read_full(&mut reader, &mut header_bytes)? < AUDIT_HEADER_LEN {
+            return Err(SecurityError::FormatError("审计日志文件头不完整".to_string()));
+        }
+        let header = AuditHeader::parse(&header_bytes)?;
+        if header.public_key != self.verifying_key.to_bytes() {
+            return Err(SecurityError::KeyError("审计日志的签名公钥与提供的公钥不一致".to_string()));
+        }
+
+        let mut report = AuditReport {
+            header,
+            entries: 0,
+            records: 0,
+            checkpoints: 0,
+            signed_records: 0,
+            sealed: false,
+            head_hash: header.genesis_hash(),
+            valid_len: AUDIT_HEADER_LEN as u64,
+            issue: None,
+        };
+        // 最后一个检查点之后第一个条目的位置
+        let mut unsigned_from = 0;
+        loop {
+            let position = report.entries;
+            let entry = match read_entry(&mut reader)? {
+                EntryRead::End => break,
+                EntryRead::Truncated(bytes) => {
+                    report.issue = Some(AuditIssue::TruncatedTail { position, bytes });
+                    break;
+                }
+                EntryRead::Malformed(reason) => {
+                    report.issue = Some(AuditIssue::Malformed { position, reason });
+                    break;
+                }
+                EntryRead::Entry(entry) => entry,
+            };
+
+            let index = u64::from_le_bytes(entry[1..9].try_into().expect("8 字节"));
+            if index != position {
+                report.issue = Some(if index > position && index_follows(&mut reader, position)? {
+                    AuditIssue::Reordered { position, found: index }
+                } else {
+                    AuditIssue::Missing { position, found: index }
+                });
+                break;
+            }
+            if entry[9..ENTRY_FIXED_LEN] != report.head_hash {
+                report.issue = Some(AuditIssue::BrokenLink { index });
+                break;
+            }
+            if let Err(issue) = self.check_entry(&entry, index, &mut report) {
+                report.issue = Some(issue);
+                break;
+            }
+
+            report.head_hash = Sha256::digest(&entry).into();
+            report.entries += 1;
+            report.valid_len += 4 + entry.len() as u64;
+            if report.unsigned_records() == 0 {
+                unsigned_from = report.entries;
+            }
+        }
+        if report.issue.is_none() && report.unsigned_records() > 0 {
+            report.issue = Some(AuditIssue::UnsignedTail { position: unsigned_from, records: report.unsigned_records() });
+        }
+        Ok(report)
+    }
+
+    // 检查条目内容，更新记录数和检查点状态
+    fn check_entry(&self, entry: &[u8], index: u64, report: &mut AuditReport) -> Result<(), AuditIssue> {
+        let malformed = |reason: String| AuditIssue::Malformed { position: index, reason };
+        match entry[0] {
+            ENTRY_RECORD if entry.len() >= RECORD_FIXED_LEN => {
+                report.records += 1;
+                report.sealed = false;
+            }
+            ENTRY_CHECKPOINT if entry.len() == CHECKPOINT_LEN => {
+                let (message, signature) = entry.split_at(CHECKPOINT_LEN - SIGNATURE_LENGTH);
+                let signature = Signature::from_slice(signature).map_err(|e| malformed(e.to_string()))?;
+                self.verifying_key
+                    .verify(&checkpoint_message(message), &signature)
+                    .map_err(|_| AuditIssue::BadSignature { index })?;
+                let offset = ENTRY_FIXED_LEN + 8;
+                let record_count = u64::from_le_bytes(entry[offset..offset + 8].try_into().expect("8 字节"));
+                if record_count != report.records {
+                    return Err(malformed(format!(
+                        "检查点记录数为 {}，实际为 {}",
+                        record_count, report.records
+                    )));
+                }
+                report.checkpoints += 1;
+                report.signed_records = report.records;
+                report.sealed = entry[offset + 8] != 0;
+            }
+            ENTRY_RECORD | ENTRY_CHECKPOINT => return Err(malformed(format!("条目长度无效: {}", entry.len()))),
+            kind => return Err(malformed(format!("未知的条目类型: {}", kind))),
+        }
+        Ok(())
+    }
+}
+
+enum EntryRead {
+    End,
+    Entry(Vec<u8>),
+    // 不完整条目的字节数（含长度字段）
+    Truncated(u64),
+    Malformed(String),
+}
+
+fn read_entry(reader: &mut impl Read) -> Result<EntryRead, SecurityError> {
+    let mut len_bytes = [0u8; 4];
+    match read_full(reader, &mut len_bytes)? {
+        0 => return Ok(EntryRead::End),
+        4 => {}
+        n => return Ok(EntryRead::Truncated(n as u64)),
+    }
+    let len = u32::from_le_bytes(len_bytes);
+    if !(ENTRY_FIXED_LEN as u32..=MAX_ENTRY_LEN).contains(&len) {
+        return Ok(EntryRead::Malformed(format!("条目长度无效: {}", len)));
+    }
+    let mut entry = vec![0u8; len as usize];
+    let read = read_full(reader, &mut entry)?;
+    if read < entry.len() {
+        return Ok(EntryRead::Truncated(4 + read as u64));
+    }
+    Ok(EntryRead::Entry(entry))
+}
+
+// 在剩余的条目中查找编号为 index 的条目，用来区分条目被调换顺序还是被删除
+fn index_follows(reader: &mut impl Read, index: u64) -> Result<bool, SecurityError> {
+    while let EntryRead::Entry(entry) = read_entry(reader)? {
+        if u64::from_le_bytes(entry[1..9].try_into().expect("8 字节")) == index {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+fn checkpoint_message(entry_without_signature: &[u8]) -> Vec<u8> {
+    let mut message = Vec::with_capacity(CHECKPOINT_DOMAIN.len() + entry_without_signature.len());
+    message.extend_from_slice(CHECKPOINT_DOMAIN);
+    message.extend_from_slice(entry_without_signature);
+    message
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn signing_key(seed: u8) -> SigningKey {
+        SigningKey::from_bytes(&[seed; 32])
+    }
+
+    // 10 条记录，每 4 条一个检查点，最后关闭：
+    // 记录 0-3、检查点 4、记录 5-8、检查点 9、记录 10-11、关闭检查点 12
+    fn sample_log() -> Vec<u8> {
+        let mut writer = AuditLogWriter::new(Vec::new(), signing_key(1)).unwrap().with_checkpoint_every(4);
+        for sequence in 0..10 {
+            writer.append(sequence, 1_000 + sequence, format!("event {}", sequence).as_bytes()).unwrap();
+        }
+        writer.seal().unwrap();
+        writer.into_inner()
+    }
+
+    // 拆出文件头和各条目（含长度字段）
+    fn split(bytes: &[u8]) -> (Vec<u8>, Vec<Vec<u8>>) {
+        let mut entries = Vec::new();
+        let mut offset = AUDIT_HEADER_LEN;
+        while offset < bytes.len() {
+            let len = u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap()) as usize;
+            entries.push(bytes[offset..offset + 4 + len].to_vec());
+            offset += 4 + len;
+        }
+        (bytes[..AUDIT_HEADER_LEN].to_vec(), entries)
+    }
+
+    fn join(header: &[u8], entries: &[Vec<u8>]) -> Vec<u8> {
+        let mut bytes = header.to_vec();
+        entries.iter().for_each(|entry| bytes.extend_from_slice(entry));
+        bytes
+    }
+
+    fn verify(bytes: &[u8]) -> AuditReport {
+        AuditLogVerifier::new(signing_key(1).verifying_key()).verify(bytes).unwrap()
+    }
+
+    #[test]
+    fn intact_log_verifies() {
+        let bytes = sample_log();
+        let report = verify(&bytes);
+        assert!(report.is_intact(), "{:?}", report.issue);
+        assert_eq!((report.entries, report.records, report.checkpoints), (13, 10, 3));
+        assert_eq!(report.signed_records, 10);
+        assert!(report.sealed);
+        assert_eq!(report.valid_len, bytes.len() as u64);
+    }
+
+    #[test]
+    fn detects_modified_record() {
+        let (header, mut entries) = split(&sample_log());
+        let last = entries[2].len() - 1;
+        entries[2][last] ^= 1;
+        assert_eq!(verify(&join(&header, &entries)).issue, Some(AuditIssue::BrokenLink { index: 3 }));
+    }
+
+    #[test]
+    fn detects_missing_and_duplicated_entries() {
+        let (header, entries) = split(&sample_log());
+        let mut deleted = entries.clone();
+        deleted.remove(3);
+        let report = verify(&join(&header, &deleted));
+        assert_eq!(report.issue, Some(AuditIssue::Missing { position: 3, found: 4 }));
+        assert_eq!(report.entries, 3);
+
+        let mut duplicated = entries.clone();
+        duplicated.insert(3, entries[2].clone());
+        assert_eq!(verify(&join(&header, &duplicated)).issue, Some(AuditIssue::Missing { position: 3, found: 2 }));
+    }
+
+    #[test]
+    fn detects_reordered_entries() {
+        let (header, mut entries) = split(&sample_log());
+        entries.swap(2, 3);
+        assert_eq!(verify(&join(&header, &entries)).issue, Some(AuditIssue::Reordered { position: 2, found: 3 }));
+    }
+
+    #[test]
+    fn detects_forged_checkpoints() {
+        let (header, mut entries) = split(&sample_log());
+        // 把关闭检查点改成普通检查点
+        let closing = entries[12].len() - SIGNATURE_LENGTH - 1;
+        entries[12][closing] = 0;
+        assert_eq!(verify(&join(&header, &entries)).issue, Some(AuditIssue::BadSignature { index: 12 }));
+
+        // 用其他密钥重新签名整个日志，并替换文件头中的公钥
+        let mut forger = AuditLogWriter::new(Vec::new(), signing_key(2)).unwrap();
+        forger.append(0, 0, b"forged").unwrap();
+        forger.seal().unwrap();
+        let forged = forger.into_inner();
+        let verifier = AuditLogVerifier::new(signing_key(1).verifying_key());
+        assert!(matches!(verifier.verify(forged.as_slice()), Err(SecurityError::KeyError(_))));
+
+        // 只把文件头中的公钥换成可信公钥：哈希链起点随文件头改变
+        let mut with_trusted_header = forged.clone();
+        with_trusted_header[28..AUDIT_HEADER_LEN].copy_from_slice(&signing_key(1).verifying_key().to_bytes());
+        let report = verifier.verify(with_trusted_header.as_slice()).unwrap();
+        assert_eq!(report.issue, Some(AuditIssue::BrokenLink { index: 0 }));
+
+        // 同时重建哈希链后，检查点签名仍无法通过
+        let (header, mut entries) = split(&with_trusted_header);
+        let mut head: [u8; HASH_LEN] = Sha256::digest(&header).into();
+        for entry in &mut entries {
+            entry[4 + 9..4 + ENTRY_FIXED_LEN].copy_from_slice(&head);
+            head = Sha256::digest(&entry[4..]).into();
+        }
+        let report = verifier.verify(join(&header, &entries).as_slice()).unwrap();
+        assert_eq!(report.issue, Some(AuditIssue::BadSignature { index: 1 }));
+    }
+
+    // 截到第 keep 个条目，再用（不需要密钥的）哈希链追加伪造的记录
+    fn append_unsigned(bytes: &[u8], keep: usize, forged: u64) -> Vec<u8> {
+        let (header, entries) = split(bytes);
+        let mut bytes = join(&header, &entries[..keep]);
+        let mut head: [u8; HASH_LEN] = match entries[..keep].last() {
+            Some(entry) => Sha256::digest(&entry[4..]).into(),
+            None => Sha256::digest(&header).into(),
+        };
+        for (offset, sequence) in (0..forged).enumerate() {
+            let mut entry = vec![ENTRY_RECORD];
+            entry.extend_from_slice(&((keep + offset) as u64).to_le_bytes());
+            entry.extend_from_slice(&head);
+            entry.extend_from_slice(&sequence.to_le_bytes());
+            entry.extend_from_slice(&0u64.to_le_bytes());
+            entry.extend_from_slice(b"forged");
+            head = Sha256::digest(&entry).into();
+            bytes.extend_from_slice(&(entry.len() as u32).to_le_bytes());
+            bytes.extend_from_slice(&entry);
+        }
+        bytes
+    }
+
+    #[test]
+    fn unsigned_tail_is_reported() {
+        // 截到第二个检查点后追加记录：哈希链正确，但没有检查点签名
+        let report = verify(&append_unsigned(&sample_log(), 10, 3));
+        assert_eq!(report.issue, Some(AuditIssue::UnsignedTail { position: 10, records: 3 }));
+        assert!(!report.is_intact() && !report.sealed);
+        assert_eq!((report.records, report.signed_records, report.unsigned_records()), (11, 8, 3));
+
+        // 没有任何检查点的日志
+        let report = verify(&append_unsigned(&sample_log(), 0, 2));
+        assert_eq!(report.issue, Some(AuditIssue::UnsignedTail { position: 0, records: 2 }));
+        assert_eq!(report.checkpoints, 0);
+
+        // 以普通检查点结束：没有问题，但没有关闭
+        let bytes = sample_log();
+        let report = verify(&append_unsigned(&bytes, 10, 0));
+        assert!(report.is_intact() && !report.sealed);
+
+        let report = verify(&bytes[..bytes.len() - 10]);
+        assert!(matches!(report.issue, Some(AuditIssue::TruncatedTail { position: 12, .. })));
+        assert!(!report.sealed);
+    }
+
+    #[test]
+    fn resume_rejects_damaged_log_or_other_key() {
+        let (header, mut entries) = split(&sample_log());
+        entries.swap(5, 6);
+        let damaged = verify(&join(&header, &entries));
+        assert!(matches!(
+            AuditLogWriter::resume(Vec::new(), signing_key(1), &damaged),
+            Err(SecurityError::IntegrityError(_))
+        ));
+
+        let report = verify(&sample_log());
+        assert!(matches!(AuditLogWriter::resume(Vec::new(), signing_key(3), &report), Err(SecurityError::KeyError(_))));
+
+        // 去掉 issue 也不能把没有签名的记录签进新的检查点
+        let mut unsigned = verify(&append_unsigned(&sample_log(), 10, 1));
+        unsigned.issue = None;
+        assert!(matches!(
+            AuditLogWriter::resume(Vec::new(), signing_key(1), &unsigned),
+            Err(SecurityError::IntegrityError(_))
+        ));
+    }
+
+    #[test]
+    fn open_refuses_unsigned_tail() {
+        let path = std::env::temp_dir().join(format!("pelican-audit-unsigned-{}.log", std::process::id()));
+        let bytes = sample_log();
+        let forged = append_unsigned(&bytes, 10, 2);
+        std::fs::write(&path, &forged).unwrap();
+        assert!(matches!(AuditLogWriter::open(&path, signing_key(1)), Err(SecurityError::IntegrityError(_))));
+        // 文件没有被改动
+        assert_eq!(std::fs::read(&path).unwrap(), forged);
+
+        // 被截断的末尾条目仍会被截掉，之后按没有签名的记录拒绝
+        let mut torn = append_unsigned(&bytes, 10, 1);
+        torn.extend_from_slice(&[9, 0, 0]);
+        std::fs::write(&path, &torn).unwrap();
+        assert!(matches!(AuditLogWriter::open(&path, signing_key(1)), Err(SecurityError::IntegrityError(_))));
+
+        // 正常关闭的日志可以接着写入
+        std::fs::write(&path, &bytes).unwrap();
+        let mut writer = AuditLogWriter::open(&path, signing_key(1)).unwrap();
+        writer.append(10, 2_000, b"next run").unwrap();
+        writer.seal().unwrap();
+        writer.flush().unwrap();
+        drop(writer);
+        let report = verify(&std::fs::read(&path).unwrap());
+        assert!(report.is_intact() && report.sealed);
+        assert_eq!(report.records, 11);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,652 @@
+use super::{DataEncryptor, DataIntegrityChecker, IntegrityAlgorithm, SecurityConfig, SecurityError};
+use aes_gcm::aead::rand_core::RngCore;
+use aes_gcm::aead::OsRng;
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+use tracing::warn;
+
+// 安全容器文件的魔数
+pub const CONTAINER_MAGIC: [u8; 8] = *b"PLCNSEC\0";
+// 安全容器格式版本
+pub const CONTAINER_VERSION: u8 = 1;
+// 文件头长度：魔数(8) + 版本(1) + 标志(1) + 加密算法(1) + 完整性算法(1) + 密钥编号(4) + 文件标识(16)
+pub const CONTAINER_HEADER_LEN: usize = 32;
+// 单条记录帧的上限，防止损坏的长度字段导致分配过大的缓冲区
+const MAX_FRAME_LEN: u32 = 256 * 1024 * 1024;
+// 帧内固定字段：序号(8) + 时间(8) + 负载长度(4)
+const FRAME_FIXED_LEN: usize = 20;
+
+const FLAG_ENCRYPTED: u8 = 0b01;
+const FLAG_INTEGRITY: u8 = 0b10;
+
+// 安全容器文件头
+//
+// 加密算法和密钥编号是创建文件时的设置；每条记录的密文自带算法和密钥编号，轮换密钥后写入的记录同样可以解密
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ContainerHeader {
+    pub version: u8,
+    pub encrypted: bool,
+    pub integrity: bool,
+    // 未加密时为 0
+    pub encryption_algorithm: u8,
+    // 未启用完整性检查时为 0
+    pub integrity_algorithm: u8,
+    pub key_id: u32,
+    // 随机生成的文件标识，参与每条记录的认证，记录无法被搬到其他文件中
+    pub file_id: [u8; 16],
+}
+
+impl ContainerHeader {
+    pub fn to_bytes(&self) -> [u8; CONTAINER_HEADER_LEN] {
+        let mut bytes = [0u8; CONTAINER_HEADER_LEN];
+        bytes[..8].copy_from_slice(&CONTAINER_MAGIC);
+        bytes[8] = self.version;
+        bytes[9] = (if self.encrypted { FLAG_ENCRYPTED } else { 0 }) | (if self.integrity { FLAG_INTEGRITY } else { 0 });
+        bytes[10] = self.encryption_algorithm;
+        bytes[11] = self.integrity_algorithm;
+        bytes[12..16].copy_from_slice(&self.key_id.to_be_bytes());
+        bytes[16..].copy_from_slice(&self.file_id);
+        bytes
+    }
+
+    pub fn parse(bytes: &[u8]) -> Result<Self, SecurityError> {
+        if bytes.len() < CONTAINER_HEADER_LEN || bytes[..8] != CONTAINER_MAGIC {
+            return Err(SecurityError::FormatError("不是安全容器文件（缺少文件头，可能是旧版本格式）".to_string()));
+        }
+        if bytes[8] != CONTAINER_VERSION {
+            return Err(SecurityError::FormatError(format!("不支持的容器版本: {}", bytes[8])));
+        }
+        let flags = bytes[9];
+        if flags & !(FLAG_ENCRYPTED | FLAG_INTEGRITY) != 0 {
+            return Err(SecurityError::FormatError(format!("未知的文件头标志: {:#04x}", flags)));
+        }
+        let mut file_id = [0u8; 16];
+        file_id.copy_from_slice(&bytes[16..CONTAINER_HEADER_LEN]);
+        Ok(Self {
+            version: bytes[8],
+            encrypted: flags & FLAG_ENCRYPTED != 0,
+            integrity: flags & FLAG_INTEGRITY != 0,
+            encryption_algorithm: bytes[10],
+            integrity_algorithm: bytes[11],
+            key_id: u32::from_be_bytes([bytes[12], bytes[13], bytes[14], bytes[15]]),
+            file_id,
+        })
+    }
+}
+
+// 从安全容器读出的一条记录
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SecureRecord {
+    // 在文件中的位置，从 0 开始
+    pub index: u64,
+    pub sequence: u64,
+    pub timestamp_ns: u64,
+    // 解密后的数据
+    pub data: Vec<u8>,
+}
+
+// 安全容器写入器
+//
+// 文件格式：文件头(32 字节) 之后是若干记录帧，每帧为
+// 帧长度(u32 LE) | 序号(u64 LE) | 时间(u64 LE) | 负载长度(u32 LE) | 负载 | [完整性算法编号(u8) | 校验值]
+// 加密时负载为 DataEncryptor 的密文，附加数据绑定记录位置、文件头、序号和时间；
+// 校验值覆盖文件头、记录位置和帧中校验值之前的全部字节。记录被改动、删除、调换顺序或搬到其他文件都能被发现，
+// 但末尾整条记录被截掉无法从文件本身发现
+pub struct SecureFileWriter<W: Write> {
+    writer: W,
+    header: [u8; CONTAINER_HEADER_LEN],
+    encryptor: Option<DataEncryptor>,
+    integrity_checker: Option<DataIntegrityChecker>,
+    next_index: u64,
+}
+
+impl<W: Write> SecureFileWriter<W> {
+    // 写入新的文件头
+    pub fn new(mut writer: W, config: &SecurityConfig) -> Result<Self, SecurityError> {
+        let (encryptor, integrity_checker) = components(config)?;
+        let key_id = match &encryptor {
+            Some(encryptor) => encryptor.keyring().active()?.0,
+            None => 0,
+        };
+        let mut file_id = [0u8; 16];
+        OsRng.fill_bytes(&mut file_id);
+        let header = ContainerHeader {
+            version: CONTAINER_VERSION,
+            encrypted: encryptor.is_some(),
+            integrity: integrity_checker.is_some(),
+            encryption_algorithm: encryptor.as_ref().map_or(0, |e| e.algorithm().id()),
+            integrity_algorithm: integrity_checker.as_ref().map_or(0, |c| c.algorithm().id()),
+            key_id,
+            file_id,
+        }
+        .to_bytes();
+        writer.write_all(&header)?;
+        Ok(Self { writer, header, encryptor, integrity_checker, next_index: 0 })
+    }
+
+    // 接着已有文件写入：header 为文件原有的文件头，next_index 为已有的记录数
+    pub fn resume(
+        writer: W,
+        header: ContainerHeader,
+        next_index: u64,
+        config: &SecurityConfig,
+    ) -> Result<Self, SecurityError> {
+        let (encryptor, integrity_checker) = components(config)?;
+        if header.encrypted != encryptor.is_some() || header.integrity != integrity_checker.is_some() {
+            return Err(SecurityError::FormatError(format!(
+                "已有文件的设置（加密: {}，完整性检查: {}）与当前配置不一致",
+                header.encrypted, header.integrity
+            )));
+        }
+        if let Some(checker) = &integrity_checker {
+            let algorithm = header_integrity_algorithm(&header)?;
+            if algorithm != checker.algorithm() {
+                return Err(SecurityError::FormatError(format!(
+                    "已有文件的完整性算法 {} 与当前配置的 {} 不一致",
+                    algorithm,
+                    checker.algorithm()
+                )));
+            }
+        }
+        Ok(Self { writer, header: header.to_bytes(), encryptor, integrity_checker, next_index })
+    }
+
+    pub fn header(&self) -> ContainerHeader {
+        ContainerHeader::parse(&self.header).expect("文件头由写入器生成")
+    }
+
+    // 已写入（含追加前已有）的记录数
+    pub fn record_count(&self) -> u64 {
+        self.next_index
+    }
+
+    pub fn write_record(&mut self, sequence: u64, timestamp_ns: u64, data: &[u8]) -> Result<(), SecurityError> {
+        let index = self.next_index;
+        let body = match &self.encryptor {
+            Some(encryptor) => {
+                encryptor.encrypt_with_context(data, index, &record_context(&self.header, sequence, timestamp_ns))?
+            }
+            None => data.to_vec(),
+        };
+        let too_large = || SecurityError::FormatError(format!("记录过大: {} 字节", body.len()));
+        let body_len = u32::try_from(body.len()).map_err(|_| too_large())?;
+
+        let mut frame = Vec::with_capacity(FRAME_FIXED_LEN + body.len() + 33);
+        frame.extend_from_slice(&sequence.to_le_bytes());
+        frame.extend_from_slice(&timestamp_ns.to_le_bytes());
+        frame.extend_from_slice(&body_len.to_le_bytes());
+        frame.extend_from_slice(&body);
+        if let Some(checker) = &self.integrity_checker {
+            let tag = checker.calculate_checksum(&tag_input(&self.header, index, &frame));
+            frame.push(checker.algorithm().id());
+            frame.extend_from_slice(&tag);
+        }
+        if frame.len() > MAX_FRAME_LEN as usize {
+            return Err(too_large());
+        }
+
+        self.writer.write_all(&(frame.len() as u32).to_le_bytes())?;
+        self.writer.write_all(&frame)?;
+        self.next_index += 1;
+        Ok(())
+    }
+
+    pub fn flush(&mut self) -> Result<(), SecurityError> {
+        self.writer.flush()?;
+        Ok(())
+    }
+
+    pub fn get_ref(&self) -> &W {
+        &self.writer
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+impl SecureFileWriter<BufWriter<File>> {
+    // 打开文件：不存在或为空时新建，否则校验文件头后接着写入。
+    // 末尾不完整的记录（例如写入时进程退出）会被截掉并记录警告
+    pub fn open(path: impl AsRef<Path>, config: &SecurityConfig) -> Result<Self, SecurityError> {
+        let path = path.as_ref();
+        let mut file = OpenOptions::new().read(true).write(true).create(true).truncate(false).open(path)?;
+        if file.metadata()?.len() == 0 {
+            return Self::new(BufWriter::new(file), config);
+        }
+
+        let mut reader = BufReader::new(&mut file);
+        let header = read_header(&mut reader)?;
+        let mut records = 0u64;
+        let mut valid_len = CONTAINER_HEADER_LEN as u64;
+        while let Some(frame_len) = skip_frame(&mut reader, records)? {
+            records += 1;
+            valid_len += 4 + frame_len as u64;
+        }
+        drop(reader);
+        let file_len = file.metadata()?.len();
+        if file_len > valid_len {
+            warn!(
+                "安全容器 {} 末尾有 {} 字节不完整的记录，已截断",
+                path.display(),
+                file_len - valid_len
+            );
+            file.set_len(valid_len)?;
+        }
+        file.seek(SeekFrom::Start(valid_len))?;
+        Self::resume(BufWriter::new(file), header, records, config)
+    }
+}
+
+// 安全容器读取器：逐条解密并校验记录
+//
+// 只读取容器格式，没有容器魔数的文件返回 FormatError；容器格式之前的旧文件需要显式使用 LegacyFileReader。
+// 遇到错误（校验失败、解密失败、文件被截断）后迭代结束，错误中带有出错记录的位置
+pub struct SecureFileReader<R: Read> {
+    reader: R,
+    header_bytes: [u8; CONTAINER_HEADER_LEN],
+    header: ContainerHeader,
+    encryptor: Option<DataEncryptor>,
+    integrity_checker: Option<DataIntegrityChecker>,
+    next_index: u64,
+    finished: bool,
+}
+
+impl<R: Read> SecureFileReader<R> {
+    // 读取并检查文件头；文件加密时 config 必须提供密钥，启用完整性检查时使用 config 的完整性密钥
+    pub fn new(mut reader: R, config: &SecurityConfig) -> Result<Self, SecurityError> {
+        let mut header_bytes = [0u8; CONTAINER_HEADER_LEN];
+        let read = read_full(&mut reader, &mut header_bytes)?;
+        if read < CONTAINER_MAGIC.len() || header_bytes[..CONTAINER_MAGIC.len()] != CONTAINER_MAGIC {
+            return Err(SecurityError::FormatError(
+                "不是安全容器文件（缺少文件头）；容器格式之前的旧文件请使用 LegacyFileReader 读取".to_string(),
+            ));
+        }
+        if read < CONTAINER_HEADER_LEN {
+            return Err(SecurityError::FormatError("文件头不完整".to_string()));
+        }
+
+        let header = ContainerHeader::parse(&header_bytes)?;
+        // 文件头的标志不受校验值保护，配置要求的保护文件中必须都有，防止去掉标志后把记录换成明文
+        if config.enable_encryption && !header.encrypted {
+            return Err(SecurityError::IntegrityError("配置要求加密，但文件头标记为未加密".to_string()));
+        }
+        if config.enable_integrity_check && !header.integrity {
+            return Err(SecurityError::IntegrityError("配置要求完整性检查，但文件头标记为没有校验值".to_string()));
+        }
+        let encryptor = if header.encrypted { Some(DataEncryptor::from_config(config)?) } else { None };
+        // 按文件头中的算法校验，每条记录的算法编号必须与文件头一致
+        let integrity_checker = if header.integrity {
+            let config = SecurityConfig { integrity_algorithm: header_integrity_algorithm(&header)?, ..config.clone() };
+            Some(DataIntegrityChecker::from_config(&config)?)
+        } else {
+            None
+        };
+        Ok(Self { reader, header_bytes, header, encryptor, integrity_checker, next_index: 0, finished: false })
+    }
+
+    pub fn header(&self) -> &ContainerHeader {
+        &self.header
+    }
+
+    // 读取下一条记录，文件正常结束时返回 None
+    pub fn next_record(&mut self) -> Result<Option<SecureRecord>, SecurityError> {
+        if self.finished {
+            return Ok(None);
+        }
+        let result = self.read_record();
+        if !matches!(result, Ok(Some(_))) {
+            self.finished = true;
+        }
+        result
+    }
+
+    fn read_record(&mut self) -> Result<Option<SecureRecord>, SecurityError> {
+        let index = self.next_index;
+        let truncated = || SecurityError::FormatError(format!("文件在第 {} 条记录处被截断", index));
+
+        let mut len_bytes = [0u8; 4];
+        match read_full(&mut self.reader, &mut len_bytes)? {
+            0 => return Ok(None),
+            4 => {}
+            _ => return Err(truncated()),
+        }
+        let frame_len = u32::from_le_bytes(len_bytes);
+        if !(FRAME_FIXED_LEN as u32..=MAX_FRAME_LEN).contains(&frame_len) {
+            return Err(SecurityError::FormatError(format!("第 {} 条记录的帧长度无效: {}", index, frame_len)));
+        }
+        let mut frame = vec![0u8; frame_len as usize];
+        if read_full(&mut self.reader, &mut frame)? < frame.len() {
+            return Err(truncated());
+        }
+
+        let sequence = u64::from_le_bytes(frame[0..8].try_into().expect("8 字节"));
+        let timestamp_ns = u64::from_le_bytes(frame[8..16].try_into().expect("8 字节"));
+        let body_len = u32::from_le_bytes(frame[16..20].try_into().expect("4 字节")) as usize;
+        let body_end = FRAME_FIXED_LEN
+            .checked_add(body_len)
+            .filter(|end| *end <= frame.len())
+            .ok_or_else(|| SecurityError::FormatError(format!("第 {} 条记录的负载长度无效: {}", index, body_len)))?;
+
+        if let Some(checker) = &self.integrity_checker {
+            let trailer = &frame[body_end..];
+            match trailer.first() {
+                Some(&id) if id == self.header.integrity_algorithm => {}
+                Some(&id) => {
+                    return Err(SecurityError::IntegrityError(format!(
+                        "第 {} 条记录的完整性算法编号 {} 与文件头的 {} 不一致",
+                        index, id, self.header.integrity_algorithm
+                    )))
+                }
+                None => return Err(SecurityError::IntegrityError(format!("第 {} 条记录缺少校验值", index))),
+            }
+            let input = tag_input(&self.header_bytes, index, &frame[..body_end]);
+            checker.verify_checksum(&input, &trailer[1..]).map_err(|e| at_record(index, e))?;
+        } else if body_end != frame.len() {
+            return Err(SecurityError::FormatError(format!("第 {} 条记录的帧长度与负载长度不一致", index)));
+        }
+
+        let body = &frame[FRAME_FIXED_LEN..body_end];
+        let data = match &self.encryptor {
+            Some(encryptor) => encryptor
+                .decrypt_with_context(body, index, &record_context(&self.header_bytes, sequence, timestamp_ns))
+                .map_err(|e| at_record(index, e))?,
+            None => body.to_vec(),
+        };
+
+        self.next_index += 1;
+        Ok(Some(SecureRecord { index, sequence, timestamp_ns, data }))
+    }
+}
+
+impl SecureFileReader<BufReader<File>> {
+    pub fn open(path: impl AsRef<Path>, config: &SecurityConfig) -> Result<Self, SecurityError> {
+        Self::new(BufReader::new(File::open(path)?), config)
+    }
+}
+
+impl<R: Read> Iterator for SecureFileReader<R> {
+    type Item = Result<SecureRecord, SecurityError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_record().transpose()
+    }
+}
+
+// 在校验、解密错误中加上记录位置
+fn at_record(index: u64, error: SecurityError) -> SecurityError {
+    match error {
+        SecurityError::IntegrityError(message) => {
+            SecurityError::IntegrityError(format!("第 {} 条记录: {}", index, message))
+        }
+        SecurityError::DecryptionError(message) => {
+            SecurityError::DecryptionError(format!("第 {} 条记录: {}", index, message))
+        }
+        other => other,
+    }
+}
+
+// 文件头中的完整性算法；安全容器不接受 legacy 校验
+fn header_integrity_algorithm(header: &ContainerHeader) -> Result<IntegrityAlgorithm, SecurityError> {
+    IntegrityAlgorithm::from_id(header.integrity_algorithm)
+        .filter(|algorithm| *algorithm != IntegrityAlgorithm::LegacyDefaultHasher)
+        .ok_or_else(|| {
+            SecurityError::IntegrityError(format!("文件头的完整性算法编号 {} 无效", header.integrity_algorithm))
+        })
+}
+
+// 按配置创建加密器和完整性检查器
+fn components(
+    config: &SecurityConfig,
+) -> Result<(Option<DataEncryptor>, Option<DataIntegrityChecker>), SecurityError> {
+    let encryptor = if config.enable_encryption { Some(DataEncryptor::from_config(config)?) } else { None };
+    let integrity_checker = if config.enable_integrity_check {
+        let checker = DataIntegrityChecker::from_config(config)?;
+        if checker.algorithm() == IntegrityAlgorithm::LegacyDefaultHasher {
+            return Err(SecurityError::KeyError("安全容器不支持 legacy 校验算法".to_string()));
+        }
+        Some(checker)
+    } else {
+        None
+    };
+    Ok((encryptor, integrity_checker))
+}
+
+fn read_header(reader: &mut impl Read) -> Result<ContainerHeader, SecurityError> {
+    let mut bytes = [0u8; CONTAINER_HEADER_LEN];
+    if read_full(reader, &mut bytes)? < CONTAINER_HEADER_LEN {
+        return Err(SecurityError::FormatError("文件头不完整".to_string()));
+    }
+    ContainerHeader::parse(&bytes)
+}
+
+// 跳过一条完整的记录帧并返回帧长度；文件结束或末尾记录不完整时返回 None，帧长度无效时返回错误
+fn skip_frame(reader: &mut impl Read, index: u64) -> Result<Option<u32>, SecurityError> {
+    let mut len_bytes = [0u8; 4];
+    if read_full(reader, &mut len_bytes)? < 4 {
+        return Ok(None);
+    }
+    let frame_len = u32::from_le_bytes(len_bytes);
+    if !(FRAME_FIXED_LEN as u32..=MAX_FRAME_LEN).contains(&frame_len) {
+        return Err(SecurityError::FormatError(format!("第 {} 条记录的帧长度无效: {}", index, frame_len)));
+    }
+    let skipped = io::copy(&mut reader.by_ref().take(frame_len as u64), &mut io::sink())?;
+    Ok((skipped == frame_len as u64).then_some(frame_len))
+}
+
+// 读满 buf，返回实际读到的字节数（小于 buf 长度表示到达文件末尾）
+pub(super) fn read_full(reader: &mut impl Read, buf: &mut [u8]) -> io::Result<usize> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match reader.read(&mut buf[filled..]) {
+            Ok(0) => break,
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(filled)
+}
+
+// 加密附加数据中的上下文：文件头 | 序号(u64 LE) | 时间(u64 LE)
+fn record_context(header: &[u8], sequence: u64, timestamp_ns: u64) -> Vec<u8> {
+    let mut context = Vec::with_capacity(header.len() + 16);
+    context.extend_from_slice(header);
+    context.extend_from_slice(&sequence.to_le_bytes());
+    context.extend_from_slice(&timestamp_ns.to_le_bytes());
+    context
+}
+
+// 校验值的输入：文件头 | 记录位置(u64 LE) | 帧中校验值之前的字节
+fn tag_input(header: &[u8], index: u64, frame: &[u8]) -> Vec<u8> {
+    let mut input = Vec::with_capacity(header.len() + 8 + frame.len());
+    input.extend_from_slice(header);
+    input.extend_from_slice(&index.to_le_bytes());
+    input.extend_from_slice(frame);
+    input
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::security::Zeroizing;
+
+    fn config(encrypted: bool, algorithm: IntegrityAlgorithm) -> SecurityConfig {
+        SecurityConfig {
+            enable_encryption: encrypted,
+            encryption_key: Some(Zeroizing::new(vec![4; 32])),
+            integrity_algorithm: algorithm,
+            integrity_key: Some(Zeroizing::new(b"container integrity key".to_vec())),
+            ..SecurityConfig::default()
+        }
+    }
+
+    fn write(config: &SecurityConfig, records: &[&[u8]]) -> Vec<u8> {
+        let mut writer = SecureFileWriter::new(Vec::new(), config).unwrap();
+        for (sequence, data) in records.iter().enumerate() {
+            writer.write_record(sequence as u64 + 100, 1_000 + sequence as u64, data).unwrap();
+        }
+        writer.into_inner()
+    }
+
+    fn read(bytes: &[u8], config: &SecurityConfig) -> Vec<Result<SecureRecord, SecurityError>> {
+        SecureFileReader::new(bytes, config).unwrap().collect()
+    }
+
+    // 第 index 条记录帧（含帧长度）在文件中的范围
+    fn frame_range(bytes: &[u8], index: usize) -> std::ops::Range<usize> {
+        let mut start = CONTAINER_HEADER_LEN;
+        for _ in 0..index {
+            start += 4 + u32::from_le_bytes(bytes[start..start + 4].try_into().unwrap()) as usize;
+        }
+        start..start + 4 + u32::from_le_bytes(bytes[start..start + 4].try_into().unwrap()) as usize
+    }
+
+    // 用同一个密钥按 algorithm 重新计算第 index 条记录的校验值
+    fn retag(bytes: &mut Vec<u8>, index: usize, algorithm: IntegrityAlgorithm, key: &[u8]) {
+        let range = frame_range(bytes, index);
+        let frame = &bytes[range.start + 4..range.end];
+        let body_end = FRAME_FIXED_LEN + u32::from_le_bytes(frame[16..20].try_into().unwrap()) as usize;
+        let checker = DataIntegrityChecker::with_algorithm(key, algorithm).unwrap();
+        let mut new_frame = frame[..body_end].to_vec();
+        let tag = checker.calculate_checksum(&tag_input(&bytes[..CONTAINER_HEADER_LEN], index as u64, &new_frame));
+        new_frame.push(algorithm.id());
+        new_frame.extend_from_slice(&tag);
+        let mut replacement = (new_frame.len() as u32).to_le_bytes().to_vec();
+        replacement.extend_from_slice(&new_frame);
+        bytes.splice(range, replacement);
+    }
+
+    #[test]
+    fn round_trips_encrypted_records() {
+        for algorithm in [IntegrityAlgorithm::HmacSha256, IntegrityAlgorithm::Blake3] {
+            let config = config(true, algorithm);
+            let bytes = write(&config, &[b"first", b"", b"third record"]);
+            let reader = SecureFileReader::new(bytes.as_slice(), &config).unwrap();
+            let header = *reader.header();
+            assert!(header.encrypted && header.integrity);
+            assert_eq!(header.integrity_algorithm, algorithm.id());
+            let records: Vec<_> = reader.map(Result::unwrap).collect();
+            assert_eq!(records.len(), 3);
+            assert_eq!(records[2], SecureRecord { index: 2, sequence: 102, timestamp_ns: 1_002, data: b"third record".to_vec() });
+            assert!(!bytes.windows(5).any(|window| window == b"first"));
+        }
+    }
+
+    #[test]
+    fn rejects_records_tagged_with_another_algorithm() {
+        let config = config(false, IntegrityAlgorithm::HmacSha256);
+        let key = config.integrity_key.clone().unwrap();
+        let mut bytes = write(&config, &[b"one", b"two"]);
+
+        // 校验值本身正确，但算法编号与文件头不一致
+        retag(&mut bytes, 1, IntegrityAlgorithm::Blake3, &key);
+        let records = read(&bytes, &config);
+        assert!(records[0].is_ok());
+        assert!(matches!(&records[1], Err(SecurityError::IntegrityError(message)) if message.contains("不一致")));
+
+        // 旧的 DefaultHasher 校验和只有 8 字节、没有密钥，容器中一律拒绝
+        let mut bytes = write(&config, &[b"one", b"two"]);
+        retag(&mut bytes, 0, IntegrityAlgorithm::LegacyDefaultHasher, &[0; 8]);
+        assert!(matches!(read(&bytes, &config)[0], Err(SecurityError::IntegrityError(_))));
+    }
+
+    #[test]
+    fn rejects_header_with_legacy_integrity_algorithm() {
+        let config = config(false, IntegrityAlgorithm::HmacSha256);
+        let mut bytes = write(&config, &[b"one"]);
+        bytes[11] = IntegrityAlgorithm::LegacyDefaultHasher.id();
+        assert!(matches!(SecureFileReader::new(bytes.as_slice(), &config), Err(SecurityError::IntegrityError(_))));
+    }
+
+    #[test]
+    fn detects_modified_reordered_and_foreign_records() {
+        let config = config(true, IntegrityAlgorithm::Blake3);
+        let bytes = write(&config, &[b"one", b"two", b"three"]);
+
+        let mut modified = bytes.clone();
+        let last = modified.len() - 40;
+        modified[last] ^= 1;
+        let records = read(&modified, &config);
+        assert!(records[..2].iter().all(Result::is_ok));
+        assert!(matches!(&records[2], Err(SecurityError::IntegrityError(message)) if message.contains("第 2 条")));
+
+        // 调换前两条记录
+        let (first, second) = (frame_range(&bytes, 0), frame_range(&bytes, 1));
+        let mut reordered = bytes[..CONTAINER_HEADER_LEN].to_vec();
+        reordered.extend_from_slice(&bytes[second.clone()]);
+        reordered.extend_from_slice(&bytes[first]);
+        reordered.extend_from_slice(&bytes[second.end..]);
+        assert!(read(&reordered, &config)[0].is_err());
+
+        // 其他文件的记录（文件标识不同）
+        let other = write(&config, &[b"one"]);
+        let mut spliced = bytes[..CONTAINER_HEADER_LEN].to_vec();
+        spliced.extend_from_slice(&other[frame_range(&other, 0)]);
+        assert!(read(&spliced, &config)[0].is_err());
+
+        let records = read(&bytes[..bytes.len() - 3], &config);
+        assert!(matches!(&records[2], Err(SecurityError::FormatError(message)) if message.contains("截断")));
+    }
+
+    #[test]
+    fn resume_requires_matching_settings() {
+        let config = config(false, IntegrityAlgorithm::HmacSha256);
+        let bytes = write(&config, &[b"one"]);
+        let header = ContainerHeader::parse(&bytes).unwrap();
+
+        let blake3 = SecurityConfig { integrity_algorithm: IntegrityAlgorithm::Blake3, ..config.clone() };
+        assert!(SecureFileWriter::resume(Vec::new(), header, 1, &blake3).is_err());
+        let encrypted = SecurityConfig { enable_encryption: true, ..config.clone() };
+        assert!(SecureFileWriter::resume(Vec::new(), header, 1, &encrypted).is_err());
+
+        let mut writer = SecureFileWriter::resume(bytes, header, 1, &config).unwrap();
+        writer.write_record(7, 7, b"two").unwrap();
+        let records: Vec<_> = read(&writer.into_inner(), &config).into_iter().map(Result::unwrap).collect();
+        assert_eq!(records[1].data, b"two");
+    }
+
+    #[test]
+    fn rejects_files_without_container_magic() {
+        let config = config(false, IntegrityAlgorithm::HmacSha256);
+        // 旧格式的记录：数据长度 | 数据 | 校验值，不会再被自动按旧格式读取
+        let mut legacy = 5u32.to_le_bytes().to_vec();
+        legacy.extend_from_slice(b"forge");
+        legacy.extend_from_slice(&[0; 8]);
+        for bytes in [legacy.as_slice(), b"PLCN", &[]] {
+            assert!(matches!(SecureFileReader::new(bytes, &config), Err(SecurityError::FormatError(_))));
+        }
+
+        let bytes = write(&config, &[b"one"]);
+        assert!(matches!(SecureFileReader::new(&bytes[..20], &config), Err(SecurityError::FormatError(_))));
+    }
+
+    #[test]
+    fn rejects_headers_without_the_configured_protection() {
+        let protected = config(true, IntegrityAlgorithm::HmacSha256);
+        let plain = SecurityConfig { enable_encryption: false, enable_integrity_check: false, ..protected.clone() };
+
+        // 去掉加密和完整性标志、把记录换成明文的文件
+        let downgraded = write(&plain, &[b"forged"]);
+        assert!(matches!(SecureFileReader::new(downgraded.as_slice(), &protected), Err(SecurityError::IntegrityError(_))));
+        let integrity_only = SecurityConfig { enable_encryption: false, ..protected.clone() };
+        assert!(matches!(
+            SecureFileReader::new(downgraded.as_slice(), &integrity_only),
+            Err(SecurityError::IntegrityError(_))
+        ));
+        let unencrypted = write(&integrity_only, &[b"forged"]);
+        assert!(matches!(SecureFileReader::new(unencrypted.as_slice(), &protected), Err(SecurityError::IntegrityError(_))));
+
+        // 配置不要求的保护文件中有也可以读取
+        let records: Vec<_> = read(&write(&protected, &[b"one"]), &plain).into_iter().map(Result::unwrap).collect();
+        assert_eq!(records[0].data, b"one");
+        assert_eq!(read(&downgraded, &plain)[0].as_ref().unwrap().data, b"forged");
+    }
+
+    #[test]
+    fn keyed_integrity_requires_key() {
+        let config = SecurityConfig { integrity_key: None, ..config(false, IntegrityAlgorithm::HmacSha256) };
+        assert!(matches!(SecureFileWriter::new(Vec::new(), &config), Err(SecurityError::KeyError(_))));
+    }
+}
@@ -0,0 +1,307 @@
+use super::keyring::{KeyRing, SecretKey};
+use super::{SecurityConfig, SecurityError};
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+use aes_gcm::Aes256Gcm;
+use chacha20poly1305::ChaCha20Poly1305;
+use std::fmt;
+
+// 密文格式版本（版本 2 在头部加入密钥编号）
+pub const ENCRYPTION_FORMAT_VERSION: u8 = 2;
+// AEAD 密钥长度（AES-256-GCM 和 ChaCha20-Poly1305 都是 32 字节）
+pub const ENCRYPTION_KEY_LEN: usize = 32;
+const NONCE_LEN: usize = 12;
+const TAG_LEN: usize = 16;
+// 版本(1) + 算法(1) + 密钥编号(4) + nonce(12)
+const HEADER_LEN: usize = 6 + NONCE_LEN;
+// 版本 1：版本(1) + 算法(1) + nonce(12)
+const HEADER_LEN_V1: usize = 2 + NONCE_LEN;
+
+// 认证加密算法
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EncryptionAlgorithm {
+    #[default]
+    Aes256Gcm,
+    ChaCha20Poly1305,
+}
+
+impl EncryptionAlgorithm {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "aes-256-gcm" | "aes256gcm" | "aes-gcm" => Some(Self::Aes256Gcm),
+            "chacha20-poly1305" | "chacha20poly1305" | "chacha20" => Some(Self::ChaCha20Poly1305),
+            _ => None,
+        }
+    }
+
+    // 写入密文头部的算法编号
+    pub fn id(self) -> u8 {
+        match self {
+            Self::Aes256Gcm => 1,
+            Self::ChaCha20Poly1305 => 2,
+        }
+    }
+
+    pub fn from_id(id: u8) -> Option<Self> {
+        match id {
+            1 => Some(Self::Aes256Gcm),
+            2 => Some(Self::ChaCha20Poly1305),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for EncryptionAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Aes256Gcm => write!(f, "aes-256-gcm"),
+            Self::ChaCha20Poly1305 => write!(f, "chacha20-poly1305"),
+        }
+    }
+}
+
+// 认证加密器：AES-256-GCM 或 ChaCha20-Poly1305，每条消息使用随机 nonce
+//
+// 密文格式：版本(1) | 算法编号(1) | 密钥编号(4, 大端) | nonce(12) | 密文 | 认证标签(16)
+// 附加数据为头部加上数据块序号（大端 u64）和可选的上下文，密文被改动、换到其他序号的数据块或头部被改动都无法解密。
+// 加密使用密钥环的活动密钥；解密按头部的算法编号和密钥编号选择算法和密钥，
+// 更换算法或轮换密钥后之前写入的密文仍可解密。版本 1 的密文没有密钥编号，解密时依次尝试密钥环中的密钥。
+// 随机 96 位 nonce 在同一密钥下加密约 2^32 条消息以内是安全的，超过后应轮换密钥
+pub struct DataEncryptor {
+    algorithm: EncryptionAlgorithm,
+    keyring: KeyRing,
+}
+
+impl DataEncryptor {
+    // 使用 AES-256-GCM 加密，密钥编号为 0
+    pub fn new(key: &[u8]) -> Result<Self, SecurityError> {
+        Self::with_algorithm(key, EncryptionAlgorithm::default())
+    }
+
+    pub fn with_algorithm(key: &[u8], algorithm: EncryptionAlgorithm) -> Result<Self, SecurityError> {
+        Ok(Self::with_keyring(KeyRing::single(0, key)?, algorithm))
+    }
+
+    // 共享密钥环：在其他地方轮换密钥后，下一条消息即使用新的活动密钥
+    pub fn with_keyring(keyring: KeyRing, algorithm: EncryptionAlgorithm) -> Self {
+        Self { algorithm, keyring }
+    }
+
+    // 按 SecurityConfig 的算法和密钥环（或单个密钥）创建
+    pub fn from_config(config: &SecurityConfig) -> Result<Self, SecurityError> {
+        if let Some(keyring) = &config.keyring {
+            return Ok(Self::with_keyring(keyring.clone(), config.encryption_algorithm));
+        }
+        let key = config
+            .encryption_key
+            .as_deref()
+            .ok_or_else(|| SecurityError::KeyError("SecurityConfig 未设置 keyring 或 encryption_key".to_string()))?;
+        Self::with_algorithm(key, config.encryption_algorithm)
+    }
+
+    pub fn algorithm(&self) -> EncryptionAlgorithm {
+        self.algorithm
+    }
+
+    pub fn keyring(&self) -> &KeyRing {
+        &self.keyring
+    }
+
+    // 加密 data，sequence 为数据块序号，解密时必须提供相同的序号
+    pub fn encrypt(&self, data: &[u8], sequence: u64) -> Result<Vec<u8>, SecurityError> {
+        self.encrypt_with_context(data, sequence, &[])
+    }
+
+    pub fn decrypt(&self, encrypted_data: &[u8], sequence: u64) -> Result<Vec<u8>, SecurityError> {
+        self.decrypt_with_context(encrypted_data, sequence, &[])
+    }
+
+    // context 追加到附加数据中（例如所在文件的文件头），解密时必须提供相同的内容
+    pub fn encrypt_with_context(&self, data: &[u8], sequence: u64, context: &[u8]) -> Result<Vec<u8>, SecurityError> {
+        let (key_id, key) = self.keyring.active()?;
+        let mut header = [0u8; HEADER_LEN];
+        header[0] = ENCRYPTION_FORMAT_VERSION;
+        header[1] = self.algorithm.id();
+        header[2..6].copy_from_slice(&key_id.to_be_bytes());
+        let nonce = match self.algorithm {
+            EncryptionAlgorithm::Aes256Gcm => Aes256Gcm::generate_nonce(&mut OsRng),
+            EncryptionAlgorithm::ChaCha20Poly1305 => ChaCha20Poly1305::generate_nonce(&mut OsRng),
+        };
+        header[6..].copy_from_slice(&nonce);
+
+        let aad = associated_data(&header, sequence, context);
+        let ciphertext = seal(self.algorithm, &key, &nonce, data, &aad)
+            .ok_or_else(|| SecurityError::EncryptionError(format!("{} 加密失败", self.algorithm)))?;
+
+        let mut result = Vec::with_capacity(HEADER_LEN + ciphertext.len());
+        result.extend_from_slice(&header);
+        result.extend_from_slice(&ciphertext);
+        Ok(result)
+    }
+
+    pub fn decrypt_with_context(
+        &self,
+        encrypted_data: &[u8],
+        sequence: u64,
+        context: &[u8],
+    ) -> Result<Vec<u8>, SecurityError> {
+        let header_len = match encrypted_data.first() {
+            Some(&ENCRYPTION_FORMAT_VERSION) => HEADER_LEN,
+            Some(&1) => HEADER_LEN_V1,
+            Some(version) => {
+                return Err(SecurityError::DecryptionError(format!("不支持的密文版本: {}", version)))
+            }
+            None => return Err(SecurityError::DecryptionError("数据为空".to_string())),
+        };
+        if encrypted_data.len() < header_len + TAG_LEN {
+            return Err(SecurityError::DecryptionError(format!(
+                "数据太短: {} 字节，至少需要 {} 字节",
+                encrypted_data.len(),
+                header_len + TAG_LEN
+            )));
+        }
+        let (header, ciphertext) = encrypted_data.split_at(header_len);
+        let algorithm = EncryptionAlgorithm::from_id(header[1])
+            .ok_or_else(|| SecurityError::DecryptionError(format!("未知的加密算法编号: {}", header[1])))?;
+        let nonce = &header[header_len - NONCE_LEN..];
+        let aad = associated_data(header, sequence, context);
+
+        let candidates = if header_len == HEADER_LEN {
+            let key_id = u32::from_be_bytes([header[2], header[3], header[4], header[5]]);
+            let key = self.keyring
+                .get(key_id)
+                .ok_or_else(|| SecurityError::DecryptionError(format!("密钥环中没有编号为 {} 的密钥", key_id)))?;
+            vec![(key_id, key)]
+        } else {
+            self.keyring.all()
+        };
+        candidates
+            .iter()
+            .find_map(|(_, key)| open(algorithm, key, nonce, ciphertext, &aad))
+            .ok_or_else(|| {
+                SecurityError::DecryptionError(format!("{} 认证失败：密钥或序号不匹配，或数据已被篡改", algorithm))
+            })
+    }
+}
+
+fn seal(algorithm: EncryptionAlgorithm, key: &SecretKey, nonce: &[u8], data: &[u8], aad: &[u8]) -> Option<Vec<u8>> {
+    let payload = Payload { msg: data, aad };
+    match algorithm {
+        EncryptionAlgorithm::Aes256Gcm => Aes256Gcm::new_from_slice(key.as_bytes()).ok()?.encrypt(nonce.into(), payload),
+        EncryptionAlgorithm::ChaCha20Poly1305 => {
+            ChaCha20Poly1305::new_from_slice(key.as_bytes()).ok()?.encrypt(nonce.into(), payload)
+        }
+    }
+    .ok()
+}
+
+fn open(algorithm: EncryptionAlgorithm, key: &SecretKey, nonce: &[u8], ciphertext: &[u8], aad: &[u8]) -> Option<Vec<u8>> {
+    let payload = Payload { msg: ciphertext, aad };
+    match algorithm {
+        EncryptionAlgorithm::Aes256Gcm => Aes256Gcm::new_from_slice(key.as_bytes()).ok()?.decrypt(nonce.into(), payload),
+        EncryptionAlgorithm::ChaCha20Poly1305 => {
+            ChaCha20Poly1305::new_from_slice(key.as_bytes()).ok()?.decrypt(nonce.into(), payload)
+        }
+    }
+    .ok()
+}
+
+fn associated_data(header: &[u8], sequence: u64, context: &[u8]) -> Vec<u8> {
+    let mut aad = Vec::with_capacity(header.len() + 8 + context.len());
+    aad.extend_from_slice(header);
+    aad.extend_from_slice(&sequence.to_be_bytes());
+    aad.extend_from_slice(context);
+    aad
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEY: [u8; 32] = [7; 32];
+    const ALGORITHMS: [EncryptionAlgorithm; 2] = [EncryptionAlgorithm::Aes256Gcm, EncryptionAlgorithm::ChaCha20Poly1305];
+
+    #[test]
+    fn round_trips_with_both_algorithms() {
+        for algorithm in ALGORITHMS {
+            let encryptor = DataEncryptor::with_algorithm(&KEY, algorithm).unwrap();
+            let encrypted = encryptor.encrypt_with_context(b"secret payload", 42, b"file header").unwrap();
+            assert_eq!(encrypted.len(), HEADER_LEN + 14 + TAG_LEN);
+            assert_eq!(&encrypted[..6], &[ENCRYPTION_FORMAT_VERSION, algorithm.id(), 0, 0, 0, 0]);
+            assert_eq!(encryptor.decrypt_with_context(&encrypted, 42, b"file header").unwrap(), b"secret payload");
+
+            // 同一明文每次使用不同的 nonce
+            let again = encryptor.encrypt_with_context(b"secret payload", 42, b"file header").unwrap();
+            assert_ne!(encrypted, again);
+            assert_eq!(encryptor.decrypt(&encryptor.encrypt(b"", 0).unwrap(), 0).unwrap(), b"");
+        }
+    }
+
+    #[test]
+    fn rejects_tampered_ciphertext_header_and_tag() {
+        for algorithm in ALGORITHMS {
+            let encryptor = DataEncryptor::with_algorithm(&KEY, algorithm).unwrap();
+            let encrypted = encryptor.encrypt(b"secret payload", 1).unwrap();
+            // 改动 nonce、密文和认证标签中的任意一个字节都无法解密
+            for position in 6..encrypted.len() {
+                let mut tampered = encrypted.clone();
+                tampered[position] ^= 0x01;
+                assert!(
+                    matches!(encryptor.decrypt(&tampered, 1), Err(SecurityError::DecryptionError(_))),
+                    "{} byte {}", algorithm, position
+                );
+            }
+            // 头部的算法编号被换成另一种算法
+            let mut swapped = encrypted.clone();
+            swapped[1] = if algorithm == EncryptionAlgorithm::Aes256Gcm { 2 } else { 1 };
+            assert!(encryptor.decrypt(&swapped, 1).is_err());
+            // 密钥编号被改动
+            let mut rekeyed = encrypted.clone();
+            rekeyed[5] = 1;
+            assert!(encryptor.decrypt(&rekeyed, 1).is_err());
+            assert!(encryptor.decrypt(&encrypted[..encrypted.len() - 1], 1).is_err());
+        }
+    }
+
+    #[test]
+    fn binds_ciphertext_to_sequence_context_and_key() {
+        let encryptor = DataEncryptor::new(&KEY).unwrap();
+        let encrypted = encryptor.encrypt_with_context(b"record", 5, b"file-a").unwrap();
+        assert!(encryptor.decrypt_with_context(&encrypted, 6, b"file-a").is_err());
+        assert!(encryptor.decrypt_with_context(&encrypted, 5, b"file-b").is_err());
+        assert!(encryptor.decrypt(&encrypted, 5).is_err());
+
+        let other = DataEncryptor::new(&[8; 32]).unwrap();
+        assert!(matches!(other.decrypt_with_context(&encrypted, 5, b"file-a"), Err(SecurityError::DecryptionError(_))));
+    }
+
+    #[test]
+    fn rejects_malformed_input() {
+        let encryptor = DataEncryptor::new(&KEY).unwrap();
+        assert!(encryptor.decrypt(&[], 0).is_err());
+        assert!(encryptor.decrypt(&[9; 64], 0).is_err());
+        assert!(encryptor.decrypt(&[ENCRYPTION_FORMAT_VERSION; HEADER_LEN + TAG_LEN - 1], 0).is_err());
+        let mut unknown = encryptor.encrypt(b"x", 0).unwrap();
+        unknown[1] = 9;
+        assert!(encryptor.decrypt(&unknown, 0).is_err());
+        assert!(DataEncryptor::new(&[1; 16]).is_err());
+    }
+
+    #[test]
+    fn decrypts_version_1_ciphertext_with_any_key_in_ring() {
+        // 版本 1 的密文没有密钥编号：头部为版本、算法和 nonce
+        let keyring = KeyRing::single(3, &[3; 32]).unwrap();
+        keyring.insert(9, SecretKey::from_slice(&[9; 32]).unwrap()).unwrap();
+        let encryptor = DataEncryptor::with_keyring(keyring, EncryptionAlgorithm::Aes256Gcm);
+
+        let mut header = vec![1, EncryptionAlgorithm::Aes256Gcm.id()];
+        header.extend_from_slice(&[0x11; NONCE_LEN]);
+        let aad = associated_data(&header, 8, &[]);
+        let key = SecretKey::from_slice(&[3; 32]).unwrap();
+        let ciphertext = seal(EncryptionAlgorithm::Aes256Gcm, &key, &header[2..], b"old format", &aad).unwrap();
+        let mut encrypted = header;
+        encrypted.extend_from_slice(&ciphertext);
+
+        assert_eq!(encryptor.decrypt(&encrypted, 8).unwrap(), b"old format");
+        assert!(encryptor.decrypt(&encrypted, 9).is_err());
+    }
+}
@@ -0,0 +1,286 @@
+use super::{SecurityConfig, SecurityError};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use std::collections::hash_map::DefaultHasher;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use subtle::ConstantTimeEq;
+use zeroize::Zeroizing;
+
+// BLAKE3 派生密钥的上下文字符串，密钥不是 32 字节时用它把密钥材料派生为 32 字节
+const BLAKE3_KEY_CONTEXT: &str = "pelican-framework 2026-10 integrity key";
+
+// 完整性校验算法；编号随每条记录保存，更换算法后旧记录仍按原算法校验
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum IntegrityAlgorithm {
+    #[default]
+    HmacSha256,
+    Blake3,
+    // 早期版本的 DefaultHasher 校验和：不是密码学算法，结果也可能随 Rust 版本变化，只用于校验旧数据
+    LegacyDefaultHasher,
+}
+
+impl IntegrityAlgorithm {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "hmac-sha256" | "hmac_sha256" | "hmacsha256" => Some(Self::HmacSha256),
+            "blake3" => Some(Self::Blake3),
+            "legacy" => Some(Self::LegacyDefaultHasher),
+            _ => None,
+        }
+    }
+
+    // 记录中保存的算法编号
+    pub fn id(self) -> u8 {
+        match self {
+            Self::LegacyDefaultHasher => 0,
+            Self::HmacSha256 => 1,
+            Self::Blake3 => 2,
+        }
+    }
+
+    pub fn from_id(id: u8) -> Option<Self> {
+        match id {
+            0 => Some(Self::LegacyDefaultHasher),
+            1 => Some(Self::HmacSha256),
+            2 => Some(Self::Blake3),
+            _ => None,
+        }
+    }
+
+    // 校验值的字节数
+    pub fn tag_len(self) -> usize {
+        match self {
+            Self::HmacSha256 | Self::Blake3 => 32,
+            Self::LegacyDefaultHasher => 8,
+        }
+    }
+}
+
+impl fmt::Display for IntegrityAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::HmacSha256 => write!(f, "hmac-sha256"),
+            Self::Blake3 => write!(f, "blake3"),
+            Self::LegacyDefaultHasher => write!(f, "legacy"),
+        }
+    }
+}
+
+// 带密钥的完整性检查器：HMAC-SHA256 或 BLAKE3 keyed hash，校验时按常数时间比较
+//
+// 计算使用构造时选定的算法，verify_with 可以按记录中保存的算法编号校验其他算法写入的数据。
+// BLAKE3 需要 32 字节密钥，其他长度的密钥先用 blake3::derive_key 派生
+pub struct DataIntegrityChecker {
+    algorithm: IntegrityAlgorithm,
+    hmac_key: Zeroizing<Vec<u8>>,
+    blake3_key: Zeroizing<[u8; 32]>,
+    legacy_salt: Option<[u8; 8]>,
+}
+
+impl fmt::Debug for DataIntegrityChecker {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DataIntegrityChecker")
+            .field("algorithm", &self.algorithm)
+            .field("legacy", &self.legacy_salt.is_some())
+            .finish_non_exhaustive()
+    }
+}
+
+impl DataIntegrityChecker {
+    // 使用 HMAC-SHA256
+    pub fn new(key: &[u8]) -> Result<Self, SecurityError> {
+        Self::with_algorithm(key, IntegrityAlgorithm::default())
+    }
+
+    // LegacyDefaultHasher 的密钥即 8 字节盐值
+    pub fn with_algorithm(key: &[u8], algorithm: IntegrityAlgorithm) -> Result<Self, SecurityError> {
+        if key.is_empty() {
+            return Err(SecurityError::KeyError("完整性密钥不能为空".to_string()));
+        }
+        let legacy_salt = match algorithm {
+            IntegrityAlgorithm::LegacyDefaultHasher => Some(<[u8; 8]>::try_from(key).map_err(|_| {
+                SecurityError::KeyError(format!("legacy 校验的盐值必须为 8 字节，实际为 {} 字节", key.len()))
+            })?),
+            _ => None,
+        };
+        let blake3_key = Zeroizing::new(match <[u8; 32]>::try_from(key) {
+            Ok(key) => key,
+            Err(_) => blake3::derive_key(BLAKE3_KEY_CONTEXT, key),
+        });
+        Ok(Self {
+            algorithm,
+            hmac_key: Zeroizing::new(key.to_vec()),
+            blake3_key,
+            legacy_salt,
+        })
+    }
+
+    // 只能校验旧的 DefaultHasher 校验和
+    pub fn legacy(salt: [u8; 8]) -> Self {
+        Self::with_algorithm(&salt, IntegrityAlgorithm::LegacyDefaultHasher).expect("盐值长度为 8 字节")
+    }
+
+    // 同时能校验旧的 DefaultHasher 校验和
+    pub fn with_legacy_salt(mut self, salt: [u8; 8]) -> Self {
+        self.legacy_salt = Some(salt);
+        self
+    }
+
+    // HMAC-SHA256 和 BLAKE3 的密钥为 integrity_key，必须设置；LegacyDefaultHasher 使用 integrity_salt
+    pub fn from_config(config: &SecurityConfig) -> Result<Self, SecurityError> {
+        match config.integrity_algorithm {
+            IntegrityAlgorithm::LegacyDefaultHasher => config
+                .integrity_salt
+                .map(Self::legacy)
+                .ok_or_else(|| SecurityError::KeyError("legacy 校验需要设置 integrity_salt".to_string())),
+            algorithm => {
+                let key = config.integrity_key.as_deref().ok_or_else(|| {
+                    SecurityError::KeyError(format!("完整性算法 {} 需要设置 integrity_key", algorithm))
+                })?;
+                Self::with_algorithm(key, algorithm)
+            }
+        }
+    }
+
+    // 只用于校验容器格式之前写入的旧文件：同时能校验 DefaultHasher 校验和（integrity_salt），
+    // 早期版本没有设置 integrity_key 时以 integrity_salt 作为 HMAC/BLAKE3 密钥写入，这里按同样的方式校验。
+    // 默认配置的全零盐值谁都知道，用它校验等于不校验，因此拒绝
+    pub fn legacy_from_config(config: &SecurityConfig) -> Result<Self, SecurityError> {
+        let salt = config
+            .integrity_salt
+            .filter(|salt| *salt != [0; 8])
+            .ok_or_else(|| SecurityError::KeyError("校验旧文件需要设置非零的 integrity_salt".to_string()))?;
+        let key = config.integrity_key.clone().unwrap_or_else(|| Zeroizing::new(salt.to_vec()));
+        let algorithm = match config.integrity_algorithm {
+            IntegrityAlgorithm::LegacyDefaultHasher => IntegrityAlgorithm::default(),
+            algorithm => algorithm,
+        };
+        Ok(Self::with_algorithm(&key, algorithm)?.with_legacy_salt(salt))
+    }
+
+    pub fn algorithm(&self) -> IntegrityAlgorithm {
+        self.algorithm
+    }
+
+    pub fn calculate_checksum(&self, data: &[u8]) -> Vec<u8> {
+        self.checksum_with(self.algorithm, data)
+            .expect("构造时已检查所选算法的密钥")
+    }
+
+    pub fn checksum_with(&self, algorithm: IntegrityAlgorithm, data: &[u8]) -> Result<Vec<u8>, SecurityError> {
+        match algorithm {
+            IntegrityAlgorithm::HmacSha256 => {
+                let mut mac = <Hmac<Sha256> as Mac>::new_from_slice(&self.hmac_key)
+                    .map_err(|e| SecurityError::KeyError(e.to_string()))?;
+                mac.update(data);
+                Ok(mac.finalize().into_bytes().to_vec())
+            }
+            IntegrityAlgorithm::Blake3 => Ok(blake3::keyed_hash(&self.blake3_key, data).as_bytes().to_vec()),
+            IntegrityAlgorithm::LegacyDefaultHasher => {
+                let salt = self.legacy_salt
+                    .ok_or_else(|| SecurityError::KeyError("校验旧数据需要 8 字节盐值".to_string()))?;
+                let mut hasher = DefaultHasher::new();
+                data.hash(&mut hasher);
+                salt.hash(&mut hasher);
+                Ok(hasher.finish().to_le_bytes().to_vec())
+            }
+        }
+    }
+
+    pub fn verify_checksum(&self, data: &[u8], expected_checksum: &[u8]) -> Result<(), SecurityError> {
+        self.verify_with(self.algorithm, data, expected_checksum)
+    }
+
+    // 按记录中保存的算法校验
+    pub fn verify_with(
+        &self,
+        algorithm: IntegrityAlgorithm,
+        data: &[u8],
+        expected_checksum: &[u8],
+    ) -> Result<(), SecurityError> {
+        let calculated = self.checksum_with(algorithm, data)?;
+        if !bool::from(calculated.as_slice().ct_eq(expected_checksum)) {
+            return Err(SecurityError::IntegrityError(format!("{} 校验失败", algorithm)));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keyed_algorithms_require_integrity_key() {
+        // 默认配置只有全零的 integrity_salt，不能作为密钥
+        for algorithm in [IntegrityAlgorithm::HmacSha256, IntegrityAlgorithm::Blake3] {
+            let config = SecurityConfig { integrity_algorithm: algorithm, ..SecurityConfig::default() };
+            assert!(matches!(DataIntegrityChecker::from_config(&config), Err(SecurityError::KeyError(_))));
+
+            let config = SecurityConfig { integrity_key: Some(Zeroizing::new(b"integrity key".to_vec())), ..config };
+            let checker = DataIntegrityChecker::from_config(&config).unwrap();
+            assert_eq!(checker.algorithm(), algorithm);
+            assert_eq!(checker.calculate_checksum(b"data").len(), 32);
+            // 普通检查器不能校验旧的 DefaultHasher 校验和
+            assert!(checker.verify_with(IntegrityAlgorithm::LegacyDefaultHasher, b"data", &[0; 8]).is_err());
+        }
+    }
+
+    #[test]
+    fn debug_output_hides_key_material() {
+        let config = SecurityConfig {
+            encryption_key: Some(Zeroizing::new(b"secret-encryption-key".to_vec())),
+            integrity_key: Some(Zeroizing::new(b"secret-integrity-key".to_vec())),
+            integrity_salt: Some(*b"pepper!!"),
+            ..SecurityConfig::default()
+        };
+        let checker = DataIntegrityChecker::from_config(&config).unwrap().with_legacy_salt(*b"pepper!!");
+        for debug in [format!("{:?}", config), format!("{:?}", checker)] {
+            for secret in ["secret", "pepper", "115, 101, 99", "112, 101, 112"] {
+                assert!(!debug.contains(secret), "{} leaks {}", debug, secret);
+            }
+        }
+        assert!(format!("{:?}", config).contains("integrity_key: Some(..)"));
+    }
+
+    #[test]
+    fn keyed_checksums_depend_on_key_and_algorithm() {
+        let checker = DataIntegrityChecker::new(b"key one").unwrap();
+        let other = DataIntegrityChecker::new(b"key two").unwrap();
+        let checksum = checker.calculate_checksum(b"record");
+        checker.verify_checksum(b"record", &checksum).unwrap();
+        assert!(checker.verify_checksum(b"record!", &checksum).is_err());
+        assert!(other.verify_checksum(b"record", &checksum).is_err());
+        assert!(checker.verify_with(IntegrityAlgorithm::Blake3, b"record", &checksum).is_err());
+        assert!(checker.verify_checksum(b"record", &checksum[..16]).is_err());
+        assert!(DataIntegrityChecker::new(&[]).is_err());
+    }
+
+    #[test]
+    fn legacy_salt_is_only_attached_on_the_legacy_path() {
+        let salt = [1, 2, 3, 4, 5, 6, 7, 8];
+        let mut hasher = DefaultHasher::new();
+        b"old record".hash(&mut hasher);
+        salt.hash(&mut hasher);
+        let old_checksum = hasher.finish().to_le_bytes();
+
+        let config = SecurityConfig {
+            integrity_key: Some(Zeroizing::new(b"k".to_vec())),
+            integrity_salt: Some(salt),
+            ..SecurityConfig::default()
+        };
+        let checker = DataIntegrityChecker::from_config(&config).unwrap();
+        assert!(checker.verify_with(IntegrityAlgorithm::LegacyDefaultHasher, b"old record", &old_checksum).is_err());
+
+        let legacy = DataIntegrityChecker::legacy_from_config(&config).unwrap();
+        legacy.verify_with(IntegrityAlgorithm::LegacyDefaultHasher, b"old record", &old_checksum).unwrap();
+        legacy.verify_checksum(b"x", &checker.calculate_checksum(b"x")).unwrap();
+
+        // 早期版本没有 integrity_key 时以盐值作为 HMAC 密钥
+        let keyless = SecurityConfig { integrity_salt: Some(salt), ..SecurityConfig::default() };
+        let legacy = DataIntegrityChecker::legacy_from_config(&keyless).unwrap();
+        let written = DataIntegrityChecker::new(&salt).unwrap().calculate_checksum(b"x");
+        legacy.verify_checksum(b"x", &written).unwrap();
+    }
+}
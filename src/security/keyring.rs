@@ -0,0 +1,579 @@
+use super::{SecurityError, ENCRYPTION_KEY_LEN};
+use argon2::{Algorithm, Argon2, Params, Version};
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+use zeroize::Zeroizing;
+
+// 目录中密钥文件的扩展名：<编号>.key
+const KEY_FILE_EXTENSION: &str = "key";
+// 口令派生密钥的盐值至少 16 字节
+const MIN_SALT_LEN: usize = 16;
+const DEFAULT_PBKDF2_ITERATIONS: u32 = 600_000;
+
+// 32 字节密钥，离开作用域时清零
+#[derive(Clone)]
+pub struct SecretKey(Zeroizing<[u8; ENCRYPTION_KEY_LEN]>);
+
+impl SecretKey {
+    pub fn from_slice(bytes: &[u8]) -> Result<Self, SecurityError> {
+        let mut key = Zeroizing::new([0u8; ENCRYPTION_KEY_LEN]);
+        if bytes.len() != ENCRYPTION_KEY_LEN {
+            return Err(SecurityError::KeyError(format!(
+                "密钥长度必须为 {} 字节，实际为 {} 字节",
+                ENCRYPTION_KEY_LEN,
+                bytes.len()
+            )));
+        }
+        key.copy_from_slice(bytes);
+        Ok(Self(key))
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        self.0.as_slice()
+    }
+}
+
+impl fmt::Debug for SecretKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("SecretKey(..)")
+    }
+}
+
+// 口令派生密钥的算法
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kdf {
+    // 默认参数为 19 MiB 内存、2 次迭代、1 个并行度
+    Argon2id { memory_kib: u32, iterations: u32, parallelism: u32 },
+    Pbkdf2Sha256 { iterations: u32 },
+}
+
+impl Default for Kdf {
+    fn default() -> Self {
+        Kdf::Argon2id {
+            memory_kib: Params::DEFAULT_M_COST,
+            iterations: Params::DEFAULT_T_COST,
+            parallelism: Params::DEFAULT_P_COST,
+        }
+    }
+}
+
+impl Kdf {
+    pub fn derive(&self, passphrase: &[u8], salt: &[u8]) -> Result<SecretKey, SecurityError> {
+        if salt.len() < MIN_SALT_LEN {
+            return Err(SecurityError::KeyError(format!("盐值至少 {} 字节，实际为 {} 字节", MIN_SALT_LEN, salt.len())));
+        }
+        let mut key = Zeroizing::new([0u8; ENCRYPTION_KEY_LEN]);
+        match *self {
+            Kdf::Argon2id { memory_kib, iterations, parallelism } => {
+                let params = Params::new(memory_kib, iterations, parallelism, Some(ENCRYPTION_KEY_LEN))
+                    .map_err(|e| SecurityError::KeyError(format!("Argon2 参数无效: {}", e)))?;
+                Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+                    .hash_password_into(passphrase, salt, key.as_mut_slice())
+                    .map_err(|e| SecurityError::KeyError(format!("Argon2 派生失败: {}", e)))?;
+            }
+            Kdf::Pbkdf2Sha256 { iterations } => {
+                if iterations == 0 {
+                    return Err(SecurityError::KeyError("PBKDF2 迭代次数不能为 0".to_string()));
+                }
+                pbkdf2::pbkdf2_hmac::<Sha256>(passphrase, salt, iterations, key.as_mut_slice());
+            }
+        }
+        Ok(SecretKey(key))
+    }
+}
+
+// 密钥来源
+#[derive(Clone)]
+pub enum KeySource {
+    // 文件内容为 32 字节原始密钥，或 base64 编码的密钥文本
+    File(PathBuf),
+    // 环境变量值为 base64 编码的密钥
+    Env(String),
+    // 口令经 KDF 派生
+    Passphrase { passphrase: Zeroizing<String>, salt: Vec<u8>, kdf: Kdf },
+}
+
+impl fmt::Debug for KeySource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KeySource::File(path) => f.debug_tuple("File").field(path).finish(),
+            KeySource::Env(name) => f.debug_tuple("Env").field(name).finish(),
+            KeySource::Passphrase { kdf, .. } => f.debug_struct("Passphrase").field("kdf", kdf).finish_non_exhaustive(),
+        }
+    }
+}
+
+impl KeySource {
+    pub fn load(&self) -> Result<SecretKey, SecurityError> {
+        match self {
+            KeySource::File(path) => read_key_file(path),
+            KeySource::Env(name) => {
+                let value = Zeroizing::new(
+                    std::env::var(name).map_err(|_| SecurityError::KeyError(format!("环境变量 {} 未设置", name)))?,
+                );
+                decode_key_text(&value).map_err(|e| SecurityError::KeyError(format!("环境变量 {}: {}", name, e)))
+            }
+            KeySource::Passphrase { passphrase, salt, kdf } => kdf.derive(passphrase.as_bytes(), salt),
+        }
+    }
+}
+
+fn decode_key_text(text: &str) -> Result<SecretKey, String> {
+    let decoded = Zeroizing::new(
+        base64::engine::general_purpose::STANDARD
+            .decode(text.trim())
+            .map_err(|e| format!("不是有效的 base64: {}", e))?,
+    );
+    SecretKey::from_slice(&decoded).map_err(|e| e.to_string())
+}
+
+fn read_key_file(path: &Path) -> Result<SecretKey, SecurityError> {
+    let contents = Zeroizing::new(
+        fs::read(path).map_err(|e| SecurityError::KeyError(format!("无法读取密钥文件 {}: {}", path.display(), e)))?,
+    );
+    if contents.len() == ENCRYPTION_KEY_LEN {
+        return SecretKey::from_slice(&contents);
+    }
+    let text = std::str::from_utf8(&contents)
+        .map_err(|_| SecurityError::KeyError(format!("{}: 既不是 32 字节原始密钥也不是 base64 文本", path.display())))?;
+    decode_key_text(text).map_err(|e| SecurityError::KeyError(format!("{}: {}", path.display(), e)))
+}
+
+// 配置文件中的单个密钥，file、env、passphrase_env 三选一
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct KeySpec {
+    pub id: u32,
+    #[serde(default)]
+    pub file: Option<PathBuf>,
+    #[serde(default)]
+    pub env: Option<String>,
+    // 存放口令的环境变量
+    #[serde(default)]
+    pub passphrase_env: Option<String>,
+    // base64 编码的盐值，passphrase_env 必填
+    #[serde(default)]
+    pub salt: Option<String>,
+    // argon2id（默认）或 pbkdf2
+    #[serde(default)]
+    pub kdf: Option<String>,
+    // Argon2 的迭代次数或 PBKDF2 的迭代次数
+    #[serde(default)]
+    pub iterations: Option<u32>,
+    #[serde(default)]
+    pub memory_kib: Option<u32>,
+}
+
+impl KeySpec {
+    pub fn source(&self) -> Result<KeySource, SecurityError> {
+        let invalid = |reason: String| SecurityError::KeyError(format!("密钥 {}: {}", self.id, reason));
+        match (&self.file, &self.env, &self.passphrase_env) {
+            (Some(path), None, None) => Ok(KeySource::File(path.clone())),
+            (None, Some(name), None) => Ok(KeySource::Env(name.clone())),
+            (None, None, Some(name)) => {
+                let passphrase = Zeroizing::new(
+                    std::env::var(name).map_err(|_| invalid(format!("环境变量 {} 未设置", name)))?,
+                );
+                let salt = self.salt.as_deref().ok_or_else(|| invalid("口令派生需要 salt".to_string()))?;
+                let salt = base64::engine::general_purpose::STANDARD
+                    .decode(salt)
+                    .map_err(|e| invalid(format!("salt 不是有效的 base64: {}", e)))?;
+                let kdf = match self.kdf.as_deref().unwrap_or("argon2id") {
+                    "argon2id" | "argon2" => Kdf::Argon2id {
+                        memory_kib: self.memory_kib.unwrap_or(Params::DEFAULT_M_COST),
+                        iterations: self.iterations.unwrap_or(Params::DEFAULT_T_COST),
+                        parallelism: Params::DEFAULT_P_COST,
+                    },
+                    "pbkdf2" | "pbkdf2-sha256" => Kdf::Pbkdf2Sha256 {
+                        iterations: self.iterations.unwrap_or(DEFAULT_PBKDF2_ITERATIONS),
+                    },
+                    other => return Err(invalid(format!("未知的 kdf '{}'，应为 argon2id 或 pbkdf2", other))),
+                };
+                Ok(KeySource::Passphrase { passphrase, salt, kdf })
+            }
+            _ => Err(invalid("file、env、passphrase_env 必须且只能设置一个".to_string())),
+        }
+    }
+}
+
+// 密钥环配置（EncryptionProcessor/DecryptionProcessor 的 keyring 参数）
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct KeyRingConfig {
+    // 存放 <编号>.key 文件的目录
+    #[serde(default)]
+    pub directory: Option<PathBuf>,
+    #[serde(default)]
+    pub keys: Vec<KeySpec>,
+    // 活动密钥编号，默认为编号最大的密钥；指定后重新扫描目录不会切换活动密钥
+    #[serde(default)]
+    pub active: Option<u32>,
+    // 重新扫描目录的间隔（秒）
+    #[serde(default)]
+    pub reload_seconds: Option<u64>,
+}
+
+struct KeyRingState {
+    keys: BTreeMap<u32, SecretKey>,
+    active: Option<u32>,
+    // 配置中指定的活动密钥，reload 不会切换到其他密钥
+    configured_active: Option<u32>,
+    directory: Option<PathBuf>,
+}
+
+struct ReloadSchedule {
+    interval: Duration,
+    last: Instant,
+}
+
+// 密钥环：多个带编号的密钥，其中一个为活动密钥
+//
+// 加密使用活动密钥并把编号写入密文头部，解密按头部的编号取密钥，所以轮换后之前的密文仍可解密。
+// KeyRing 可以克隆，所有克隆共享同一组密钥：持有一份克隆调用 rotate/set_active/reload，
+// 正在运行的管道中的加密处理器从下一个数据块开始使用新的活动密钥
+#[derive(Clone)]
+pub struct KeyRing {
+    state: Arc<RwLock<KeyRingState>>,
+    reload: Arc<Mutex<Option<ReloadSchedule>>>,
+}
+
+impl Default for KeyRing {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for KeyRing {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("KeyRing")
+            .field("key_ids", &self.key_ids())
+            .field("active", &self.active_id())
+            .finish()
+    }
+}
+
+impl KeyRing {
+    pub fn new() -> Self {
+        Self {
+            state: Arc::new(RwLock::new(KeyRingState {
+                keys: BTreeMap::new(),
+                active: None,
+                configured_active: None,
+                directory: None,
+            })),
+            reload: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    // 只有一个密钥的密钥环，该密钥为活动密钥
+    pub fn single(id: u32, key: &[u8]) -> Result<Self, SecurityError> {
+        let keyring = Self::new();
+        keyring.insert(id, SecretKey::from_slice(key)?)?;
+        keyring.set_active(id)?;
+        Ok(keyring)
+    }
+
+    // 从目录加载所有 <编号>.key 文件，编号最大的为活动密钥；reload 时重新扫描该目录
+    pub fn from_directory(directory: impl Into<PathBuf>) -> Result<Self, SecurityError> {
+        let keyring = Self::new();
+        keyring.write().directory = Some(directory.into());
+        keyring.reload()?;
+        if keyring.active_id().is_none() {
+            return Err(SecurityError::KeyError("密钥目录中没有 <编号>.key 文件".to_string()));
+        }
+        Ok(keyring)
+    }
+
+    pub fn from_config(config: &KeyRingConfig) -> Result<Self, SecurityError> {
+        let keyring = match &config.directory {
+            Some(directory) => Self::from_directory(directory)?,
+            None => Self::new(),
+        };
+        for spec in &config.keys {
+            keyring.insert(spec.id, spec.source()?.load()?)?;
+        }
+        keyring.write().configured_active = config.active;
+        match config.active.or_else(|| keyring.key_ids().last().copied()) {
+            Some(id) => keyring.set_active(id)?,
+            None => return Err(SecurityError::KeyError("密钥环中没有密钥".to_string())),
+        }
+        if let Some(seconds) = config.reload_seconds.filter(|seconds| *seconds > 0) {
+            if config.directory.is_none() {
+                return Err(SecurityError::KeyError("reload_seconds 需要同时设置 directory".to_string()));
+            }
+            keyring.set_reload_interval(Duration::from_secs(seconds));
+        }
+        Ok(keyring)
+    }
+
+    fn read(&self) -> std::sync::RwLockReadGuard<'_, KeyRingState> {
+        self.state.read().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn write(&self) -> std::sync::RwLockWriteGuard<'_, KeyRingState> {
+        self.state.write().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    // 添加密钥；编号已存在时报错，避免覆盖仍被旧密文使用的密钥
+    pub fn insert(&self, id: u32, key: SecretKey) -> Result<(), SecurityError> {
+        let mut state = self.write();
+        if state.keys.contains_key(&id) {
+            return Err(SecurityError::KeyError(format!("密钥编号 {} 已存在", id)));
+        }
+        state.keys.insert(id, key);
+        Ok(())
+    }
+
+    pub fn load(&self, id: u32, source: &KeySource) -> Result<(), SecurityError> {
+        self.insert(id, source.load()?)
+    }
+
+    pub fn set_active(&self, id: u32) -> Result<(), SecurityError> {
+        let mut state = self.write();
+        if !state.keys.contains_key(&id) {
+            return Err(SecurityError::KeyError(format!("密钥编号 {} 不存在", id)));
+        }
+        if let Some(previous) = state.active.replace(id).filter(|previous| *previous != id) {
+            info!("活动密钥从 {} 切换为 {}", previous, id);
+        }
+        Ok(())
+    }
+
+    // 添加新密钥并设为活动密钥
+    pub fn rotate(&self, id: u32, source: &KeySource) -> Result<(), SecurityError> {
+        self.load(id, source)?;
+        self.set_active(id)
+    }
+
+    // 删除不再需要的旧密钥；不能删除活动密钥
+    pub fn remove(&self, id: u32) -> Result<(), SecurityError> {
+        let mut state = self.write();
+        if state.active == Some(id) {
+            return Err(SecurityError::KeyError(format!("不能删除活动密钥 {}", id)));
+        }
+        state.keys.remove(&id);
+        Ok(())
+    }
+
+    pub fn active_id(&self) -> Option<u32> {
+        self.read().active
+    }
+
+    pub fn key_ids(&self) -> Vec<u32> {
+        self.read().keys.keys().copied().collect()
+    }
+
+    // 活动密钥的编号和密钥
+    pub fn active(&self) -> Result<(u32, SecretKey), SecurityError> {
+        self.reload_if_due();
+        let state = self.read();
+        let id = state.active.ok_or_else(|| SecurityError::KeyError("密钥环没有活动密钥".to_string()))?;
+        Ok((id, state.keys[&id].clone()))
+    }
+
+    pub fn get(&self, id: u32) -> Option<SecretKey> {
+        self.reload_if_due();
+        self.read().keys.get(&id).cloned()
+    }
+
+    // 所有密钥，用于解密没有密钥编号的旧密文
+    pub fn all(&self) -> Vec<(u32, SecretKey)> {
+        self.read().keys.iter().map(|(id, key)| (*id, key.clone())).collect()
+    }
+
+    // 按间隔在使用密钥时重新扫描目录
+    pub fn set_reload_interval(&self, interval: Duration) {
+        *self.reload.lock().unwrap_or_else(|poisoned| poisoned.into_inner()) =
+            Some(ReloadSchedule { interval, last: Instant::now() });
+    }
+
+    fn reload_if_due(&self) {
+        let due = {
+            let mut schedule = self.reload.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+            match schedule.as_mut() {
+                Some(schedule) if schedule.last.elapsed() >= schedule.interval => {
+                    schedule.last = Instant::now();
+                    true
+                }
+                _ => false,
+            }
+        };
+        if due {
+            if let Err(e) = self.reload() {
+                warn!("重新加载密钥目录失败，继续使用现有密钥: {}", e);
+            }
+        }
+    }
+
+    // 重新扫描密钥目录并加入新出现的密钥，已有编号的密钥不会被替换。编号大于当前活动密钥的新密钥中
+    // 编号最大的成为活动密钥；配置中指定了 active 时只会切换到该密钥。返回新加入的密钥数量
+    pub fn reload(&self) -> Result<usize, SecurityError> {
+        let Some(directory) = self.read().directory.clone() else {
+            return Ok(0);
+        };
+        let entries = fs::read_dir(&directory)
+            .map_err(|e| SecurityError::KeyError(format!("无法读取密钥目录 {}: {}", directory.display(), e)))?;
+
+        let mut found = Vec::new();
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.extension().and_then(|extension| extension.to_str()) != Some(KEY_FILE_EXTENSION) {
+                continue;
+            }
+            let Some(id) = path.file_stem().and_then(|stem| stem.to_str()).and_then(|stem| stem.parse::<u32>().ok())
+            else {
+                continue;
+            };
+            if !self.read().keys.contains_key(&id) {
+                found.push((id, path));
+            }
+        }
+        found.sort_by_key(|(id, _)| *id);
+
+        let mut added = 0;
+        for (id, path) in &found {
+            let key = read_key_file(path)?;
+            self.write().keys.entry(*id).or_insert(key);
+            info!("已从 {} 加载密钥 {}", path.display(), id);
+            added += 1;
+        }
+        let next_active = {
+            let state = self.read();
+            match state.configured_active {
+                Some(id) => found.iter().any(|(new_id, _)| *new_id == id).then_some(id),
+                None => found.last().map(|(id, _)| *id).filter(|id| state.active.is_none_or(|active| *id > active)),
+            }
+        };
+        if let Some(id) = next_active {
+            self.set_active(id)?;
+        }
+        Ok(added)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::security::{DataEncryptor, EncryptionAlgorithm};
+
+    // 每个测试使用自己的密钥目录，结束时删除
+    struct KeyDirectory(PathBuf);
+
+    impl KeyDirectory {
+        fn new(name: &str) -> Self {
+            let path = std::env::temp_dir().join(format!("pelican-keyring-{}-{}", name, std::process::id()));
+            let _ = fs::remove_dir_all(&path);
+            fs::create_dir_all(&path).unwrap();
+            Self(path)
+        }
+
+        fn add(&self, id: u32) {
+            fs::write(self.0.join(format!("{}.key", id)), [id as u8; ENCRYPTION_KEY_LEN]).unwrap();
+        }
+    }
+
+    impl Drop for KeyDirectory {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn passphrase(text: &str) -> KeySource {
+        KeySource::Passphrase {
+            passphrase: Zeroizing::new(text.to_string()),
+            salt: b"0123456789abcdef".to_vec(),
+            kdf: Kdf::Pbkdf2Sha256 { iterations: 1000 },
+        }
+    }
+
+    #[test]
+    fn decrypts_by_key_id_after_rotation() {
+        let keyring = KeyRing::single(1, &[1; 32]).unwrap();
+        let encryptor = DataEncryptor::with_keyring(keyring.clone(), EncryptionAlgorithm::ChaCha20Poly1305);
+        let before = encryptor.encrypt(b"before rotation", 0).unwrap();
+
+        // 在另一份克隆上轮换，加密器从下一条消息开始使用新密钥
+        keyring.rotate(2, &passphrase("second key")).unwrap();
+        let after = encryptor.encrypt(b"after rotation", 1).unwrap();
+        assert_eq!(&before[2..6], &1u32.to_be_bytes());
+        assert_eq!(&after[2..6], &2u32.to_be_bytes());
+        assert_eq!(encryptor.decrypt(&before, 0).unwrap(), b"before rotation");
+        assert_eq!(encryptor.decrypt(&after, 1).unwrap(), b"after rotation");
+
+        // 只有编号 2 的密钥环不能解密编号 1 的密文
+        let newer = KeyRing::new();
+        newer.load(2, &passphrase("second key")).unwrap();
+        newer.set_active(2).unwrap();
+        let decryptor = DataEncryptor::with_keyring(newer, EncryptionAlgorithm::Aes256Gcm);
+        assert_eq!(decryptor.decrypt(&after, 1).unwrap(), b"after rotation");
+        assert!(matches!(decryptor.decrypt(&before, 0), Err(SecurityError::DecryptionError(message)) if message.contains('1')));
+
+        keyring.remove(1).unwrap();
+        assert!(encryptor.decrypt(&before, 0).is_err());
+        assert!(keyring.remove(2).is_err());
+    }
+
+    #[test]
+    fn rejects_duplicate_ids_and_unknown_active_key() {
+        let keyring = KeyRing::single(7, &[7; 32]).unwrap();
+        assert!(keyring.insert(7, SecretKey::from_slice(&[8; 32]).unwrap()).is_err());
+        assert_eq!(keyring.get(7).unwrap().as_bytes(), &[7; 32]);
+        assert!(keyring.set_active(8).is_err());
+        assert_eq!(keyring.active_id(), Some(7));
+        assert!(KeyRing::new().active().is_err());
+        assert!(SecretKey::from_slice(&[0; 16]).is_err());
+    }
+
+    #[test]
+    fn reload_only_advances_to_newer_key() {
+        let directory = KeyDirectory::new("advance");
+        directory.add(5);
+        let keyring = KeyRing::from_directory(&directory.0).unwrap();
+        assert_eq!(keyring.active_id(), Some(5));
+
+        // 后加入的旧编号密钥只用于解密，不会成为活动密钥
+        directory.add(3);
+        assert_eq!(keyring.reload().unwrap(), 1);
+        assert_eq!(keyring.key_ids(), vec![3, 5]);
+        assert_eq!(keyring.active_id(), Some(5));
+
+        directory.add(8);
+        directory.add(9);
+        assert_eq!(keyring.reload().unwrap(), 2);
+        assert_eq!(keyring.active_id(), Some(9));
+        assert_eq!(keyring.reload().unwrap(), 0);
+    }
+
+    #[test]
+    fn reload_keeps_configured_active_key() {
+        let directory = KeyDirectory::new("configured");
+        directory.add(5);
+        directory.add(7);
+        let config = KeyRingConfig { directory: Some(directory.0.clone()), active: Some(5), ..KeyRingConfig::default() };
+        let keyring = KeyRing::from_config(&config).unwrap();
+        assert_eq!(keyring.active_id(), Some(5));
+
+        directory.add(9);
+        keyring.reload().unwrap();
+        assert_eq!(keyring.key_ids(), vec![5, 7, 9]);
+        assert_eq!(keyring.active_id(), Some(5));
+        assert_eq!(keyring.active().unwrap().1.as_bytes(), &[5; 32]);
+    }
+
+    #[test]
+    fn passphrase_keys_are_deterministic() {
+        let first = passphrase("correct horse").load().unwrap();
+        assert_eq!(first.as_bytes(), passphrase("correct horse").load().unwrap().as_bytes());
+        assert_ne!(first.as_bytes(), passphrase("battery staple").load().unwrap().as_bytes());
+        assert!(Kdf::default().derive(b"secret", b"short").is_err());
+        assert!(Kdf::Pbkdf2Sha256 { iterations: 0 }.derive(b"secret", b"0123456789abcdef").is_err());
+    }
+}
@@ -0,0 +1,255 @@
+use super::container::{read_full, SecureRecord};
+use super::{DataIntegrityChecker, IntegrityAlgorithm, SecurityConfig, SecurityError};
+use std::fs::File;
+use std::io::{self, BufReader, Read};
+use std::path::Path;
+
+// 单条旧记录的上限，防止损坏的长度字段导致分配过大的缓冲区
+const MAX_RECORD_LEN: usize = 256 * 1024 * 1024;
+// 数据长度字段(u32 LE)
+const LEN_FIELD: usize = 4;
+
+// 容器格式之前的 SecureFileSink 文件读取器
+//
+// SecureFileReader 不会自动按旧格式读取：旧格式没有文件头，任何能写文件的人都可以把容器文件改写成旧格式，
+// 只有明确要读取旧文件时才使用这个读取器。
+// 每条记录为 数据长度(u32 LE) | 数据 | 校验值，校验值有两种布局：
+//   最早的版本：DefaultHasher 加 integrity_salt 的 8 字节校验和
+//   之后的版本：算法编号(u8) | 校验值（HMAC-SHA256/BLAKE3 为 32 字节）
+// 新版本会接着旧文件追加，同一文件中可能先后出现两种布局，每条记录按能通过校验的布局解析。
+// 旧格式没有加密、序号和时间：sequence 为记录位置，timestamp_ns 为 0。
+// 校验值不覆盖记录位置，记录被删除或调换顺序无法发现；没有启用完整性检查写入的旧文件没有记录边界，不能读取
+pub struct LegacyFileReader<R: Read> {
+    reader: R,
+    // 预读的字节：判断布局时需要读到数据之后的校验值
+    buffer: Vec<u8>,
+    checker: DataIntegrityChecker,
+    next_index: u64,
+    finished: bool,
+}
+
+impl<R: Read> LegacyFileReader<R> {
+    // 使用 config 的 integrity_key 和 integrity_salt 校验（见 DataIntegrityChecker::legacy_from_config），
+    // 不接受默认配置中全零的 integrity_salt
+    pub fn new(reader: R, config: &SecurityConfig) -> Result<Self, SecurityError> {
+        Ok(Self {
+            reader,
+            buffer: Vec::new(),
+            checker: DataIntegrityChecker::legacy_from_config(config)?,
+            next_index: 0,
+            finished: false,
+        })
+    }
+
+    // 读取下一条记录，文件正常结束时返回 None
+    pub fn next_record(&mut self) -> Result<Option<SecureRecord>, SecurityError> {
+        if self.finished {
+            return Ok(None);
+        }
+        let result = self.read_record();
+        if !matches!(result, Ok(Some(_))) {
+            self.finished = true;
+        }
+        result
+    }
+
+    fn read_record(&mut self) -> Result<Option<SecureRecord>, SecurityError> {
+        let index = self.next_index;
+        let truncated = || SecurityError::FormatError(format!("文件在第 {} 条记录处被截断", index));
+
+        match self.fill(LEN_FIELD)? {
+            0 => return Ok(None),
+            LEN_FIELD => {}
+            _ => return Err(truncated()),
+        }
+        let data_len = u32::from_le_bytes(self.buffer[..LEN_FIELD].try_into().expect("4 字节")) as usize;
+        if data_len > MAX_RECORD_LEN {
+            return Err(SecurityError::FormatError(format!("第 {} 条记录的数据长度无效: {}", index, data_len)));
+        }
+        let data_end = LEN_FIELD + data_len;
+
+        // 算法编号 | 校验值
+        self.fill(data_end + 1)?;
+        let mut tagged_truncated = false;
+        if let Some(algorithm) = self.buffer.get(data_end).and_then(|id| IntegrityAlgorithm::from_id(*id)) {
+            let record_end = data_end + 1 + algorithm.tag_len();
+            if self.fill(record_end)? < record_end {
+                tagged_truncated = true;
+            } else if self
+                .checker
+                .verify_with(algorithm, &self.buffer[LEN_FIELD..data_end], &self.buffer[data_end + 1..record_end])
+                .is_ok()
+            {
+                return Ok(Some(self.take_record(index, data_end, record_end)));
+            }
+        }
+
+        // 最早的 8 字节 DefaultHasher 校验和
+        let record_end = data_end + IntegrityAlgorithm::LegacyDefaultHasher.tag_len();
+        if self.fill(record_end)? < record_end {
+            return Err(truncated());
+        }
+        let verified = self.checker.verify_with(
+            IntegrityAlgorithm::LegacyDefaultHasher,
+            &self.buffer[LEN_FIELD..data_end],
+            &self.buffer[data_end..record_end],
+        );
+        match verified {
+            Ok(()) => Ok(Some(self.take_record(index, data_end, record_end))),
+            Err(_) if tagged_truncated => Err(truncated()),
+            Err(_) => Err(SecurityError::IntegrityError(format!(
+                "第 {} 条记录: 校验失败（数据被改动，或 integrity_key/integrity_salt 与写入时不同）",
+                index
+            ))),
+        }
+    }
+
+    // 预读到缓冲区至少有 len 字节，返回缓冲区长度（文件结束时可能小于 len）
+    fn fill(&mut self, len: usize) -> io::Result<usize> {
+        let start = self.buffer.len();
+        if start < len {
+            self.buffer.resize(len, 0);
+            let read = read_full(&mut self.reader, &mut self.buffer[start..])?;
+            self.buffer.truncate(start + read);
+        }
+        Ok(self.buffer.len())
+    }
+
+    fn take_record(&mut self, index: u64, data_end: usize, record_end: usize) -> SecureRecord {
+        let data = self.buffer[LEN_FIELD..data_end].to_vec();
+        self.buffer.drain(..record_end);
+        self.next_index += 1;
+        SecureRecord { index, sequence: index, timestamp_ns: 0, data }
+    }
+}
+
+impl LegacyFileReader<BufReader<File>> {
+    pub fn open(path: impl AsRef<Path>, config: &SecurityConfig) -> Result<Self, SecurityError> {
+        Self::new(BufReader::new(File::open(path)?), config)
+    }
+}
+
+impl<R: Read> Iterator for LegacyFileReader<R> {
+    type Item = Result<SecureRecord, SecurityError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_record().transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::security::Zeroizing;
+    use std::collections::hash_map::DefaultHasher;
+    use std::hash::{Hash, Hasher};
+
+    const SALT: [u8; 8] = [1, 2, 3, 4, 5, 6, 7, 8];
+
+    // 最早的 SecureFileSink：数据长度 | 数据 | DefaultHasher(数据, 盐值)
+    fn baseline_record(data: &[u8]) -> Vec<u8> {
+        let mut hasher = DefaultHasher::new();
+        data.hash(&mut hasher);
+        SALT.hash(&mut hasher);
+        let mut record = (data.len() as u32).to_le_bytes().to_vec();
+        record.extend_from_slice(data);
+        record.extend_from_slice(&hasher.finish().to_le_bytes());
+        record
+    }
+
+    // 之后的 SecureFileSink：数据长度 | 数据 | 算法编号 | 校验值
+    fn tagged_record(data: &[u8], key: &[u8], algorithm: IntegrityAlgorithm) -> Vec<u8> {
+        let checker = DataIntegrityChecker::with_algorithm(key, algorithm).unwrap();
+        let mut record = (data.len() as u32).to_le_bytes().to_vec();
+        record.extend_from_slice(data);
+        record.push(algorithm.id());
+        record.extend_from_slice(&checker.calculate_checksum(data));
+        record
+    }
+
+    fn config(integrity_key: Option<&[u8]>) -> SecurityConfig {
+        SecurityConfig {
+            integrity_key: integrity_key.map(|key| Zeroizing::new(key.to_vec())),
+            integrity_salt: Some(SALT),
+            ..SecurityConfig::default()
+        }
+    }
+
+    fn read(bytes: &[u8], config: &SecurityConfig) -> Vec<Result<SecureRecord, SecurityError>> {
+        LegacyFileReader::new(bytes, config).unwrap().collect()
+    }
+
+    #[test]
+    fn reads_baseline_format_file() {
+        let mut file = baseline_record(b"first chunk");
+        file.extend(baseline_record(b""));
+        file.extend(baseline_record(&[0xab; 300]));
+
+        let records: Vec<_> = read(&file, &config(None)).into_iter().map(Result::unwrap).collect();
+        assert_eq!(records.len(), 3);
+        assert_eq!(records[0], SecureRecord { index: 0, sequence: 0, timestamp_ns: 0, data: b"first chunk".to_vec() });
+        assert!(records[1].data.is_empty());
+        assert_eq!(records[2].data, vec![0xab; 300]);
+    }
+
+    #[test]
+    fn reads_tagged_records_appended_to_baseline_file() {
+        let key = b"integrity key".as_slice();
+        let mut file = baseline_record(b"old");
+        file.extend(tagged_record(b"hmac", key, IntegrityAlgorithm::HmacSha256));
+        file.extend(tagged_record(b"blake3", key, IntegrityAlgorithm::Blake3));
+        // 没有设置 integrity_key 时写入的记录以盐值为密钥
+        let mut keyless = tagged_record(b"keyless", &SALT, IntegrityAlgorithm::HmacSha256);
+
+        let records: Vec<_> = read(&file, &config(Some(key))).into_iter().map(Result::unwrap).collect();
+        let data: Vec<_> = records.iter().map(|record| record.data.as_slice()).collect();
+        assert_eq!(data, vec![b"old".as_slice(), b"hmac", b"blake3"]);
+
+        keyless.extend(baseline_record(b"tail"));
+        let records: Vec<_> = read(&keyless, &config(None)).into_iter().map(Result::unwrap).collect();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[1].index, 1);
+    }
+
+    #[test]
+    fn detects_modified_records_and_wrong_keys() {
+        let mut file = baseline_record(b"record one");
+        file.extend(baseline_record(b"record two"));
+        let mut tampered = file.clone();
+        tampered[LEN_FIELD + 1] ^= 0x20;
+        let records = read(&tampered, &config(None));
+        assert_eq!(records.len(), 1);
+        assert!(matches!(&records[0], Err(SecurityError::IntegrityError(message)) if message.contains("第 0 条")));
+
+        let other_salt = SecurityConfig { integrity_salt: Some([9; 8]), ..config(None) };
+        assert!(read(&file, &other_salt)[0].is_err());
+
+        let tagged = tagged_record(b"data", b"right key", IntegrityAlgorithm::Blake3);
+        assert!(matches!(read(&tagged, &config(Some(b"wrong key")))[0], Err(SecurityError::IntegrityError(_))));
+    }
+
+    #[test]
+    fn reports_truncated_tail() {
+        let mut file = baseline_record(b"complete");
+        let partial = tagged_record(b"partial", b"k", IntegrityAlgorithm::HmacSha256);
+        file.extend_from_slice(&partial[..partial.len() - 5]);
+        let records = read(&file, &config(Some(b"k")));
+        assert_eq!(records.len(), 2);
+        assert!(records[0].is_ok());
+        assert!(matches!(&records[1], Err(SecurityError::FormatError(message)) if message.contains("截断")));
+
+        let records = read(&[1, 0], &config(None));
+        assert!(matches!(records[0], Err(SecurityError::FormatError(_))));
+        assert!(read(&[], &config(None)).is_empty());
+    }
+
+    #[test]
+    fn refuses_default_zero_salt() {
+        // 全零盐值是默认配置，等于没有密钥
+        for config in [SecurityConfig::default(), SecurityConfig { integrity_salt: None, ..SecurityConfig::default() }] {
+            assert!(matches!(LegacyFileReader::new(&[][..], &config), Err(SecurityError::KeyError(_))));
+        }
+        let with_key = SecurityConfig { integrity_key: Some(Zeroizing::new(b"k".to_vec())), ..SecurityConfig::default() };
+        assert!(matches!(LegacyFileReader::new(&[][..], &with_key), Err(SecurityError::KeyError(_))));
+    }
+}
@@ -1,6 +1,25 @@
-use std::collections::hash_map::DefaultHasher;
-use std::hash::{Hash, Hasher};
-use std::sync::Arc;
+pub mod audit;
+pub mod container;
+pub mod encryption;
+pub mod integrity;
+pub mod keyring;
+pub mod legacy;
+
+pub use audit::{
+    AuditHeader, AuditIssue, AuditLogVerifier, AuditLogWriter, AuditReport, AUDIT_HEADER_LEN, AUDIT_MAGIC, AUDIT_VERSION,
+    DEFAULT_CHECKPOINT_EVERY,
+};
+pub use container::{
+    ContainerHeader, SecureFileReader, SecureFileWriter, SecureRecord, CONTAINER_HEADER_LEN, CONTAINER_MAGIC,
+    CONTAINER_VERSION,
+};
+pub use encryption::{DataEncryptor, EncryptionAlgorithm, ENCRYPTION_FORMAT_VERSION, ENCRYPTION_KEY_LEN};
+pub use integrity::{DataIntegrityChecker, IntegrityAlgorithm};
+pub use keyring::{Kdf, KeyRing, KeyRingConfig, KeySource, KeySpec, SecretKey};
+pub use legacy::LegacyFileReader;
+pub use zeroize::Zeroizing;
+
+use std::fmt;
 use thiserror::Error;
 
 #[derive(Debug, Error)]
@@ -13,99 +32,27 @@ pub enum SecurityError {
     KeyError(String),
     #[error("完整性检查失败: {0}")]
     IntegrityError(String),
-}
-
-// 简化版加密器 - 使用简单的XOR加密
-pub struct DataEncryptor {
-    key: Arc<[u8]>,
-}
-
-impl DataEncryptor {
-    pub fn new(key: &[u8]) -> Result<Self, SecurityError> {
-        if key.len() < 16 {
-            return Err(SecurityError::KeyError("密钥长度至少16字节".to_string()));
-        }
-
-        Ok(Self {
-            key: Arc::from(key),
-        })
-    }
-
-    pub fn encrypt(&self, data: &[u8]) -> Result<Vec<u8>, SecurityError> {
-        let mut result = Vec::with_capacity(data.len() + 4);
-
-        // 添加数据长度前缀
-        let len = data.len() as u32;
-        result.extend_from_slice(&len.to_le_bytes());
-
-        // XOR加密
-        for (i, &byte) in data.iter().enumerate() {
-            let key_byte = self.key[i % self.key.len()];
-            result.push(byte ^ key_byte);
-        }
-
-        Ok(result)
-    }
-
-    pub fn decrypt(&self, encrypted_data: &[u8]) -> Result<Vec<u8>, SecurityError> {
-        if encrypted_data.len() < 4 {
-            return Err(SecurityError::DecryptionError("数据太短".to_string()));
-        }
-
-        // 解析长度
-        let len_bytes = &encrypted_data[0..4];
-        let expected_len = u32::from_le_bytes([len_bytes[0], len_bytes[1], len_bytes[2], len_bytes[3]]) as usize;
-
-        let data = &encrypted_data[4..];
-        if data.len() != expected_len {
-            return Err(SecurityError::DecryptionError("数据长度不匹配".to_string()));
-        }
-
-        // XOR解密
-        let mut result = Vec::with_capacity(expected_len);
-        for (i, &byte) in data.iter().enumerate() {
-            let key_byte = self.key[i % self.key.len()];
-            result.push(byte ^ key_byte);
-        }
-
-        Ok(result)
-    }
-}
-
-// 简化版完整性检查器
-pub struct DataIntegrityChecker {
-    salt: [u8; 8],
-}
-
-impl DataIntegrityChecker {
-    pub fn new(salt: [u8; 8]) -> Self {
-        Self { salt }
-    }
-
-    pub fn calculate_checksum(&self, data: &[u8]) -> Vec<u8> {
-        let mut hasher = DefaultHasher::new();
-        data.hash(&mut hasher);
-        self.salt.hash(&mut hasher);
-
-        let hash = hasher.finish();
-        hash.to_le_bytes().to_vec()
-    }
-
-    pub fn verify_checksum(&self, data: &[u8], expected_checksum: &[u8]) -> Result<(), SecurityError> {
-        let calculated = self.calculate_checksum(data);
-        if calculated != expected_checksum {
-            return Err(SecurityError::IntegrityError("数据完整性检查失败".to_string()));
-        }
-        Ok(())
-    }
+    #[error("文件格式错误: {0}")]
+    FormatError(String),
+    #[error("I/O 错误: {0}")]
+    Io(#[from] std::io::Error),
 }
 
 // 安全配置
-#[derive(Debug, Clone)]
+//
+// 密钥在释放时清零，Debug 输出中不包含密钥内容
+#[derive(Clone)]
 pub struct SecurityConfig {
     pub enable_encryption: bool,
     pub enable_integrity_check: bool,
-    pub encryption_key: Option<Vec<u8>>,
+    pub encryption_algorithm: EncryptionAlgorithm,
+    // 单个加密密钥（编号 0）；设置了 keyring 时忽略
+    pub encryption_key: Option<Zeroizing<Vec<u8>>>,
+    pub keyring: Option<KeyRing>,
+    pub integrity_algorithm: IntegrityAlgorithm,
+    // 完整性密钥，HMAC-SHA256 和 BLAKE3 必须设置
+    pub integrity_key: Option<Zeroizing<Vec<u8>>>,
+    // 旧版 DefaultHasher 校验和的盐值，只在校验旧文件时使用
     pub integrity_salt: Option<[u8; 8]>,
 }
 
@@ -114,8 +61,28 @@ impl Default for SecurityConfig {
         Self {
             enable_encryption: false,
             enable_integrity_check: true,
+            encryption_algorithm: EncryptionAlgorithm::default(),
             encryption_key: None,
+            keyring: None,
+            integrity_algorithm: IntegrityAlgorithm::default(),
+            integrity_key: None,
             integrity_salt: Some([0; 8]),
         }
     }
+}
+
+impl fmt::Debug for SecurityConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let redacted = |set: bool| if set { "Some(..)" } else { "None" };
+        f.debug_struct("SecurityConfig")
+            .field("enable_encryption", &self.enable_encryption)
+            .field("enable_integrity_check", &self.enable_integrity_check)
+            .field("encryption_algorithm", &self.encryption_algorithm)
+            .field("encryption_key", &format_args!("{}", redacted(self.encryption_key.is_some())))
+            .field("keyring", &self.keyring)
+            .field("integrity_algorithm", &self.integrity_algorithm)
+            .field("integrity_key", &format_args!("{}", redacted(self.integrity_key.is_some())))
+            .field("integrity_salt", &format_args!("{}", redacted(self.integrity_salt.is_some())))
+            .finish()
+    }
 }
\ No newline at end of file
@@ -1,11 +1,7 @@
 use async_trait::async_trait;
 use crate::DataChunk;
 use super::Sink;
-use std::fs::OpenOptions;
-use std::io::Write;
-use tokio::sync::mpsc;
-use tracing::{info, error};
-use std::time::{SystemTime, UNIX_EPOCH};
+use tracing::info;
 
 // 性能指标输出端
 pub struct MetricsSink {
@@ -49,6 +45,12 @@ impl MetricsSink {
     }
 }
 
+impl Default for MetricsSink {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 #[derive(Debug)]
 pub struct Metrics {
     pub packet_count: u64,
@@ -65,7 +67,7 @@ impl Sink for MetricsSink {
         self.total_bytes += chunk.data.len() as u64;
 
         // 每50个包打印一次性能指标
-        if self.packet_count % 50 == 0 {
+        if self.packet_count.is_multiple_of(50) {
             let metrics = self.get_metrics();
             info!(
                 "📊 Performance Metrics - Packets: {}/s, Throughput: {:.2} KB/s, Total: {} packets",
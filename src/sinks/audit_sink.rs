@@ -0,0 +1,126 @@
+use async_trait::async_trait;
+use crate::security::{AuditLogWriter, DEFAULT_CHECKPOINT_EVERY};
+use crate::{DataChunk, Sink};
+use base64::Engine;
+use ed25519_dalek::SigningKey;
+use std::fs::File;
+use std::io::BufWriter;
+use std::time::{Duration, Instant};
+use tracing::info;
+
+// 防篡改的审计日志输出：每条记录带前一条的哈希，定期写入 Ed25519 签名的检查点（格式见 AuditLogWriter）
+//
+// 只追加：已有日志会先完整校验，再接着写入；日志被改动时拒绝打开。关闭时写入关闭检查点。
+// 用 AuditLogVerifier 或 `pelican verify-audit` 按公钥校验，打开时日志中会输出公钥
+pub struct AuditLogSink {
+    file_path: String,
+    signing_key: SigningKey,
+    checkpoint_every: u64,
+    checkpoint_interval: Option<Duration>,
+    writer: Option<AuditLogWriter<BufWriter<File>>>,
+    last_checkpoint: Instant,
+    records: u64,
+    name: String,
+}
+
+impl AuditLogSink {
+    // 默认每 1000 条记录写一个检查点
+    pub fn new(file_path: &str, signing_key: SigningKey) -> Self {
+        Self {
+            file_path: file_path.to_string(),
+            signing_key,
+            checkpoint_every: DEFAULT_CHECKPOINT_EVERY,
+            checkpoint_interval: None,
+            writer: None,
+            last_checkpoint: Instant::now(),
+            records: 0,
+            name: "AuditLogSink".to_string(),
+        }
+    }
+
+    pub fn with_name(mut self, name: &str) -> Self {
+        self.name = name.to_string();
+        self
+    }
+
+    // 每隔 records 条记录写一个检查点，0 表示只按时间和在关闭时写
+    pub fn with_checkpoint_every(mut self, records: u64) -> Self {
+        self.checkpoint_every = records;
+        self
+    }
+
+    // 距上一个检查点超过 interval 且有新记录时写一个检查点（在记录到达时检查）
+    pub fn with_checkpoint_interval(mut self, interval: Duration) -> Self {
+        self.checkpoint_interval = Some(interval);
+        self
+    }
+
+    // 打开输出文件并在整个运行期间保持
+    fn ensure_open(&mut self) -> anyhow::Result<&mut AuditLogWriter<BufWriter<File>>> {
+        if self.writer.is_none() {
+            let writer = AuditLogWriter::open(&self.file_path, self.signing_key.clone())?
+                .with_checkpoint_every(self.checkpoint_every);
+            info!(
+                "[{}] 已打开审计日志: {}（已有 {} 条记录，公钥 {}）",
+                self.name,
+                self.file_path,
+                writer.record_count(),
+                base64::engine::general_purpose::STANDARD.encode(writer.verifying_key().as_bytes())
+            );
+            self.last_checkpoint = Instant::now();
+            self.writer = Some(writer);
+        }
+        Ok(self.writer.as_mut().expect("writer opened above"))
+    }
+}
+
+#[async_trait]
+impl Sink for AuditLogSink {
+    async fn open(&mut self) -> anyhow::Result<()> {
+        self.ensure_open()?;
+        Ok(())
+    }
+
+    async fn send(&mut self, chunk: DataChunk) -> anyhow::Result<()> {
+        let interval = self.checkpoint_interval;
+        let elapsed = self.last_checkpoint.elapsed();
+        let writer = self.ensure_open()?;
+        writer.append(chunk.sequence, chunk.time_ns(), &chunk.data)?;
+        if interval.is_some_and(|interval| elapsed >= interval) && writer.unsigned_records() > 0 {
+            writer.checkpoint()?;
+        }
+        if writer.unsigned_records() == 0 {
+            self.last_checkpoint = Instant::now();
+        }
+        self.records += 1;
+        Ok(())
+    }
+
+    async fn flush(&mut self) -> anyhow::Result<()> {
+        if let Some(writer) = self.writer.as_mut() {
+            writer.flush()?;
+            writer.get_ref().get_ref().sync_data()?;
+        }
+        Ok(())
+    }
+
+    async fn close(&mut self) -> anyhow::Result<()> {
+        if let Some(mut writer) = self.writer.take() {
+            writer.seal()?;
+            writer.flush()?;
+            writer.get_ref().get_ref().sync_data()?;
+            info!(
+                "[{}] 已关闭审计日志: {}，本次写入 {} 条记录，共 {} 条",
+                self.name,
+                self.file_path,
+                self.records,
+                writer.record_count()
+            );
+        }
+        Ok(())
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
@@ -0,0 +1,105 @@
+use async_trait::async_trait;
+use crate::{DataChunk, Sink};
+use base64::Engine;
+use std::fs::{File, OpenOptions};
+use std::io::{BufWriter, Write};
+use tracing::info;
+
+// JSON Lines 文件输出：每个数据块写成一行 JSON
+// 负载本身是 JSON（例如死信记录）时原样写出，
+// 否则写成 { id, sequence, ingest_time_ns, event_time_ns, data(base64), key, source_id, attributes }
+pub struct JsonLinesSink {
+    file_path: String,
+    append: bool,
+    writer: Option<BufWriter<File>>,
+    name: String,
+}
+
+impl JsonLinesSink {
+    pub fn new(file_path: &str) -> Self {
+        Self {
+            file_path: file_path.to_string(),
+            append: true,
+            writer: None,
+            name: "JsonLinesSink".to_string(),
+        }
+    }
+
+    pub fn with_name(mut self, name: &str) -> Self {
+        self.name = name.to_string();
+        self
+    }
+
+    // false 时每次运行清空已有文件
+    pub fn with_append(mut self, append: bool) -> Self {
+        self.append = append;
+        self
+    }
+
+    fn ensure_open(&mut self) -> anyhow::Result<&mut BufWriter<File>> {
+        if self.writer.is_none() {
+            let file = OpenOptions::new()
+                .create(true)
+                .write(true)
+                .append(self.append)
+                .truncate(!self.append)
+                .open(&self.file_path)?;
+            info!("[{}] 已打开输出文件: {}", self.name, self.file_path);
+            self.writer = Some(BufWriter::new(file));
+        }
+        Ok(self.writer.as_mut().expect("writer opened above"))
+    }
+}
+
+fn to_json_line(chunk: &DataChunk) -> anyhow::Result<Vec<u8>> {
+    let value = match serde_json::from_slice::<serde_json::Value>(&chunk.data) {
+        Ok(value) => value,
+        Err(_) => serde_json::json!({
+            "id": chunk.id,
+            "sequence": chunk.sequence,
+            "ingest_time_ns": chunk.ingest_time_ns,
+            "event_time_ns": chunk.event_time_ns,
+            "data": base64::engine::general_purpose::STANDARD.encode(&chunk.data),
+            "key": chunk.key.as_deref(),
+            "source_id": chunk.source_id.as_deref(),
+            "attributes": chunk.attributes.as_ref(),
+        }),
+    };
+    let mut line = serde_json::to_vec(&value)?;
+    line.push(b'\n');
+    Ok(line)
+}
+
+#[async_trait]
+impl Sink for JsonLinesSink {
+    async fn open(&mut self) -> anyhow::Result<()> {
+        self.ensure_open()?;
+        Ok(())
+    }
+
+    async fn send(&mut self, chunk: DataChunk) -> anyhow::Result<()> {
+        let line = to_json_line(&chunk)?;
+        self.ensure_open()?.write_all(&line)?;
+        Ok(())
+    }
+
+    async fn flush(&mut self) -> anyhow::Result<()> {
+        if let Some(writer) = self.writer.as_mut() {
+            writer.flush()?;
+            writer.get_ref().sync_data()?;
+        }
+        Ok(())
+    }
+
+    async fn close(&mut self) -> anyhow::Result<()> {
+        if let Some(mut writer) = self.writer.take() {
+            writer.flush()?;
+            info!("[{}] 已关闭输出文件: {}", self.name, self.file_path);
+        }
+        Ok(())
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
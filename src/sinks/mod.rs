@@ -1,18 +1,40 @@
 pub mod advanced_sinks;
+pub mod audit_sink;
+pub mod file_sink;
+pub mod pcap_sink;
 pub mod security_sink;  // 添加安全输出端模块
 
 // 重新导出公共类型
 pub use advanced_sinks::{MetricsSink, Metrics};
+pub use audit_sink::AuditLogSink;
+pub use file_sink::JsonLinesSink;
+pub use pcap_sink::PcapFileSink;
 pub use security_sink::SecureFileSink;  // 导出安全输出端
 
 use async_trait::async_trait;
 use crate::DataChunk;
+use std::collections::BTreeMap;
 use tracing::info;
 
 #[async_trait]
 pub trait Sink: Send + Sync {
     async fn send(&mut self, chunk: DataChunk) -> anyhow::Result<()>;
     fn name(&self) -> &str;
+
+    // 管道启动前调用，用于打开文件、套接字等资源
+    async fn open(&mut self) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    // 管道停止时调用，把缓冲的数据写出
+    async fn flush(&mut self) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    // 刷新之后调用，释放资源
+    async fn close(&mut self) -> anyhow::Result<()> {
+        Ok(())
+    }
 }
 
 // 控制台输出
@@ -32,9 +54,28 @@ impl ConsoleSink {
 impl Sink for ConsoleSink {
     async fn send(&mut self, chunk: DataChunk) -> anyhow::Result<()> {
         let data_str = String::from_utf8_lossy(&chunk.data);
+        // 附带数据源、分区键和处理器写入的属性
+        let mut annotations = String::new();
+        if let Some(event_time_ns) = chunk.event_time_ns {
+            annotations.push_str(&format!(", Event: {}", format_unix_ns(event_time_ns)));
+        }
+        if let Some(source_id) = &chunk.source_id {
+            annotations.push_str(&format!(", Source: {}", source_id));
+        }
+        if let Some(key) = &chunk.key {
+            annotations.push_str(&format!(", Key: {}", key));
+        }
+        if !chunk.attributes.is_empty() {
+            let attributes: Vec<String> = chunk
+                .attributes
+                .iter()
+                .map(|(name, value)| format!("{}={}", name, value))
+                .collect();
+            annotations.push_str(&format!(", Attributes: {{{}}}", attributes.join(", ")));
+        }
         info!(
-            "[{}] Seq: {}, Time: {}, Data: '{}'",
-            self.name, chunk.sequence, chunk.timestamp, data_str
+            "[{}] Id: {}, Seq: {}, Ingest: {}, Latency: {:?}, Data: '{}'{}",
+            self.name, chunk.id, chunk.sequence, format_unix_ns(chunk.ingest_time_ns), chunk.age(), data_str, annotations
         );
         Ok(())
     }
@@ -44,10 +85,23 @@ impl Sink for ConsoleSink {
     }
 }
 
+// Unix 纳秒显示为 "秒.纳秒"
+fn format_unix_ns(ns: u64) -> String {
+    format!("{}.{:09}", ns / 1_000_000_000, ns % 1_000_000_000)
+}
+
+impl Default for ConsoleSink {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 // 统计输出 - 只记录统计信息
 pub struct StatsSink {
     count: u64,
     total_bytes: u64,
+    // 按 protocol 属性分类的数量，未标注的不计入
+    by_protocol: BTreeMap<String, u64>,
     name: String,
 }
 
@@ -56,18 +110,28 @@ impl StatsSink {
         Self {
             count: 0,
             total_bytes: 0,
+            by_protocol: BTreeMap::new(),
             name: "StatsSink".to_string(),
         }
     }
 }
 
+impl Default for StatsSink {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 #[async_trait]
 impl Sink for StatsSink {
     async fn send(&mut self, chunk: DataChunk) -> anyhow::Result<()> {
         self.count += 1;
         self.total_bytes += chunk.data.len() as u64;
+        if let Some(protocol) = chunk.attribute_str(crate::processors::PROTOCOL_ATTRIBUTE) {
+            *self.by_protocol.entry(protocol.to_string()).or_insert(0) += 1;
+        }
 
-        if self.count % 10 == 0 {
+        if self.count.is_multiple_of(10) {
             info!(
                 "[{}] Processed {} chunks, total bytes: {}",
                 self.name, self.count, self.total_bytes
@@ -77,6 +141,17 @@ impl Sink for StatsSink {
         Ok(())
     }
 
+    async fn close(&mut self) -> anyhow::Result<()> {
+        info!(
+            "[{}] Final: {} chunks, total bytes: {}",
+            self.name, self.count, self.total_bytes
+        );
+        if !self.by_protocol.is_empty() {
+            info!("[{}] By protocol: {:?}", self.name, self.by_protocol);
+        }
+        Ok(())
+    }
+
     fn name(&self) -> &str {
         &self.name
     }
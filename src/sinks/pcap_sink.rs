@@ -0,0 +1,313 @@
+use async_trait::async_trait;
+use crate::packet::{CaptureFormat, LinkType, PacketRecord, PcapWriter, LINK_TYPE_ATTRIBUTE};
+use crate::sources::{INTERFACE_ATTRIBUTE, ORIGINAL_LENGTH_ATTRIBUTE};
+use crate::{DataChunk, Sink};
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::Path;
+use std::time::{Duration, Instant};
+use tracing::info;
+
+// 抓包文件输出：把数据块负载作为数据包写成 pcap 或 pcapng，可以直接用 Wireshark 打开
+//
+// 时间戳取事件时间（没有时为进入时间），链路层类型取数据块的 link_type 属性（没有时使用输出端的默认值），
+// interface、original_length 属性（PcapFileSource/PacketCaptureSource 写入）分别决定 pcapng 接口和原始长度。
+// 设置了按大小或时间切分时，文件名带序号（capture.pcap → capture-0000.pcap、capture-0001.pcap ...）
+pub struct PcapFileSink {
+    file_path: String,
+    format: CaptureFormat,
+    link_type: LinkType,
+    snaplen: u32,
+    rotate_size: Option<u64>,
+    rotate_interval: Option<Duration>,
+    writer: Option<PcapWriter<BufWriter<File>>>,
+    current_path: String,
+    opened_at: Instant,
+    file_index: u64,
+    packets: u64,
+    name: String,
+}
+
+impl PcapFileSink {
+    // 默认写经典 pcap（纳秒时间戳），file_path 以 .pcapng 结尾时写 pcapng
+    pub fn new(file_path: &str) -> Self {
+        let format = if file_path.ends_with(".pcapng") {
+            CaptureFormat::PcapNg
+        } else {
+            CaptureFormat::Pcap { nanos: true, big_endian: false }
+        };
+        Self {
+            file_path: file_path.to_string(),
+            format,
+            link_type: LinkType::default(),
+            snaplen: 0,
+            rotate_size: None,
+            rotate_interval: None,
+            writer: None,
+            current_path: file_path.to_string(),
+            opened_at: Instant::now(),
+            file_index: 0,
+            packets: 0,
+            name: "PcapFileSink".to_string(),
+        }
+    }
+
+    pub fn with_name(mut self, name: &str) -> Self {
+        self.name = name.to_string();
+        self
+    }
+
+    pub fn with_format(mut self, format: CaptureFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    // 数据块没有 link_type 属性时使用的链路层类型，默认 Ethernet
+    pub fn with_link_type(mut self, link_type: LinkType) -> Self {
+        self.link_type = link_type;
+        self
+    }
+
+    // 每个数据包最多写入的字节数，0 表示不截断
+    pub fn with_snaplen(mut self, snaplen: u32) -> Self {
+        self.snaplen = snaplen;
+        self
+    }
+
+    // 文件超过指定字节数时切换到下一个文件
+    pub fn with_rotate_size(mut self, bytes: u64) -> Self {
+        self.rotate_size = Some(bytes);
+        self
+    }
+
+    // 文件打开超过指定时间时切换到下一个文件
+    pub fn with_rotate_interval(mut self, interval: Duration) -> Self {
+        self.rotate_interval = Some(interval);
+        self
+    }
+
+    fn rotating(&self) -> bool {
+        self.rotate_size.is_some() || self.rotate_interval.is_some()
+    }
+
+    // 切分时在扩展名前插入序号
+    fn path_for(&self, index: u64) -> String {
+        if !self.rotating() {
+            return self.file_path.clone();
+        }
+        let path = Path::new(&self.file_path);
+        let stem = path.file_stem().map(|stem| stem.to_string_lossy()).unwrap_or_default();
+        let file_name = match path.extension() {
+            Some(extension) => format!("{}-{:04}.{}", stem, index, extension.to_string_lossy()),
+            None => format!("{}-{:04}", stem, index),
+        };
+        path.with_file_name(file_name).to_string_lossy().into_owned()
+    }
+
+    fn open_file(&mut self) -> anyhow::Result<()> {
+        let path = self.path_for(self.file_index);
+        let file = File::create(&path).map_err(|e| anyhow::anyhow!("cannot create {}: {}", path, e))?;
+        let writer = PcapWriter::new(BufWriter::new(file), self.format, self.snaplen)?;
+        info!("[{}] 已打开抓包文件: {} ({:?})", self.name, path, self.format);
+        self.writer = Some(writer);
+        self.current_path = path;
+        self.opened_at = Instant::now();
+        Ok(())
+    }
+
+    fn close_file(&mut self) -> anyhow::Result<()> {
+        if let Some(writer) = self.writer.take() {
+            let packets = writer.packets();
+            let file = writer.into_inner().into_inner().map_err(|e| e.into_error())?;
+            file.sync_data()?;
+            info!("[{}] 已关闭抓包文件: {} ({} 个数据包)", self.name, self.current_path, packets);
+        }
+        Ok(())
+    }
+
+    // 当前文件已有数据包且再写入会超过大小或时间限制时切换文件
+    fn should_rotate(&self, writer: &PcapWriter<BufWriter<File>>, record: &PacketRecord) -> bool {
+        if writer.packets() == 0 {
+            return false;
+        }
+        let too_large = self.rotate_size
+            .is_some_and(|limit| writer.bytes_written() + writer.encoded_len(record) > limit);
+        let too_old = self.rotate_interval.is_some_and(|interval| self.opened_at.elapsed() >= interval);
+        too_large || too_old
+    }
+}
+
+fn attribute_u32(chunk: &DataChunk, name: &str) -> Option<u32> {
+    chunk.attribute(name).and_then(|value| value.as_i64()).and_then(|value| u32::try_from(value).ok())
+}
+
+#[async_trait]
+impl Sink for PcapFileSink {
+    async fn open(&mut self) -> anyhow::Result<()> {
+        if self.writer.is_none() {
+            self.open_file()?;
+        }
+        Ok(())
+    }
+
+    async fn send(&mut self, chunk: DataChunk) -> anyhow::Result<()> {
+        let record = PacketRecord {
+            timestamp_ns: chunk.time_ns(),
+            link_type: attribute_u32(&chunk, LINK_TYPE_ATTRIBUTE).unwrap_or(self.link_type.code()),
+            interface: attribute_u32(&chunk, INTERFACE_ATTRIBUTE).unwrap_or(0),
+            original_len: attribute_u32(&chunk, ORIGINAL_LENGTH_ATTRIBUTE).unwrap_or(0),
+            data: &chunk.data,
+        };
+
+        if self.writer.as_ref().is_some_and(|writer| self.should_rotate(writer, &record)) {
+            self.close_file()?;
+            self.file_index += 1;
+        }
+        if self.writer.is_none() {
+            self.open_file()?;
+        }
+        let writer = self.writer.as_mut().expect("writer opened above");
+        writer.write_packet(&record)
+            .map_err(|e| anyhow::anyhow!("{} (chunk {}): {}", self.current_path, chunk.sequence, e))?;
+        self.packets += 1;
+        Ok(())
+    }
+
+    async fn flush(&mut self) -> anyhow::Result<()> {
+        if let Some(writer) = self.writer.as_mut() {
+            writer.flush()?;
+            writer.get_ref().get_ref().sync_data()?;
+        }
+        Ok(())
+    }
+
+    async fn close(&mut self) -> anyhow::Result<()> {
+        self.close_file()?;
+        info!("[{}] 共写入 {} 个数据包，{} 个文件", self.name, self.packets, self.file_index + 1);
+        Ok(())
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::packet::PcapReader;
+    use std::fs;
+    use std::path::PathBuf;
+
+    // 每个测试使用自己的输出目录，结束时删除
+    struct OutputDirectory(PathBuf);
+
+    impl OutputDirectory {
+        fn new(name: &str) -> Self {
+            let path = std::env::temp_dir().join(format!("pelican-pcap-sink-{}-{}", name, std::process::id()));
+            let _ = fs::remove_dir_all(&path);
+            fs::create_dir_all(&path).unwrap();
+            Self(path)
+        }
+
+        fn path(&self, file_name: &str) -> String {
+            self.0.join(file_name).to_string_lossy().into_owned()
+        }
+
+        // 目录中的文件名（排序后）及每个文件中数据包的首字节
+        fn files(&self) -> Vec<(String, Vec<u8>)> {
+            let mut names: Vec<String> = fs::read_dir(&self.0)
+                .unwrap()
+                .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+                .collect();
+            names.sort();
+            names
+                .into_iter()
+                .map(|name| {
+                    let mut reader = PcapReader::new(File::open(self.0.join(&name)).unwrap()).unwrap();
+                    let mut packets = Vec::new();
+                    while let Some(packet) = reader.next_packet().unwrap() {
+                        packets.push(packet.data[0]);
+                    }
+                    (name, packets)
+                })
+                .collect()
+        }
+    }
+
+    impl Drop for OutputDirectory {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn packet(id: u8, len: usize) -> DataChunk {
+        DataChunk::new(vec![id; len]).with_event_time_ns(1_700_000_000_000_000_000 + u64::from(id))
+    }
+
+    #[tokio::test]
+    async fn rotates_before_a_file_would_exceed_the_size_limit() {
+        let directory = OutputDirectory::new("size");
+        // 文件头 24 字节，每个数据包 16 + 100 字节：每个文件放得下两个
+        let mut sink = PcapFileSink::new(&directory.path("capture.pcap")).with_rotate_size(300);
+        sink.open().await.unwrap();
+        for id in 0..5 {
+            sink.send(packet(id, 100)).await.unwrap();
+        }
+        // 单个超过限制的数据包仍然写入，独占一个文件
+        sink.send(packet(5, 400)).await.unwrap();
+        sink.close().await.unwrap();
+
+        let files = directory.files();
+        let expected = [
+            ("capture-0000.pcap", vec![0, 1]),
+            ("capture-0001.pcap", vec![2, 3]),
+            ("capture-0002.pcap", vec![4]),
+            ("capture-0003.pcap", vec![5]),
+        ];
+        assert_eq!(files, expected.map(|(name, packets)| (name.to_string(), packets)));
+        for (name, _) in &files[..3] {
+            assert!(fs::metadata(directory.0.join(name)).unwrap().len() <= 300);
+        }
+    }
+
+    #[tokio::test]
+    async fn rotates_after_the_interval() {
+        let directory = OutputDirectory::new("interval");
+        let mut sink =
+            PcapFileSink::new(&directory.path("capture.pcapng")).with_rotate_interval(Duration::from_millis(50));
+        sink.open().await.unwrap();
+        sink.send(packet(0, 60)).await.unwrap();
+        sink.send(packet(1, 60)).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(60)).await;
+        sink.send(packet(2, 60)).await.unwrap();
+        sink.close().await.unwrap();
+
+        let files = directory.files();
+        let expected = [("capture-0000.pcapng", vec![0, 1]), ("capture-0001.pcapng", vec![2])];
+        assert_eq!(files, expected.map(|(name, packets)| (name.to_string(), packets)));
+        let mut reader = PcapReader::new(File::open(directory.0.join("capture-0001.pcapng")).unwrap()).unwrap();
+        assert_eq!(reader.format(), CaptureFormat::PcapNg);
+        assert_eq!(reader.next_packet().unwrap().unwrap().timestamp_ns, Some(1_700_000_000_000_000_002));
+    }
+
+    #[tokio::test]
+    async fn keeps_the_file_name_without_rotation() {
+        let directory = OutputDirectory::new("single");
+        let mut sink = PcapFileSink::new(&directory.path("capture")).with_snaplen(16);
+        sink.open().await.unwrap();
+        for id in 0..3 {
+            sink.send(packet(id, 100)).await.unwrap();
+        }
+        sink.close().await.unwrap();
+
+        assert_eq!(directory.files(), [("capture".to_string(), vec![0, 1, 2])]);
+        let mut reader = PcapReader::new(File::open(directory.0.join("capture")).unwrap()).unwrap();
+        let packet = reader.next_packet().unwrap().unwrap();
+        assert_eq!((packet.data.len(), packet.original_len), (16, 100));
+
+        // 没有扩展名的文件切分时序号直接接在文件名后
+        let sink = PcapFileSink::new(&directory.path("capture")).with_rotate_size(1000);
+        assert_eq!(sink.path_for(7), directory.path("capture-0007"));
+    }
+}
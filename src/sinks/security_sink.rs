@@ -1,15 +1,19 @@
 use async_trait::async_trait;
 use crate::{DataChunk, Sink};
-use crate::security::{DataIntegrityChecker, SecurityConfig};
-use std::fs::OpenOptions;
-use std::io::Write;
-use std::path::Path;
-use tracing::{info, error};
+use crate::security::{SecureFileWriter, SecurityConfig};
+use std::fs::File;
+use std::io::BufWriter;
+use tracing::info;
 
+// 把数据块写成安全容器文件（格式见 SecureFileWriter），可以用 SecureFileReader 逐条解密和校验
+//
+// 启用加密时使用 SecurityConfig 的算法和密钥环（或单个密钥）；启用完整性检查时每条记录带算法编号和校验值。
+// 已有文件会接着写入，文件头中的加密、完整性设置必须与当前配置一致
 pub struct SecureFileSink {
     file_path: String,
-    integrity_checker: Option<DataIntegrityChecker>,
-    enable_encryption: bool,
+    security_config: SecurityConfig,
+    writer: Option<SecureFileWriter<BufWriter<File>>>,
+    records: u64,
     name: String,
 }
 
@@ -19,62 +23,65 @@ impl SecureFileSink {
         security_config: &SecurityConfig,
         name: &str,
     ) -> Result<Self, anyhow::Error> {
-        let integrity_checker = if security_config.enable_integrity_check {
-            security_config.integrity_salt.map(|salt| DataIntegrityChecker::new(salt))
-        } else {
-            None
-        };
+        // 提前检查密钥配置，避免运行时才发现
+        SecureFileWriter::new(std::io::sink(), security_config)?;
 
         Ok(Self {
             file_path,
-            integrity_checker,
-            enable_encryption: security_config.enable_encryption,
+            security_config: security_config.clone(),
+            writer: None,
+            records: 0,
             name: name.to_string(),
         })
     }
 
-    fn write_with_integrity(&self, data: &[u8]) -> anyhow::Result<()> {
-        let path = Path::new(&self.file_path);
-
-        let mut file = OpenOptions::new()
-            .create(true)
-            .append(true)
-            .open(path)?;
-
-        // 如果启用了完整性检查，计算校验和
-        if let Some(checker) = &self.integrity_checker {
-            let checksum = checker.calculate_checksum(data);
-
-            // 写入数据长度、数据和校验和
-            let data_len = data.len() as u32;
-            file.write_all(&data_len.to_le_bytes())?;
-            file.write_all(data)?;
-            file.write_all(&checksum)?;
-
-            info!("已写入带完整性检查的数据: {} bytes", data_len);
-        } else {
-            // 普通写入
-            file.write_all(data)?;
-            info!("已写入数据: {} bytes", data.len());
+    // 打开输出文件并在整个运行期间保持
+    fn ensure_open(&mut self) -> anyhow::Result<&mut SecureFileWriter<BufWriter<File>>> {
+        if self.writer.is_none() {
+            let writer = SecureFileWriter::open(&self.file_path, &self.security_config)?;
+            info!(
+                "[{}] 已打开输出文件: {}（已有 {} 条记录）",
+                self.name,
+                self.file_path,
+                writer.record_count()
+            );
+            self.writer = Some(writer);
         }
-
-        Ok(())
+        Ok(self.writer.as_mut().expect("writer opened above"))
     }
 }
 
 #[async_trait]
 impl Sink for SecureFileSink {
+    async fn open(&mut self) -> anyhow::Result<()> {
+        self.ensure_open()?;
+        Ok(())
+    }
+
     async fn send(&mut self, chunk: DataChunk) -> anyhow::Result<()> {
-        if self.enable_encryption {
-            info!("加密功能已启用（简化实现）");
-        }
+        let timestamp_ns = chunk.time_ns();
+        self.ensure_open()?.write_record(chunk.sequence, timestamp_ns, &chunk.data)?;
+        self.records += 1;
+        Ok(())
+    }
 
-        self.write_with_integrity(&chunk.data)?;
+    async fn flush(&mut self) -> anyhow::Result<()> {
+        if let Some(writer) = self.writer.as_mut() {
+            writer.flush()?;
+            writer.get_ref().get_ref().sync_data()?;
+        }
+        Ok(())
+    }
 
+    async fn close(&mut self) -> anyhow::Result<()> {
+        if let Some(mut writer) = self.writer.take() {
+            writer.flush()?;
+            info!("[{}] 已关闭输出文件: {}，本次写入 {} 条记录", self.name, self.file_path, self.records);
+        }
         Ok(())
     }
 
     fn name(&self) -> &str {
         &self.name
     }
-}
\ No newline at end of file
+}
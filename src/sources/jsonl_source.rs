@@ -0,0 +1,80 @@
+use async_trait::async_trait;
+use crate::pipeline::{DeadLetterChunk, DeadLetterRecord};
+use crate::{DataChunk, Source};
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use tokio::sync::mpsc;
+use tracing::{info, warn};
+
+// JSON Lines 文件数据源：读取 JsonLinesSink 或死信输出端写出的文件，每行还原为一个数据块
+//
+// 每行可以是数据块记录（{ id, sequence, ingest_time_ns, event_time_ns, data(base64), ... }）、
+// 死信记录（还原其中的原始数据块），或任意 JSON（整行作为负载）；空行跳过
+pub struct JsonLinesSource {
+    file_path: String,
+    name: String,
+}
+
+impl JsonLinesSource {
+    pub fn new(file_path: &str) -> Self {
+        Self {
+            file_path: file_path.to_string(),
+            name: "JsonLinesSource".to_string(),
+        }
+    }
+
+    pub fn with_name(mut self, name: &str) -> Self {
+        self.name = name.to_string();
+        self
+    }
+}
+
+// 解析一行记录
+fn parse_line(line: &str) -> anyhow::Result<DataChunk> {
+    let value: serde_json::Value = serde_json::from_str(line)?;
+    if value.get("chunk").is_some() && value.get("component").is_some() {
+        return DeadLetterRecord::from_json(line.as_bytes())?.original_chunk();
+    }
+    if value.get("data").is_some_and(|data| data.is_string()) && value.get("sequence").is_some() {
+        let chunk: DeadLetterChunk = serde_json::from_value(value)?;
+        return chunk.restore();
+    }
+    Ok(DataChunk::new(line.to_string()))
+}
+
+#[async_trait]
+impl Source for JsonLinesSource {
+    async fn open(&mut self) -> anyhow::Result<()> {
+        File::open(&self.file_path).map_err(|e| anyhow::anyhow!("cannot open {}: {}", self.file_path, e))?;
+        Ok(())
+    }
+
+    async fn stream_data(&mut self, tx: mpsc::Sender<DataChunk>) -> anyhow::Result<()> {
+        let file = File::open(&self.file_path).map_err(|e| anyhow::anyhow!("cannot open {}: {}", self.file_path, e))?;
+        let mut count = 0u64;
+        for (number, line) in BufReader::new(file).lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let chunk = match parse_line(&line) {
+                Ok(chunk) => chunk,
+                Err(e) => {
+                    warn!("[{}] Skipping line {} of {}: {}", self.name, number + 1, self.file_path, e);
+                    continue;
+                }
+            };
+            count += 1;
+            if tx.send(chunk).await.is_err() {
+                break;
+            }
+        }
+
+        info!("[{}] Read {} chunks from {}", self.name, count, self.file_path);
+        Ok(())
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
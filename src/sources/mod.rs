@@ -1,10 +1,19 @@
 // 只声明一次模块
 pub mod packet_source;
+pub mod pcap_source;
+pub mod jsonl_source;
+pub mod replay;
 
 // 重新导出公共类型
-pub use packet_source::{PacketCaptureSource, MockPacketSource};
+pub use packet_source::{CaptureStats, PacketCaptureSource, MockPacketSource};
+pub use pcap_source::{PcapFileSource, INTERFACE_ATTRIBUTE, ORIGINAL_LENGTH_ATTRIBUTE};
+pub use jsonl_source::JsonLinesSource;
+pub use replay::{ReplaySource, REPLAY_PASS_ATTRIBUTE};
 
 use async_trait::async_trait;
+use bytes::Bytes;
+use std::collections::HashMap;
+use std::time::SystemTime;
 use tokio::sync::mpsc;
 use crate::DataChunk;
 
@@ -12,24 +21,57 @@ use crate::DataChunk;
 pub trait Source: Send + Sync {
     async fn stream_data(&mut self, tx: mpsc::Sender<DataChunk>) -> anyhow::Result<()>;
     fn name(&self) -> &str;
+
+    // 管道启动前调用，用于打开文件、套接字等资源
+    async fn open(&mut self) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    // 数据源停止后调用，释放资源
+    async fn close(&mut self) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    // 关闭后报告的计数，汇总到 RunSummary::sources
+    fn metrics(&self) -> HashMap<String, u64> {
+        HashMap::new()
+    }
 }
 
-// 内存数据源
+// 内存数据源：每次发送共享同一份负载，不复制数据
 pub struct MemorySource {
-    data: Vec<Vec<u8>>,
+    data: Vec<Bytes>,
     sequence: u64,
+    delay_ms: u64,
     name: String,
 }
 
 impl MemorySource {
     pub fn new(data: Vec<Vec<u8>>) -> Self {
+        Self {
+            data: data.into_iter().map(Bytes::from).collect(),
+            sequence: 0,
+            delay_ms: 10,
+            name: "MemorySource".to_string(),
+        }
+    }
+
+    // 直接使用共享负载，例如多次发送同一个 Bytes
+    pub fn from_bytes(data: Vec<Bytes>) -> Self {
         Self {
             data,
             sequence: 0,
+            delay_ms: 10,
             name: "MemorySource".to_string(),
         }
     }
 
+    // 每条数据之间的延迟（毫秒），默认 10
+    pub fn with_delay(mut self, delay_ms: u64) -> Self {
+        self.delay_ms = delay_ms;
+        self
+    }
+
     pub fn with_name(mut self, name: &str) -> Self {
         self.name = name.to_string();
         self
@@ -41,7 +83,8 @@ impl Source for MemorySource {
     async fn stream_data(&mut self, tx: mpsc::Sender<DataChunk>) -> anyhow::Result<()> {
         for chunk_data in &self.data {
             let chunk = DataChunk::new(chunk_data.clone())
-                .with_sequence(self.sequence);
+                .with_sequence(self.sequence)
+                .with_event_time(SystemTime::now());
 
             self.sequence += 1;
 
@@ -50,7 +93,9 @@ impl Source for MemorySource {
             }
 
             // 模拟一些延迟
-            tokio::time::sleep(tokio::time::Duration::from_millis(10)).await;
+            if self.delay_ms > 0 {
+                tokio::time::sleep(tokio::time::Duration::from_millis(self.delay_ms)).await;
+            }
         }
 
         Ok(())
@@ -87,7 +132,9 @@ impl Source for RepeatingSource {
             let mut data = self.template.clone();
             data.extend_from_slice(format!("-{}", i).as_bytes());
 
-            let chunk = DataChunk::new(data).with_sequence(i as u64);
+            let chunk = DataChunk::new(data)
+                .with_sequence(i as u64)
+                .with_event_time(SystemTime::now());
 
             if tx.send(chunk).await.is_err() {
                 break;
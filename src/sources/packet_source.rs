@@ -1,13 +1,76 @@
 use async_trait::async_trait;
 use tokio::sync::mpsc;
 use crate::{DataChunk, Source};
-use tracing::info;  // 移除 error
+use crate::packet::LINK_TYPE_ATTRIBUTE;
+use crate::sources::pcap_source::{INTERFACE_ATTRIBUTE, ORIGINAL_LENGTH_ATTRIBUTE};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::SystemTime;
+use tracing::{info, warn};
+#[cfg(target_os = "linux")]
+use crate::packet::afpacket::{PacketRing, RingOptions};
+#[cfg(target_os = "linux")]
+use crate::packet::BpfProgram;
 
-// ... 其余代码保持不变 ...
+// 实时抓包的计数，可克隆后在管道运行期间读取
+#[derive(Debug, Clone, Default)]
+pub struct CaptureStats {
+    received: Arc<AtomicU64>,
+    bytes: Arc<AtomicU64>,
+    dropped: Arc<AtomicU64>,
+    freezes: Arc<AtomicU64>,
+}
+
+impl CaptureStats {
+    // 交给管道的数据包数
+    pub fn received(&self) -> u64 {
+        self.received.load(Ordering::Relaxed)
+    }
+
+    // 内核因接收环已满丢弃的数据包数
+    pub fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+
+    // 累加内核计数（每次读取后内核清零）
+    #[cfg(target_os = "linux")]
+    fn update_from_ring(&self, ring: &PacketRing, source: &str) {
+        match ring.statistics() {
+            Ok(statistics) => {
+                self.dropped.fetch_add(statistics.drops, Ordering::Relaxed);
+                self.freezes.fetch_add(statistics.freezes, Ordering::Relaxed);
+            }
+            Err(e) => warn!("[{}] Failed to read capture statistics: {}", source, e),
+        }
+    }
+
+    pub fn get_metrics(&self) -> HashMap<String, u64> {
+        let mut metrics = HashMap::new();
+        metrics.insert("packets_received".to_string(), self.received());
+        metrics.insert("bytes_received".to_string(), self.bytes.load(Ordering::Relaxed));
+        metrics.insert("packets_dropped".to_string(), self.dropped());
+        metrics.insert("queue_freezes".to_string(), self.freezes.load(Ordering::Relaxed));
+        metrics
+    }
+}
+
+// 实时抓包数据源：在 Linux 上通过 AF_PACKET 套接字（TPACKET_V3 接收环）从网卡抓包，需要 CAP_NET_RAW 权限
+//
+// 过滤表达式编译为经典 BPF 在内核中执行（语法见 BpfProgram），snaplen 之外的部分被截断。
+// 数据块与 PcapFileSource 相同：事件时间为内核时间戳，带 link_type、interface（网卡序号）属性，
+// 被截断时带 original_length 属性。内核丢包数等计数通过 stats() 和 RunSummary::sources 报告
 pub struct PacketCaptureSource {
     device_name: String,
     filter: Option<String>,
-    packet_count: usize,
+    packet_count: Option<usize>,
+    snaplen: u32,
+    promiscuous: bool,
+    buffer_size: usize,
+    stats: CaptureStats,
+    #[cfg(target_os = "linux")]
+    ring: Option<PacketRing>,
+    name: String,
 }
 
 impl PacketCaptureSource {
@@ -15,37 +78,156 @@ impl PacketCaptureSource {
         Self {
             device_name: device_name.to_string(),
             filter: None,
-            packet_count: 1000,
+            packet_count: None,
+            snaplen: 65535,
+            promiscuous: true,
+            buffer_size: 16 << 20,
+            stats: CaptureStats::default(),
+            #[cfg(target_os = "linux")]
+            ring: None,
+            name: "PacketCaptureSource".to_string(),
         }
     }
 
+    pub fn with_name(mut self, name: &str) -> Self {
+        self.name = name.to_string();
+        self
+    }
+
+    // 过滤表达式，例如 "tcp port 80 and not host 10.0.0.1"
     pub fn with_filter(mut self, filter: &str) -> Self {
         self.filter = Some(filter.to_string());
         self
     }
 
+    // 抓到指定数量的数据包后结束，默认一直抓包直到管道停止
     pub fn with_packet_count(mut self, count: usize) -> Self {
-        self.packet_count = count;
+        self.packet_count = Some(count);
+        self
+    }
+
+    // 每个数据包最多保留的字节数，默认 65535
+    pub fn with_snaplen(mut self, snaplen: u32) -> Self {
+        self.snaplen = snaplen.max(1);
+        self
+    }
+
+    // 是否开启混杂模式，默认开启
+    pub fn with_promiscuous(mut self, promiscuous: bool) -> Self {
+        self.promiscuous = promiscuous;
+        self
+    }
+
+    // 内核接收环的大小（字节），按 1 MiB 的块向上取整，默认 16 MiB
+    pub fn with_buffer_size(mut self, buffer_size: usize) -> Self {
+        self.buffer_size = buffer_size;
         self
     }
+
+    pub fn stats(&self) -> CaptureStats {
+        self.stats.clone()
+    }
+
+    #[cfg(target_os = "linux")]
+    fn open_ring(&self) -> anyhow::Result<PacketRing> {
+        let defaults = RingOptions::default();
+        let block_size = defaults.block_size as usize;
+        let options = RingOptions {
+            promiscuous: self.promiscuous,
+            block_count: self.buffer_size.div_ceil(block_size).clamp(1, u32::MAX as usize) as u32,
+            ..defaults
+        };
+        let filter = self.filter.clone().unwrap_or_default();
+        let ring = PacketRing::open(&self.device_name, &options, |link_type| {
+            BpfProgram::compile(&filter, link_type, self.snaplen)
+                .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e.to_string()))
+        })
+        .map_err(|e| anyhow::anyhow!("cannot capture on {}: {}", self.device_name, e))?;
+        info!("[{}] Capturing on {} ({}, snaplen {}, promiscuous {}, filter '{}')",
+              self.name, self.device_name, ring.link_type(), self.snaplen, self.promiscuous, filter);
+        Ok(ring)
+    }
 }
 
 #[async_trait]
 impl Source for PacketCaptureSource {
+    // 启动前打开套接字并挂上过滤程序，网卡不存在、没有权限或过滤表达式有误时管道不会启动
+    #[cfg(target_os = "linux")]
+    async fn open(&mut self) -> anyhow::Result<()> {
+        self.ring = Some(self.open_ring()?);
+        Ok(())
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    async fn open(&mut self) -> anyhow::Result<()> {
+        anyhow::bail!("live capture on {} requires Linux AF_PACKET sockets", self.device_name)
+    }
+
+    #[cfg(target_os = "linux")]
     async fn stream_data(&mut self, tx: mpsc::Sender<DataChunk>) -> anyhow::Result<()> {
-        info!("Starting packet capture on device: {}", self.device_name);
+        if self.ring.is_none() {
+            self.ring = Some(self.open_ring()?);
+        }
+        // 原地借用接收环：读取出错或任务被取消时，close() 仍能读取最后的内核计数
+        let ring = self.ring.as_mut().expect("ring opened above");
 
-        // 暂时使用模拟数据
-        info!("Real packet capture not implemented, using mock data instead");
-        let mut mock_source = MockPacketSource::new(self.packet_count);
-        mock_source.stream_data(tx).await?;
+        let mut sequence = 0u64;
+        'capture: while self.packet_count.is_none_or(|count| (sequence as usize) < count) {
+            let packets = ring.next_block().await?;
+            self.stats.update_from_ring(ring, &self.name);
+            for packet in packets {
+                let mut chunk = DataChunk::new(packet.data)
+                    .with_sequence(sequence)
+                    .with_attribute(LINK_TYPE_ATTRIBUTE, i64::from(packet.link_type))
+                    .with_attribute(INTERFACE_ATTRIBUTE, packet.interface);
+                chunk = match packet.timestamp_ns {
+                    Some(timestamp_ns) => chunk.with_event_time_ns(timestamp_ns),
+                    None => chunk.with_event_time(SystemTime::now()),
+                };
+                if packet.original_len as usize > chunk.data.len() {
+                    chunk.set_attribute(ORIGINAL_LENGTH_ATTRIBUTE, packet.original_len);
+                }
+                self.stats.received.fetch_add(1, Ordering::Relaxed);
+                self.stats.bytes.fetch_add(chunk.data.len() as u64, Ordering::Relaxed);
+                sequence += 1;
+
+                if tx.send(chunk).await.is_err() {
+                    break 'capture;
+                }
+                if self.packet_count.is_some_and(|count| sequence as usize >= count) {
+                    break 'capture;
+                }
+            }
+        }
+
+        self.stats.update_from_ring(ring, &self.name);
+        info!("[{}] Captured {} packets on {}", self.name, sequence, self.device_name);
+        Ok(())
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    async fn stream_data(&mut self, _tx: mpsc::Sender<DataChunk>) -> anyhow::Result<()> {
+        anyhow::bail!("live capture on {} requires Linux AF_PACKET sockets", self.device_name)
+    }
 
-        info!("Packet capture completed: {} packets", self.packet_count);
+    async fn close(&mut self) -> anyhow::Result<()> {
+        #[cfg(target_os = "linux")]
+        if let Some(ring) = self.ring.take() {
+            self.stats.update_from_ring(&ring, &self.name);
+        }
+        let dropped = self.stats.dropped();
+        if dropped > 0 {
+            warn!("[{}] Kernel dropped {} packets on {} (ring buffer full)", self.name, dropped, self.device_name);
+        }
         Ok(())
     }
 
+    fn metrics(&self) -> HashMap<String, u64> {
+        self.stats.get_metrics()
+    }
+
     fn name(&self) -> &str {
-        "PacketCaptureSource"
+        &self.name
     }
 }
 
@@ -53,6 +235,7 @@ impl Source for PacketCaptureSource {
 pub struct MockPacketSource {
     packet_count: usize,
     delay_ms: u64,
+    raw_frames: bool,
 }
 
 impl MockPacketSource {
@@ -60,6 +243,7 @@ impl MockPacketSource {
         Self {
             packet_count,
             delay_ms: 1,
+            raw_frames: false,
         }
     }
 
@@ -67,6 +251,76 @@ impl MockPacketSource {
         self.delay_ms = delay_ms;
         self
     }
+
+    // 生成 Ethernet/IPv4 原始帧（负载为同样的文本），供 PacketDecodeProcessor 解码
+    pub fn with_raw_frames(mut self, raw_frames: bool) -> Self {
+        self.raw_frames = raw_frames;
+        self
+    }
+}
+
+// 构造一个 Ethernet II + IPv4 帧；transport 为传输层头，不计算传输层校验和
+fn mock_frame(src: [u8; 4], dst: [u8; 4], protocol: u8, transport: &[u8], payload: &[u8]) -> Vec<u8> {
+    let total_len = (20 + transport.len() + payload.len()) as u16;
+    let mut frame = Vec::with_capacity(14 + total_len as usize);
+    frame.extend_from_slice(&[0x02, 0x00, 0x00, 0x00, 0x00, 0x01]);
+    frame.extend_from_slice(&[0x02, 0x00, 0x00, 0x00, 0x00, src[3]]);
+    frame.extend_from_slice(&0x0800u16.to_be_bytes());
+
+    let mut ip = [0u8; 20];
+    ip[0] = 0x45;
+    ip[2..4].copy_from_slice(&total_len.to_be_bytes());
+    ip[8] = 64;
+    ip[9] = protocol;
+    ip[12..16].copy_from_slice(&src);
+    ip[16..20].copy_from_slice(&dst);
+    let sum = ip.chunks(2).map(|word| u32::from(u16::from_be_bytes([word[0], word[1]]))).sum::<u32>();
+    let checksum = !((sum & 0xffff) + (sum >> 16)) as u16;
+    ip[10..12].copy_from_slice(&checksum.to_be_bytes());
+
+    frame.extend_from_slice(&ip);
+    frame.extend_from_slice(transport);
+    frame.extend_from_slice(payload);
+    frame
+}
+
+fn tcp_header(src_port: u16, dst_port: u16, seq: u32, flags: u8) -> Vec<u8> {
+    let mut tcp = vec![0u8; 20];
+    tcp[0..2].copy_from_slice(&src_port.to_be_bytes());
+    tcp[2..4].copy_from_slice(&dst_port.to_be_bytes());
+    tcp[4..8].copy_from_slice(&seq.to_be_bytes());
+    tcp[12] = 5 << 4;
+    tcp[13] = flags;
+    tcp[14..16].copy_from_slice(&65535u16.to_be_bytes());
+    tcp
+}
+
+fn udp_header(src_port: u16, dst_port: u16, payload_len: usize) -> Vec<u8> {
+    let mut udp = vec![0u8; 8];
+    udp[0..2].copy_from_slice(&src_port.to_be_bytes());
+    udp[2..4].copy_from_slice(&dst_port.to_be_bytes());
+    udp[4..6].copy_from_slice(&((8 + payload_len) as u16).to_be_bytes());
+    udp
+}
+
+// 与文本模式相同的五类数据包：TCP、UDP、HTTP（TCP 80）、DNS（UDP 53）、ICMP 回显请求
+fn mock_raw_packet(i: usize, payload: &[u8]) -> Vec<u8> {
+    let src = [192, 168, 1, (i % 255) as u8];
+    let src_port = 40000 + (i % 20000) as u16;
+    match i % 5 {
+        0 => mock_frame(src, [10, 0, 0, 1], 6, &tcp_header(src_port, 22, i as u32, 0x18), payload),
+        1 => {
+            let dst_port = 8000 + (i % 1000) as u16;
+            mock_frame(src, [10, 0, 0, 2], 17, &udp_header(src_port, dst_port, payload.len()), payload)
+        }
+        2 => mock_frame(src, [10, 0, 0, 80], 6, &tcp_header(src_port, 80, i as u32, 0x18), payload),
+        3 => mock_frame(src, [10, 0, 0, 53], 17, &udp_header(src_port, 53, payload.len()), payload),
+        _ => {
+            let mut icmp = vec![8, 0, 0, 0, 0, 1];
+            icmp.extend_from_slice(&(i as u16).to_be_bytes());
+            mock_frame(src, [10, 0, 0, 1], 1, &icmp, payload)
+        }
+    }
 }
 
 #[async_trait]
@@ -84,7 +338,12 @@ impl Source for MockPacketSource {
                 _ => format!("ICMP packet seq {} ttl 64", i).into_bytes(),
             };
 
-            let chunk = DataChunk::new(packet_data).with_sequence(i as u64);
+            let packet_data = if self.raw_frames { mock_raw_packet(i, &packet_data) } else { packet_data };
+
+            // 生成时刻作为模拟的捕获时间
+            let chunk = DataChunk::new(packet_data)
+                .with_sequence(i as u64)
+                .with_event_time(SystemTime::now());
 
             if tx.send(chunk).await.is_err() {
                 break;
@@ -106,4 +365,121 @@ impl Source for MockPacketSource {
     fn name(&self) -> &str {
         "MockPacketSource"
     }
-}
\ No newline at end of file
+}
+// 实时抓包测试需要 CAP_NET_RAW 和 CAP_SYS_ADMIN（创建网络命名空间），默认忽略：
+// sudo -E cargo test --lib packet_source -- --ignored
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::*;
+    use crate::AttributeValue;
+    use std::net::UdpSocket;
+    use std::os::fd::AsRawFd;
+    use std::time::Duration;
+
+    const MATCHED_PORT: u16 = 40001;
+    const OTHER_PORT: u16 = 40002;
+    // 以太网 14 + IPv4 20 + UDP 8
+    const HEADERS_LEN: usize = 42;
+
+    // 在新的网络命名空间中启用 lo，只对当前线程生效，抓到的数据包只有测试自己发出的
+    fn enter_network_namespace() {
+        assert_eq!(unsafe { libc::unshare(libc::CLONE_NEWNET) }, 0, "unshare: {}", std::io::Error::last_os_error());
+        let socket = UdpSocket::bind("0.0.0.0:0").unwrap();
+        let mut request: libc::ifreq = unsafe { std::mem::zeroed() };
+        for (target, &byte) in request.ifr_name.iter_mut().zip(b"lo\0") {
+            *target = byte as libc::c_char;
+        }
+        unsafe {
+            assert_eq!(libc::ioctl(socket.as_raw_fd(), libc::SIOCGIFFLAGS, &mut request), 0);
+            request.ifr_ifru.ifru_flags |= libc::IFF_UP as libc::c_short;
+            assert_eq!(libc::ioctl(socket.as_raw_fd(), libc::SIOCSIFFLAGS, &mut request), 0);
+        }
+    }
+
+    fn send_udp(count: usize, port: u16, payload_len: usize) {
+        let sender = UdpSocket::bind("127.0.0.1:0").unwrap();
+        for i in 0..count {
+            sender.send_to(&vec![i as u8; payload_len], ("127.0.0.1", port)).unwrap();
+        }
+    }
+
+    async fn capture(source: &mut PacketCaptureSource) -> Vec<DataChunk> {
+        let (tx, mut rx) = mpsc::channel(1024);
+        tokio::time::timeout(Duration::from_secs(5), source.stream_data(tx))
+            .await
+            .expect("capture did not see the expected packets")
+            .unwrap();
+        source.close().await.unwrap();
+        let mut chunks = Vec::new();
+        while let Ok(chunk) = rx.try_recv() {
+            chunks.push(chunk);
+        }
+        chunks
+    }
+
+    #[tokio::test]
+    #[ignore = "需要 CAP_NET_RAW 和 CAP_SYS_ADMIN"]
+    async fn captures_filtered_truncated_frames_on_loopback() {
+        enter_network_namespace();
+        // 接收端占住端口，避免产生 ICMP 端口不可达
+        let _receivers = [MATCHED_PORT, OTHER_PORT].map(|port| UdpSocket::bind(("127.0.0.1", port)).unwrap());
+        let mut source = PacketCaptureSource::new("lo")
+            .with_filter(&format!("udp dst port {}", MATCHED_PORT))
+            .with_snaplen(64)
+            .with_promiscuous(true)
+            .with_buffer_size(1 << 20)
+            .with_packet_count(3);
+        source.open().await.unwrap();
+
+        send_udp(3, OTHER_PORT, 200);
+        send_udp(3, MATCHED_PORT, 200);
+        let chunks = capture(&mut source).await;
+
+        // 只有过滤条件匹配的数据包，回环网卡上发出的副本被跳过，每个只出现一次
+        assert_eq!(chunks.len(), 3);
+        for (i, chunk) in chunks.iter().enumerate() {
+            assert_eq!(chunk.sequence, i as u64);
+            assert_eq!(u16::from_be_bytes([chunk.data[36], chunk.data[37]]), MATCHED_PORT);
+            assert_eq!(chunk.data[HEADERS_LEN], i as u8);
+            // snaplen 之外的部分被截断，原始长度保留在属性中
+            assert_eq!(chunk.data.len(), 64);
+            assert_eq!(
+                chunk.attribute(ORIGINAL_LENGTH_ATTRIBUTE).and_then(AttributeValue::as_i64),
+                Some((HEADERS_LEN + 200) as i64)
+            );
+            assert_eq!(
+                chunk.attribute(LINK_TYPE_ATTRIBUTE).and_then(AttributeValue::as_i64),
+                Some(i64::from(crate::packet::LinkType::Ethernet.code()))
+            );
+            assert!(chunk.event_time_ns.is_some());
+        }
+
+        let stats = source.stats();
+        assert_eq!(stats.received(), 3);
+        assert_eq!(stats.dropped(), 0);
+        assert_eq!(stats.get_metrics()["bytes_received"], 3 * 64);
+    }
+
+    #[tokio::test]
+    #[ignore = "需要 CAP_NET_RAW 和 CAP_SYS_ADMIN"]
+    async fn reports_kernel_drops_when_the_ring_is_full() {
+        enter_network_namespace();
+        let _receiver = UdpSocket::bind(("127.0.0.1", MATCHED_PORT)).unwrap();
+        // 只有一个 1 MiB 的块，读取之前写满后内核开始丢包
+        let mut source = PacketCaptureSource::new("lo")
+            .with_filter("udp")
+            .with_snaplen(128)
+            .with_buffer_size(1)
+            .with_packet_count(1);
+        source.open().await.unwrap();
+
+        send_udp(20_000, MATCHED_PORT, 64);
+        let chunks = capture(&mut source).await;
+
+        assert_eq!(chunks.len(), 1);
+        let stats = source.stats();
+        assert_eq!(stats.received(), 1);
+        assert!(stats.dropped() > 0, "expected kernel drops, got {:?}", stats.get_metrics());
+        assert!(stats.get_metrics()["queue_freezes"] > 0);
+    }
+}
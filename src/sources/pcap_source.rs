@@ -0,0 +1,107 @@
+use async_trait::async_trait;
+use crate::packet::pcap::{PcapError, PcapReader};
+use crate::packet::LINK_TYPE_ATTRIBUTE;
+use crate::{DataChunk, Source};
+use std::fs::File;
+use std::io::BufReader;
+use tokio::sync::mpsc;
+use tracing::{info, warn};
+
+// 抓包文件数据块上的属性：pcapng 接口序号、被截断数据包的原始长度
+pub const INTERFACE_ATTRIBUTE: &str = "interface";
+pub const ORIGINAL_LENGTH_ATTRIBUTE: &str = "original_length";
+
+// 抓包文件数据源：读取 pcap/pcapng 文件，每个数据包输出一个数据块，不需要 libpcap
+//
+// 事件时间为捕获时间，link_type 属性为 pcap 链路层编号（供 PacketDecodeProcessor 使用），
+// 序号为数据包在文件中的序号（从 0 开始）。文件在记录中间结束时输出已读取的数据包后正常结束
+pub struct PcapFileSource {
+    file_path: String,
+    reader: Option<PcapReader<BufReader<File>>>,
+    limit: Option<u64>,
+    name: String,
+}
+
+impl PcapFileSource {
+    pub fn new(file_path: &str) -> Self {
+        Self {
+            file_path: file_path.to_string(),
+            reader: None,
+            limit: None,
+            name: "PcapFileSource".to_string(),
+        }
+    }
+
+    pub fn with_name(mut self, name: &str) -> Self {
+        self.name = name.to_string();
+        self
+    }
+
+    // 最多读取的数据包数量
+    pub fn with_limit(mut self, limit: u64) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    fn open_reader(&self) -> anyhow::Result<PcapReader<BufReader<File>>> {
+        let file = File::open(&self.file_path)
+            .map_err(|e| anyhow::anyhow!("cannot open capture file {}: {}", self.file_path, e))?;
+        let reader = PcapReader::new(BufReader::new(file))
+            .map_err(|e| anyhow::anyhow!("{}: {}", self.file_path, e))?;
+        info!("[{}] Reading {} ({:?})", self.name, self.file_path, reader.format());
+        Ok(reader)
+    }
+}
+
+#[async_trait]
+impl Source for PcapFileSource {
+    // 启动前打开文件并检查文件头
+    async fn open(&mut self) -> anyhow::Result<()> {
+        self.reader = Some(self.open_reader()?);
+        Ok(())
+    }
+
+    async fn stream_data(&mut self, tx: mpsc::Sender<DataChunk>) -> anyhow::Result<()> {
+        let mut reader = match self.reader.take() {
+            Some(reader) => reader,
+            None => self.open_reader()?,
+        };
+
+        let mut sequence = 0u64;
+        while self.limit.is_none_or(|limit| sequence < limit) {
+            let packet = match reader.next_packet() {
+                Ok(Some(packet)) => packet,
+                Ok(None) => break,
+                Err(PcapError::Truncated) => {
+                    warn!("[{}] {} ends in the middle of a record, stopping after {} packets",
+                          self.name, self.file_path, sequence);
+                    break;
+                }
+                Err(e) => return Err(anyhow::anyhow!("{} (packet {}): {}", self.file_path, sequence, e)),
+            };
+
+            let mut chunk = DataChunk::new(packet.data)
+                .with_sequence(sequence)
+                .with_attribute(LINK_TYPE_ATTRIBUTE, i64::from(packet.link_type))
+                .with_attribute(INTERFACE_ATTRIBUTE, packet.interface);
+            if let Some(timestamp_ns) = packet.timestamp_ns {
+                chunk = chunk.with_event_time_ns(timestamp_ns);
+            }
+            if packet.original_len as usize > chunk.data.len() {
+                chunk.set_attribute(ORIGINAL_LENGTH_ATTRIBUTE, packet.original_len);
+            }
+            sequence += 1;
+
+            if tx.send(chunk).await.is_err() {
+                break;
+            }
+        }
+
+        info!("[{}] Read {} packets from {}", self.name, sequence, self.file_path);
+        Ok(())
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
@@ -0,0 +1,348 @@
+use async_trait::async_trait;
+use crate::{DataChunk, Source};
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio::time::Instant;
+use tracing::info;
+
+// 重放数据块上的属性：第几遍重放（从 0 开始），只在循环重放时写入
+pub const REPLAY_PASS_ATTRIBUTE: &str = "replay_pass";
+
+// 按录制时的节奏重放带时间戳的输入（例如 PcapFileSource、JsonLinesSource）
+//
+// 相邻数据块按时间差（事件时间，没有时为进入时间）乘以 1/speed 的间隔发出，保留原始的突发模式；
+// speed 为 None 时不等待。时间倒退的数据块立即发出。
+// 时间范围相对于输入中第一个数据块的时间，超过结束时间后本遍重放结束
+pub struct ReplaySource {
+    inner: Box<dyn Source>,
+    // 相对录制速度的倍数，None 表示尽快
+    speed: Option<f64>,
+    // 重放遍数，None 表示一直循环
+    passes: Option<u64>,
+    start: Option<Duration>,
+    end: Option<Duration>,
+    // 录制中的最长等待间隔，超过时按此间隔计算
+    max_gap: Option<Duration>,
+    retime: bool,
+    name: String,
+}
+
+impl ReplaySource {
+    pub fn new(inner: Box<dyn Source>) -> Self {
+        let name = format!("ReplaySource({})", inner.name());
+        Self {
+            inner,
+            speed: Some(1.0),
+            passes: Some(1),
+            start: None,
+            end: None,
+            max_gap: None,
+            retime: false,
+            name,
+        }
+    }
+
+    pub fn with_name(mut self, name: &str) -> Self {
+        self.name = name.to_string();
+        self
+    }
+
+    // 2.0 为两倍速，0.5 为半速；None 表示不等待
+    pub fn with_speed(mut self, speed: Option<f64>) -> Self {
+        self.speed = speed.filter(|speed| speed.is_finite() && *speed > 0.0);
+        self
+    }
+
+    // 重放遍数，None 表示一直循环直到管道停止
+    pub fn with_passes(mut self, passes: Option<u64>) -> Self {
+        self.passes = passes;
+        self
+    }
+
+    // 只重放 [start, end) 范围内的数据块，相对于输入中第一个数据块的时间
+    pub fn with_range(mut self, start: Option<Duration>, end: Option<Duration>) -> Self {
+        self.start = start;
+        self.end = end;
+        self
+    }
+
+    pub fn with_max_gap(mut self, max_gap: Duration) -> Self {
+        self.max_gap = Some(max_gap);
+        self
+    }
+
+    // 把事件时间改写为重放时刻，使窗口等按事件时间计算的处理器看到的是实时数据
+    pub fn with_retime(mut self, retime: bool) -> Self {
+        self.retime = retime;
+        self
+    }
+}
+
+// 一遍重放的节奏控制
+struct Pacer {
+    speed: Option<f64>,
+    start_ns: Option<u64>,
+    end_ns: Option<u64>,
+    max_gap_ns: u64,
+    retime: bool,
+    // 输入中第一个数据块的时间，第一遍重放时确定
+    origin_ns: Option<u64>,
+    // 上一个发出的数据块：(录制时间, 计划发出时刻, 改写后的事件时间)
+    previous: Option<(u64, Instant, u64)>,
+    // 上一遍最后一个改写后的事件时间，保证循环重放时事件时间递增
+    last_retimed_ns: u64,
+}
+
+enum Step {
+    Skip,
+    Emit { at: Instant, event_time_ns: u64 },
+    Stop,
+}
+
+impl Pacer {
+    fn next_pass(&mut self) {
+        self.previous = None;
+    }
+
+    fn schedule(&mut self, time_ns: u64) -> Step {
+        let origin_ns = *self.origin_ns.get_or_insert(time_ns);
+        let offset_ns = time_ns.saturating_sub(origin_ns);
+        if self.start_ns.is_some_and(|start| offset_ns < start) {
+            return Step::Skip;
+        }
+        if self.end_ns.is_some_and(|end| offset_ns >= end) {
+            return Step::Stop;
+        }
+
+        let now = Instant::now();
+        let (at, event_time_ns) = match self.previous {
+            None => {
+                let event_time_ns = crate::now_ns().max(self.last_retimed_ns + 1);
+                (now, event_time_ns)
+            }
+            Some((previous_ns, previous_at, previous_event_ns)) => {
+                let gap_ns = time_ns.saturating_sub(previous_ns).min(self.max_gap_ns);
+                let wait = match self.speed {
+                    Some(speed) => Duration::from_nanos((gap_ns as f64 / speed) as u64),
+                    None => Duration::ZERO,
+                };
+                let retimed_gap_ns = match self.speed {
+                    Some(_) => wait.as_nanos() as u64,
+                    None => gap_ns,
+                };
+                (previous_at + wait, previous_event_ns + retimed_gap_ns)
+            }
+        };
+        self.previous = Some((time_ns, at, event_time_ns));
+        self.last_retimed_ns = self.last_retimed_ns.max(event_time_ns);
+        Step::Emit { at, event_time_ns }
+    }
+}
+
+#[async_trait]
+impl Source for ReplaySource {
+    async fn open(&mut self) -> anyhow::Result<()> {
+        self.inner.open().await
+    }
+
+    async fn stream_data(&mut self, tx: mpsc::Sender<DataChunk>) -> anyhow::Result<()> {
+        let to_ns = |duration: Duration| u64::try_from(duration.as_nanos()).unwrap_or(u64::MAX);
+        let mut pacer = Pacer {
+            speed: self.speed,
+            start_ns: self.start.map(to_ns),
+            end_ns: self.end.map(to_ns),
+            max_gap_ns: self.max_gap.map(to_ns).unwrap_or(u64::MAX),
+            retime: self.retime,
+            origin_ns: None,
+            previous: None,
+            last_retimed_ns: 0,
+        };
+        let looping = self.passes != Some(1);
+        info!("[{}] Replaying at {} ({} passes)",
+              self.name,
+              self.speed.map_or("full speed".to_string(), |speed| format!("{}x", speed)),
+              self.passes.map_or("unlimited".to_string(), |passes| passes.to_string()));
+
+        let mut pass = 0u64;
+        let mut replayed = 0u64;
+        while self.passes.is_none_or(|passes| pass < passes) {
+            pacer.next_pass();
+            let before = replayed;
+            let (inner_tx, mut inner_rx) = mpsc::channel::<DataChunk>(64);
+            let produce = self.inner.stream_data(inner_tx);
+            // 返回 false 表示下游已关闭
+            let pace = async {
+                while let Some(mut chunk) = inner_rx.recv().await {
+                    let (at, event_time_ns) = match pacer.schedule(chunk.time_ns()) {
+                        Step::Skip => continue,
+                        Step::Stop => break,
+                        Step::Emit { at, event_time_ns } => (at, event_time_ns),
+                    };
+                    tokio::time::sleep_until(at).await;
+                    if pacer.retime {
+                        chunk.event_time_ns = Some(event_time_ns);
+                    }
+                    if looping {
+                        chunk.set_attribute(REPLAY_PASS_ATTRIBUTE, pass as i64);
+                    }
+                    if tx.send(chunk).await.is_err() {
+                        return false;
+                    }
+                    replayed += 1;
+                }
+                true
+            };
+            // 提前结束（超出时间范围或下游关闭）时 inner_rx 被丢弃，内部数据源随之停止
+            let (produced, open) = tokio::join!(produce, pace);
+            produced?;
+            if !open {
+                break;
+            }
+            pass += 1;
+            // 输入（或时间范围内）为空时不再循环
+            if replayed == before {
+                break;
+            }
+        }
+
+        info!("[{}] Replayed {} chunks in {} passes", self.name, replayed, pass);
+        Ok(())
+    }
+
+    async fn close(&mut self) -> anyhow::Result<()> {
+        self.inner.close().await
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MS: u64 = 1_000_000;
+
+    // 按给定的事件时间（毫秒）发出数据块，每次 stream_data 从头开始
+    struct Recording(Vec<u64>);
+
+    #[async_trait]
+    impl Source for Recording {
+        async fn stream_data(&mut self, tx: mpsc::Sender<DataChunk>) -> anyhow::Result<()> {
+            for (i, time_ms) in self.0.iter().enumerate() {
+                let chunk = DataChunk::new(vec![i as u8]).with_event_time_ns(1_700_000_000_000 * MS + time_ms * MS);
+                if tx.send(chunk).await.is_err() {
+                    break;
+                }
+            }
+            Ok(())
+        }
+
+        fn name(&self) -> &str {
+            "Recording"
+        }
+    }
+
+    fn pacer(speed: Option<f64>) -> Pacer {
+        Pacer {
+            speed,
+            start_ns: None,
+            end_ns: None,
+            max_gap_ns: u64::MAX,
+            retime: true,
+            origin_ns: None,
+            previous: None,
+            last_retimed_ns: 0,
+        }
+    }
+
+    // 每个数据块的计划发出时刻相对第一个的偏移（毫秒）和改写后的事件时间
+    fn schedule(pacer: &mut Pacer, times_ms: &[u64]) -> Vec<Option<(u64, u64)>> {
+        let mut first: Option<Instant> = None;
+        times_ms
+            .iter()
+            .map(|time_ms| match pacer.schedule(time_ms * MS) {
+                Step::Emit { at, event_time_ns } => {
+                    let first = *first.get_or_insert(at);
+                    Some(((at - first).as_millis() as u64, event_time_ns))
+                }
+                Step::Skip => None,
+                Step::Stop => Some((u64::MAX, 0)),
+            })
+            .collect()
+    }
+
+    fn offsets(steps: &[Option<(u64, u64)>]) -> Vec<Option<u64>> {
+        steps.iter().map(|step| step.map(|(offset, _)| offset)).collect()
+    }
+
+    #[test]
+    fn gaps_are_scaled_by_the_speed_factor() {
+        let times = [1000, 1100, 1100, 1400, 1300];
+        let scaled = |speed| offsets(&schedule(&mut pacer(speed), &times)).into_iter().map(Option::unwrap).collect::<Vec<_>>();
+        // 时间倒退的数据块立即发出
+        assert_eq!(scaled(Some(1.0)), [0, 100, 100, 400, 400]);
+        assert_eq!(scaled(Some(2.0)), [0, 50, 50, 200, 200]);
+        assert_eq!(scaled(Some(0.5)), [0, 200, 200, 800, 800]);
+        assert_eq!(scaled(None), [0; 5]);
+
+        // 改写后的事件时间按重放节奏递增
+        let steps = schedule(&mut pacer(Some(2.0)), &times);
+        let event_times: Vec<u64> = steps.iter().map(|step| step.unwrap().1 - steps[0].unwrap().1).collect();
+        assert_eq!(event_times, [0, 50 * MS, 50 * MS, 200 * MS, 200 * MS]);
+    }
+
+    #[test]
+    fn long_gaps_are_capped_and_ranges_are_relative_to_the_first_chunk() {
+        let mut capped = Pacer { max_gap_ns: 100 * MS, ..pacer(Some(1.0)) };
+        let steps = offsets(&schedule(&mut capped, &[0, 50, 10_050, 10_060]));
+        assert_eq!(steps, [Some(0), Some(50), Some(150), Some(160)]);
+
+        let mut ranged = Pacer { start_ns: Some(100 * MS), end_ns: Some(300 * MS), ..pacer(Some(1.0)) };
+        let steps = offsets(&schedule(&mut ranged, &[1000, 1050, 1100, 1250, 1300]));
+        assert_eq!(steps, [None, None, Some(0), Some(150), Some(u64::MAX)]);
+    }
+
+    #[test]
+    fn retimed_event_times_keep_increasing_across_passes() {
+        let mut pacer = pacer(None);
+        let first = schedule(&mut pacer, &[0, 10, 20]);
+        pacer.next_pass();
+        let second = schedule(&mut pacer, &[0, 10, 20]);
+        assert!(second[0].unwrap().1 > first[2].unwrap().1);
+    }
+
+    #[tokio::test]
+    async fn replays_with_recorded_pacing_and_pass_attributes() {
+        let mut source = ReplaySource::new(Box::new(Recording(vec![0, 40, 80])))
+            .with_speed(Some(2.0))
+            .with_passes(Some(2));
+        let (tx, mut rx) = mpsc::channel(16);
+        let started = std::time::Instant::now();
+        source.stream_data(tx).await.unwrap();
+        let elapsed = started.elapsed();
+
+        let mut chunks = Vec::new();
+        while let Ok(chunk) = rx.try_recv() {
+            chunks.push(chunk);
+        }
+        let passes: Vec<_> = chunks
+            .iter()
+            .map(|chunk| chunk.attribute(REPLAY_PASS_ATTRIBUTE).and_then(|pass| pass.as_i64()))
+            .collect();
+        assert_eq!(passes, [Some(0), Some(0), Some(0), Some(1), Some(1), Some(1)]);
+        assert!(chunks.iter().map(|chunk| chunk.data[0]).eq([0, 1, 2, 0, 1, 2]));
+        // 两遍各 80ms 的录制，两倍速共约 80ms
+        assert!(elapsed >= Duration::from_millis(80), "{:?}", elapsed);
+        assert!(elapsed < Duration::from_secs(2), "{:?}", elapsed);
+        // 不改写时保留原始事件时间
+        assert_eq!(chunks[1].event_time_ns, Some(1_700_000_000_000 * MS + 40 * MS));
+
+        // 单遍重放不写入 replay_pass；速度无效时按尽快重放
+        let mut source = ReplaySource::new(Box::new(Recording(vec![0, 5_000]))).with_speed(Some(0.0));
+        let (tx, mut rx) = mpsc::channel(16);
+        tokio::time::timeout(Duration::from_secs(2), source.stream_data(tx)).await.unwrap().unwrap();
+        assert!(rx.recv().await.unwrap().attribute(REPLAY_PASS_ATTRIBUTE).is_none());
+    }
+}
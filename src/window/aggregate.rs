@@ -0,0 +1,347 @@
+use super::Field;
+use crate::DataChunk;
+use serde_json::{json, Value};
+use std::any::Any;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::sync::Arc;
+
+// 窗口聚合器：每个窗口（和分组）持有一组独立实例
+//
+// 自定义聚合器实现该 trait 并通过 AggregateSpec::custom 注册
+pub trait Aggregator: Send + Sync {
+    fn update(&mut self, chunk: &DataChunk);
+
+    // 合并同类型聚合器的状态（会话窗口合并时使用）
+    fn merge(&mut self, other: &dyn Aggregator);
+
+    // 窗口关闭时的聚合结果
+    fn result(&self) -> Value;
+
+    fn as_any(&self) -> &dyn Any;
+}
+
+pub type AggregatorFactory = Arc<dyn Fn() -> Box<dyn Aggregator> + Send + Sync>;
+
+// 聚合定义：输出名 + 创建聚合器实例的工厂
+#[derive(Clone)]
+pub struct AggregateSpec {
+    name: String,
+    factory: AggregatorFactory,
+}
+
+impl AggregateSpec {
+    pub fn custom<F>(name: &str, factory: F) -> Self
+    where
+        F: Fn() -> Box<dyn Aggregator> + Send + Sync + 'static,
+    {
+        Self { name: name.to_string(), factory: Arc::new(factory) }
+    }
+
+    pub fn count() -> Self {
+        Self::custom("count", || Box::new(Count::default()))
+    }
+
+    pub fn sum(field: Field) -> Self {
+        Self::custom(&format!("sum({})", field), move || Box::new(Sum::new(field.clone())))
+    }
+
+    pub fn min(field: Field) -> Self {
+        Self::custom(&format!("min({})", field), move || Box::new(Extremum::min(field.clone())))
+    }
+
+    pub fn max(field: Field) -> Self {
+        Self::custom(&format!("max({})", field), move || Box::new(Extremum::max(field.clone())))
+    }
+
+    pub fn distinct(field: Field) -> Self {
+        Self::custom(&format!("distinct({})", field), move || Box::new(Distinct::new(field.clone())))
+    }
+
+    pub fn top_k(field: Field, k: usize) -> Self {
+        Self::custom(&format!("top_k({}, {})", field, k), move || Box::new(TopK::new(field.clone(), k)))
+    }
+
+    // 解析配置中的聚合表达式：count、sum(字段)、min(字段)、max(字段)、distinct(字段)、top_k(字段, k)
+    pub fn parse(expr: &str) -> Result<Self, String> {
+        let expr = expr.trim();
+        if expr == "count" {
+            return Ok(Self::count());
+        }
+
+        let (function, args) = expr
+            .strip_suffix(')')
+            .and_then(|rest| rest.split_once('('))
+            .ok_or_else(|| format!("无效的聚合表达式 '{}'", expr))?;
+        let args: Vec<&str> = args.split(',').map(str::trim).collect();
+        let field = match args[0] {
+            "" => return Err(format!("聚合 '{}' 缺少字段", expr)),
+            name => Field::parse(name),
+        };
+
+        match (function.trim(), args.len()) {
+            ("sum", 1) => Ok(Self::sum(field)),
+            ("min", 1) => Ok(Self::min(field)),
+            ("max", 1) => Ok(Self::max(field)),
+            ("distinct", 1) => Ok(Self::distinct(field)),
+            ("top_k", 2) => match args[1].parse::<usize>() {
+                Ok(k) if k > 0 => Ok(Self::top_k(field, k)),
+                _ => Err(format!("top_k 的 k 应为正整数: '{}'", args[1])),
+            },
+            ("top_k", _) => Err(format!("top_k 需要两个参数: '{}'", expr)),
+            (function, _) => Err(format!("未知的聚合函数 '{}'", function)),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn create(&self) -> Box<dyn Aggregator> {
+        (self.factory)()
+    }
+}
+
+impl fmt::Debug for AggregateSpec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("AggregateSpec").field(&self.name).finish()
+    }
+}
+
+// 整数结果写成 JSON 整数，其余写成浮点数
+fn number(value: f64) -> Value {
+    if value.fract() == 0.0 && value.abs() < 9.0e15 {
+        json!(value as i64)
+    } else {
+        json!(value)
+    }
+}
+
+// 数据块数量
+#[derive(Debug, Default)]
+pub struct Count {
+    count: u64,
+}
+
+impl Aggregator for Count {
+    fn update(&mut self, _chunk: &DataChunk) {
+        self.count += 1;
+    }
+
+    fn merge(&mut self, other: &dyn Aggregator) {
+        if let Some(other) = other.as_any().downcast_ref::<Self>() {
+            self.count += other.count;
+        }
+    }
+
+    fn result(&self) -> Value {
+        json!(self.count)
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+// 数值字段之和，缺少该字段的数据块不计入
+#[derive(Debug)]
+pub struct Sum {
+    field: Field,
+    sum: f64,
+}
+
+impl Sum {
+    pub fn new(field: Field) -> Self {
+        Self { field, sum: 0.0 }
+    }
+}
+
+impl Aggregator for Sum {
+    fn update(&mut self, chunk: &DataChunk) {
+        if let Some(value) = self.field.number(chunk) {
+            self.sum += value;
+        }
+    }
+
+    fn merge(&mut self, other: &dyn Aggregator) {
+        if let Some(other) = other.as_any().downcast_ref::<Self>() {
+            self.sum += other.sum;
+        }
+    }
+
+    fn result(&self) -> Value {
+        number(self.sum)
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+// 数值字段的最小值或最大值，窗口内没有数值时为 null
+#[derive(Debug)]
+pub struct Extremum {
+    field: Field,
+    max: bool,
+    value: Option<f64>,
+}
+
+impl Extremum {
+    pub fn min(field: Field) -> Self {
+        Self { field, max: false, value: None }
+    }
+
+    pub fn max(field: Field) -> Self {
+        Self { field, max: true, value: None }
+    }
+
+    fn observe(&mut self, value: f64) {
+        self.value = Some(match self.value {
+            Some(current) if self.max => current.max(value),
+            Some(current) => current.min(value),
+            None => value,
+        });
+    }
+}
+
+impl Aggregator for Extremum {
+    fn update(&mut self, chunk: &DataChunk) {
+        if let Some(value) = self.field.number(chunk) {
+            self.observe(value);
+        }
+    }
+
+    fn merge(&mut self, other: &dyn Aggregator) {
+        if let Some(value) = other.as_any().downcast_ref::<Self>().and_then(|other| other.value) {
+            self.observe(value);
+        }
+    }
+
+    fn result(&self) -> Value {
+        self.value.map(number).unwrap_or(Value::Null)
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+// 字段不同取值的数量
+#[derive(Debug)]
+pub struct Distinct {
+    field: Field,
+    values: HashSet<String>,
+}
+
+impl Distinct {
+    pub fn new(field: Field) -> Self {
+        Self { field, values: HashSet::new() }
+    }
+}
+
+impl Aggregator for Distinct {
+    fn update(&mut self, chunk: &DataChunk) {
+        if let Some(value) = self.field.value(chunk) {
+            self.values.insert(value);
+        }
+    }
+
+    fn merge(&mut self, other: &dyn Aggregator) {
+        if let Some(other) = other.as_any().downcast_ref::<Self>() {
+            self.values.extend(other.values.iter().cloned());
+        }
+    }
+
+    fn result(&self) -> Value {
+        json!(self.values.len())
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+// 出现次数最多的 k 个取值，结果为 [{ value, count }]，按次数降序
+#[derive(Debug)]
+pub struct TopK {
+    field: Field,
+    k: usize,
+    counts: HashMap<String, u64>,
+}
+
+impl TopK {
+    pub fn new(field: Field, k: usize) -> Self {
+        Self { field, k, counts: HashMap::new() }
+    }
+}
+
+impl Aggregator for TopK {
+    fn update(&mut self, chunk: &DataChunk) {
+        if let Some(value) = self.field.value(chunk) {
+            *self.counts.entry(value).or_default() += 1;
+        }
+    }
+
+    fn merge(&mut self, other: &dyn Aggregator) {
+        if let Some(other) = other.as_any().downcast_ref::<Self>() {
+            for (value, count) in &other.counts {
+                *self.counts.entry(value.clone()).or_default() += count;
+            }
+        }
+    }
+
+    fn result(&self) -> Value {
+        let mut top: Vec<(&String, &u64)> = self.counts.iter().collect();
+        top.sort_by(|a, b| b.1.cmp(a.1).then_with(|| a.0.cmp(b.0)));
+        top.truncate(self.k);
+        Value::Array(top.into_iter().map(|(value, count)| json!({ "value": value, "count": count })).collect())
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chunk(size: usize, host: &str) -> DataChunk {
+        DataChunk::new(vec![0; size]).with_attribute("host", host)
+    }
+
+    #[test]
+    fn parses_aggregate_expressions() {
+        for expr in ["count", "sum(length)", "min(bytes)", "max(length)", "distinct(host)", "top_k(host, 2)"] {
+            assert_eq!(AggregateSpec::parse(expr).unwrap().name(), expr);
+        }
+        for expr in ["avg(length)", "sum()", "top_k(host)", "top_k(host, 0)", "count(", "sum"] {
+            assert!(AggregateSpec::parse(expr).is_err(), "{}", expr);
+        }
+    }
+
+    #[test]
+    fn aggregators_update_and_merge() {
+        let specs = ["count", "sum(length)", "min(length)", "max(length)", "distinct(host)", "top_k(host, 2)"]
+            .map(|expr| AggregateSpec::parse(expr).unwrap());
+        let mut left: Vec<_> = specs.iter().map(AggregateSpec::create).collect();
+        let mut right: Vec<_> = specs.iter().map(AggregateSpec::create).collect();
+        for chunk in [chunk(10, "a"), chunk(30, "b")] {
+            left.iter_mut().for_each(|aggregator| aggregator.update(&chunk));
+        }
+        for chunk in [chunk(5, "a"), chunk(20, "c")] {
+            right.iter_mut().for_each(|aggregator| aggregator.update(&chunk));
+        }
+        for (aggregator, other) in left.iter_mut().zip(&right) {
+            aggregator.merge(other.as_ref());
+        }
+
+        let results: Vec<Value> = left.iter().map(|aggregator| aggregator.result()).collect();
+        assert_eq!(results[..4], [json!(4), json!(65), json!(5), json!(30)]);
+        assert_eq!(results[4], json!(3));
+        assert_eq!(
+            results[5],
+            json!([{ "value": "a", "count": 2 }, { "value": "b", "count": 1 }])
+        );
+    }
+}
@@ -0,0 +1,561 @@
+mod aggregate;
+
+pub use aggregate::{
+    AggregateSpec, Aggregator, AggregatorFactory, Count, Distinct, Extremum, Sum, TopK,
+};
+
+use crate::DataChunk;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::time::Duration;
+
+// 分组和聚合使用的数据块字段
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Field {
+    // 负载长度（字节）
+    Length,
+    Key,
+    Source,
+    Attribute(String),
+}
+
+impl Field {
+    // length、key、source 为保留名，其余按属性名处理
+    pub fn parse(name: &str) -> Self {
+        match name {
+            "length" => Field::Length,
+            "key" => Field::Key,
+            "source" => Field::Source,
+            attribute => Field::Attribute(attribute.to_string()),
+        }
+    }
+
+    // 字段取值的文本形式，用于分组、去重和计数
+    pub fn value(&self, chunk: &DataChunk) -> Option<String> {
+        match self {
+            Field::Length => Some(chunk.data.len().to_string()),
+            Field::Key => chunk.key.as_deref().map(String::from),
+            Field::Source => chunk.source_id.as_deref().map(String::from),
+            Field::Attribute(name) => chunk.attribute(name).map(ToString::to_string),
+        }
+    }
+
+    // 字段的数值，用于 sum/min/max；不是数值时返回 None
+    pub fn number(&self, chunk: &DataChunk) -> Option<f64> {
+        match self {
+            Field::Length => Some(chunk.data.len() as f64),
+            Field::Attribute(name) => chunk.attribute(name).and_then(|value| value.as_f64()),
+            Field::Key | Field::Source => self.value(chunk).and_then(|value| value.parse().ok()),
+        }
+    }
+}
+
+impl fmt::Display for Field {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Field::Length => write!(f, "length"),
+            Field::Key => write!(f, "key"),
+            Field::Source => write!(f, "source"),
+            Field::Attribute(name) => write!(f, "{}", name),
+        }
+    }
+}
+
+// 窗口使用的时间
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TimeDomain {
+    // 事件时间，数据源未设置时使用进入管道的时间
+    #[default]
+    EventTime,
+    IngestTime,
+}
+
+impl TimeDomain {
+    pub fn time_of(&self, chunk: &DataChunk) -> u64 {
+        match self {
+            TimeDomain::EventTime => chunk.time_ns(),
+            TimeDomain::IngestTime => chunk.ingest_time_ns,
+        }
+    }
+}
+
+// 窗口类型，窗口区间为 [start, end)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WindowKind {
+    // 固定长度、互不重叠
+    Tumbling { size: Duration },
+    // 固定长度、每隔 slide 开始一个新窗口，一个数据块可以属于多个窗口
+    Sliding { size: Duration, slide: Duration },
+    // 同一分组内相邻数据块间隔不超过 gap 时属于同一会话
+    Session { gap: Duration },
+}
+
+impl WindowKind {
+    pub fn tumbling(size: Duration) -> Self {
+        WindowKind::Tumbling { size }
+    }
+
+    pub fn sliding(size: Duration, slide: Duration) -> Self {
+        WindowKind::Sliding { size, slide }
+    }
+
+    pub fn session(gap: Duration) -> Self {
+        WindowKind::Session { gap }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            WindowKind::Tumbling { .. } => "tumbling",
+            WindowKind::Sliding { .. } => "sliding",
+            WindowKind::Session { .. } => "session",
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        match *self {
+            WindowKind::Tumbling { size } if size.is_zero() => Err("窗口长度必须大于 0".to_string()),
+            WindowKind::Sliding { size, slide } if size.is_zero() || slide.is_zero() => {
+                Err("窗口长度和滑动步长必须大于 0".to_string())
+            }
+            WindowKind::Sliding { size, slide } if slide > size => {
+                Err("滑动步长不能大于窗口长度".to_string())
+            }
+            WindowKind::Session { gap } if gap.is_zero() => Err("会话超时必须大于 0".to_string()),
+            _ => Ok(()),
+        }
+    }
+
+    // 包含时间 time_ns 的固定窗口；会话窗口由 WindowStore 按分组合并
+    fn assign(&self, time_ns: u64) -> Vec<(u64, u64)> {
+        match *self {
+            WindowKind::Tumbling { size } => {
+                let size = duration_ns(size);
+                let start = time_ns - time_ns % size;
+                vec![(start, start.saturating_add(size))]
+            }
+            WindowKind::Sliding { size, slide } => {
+                let (size, slide) = (duration_ns(size), duration_ns(slide));
+                let mut windows = Vec::new();
+                let mut start = time_ns - time_ns % slide;
+                while start.saturating_add(size) > time_ns {
+                    windows.push((start, start.saturating_add(size)));
+                    match start.checked_sub(slide) {
+                        Some(previous) => start = previous,
+                        None => break,
+                    }
+                }
+                windows
+            }
+            WindowKind::Session { gap } => vec![(time_ns, time_ns.saturating_add(duration_ns(gap)))],
+        }
+    }
+}
+
+fn duration_ns(duration: Duration) -> u64 {
+    u64::try_from(duration.as_nanos()).unwrap_or(u64::MAX).max(1)
+}
+
+// 迟到数据块（所属窗口都已关闭）的处理方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LatePolicy {
+    // 丢弃并计数
+    #[default]
+    Drop,
+    // 发送到处理器的 late 输出
+    SideOutput,
+    // 窗口关闭后再保留 allowed_lateness，期间到达的数据块更新窗口并重新输出结果
+    Update { allowed_lateness: Duration },
+}
+
+// 已关闭窗口的聚合结果，values 与聚合定义一一对应；updated 表示迟到数据更新后的结果
+#[derive(Debug, Clone)]
+pub struct ClosedWindow {
+    pub start_ns: u64,
+    pub end_ns: u64,
+    pub group: Option<String>,
+    pub values: Vec<(String, Value)>,
+    pub updated: bool,
+}
+
+// 数据块加入窗口的结果
+#[derive(Debug)]
+pub enum Assignment {
+    Added,
+    // 更新了已关闭但仍在允许迟到时间内的窗口
+    Updated(Vec<ClosedWindow>),
+    // 所属窗口都已关闭
+    Late,
+}
+
+struct OpenWindow {
+    end_ns: u64,
+    aggregators: Vec<Box<dyn Aggregator>>,
+}
+
+type WindowKey = (Option<String>, u64);
+
+// 按分组维护打开的窗口，水位线推进到窗口结束时关闭窗口并输出聚合结果
+//
+// 落在已关闭窗口中的数据块为迟到数据；设置了允许迟到时间时，
+// 关闭的窗口继续保留到 结束时间 + allowed_lateness，期间可以被迟到数据更新
+pub struct WindowStore {
+    kind: WindowKind,
+    time: TimeDomain,
+    group_by: Option<Field>,
+    aggregates: Vec<AggregateSpec>,
+    allowed_lateness_ns: u64,
+    // (分组, 窗口开始时间) → 窗口
+    open: BTreeMap<WindowKey, OpenWindow>,
+    // 已关闭、等待迟到数据的窗口
+    retained: BTreeMap<WindowKey, OpenWindow>,
+    watermark_ns: u64,
+    // 打开窗口中最早的结束时间（可能偏小），水位线未到达时跳过扫描
+    min_end_ns: u64,
+    late: u64,
+}
+
+impl WindowStore {
+    pub fn new(kind: WindowKind) -> Self {
+        Self {
+            kind,
+            time: TimeDomain::default(),
+            group_by: None,
+            aggregates: Vec::new(),
+            allowed_lateness_ns: 0,
+            open: BTreeMap::new(),
+            retained: BTreeMap::new(),
+            watermark_ns: 0,
+            min_end_ns: u64::MAX,
+            late: 0,
+        }
+    }
+
+    pub fn with_time_domain(mut self, time: TimeDomain) -> Self {
+        self.time = time;
+        self
+    }
+
+    pub fn with_group_by(mut self, field: Field) -> Self {
+        self.group_by = Some(field);
+        self
+    }
+
+    pub fn with_aggregate(mut self, aggregate: AggregateSpec) -> Self {
+        self.aggregates.push(aggregate);
+        self
+    }
+
+    pub fn with_allowed_lateness(mut self, allowed_lateness: Duration) -> Self {
+        self.allowed_lateness_ns = u64::try_from(allowed_lateness.as_nanos()).unwrap_or(u64::MAX);
+        self
+    }
+
+    pub fn kind(&self) -> WindowKind {
+        self.kind
+    }
+
+    pub fn time_domain(&self) -> TimeDomain {
+        self.time
+    }
+
+    pub fn group_by(&self) -> Option<&Field> {
+        self.group_by.as_ref()
+    }
+
+    // 迟到的数据块数量（不含更新了保留窗口的数据块）
+    pub fn late(&self) -> u64 {
+        self.late
+    }
+
+    pub fn watermark(&self) -> u64 {
+        self.watermark_ns
+    }
+
+    pub fn open_windows(&self) -> usize {
+        self.open.len()
+    }
+
+    fn new_aggregators(&mut self) -> Vec<Box<dyn Aggregator>> {
+        // 没有指定聚合时只计数
+        if self.aggregates.is_empty() {
+            self.aggregates.push(AggregateSpec::count());
+        }
+        self.aggregates.iter().map(AggregateSpec::create).collect()
+    }
+
+    // 把数据块加入所属窗口；窗口只在 advance 时关闭
+    pub fn add(&mut self, chunk: &DataChunk) -> Assignment {
+        let time_ns = self.time.time_of(chunk);
+        let group = self.group_by.as_ref().and_then(|field| field.value(chunk));
+
+        let (added, updated) = match self.kind {
+            WindowKind::Session { .. } => self.add_to_session(group, time_ns, chunk),
+            _ => {
+                let mut added = false;
+                let mut updated = Vec::new();
+                for (start, end) in self.kind.assign(time_ns) {
+                    let key = (group.clone(), start);
+                    if end > self.watermark_ns {
+                        if !self.open.contains_key(&key) {
+                            let aggregators = self.new_aggregators();
+                            self.open.insert(key.clone(), OpenWindow { end_ns: end, aggregators });
+                        }
+                        let window = self.open.get_mut(&key).expect("window inserted above");
+                        window.aggregators.iter_mut().for_each(|aggregator| aggregator.update(chunk));
+                        self.min_end_ns = self.min_end_ns.min(end);
+                        added = true;
+                    } else if let Some(window) = self.retained.get_mut(&key) {
+                        window.aggregators.iter_mut().for_each(|aggregator| aggregator.update(chunk));
+                        updated.push(key);
+                    }
+                }
+                (added, updated)
+            }
+        };
+
+        if !updated.is_empty() {
+            Assignment::Updated(self.results(updated))
+        } else if added {
+            Assignment::Added
+        } else {
+            self.late += 1;
+            Assignment::Late
+        }
+    }
+
+    // 与同一分组中重叠的打开会话合并；不重叠且已过水位线时尝试更新保留的会话
+    fn add_to_session(&mut self, group: Option<String>, time_ns: u64, chunk: &DataChunk) -> (bool, Vec<WindowKey>) {
+        let (mut start, mut end) = self.kind.assign(time_ns)[0];
+        let overlapping = |windows: &BTreeMap<WindowKey, OpenWindow>| -> Vec<u64> {
+            windows
+                .range((group.clone(), 0)..=(group.clone(), u64::MAX))
+                .filter(|((_, window_start), window)| *window_start < end && time_ns < window.end_ns)
+                .map(|((_, window_start), _)| *window_start)
+                .collect()
+        };
+
+        let open = overlapping(&self.open);
+        if open.is_empty() && end <= self.watermark_ns {
+            let Some(&window_start) = overlapping(&self.retained).first() else {
+                return (false, Vec::new());
+            };
+            let key = (group, window_start);
+            let window = self.retained.get_mut(&key).expect("window found above");
+            window.aggregators.iter_mut().for_each(|aggregator| aggregator.update(chunk));
+            return (false, vec![key]);
+        }
+
+        let mut aggregators = self.new_aggregators();
+        aggregators.iter_mut().for_each(|aggregator| aggregator.update(chunk));
+        for window_start in open {
+            let window = self.open.remove(&(group.clone(), window_start)).expect("window found above");
+            for (aggregator, other) in aggregators.iter_mut().zip(&window.aggregators) {
+                aggregator.merge(other.as_ref());
+            }
+            start = start.min(window_start);
+            end = end.max(window.end_ns);
+        }
+
+        self.open.insert((group, start), OpenWindow { end_ns: end, aggregators });
+        self.min_end_ns = self.min_end_ns.min(end);
+        (true, Vec::new())
+    }
+
+    // 把水位线推进到 watermark_ns，关闭结束时间不晚于它的窗口（按结束时间、分组排序），
+    // 并释放超过允许迟到时间的保留窗口
+    pub fn advance(&mut self, watermark_ns: u64) -> Vec<ClosedWindow> {
+        if watermark_ns <= self.watermark_ns {
+            return Vec::new();
+        }
+        self.watermark_ns = watermark_ns;
+
+        let allowed_lateness_ns = self.allowed_lateness_ns;
+        self.retained
+            .retain(|_, window| window.end_ns.saturating_add(allowed_lateness_ns) > watermark_ns);
+        if self.min_end_ns > watermark_ns {
+            return Vec::new();
+        }
+
+        let closing: Vec<WindowKey> = self
+            .open
+            .iter()
+            .filter(|(_, window)| window.end_ns <= watermark_ns)
+            .map(|(key, _)| key.clone())
+            .collect();
+        let closed = self.close(closing);
+        self.min_end_ns = self.open.values().map(|window| window.end_ns).min().unwrap_or(u64::MAX);
+        closed
+    }
+
+    // 关闭所有打开的窗口（管道结束时调用）
+    pub fn close_all(&mut self) -> Vec<ClosedWindow> {
+        let keys = self.open.keys().cloned().collect();
+        self.min_end_ns = u64::MAX;
+        let closed = self.close(keys);
+        self.retained.clear();
+        closed
+    }
+
+    fn close(&mut self, keys: Vec<WindowKey>) -> Vec<ClosedWindow> {
+        let mut closed = Vec::with_capacity(keys.len());
+        for key in keys {
+            let Some(window) = self.open.remove(&key) else { continue };
+            closed.push(self.result(&key, &window, false));
+            // 允许迟到时保留窗口，结束时间 + allowed_lateness 已过水位线的直接丢弃
+            if window.end_ns.saturating_add(self.allowed_lateness_ns) > self.watermark_ns {
+                self.retained.insert(key, window);
+            }
+        }
+        closed.sort_by(|a, b| (a.end_ns, &a.group, a.start_ns).cmp(&(b.end_ns, &b.group, b.start_ns)));
+        closed
+    }
+
+    fn results(&self, keys: Vec<WindowKey>) -> Vec<ClosedWindow> {
+        keys.into_iter()
+            .filter_map(|key| self.retained.get(&key).map(|window| self.result(&key, window, true)))
+            .collect()
+    }
+
+    fn result(&self, (group, start_ns): &WindowKey, window: &OpenWindow, updated: bool) -> ClosedWindow {
+        ClosedWindow {
+            start_ns: *start_ns,
+            end_ns: window.end_ns,
+            group: group.clone(),
+            values: self
+                .aggregates
+                .iter()
+                .zip(&window.aggregators)
+                .map(|(spec, aggregator)| (spec.name().to_string(), aggregator.result()))
+                .collect(),
+            updated,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const S: u64 = 1_000_000_000;
+
+    fn chunk(time_s: u64, host: &str) -> DataChunk {
+        DataChunk::new(vec![0; 10]).with_event_time_ns(time_s * S).with_attribute("host", host)
+    }
+
+    // (开始秒, 结束秒, 分组, count)
+    fn summary(windows: &[ClosedWindow]) -> Vec<(u64, u64, Option<&str>, Value)> {
+        windows
+            .iter()
+            .map(|w| (w.start_ns / S, w.end_ns / S, w.group.as_deref(), w.values[0].1.clone()))
+            .collect()
+    }
+
+    #[test]
+    fn tumbling_windows_close_when_the_watermark_passes_their_end() {
+        let mut store = WindowStore::new(WindowKind::tumbling(Duration::from_secs(10)))
+            .with_group_by(Field::parse("host"))
+            .with_aggregate(AggregateSpec::count())
+            .with_aggregate(AggregateSpec::sum(Field::Length));
+        for (time, host) in [(1, "a"), (9, "b"), (9, "a"), (10, "a"), (25, "b")] {
+            assert!(matches!(store.add(&chunk(time, host)), Assignment::Added));
+        }
+        assert_eq!(store.open_windows(), 4);
+
+        // 窗口区间为 [start, end)，水位线到达 end 时关闭
+        assert!(store.advance(9 * S).is_empty());
+        let closed = store.advance(10 * S);
+        assert_eq!(summary(&closed), [(0, 10, Some("a"), json!(2)), (0, 10, Some("b"), json!(1))]);
+        assert_eq!(closed[0].values[1], ("sum(length)".to_string(), json!(20)));
+        assert!(!closed[0].updated);
+
+        assert_eq!(summary(&store.advance(20 * S)), [(10, 20, Some("a"), json!(1))]);
+        // 水位线不会后退
+        assert!(store.advance(5 * S).is_empty());
+        assert_eq!(store.watermark(), 20 * S);
+        assert_eq!(summary(&store.close_all()), [(20, 30, Some("b"), json!(1))]);
+    }
+
+    #[test]
+    fn sliding_windows_count_a_chunk_in_every_overlapping_window() {
+        let mut store = WindowStore::new(WindowKind::sliding(Duration::from_secs(10), Duration::from_secs(5)));
+        store.add(&chunk(7, "a"));
+        store.add(&chunk(12, "a"));
+        let closed = store.advance(20 * S);
+        assert_eq!(
+            summary(&closed),
+            [(0, 10, None, json!(1)), (5, 15, None, json!(2)), (10, 20, None, json!(1))]
+        );
+    }
+
+    #[test]
+    fn session_windows_merge_chunks_within_the_gap() {
+        let mut store = WindowStore::new(WindowKind::session(Duration::from_secs(5))).with_group_by(Field::parse("host"));
+        for (time, host) in [(0, "a"), (3, "a"), (20, "a"), (2, "b")] {
+            store.add(&chunk(time, host));
+        }
+        // 落在两个会话之间的数据块把它们合并为一个
+        store.add(&chunk(10, "a"));
+        store.add(&chunk(6, "a"));
+        assert_eq!(store.open_windows(), 3);
+
+        assert_eq!(summary(&store.advance(7 * S)), [(2, 7, Some("b"), json!(1))]);
+        assert_eq!(
+            summary(&store.advance(30 * S)),
+            [(0, 15, Some("a"), json!(4)), (20, 25, Some("a"), json!(1))]
+        );
+    }
+
+    #[test]
+    fn late_chunks_are_counted_without_allowed_lateness() {
+        let mut store = WindowStore::new(WindowKind::tumbling(Duration::from_secs(10)));
+        store.add(&chunk(3, "a"));
+        assert_eq!(store.advance(10 * S).len(), 1);
+
+        assert!(matches!(store.add(&chunk(4, "a")), Assignment::Late));
+        assert!(matches!(store.add(&chunk(10, "a")), Assignment::Added));
+        assert_eq!(store.late(), 1);
+
+        // 会话窗口：与已关闭的会话不相交、结束时间早于水位线的数据块也是迟到数据
+        let mut store = WindowStore::new(WindowKind::session(Duration::from_secs(5)));
+        store.advance(30 * S);
+        assert!(matches!(store.add(&chunk(10, "a")), Assignment::Late));
+        assert!(matches!(store.add(&chunk(28, "a")), Assignment::Added));
+    }
+
+    #[test]
+    fn allowed_lateness_updates_retained_windows() {
+        let mut store = WindowStore::new(WindowKind::tumbling(Duration::from_secs(10)))
+            .with_allowed_lateness(Duration::from_secs(5));
+        store.add(&chunk(3, "a"));
+        assert_eq!(summary(&store.advance(12 * S)), [(0, 10, None, json!(1))]);
+
+        // 窗口已关闭但仍在允许迟到时间内，迟到数据更新窗口并输出新的结果
+        let Assignment::Updated(updated) = store.add(&chunk(4, "a")) else { panic!("expected an update") };
+        assert_eq!(summary(&updated), [(0, 10, None, json!(2))]);
+        assert!(updated[0].updated);
+        assert_eq!(store.late(), 0);
+
+        // 水位线越过 结束时间 + allowed_lateness 后不再保留
+        store.advance(15 * S);
+        assert!(matches!(store.add(&chunk(5, "a")), Assignment::Late));
+        assert_eq!(store.late(), 1);
+
+        // 会话窗口同样保留到允许迟到时间结束
+        let mut store = WindowStore::new(WindowKind::session(Duration::from_secs(5)))
+            .with_allowed_lateness(Duration::from_secs(10));
+        store.add(&chunk(0, "a"));
+        assert_eq!(summary(&store.advance(8 * S)), [(0, 5, None, json!(1))]);
+        let Assignment::Updated(updated) = store.add(&chunk(1, "a")) else { panic!("expected an update") };
+        assert_eq!(summary(&updated), [(0, 5, None, json!(2))]);
+    }
+
+    #[test]
+    fn invalid_window_definitions_are_rejected() {
+        assert!(WindowKind::tumbling(Duration::ZERO).validate().is_err());
+        assert!(WindowKind::sliding(Duration::from_secs(5), Duration::from_secs(10)).validate().is_err());
+        assert!(WindowKind::session(Duration::ZERO).validate().is_err());
+        assert!(WindowKind::sliding(Duration::from_secs(10), Duration::from_secs(10)).validate().is_ok());
+    }
+}